@@ -1,6 +1,7 @@
 ﻿using Microsoft.Web.WebView2.Core;
 using Microsoft.Web.WebView2.WinForms;
 using System;
+using System.Collections.Generic;
 using System.IO;
 using System.IO.Pipes;
 using System.Threading.Tasks;
@@ -14,13 +15,41 @@ namespace TauriWebView2Download
     {
         private TabControl tabControl;
         private static readonly string PipeName = "TauriWebView2DownloadPipe";
+
+        // Must match PROTOCOL_VERSION in src-tauri/src/sidecar.rs
+        private const int ProtocolVersion = 1;
+        private static readonly string[] Capabilities = { "hello", "setDownload", "cancelDownload", "restartDownload", "pauseDownload", "resumeDownload", "cookies" };
         private NamedPipeServerStream pipeServer;
         private bool isClosing;
+        private readonly bool stdioMode;
+
+        // Per-download bookkeeping so cancel requests reach the right transfer (UI thread only)
+        private readonly Dictionary<string, Tuple<TabPage, WebView2>> downloadTabs = new Dictionary<string, Tuple<TabPage, WebView2>>();
+        private readonly Dictionary<string, CoreWebView2DownloadOperation> downloadOperations = new Dictionary<string, CoreWebView2DownloadOperation>();
+        private readonly HashSet<string> cancelRequested = new HashSet<string>();
+        private readonly HashSet<string> restarting = new HashSet<string>();
+        private readonly HashSet<string> paused = new HashSet<string>();
 
-        public MainForm(string initialMessage)
+        public MainForm(string initialMessage, bool stdioMode = false)
         {
+            this.stdioMode = stdioMode;
             InitializeForm();
-            StartPipeServer();
+            if (stdioMode)
+            {
+                // Long-lived helper: the app writes one JSON message per line to stdin
+                this.Shown += (s, e) =>
+                {
+                    if (tabControl.TabPages.Count == 0)
+                    {
+                        Hide();
+                    }
+                    StartStdinReader();
+                };
+            }
+            else
+            {
+                StartPipeServer();
+            }
             if (!string.IsNullOrEmpty(initialMessage))
             {
                 _ = ProcessInitialMessage(initialMessage); // Fire-and-forget async call
@@ -48,6 +77,32 @@ namespace TauriWebView2Download
             try
             {
                 dynamic data = JsonConvert.DeserializeObject(message);
+                string action = data.action?.ToString();
+                if (action == "hello")
+                {
+                    PostHello();
+                    return;
+                }
+                if (action == "cancelDownload")
+                {
+                    CancelDownload(data.downloadId?.ToString());
+                    return;
+                }
+                if (action == "restartDownload")
+                {
+                    RestartDownload(data.downloadId?.ToString());
+                    return;
+                }
+                if (action == "pauseDownload")
+                {
+                    PauseDownload(data.downloadId?.ToString());
+                    return;
+                }
+                if (action == "resumeDownload")
+                {
+                    ResumeDownload(data.downloadId?.ToString());
+                    return;
+                }
                 await AddNewDownloadTab(data);
             }
             catch (Exception ex)
@@ -63,6 +118,10 @@ namespace TauriWebView2Download
             string saveFolder = data.saveFolder?.ToString();
             string downloadId = data.downloadId?.ToString();
             string filename = data.filename?.ToString();
+            string userAgent = data.userAgent?.ToString();
+            string referer = data.referer?.ToString();
+            dynamic cookies = data.cookies;
+            List<Tuple<string, Dictionary<string, string>>> providerHeaders = ParseProviderHeaders(data.providerHeaders);
 
             if (string.IsNullOrEmpty(downloadUrl) || !Uri.IsWellFormedUriString(downloadUrl, UriKind.Absolute))
             {
@@ -82,12 +141,18 @@ namespace TauriWebView2Download
                 return;
             }
 
+            if (stdioMode && !Visible)
+            {
+                Show();
+            }
+
             // Create a new tab
             TabPage tabPage = new TabPage($"Download {tabControl.TabPages.Count + 1}");
             WebView2 webView = new WebView2 { Dock = DockStyle.Fill };
             tabPage.Controls.Add(webView);
             tabControl.TabPages.Add(tabPage);
             tabControl.SelectedTab = tabPage;
+            downloadTabs[downloadId] = Tuple.Create(tabPage, webView);
 
             // Initialize WebView2
             string userDataFolder = Path.Combine(
@@ -116,6 +181,10 @@ namespace TauriWebView2Download
                 webView.CoreWebView2.Settings.IsScriptEnabled = true;
                 webView.CoreWebView2.Settings.AreHostObjectsAllowed = true;
                 webView.CoreWebView2.Settings.AreDefaultScriptDialogsEnabled = true;
+                if (!string.IsNullOrWhiteSpace(userAgent))
+                {
+                    webView.CoreWebView2.Settings.UserAgent = userAgent;
+                }
 
                 // Add WebResourceRequested handler to modify headers
                 webView.CoreWebView2.AddWebResourceRequestedFilter("*", CoreWebView2WebResourceContext.All);
@@ -135,6 +204,18 @@ namespace TauriWebView2Download
                         requestHeaders.SetHeader("Origin", origin);
                         requestHeaders.SetHeader("Access-Control-Request-Method", "GET, POST, PUT, DELETE, OPTIONS");
                         requestHeaders.SetHeader("Access-Control-Request-Headers", "*");
+
+                        foreach (var provider in providerHeaders)
+                        {
+                            if (!HostMatches(uri.Host, provider.Item1))
+                            {
+                                continue;
+                            }
+                            foreach (var header in provider.Item2)
+                            {
+                                requestHeaders.SetHeader(header.Key, header.Value);
+                            }
+                        }
                     }
                     catch (Exception ex)
                     {
@@ -189,8 +270,20 @@ namespace TauriWebView2Download
                 webView.CoreWebView2.DownloadStarting += (sender, e) =>
                     CoreWebView2_DownloadStarting(sender, e, saveFolder, downloadId, filename, webView, tabPage);
 
-                // Navigate to download URL
-                webView.CoreWebView2.Navigate(downloadUrl);
+                // Each download gets a fresh profile, so cookies saved from earlier visits are loaded first
+                LoadCookies(webView, cookies);
+
+                // Navigate to download URL, with the article page as Referer for hosts that check it
+                if (!string.IsNullOrWhiteSpace(referer) && Uri.IsWellFormedUriString(referer, UriKind.Absolute))
+                {
+                    var request = webView.CoreWebView2.Environment.CreateWebResourceRequest(
+                        downloadUrl, "GET", null, $"Referer: {referer}");
+                    webView.CoreWebView2.NavigateWithWebResourceRequest(request);
+                }
+                else
+                {
+                    webView.CoreWebView2.Navigate(downloadUrl);
+                }
                 Console.WriteLine($"Navigating to: {downloadUrl}");
                 PostMessage(new { status = "success", message = $"Navigating to: {downloadUrl} with save folder: {saveFolder}", downloadId });
             }
@@ -250,6 +343,8 @@ namespace TauriWebView2Download
                     return;
                 }
 
+                _ = PostCookies(webView, downloadId);
+
                 // Check if file already exists and prompt for overwrite
                 if (File.Exists(fullPath))
                 {
@@ -283,8 +378,16 @@ namespace TauriWebView2Download
                     }
                 }
 
-                e.ResultFilePath = fullPath;
+                // Write to <name>.part; the app renames it once the transfer has completed
+                string partPath = fullPath + ".part";
+                if (File.Exists(partPath))
+                {
+                    File.Delete(partPath);
+                }
+
+                e.ResultFilePath = partPath;
                 e.Handled = true;
+                downloadOperations[downloadId] = e.DownloadOperation;
 
                 Console.WriteLine($"Downloading to: {fullPath}");
                 PostMessage(new
@@ -294,7 +397,10 @@ namespace TauriWebView2Download
                     downloadId,
                     progress = 0.1f,
                     downloadStarted = true,
-                    filename = suggestedFileName
+                    filename = suggestedFileName,
+                    // Lets the app take the transfer over with its own HTTP client
+                    sourceUrl = e.DownloadOperation.Uri,
+                    partPath
                 });
 
                 e.DownloadOperation.StateChanged += (s, args) =>
@@ -315,12 +421,13 @@ namespace TauriWebView2Download
                         else if (e.DownloadOperation.State == CoreWebView2DownloadState.Completed)
                         {
                             Console.WriteLine($"Download completed: {fullPath}");
+                            ForgetDownload(downloadId);
                             PostMessage(new
                             {
                                 status = "success",
                                 message = $"Download completed: {fullPath}",
                                 downloadId,
-                                path = fullPath,
+                                path = partPath,
                                 filename = suggestedFileName
                             });
                             this.Invoke((Action)(() => RemoveTab(tabPage, webView)));
@@ -328,10 +435,24 @@ namespace TauriWebView2Download
                         else if (e.DownloadOperation.State == CoreWebView2DownloadState.Interrupted)
                         {
                             Console.WriteLine($"Download interrupted: {e.DownloadOperation.InterruptReason}");
+                            if (restarting.Contains(downloadId))
+                            {
+                                // Paused on purpose by RestartDownload, which resumes it right away
+                                return;
+                            }
+                            if (paused.Contains(downloadId))
+                            {
+                                // Paused by PauseDownload, kept until ResumeDownload or CancelDownload
+                                return;
+                            }
+                            bool wasCancelled = cancelRequested.Contains(downloadId);
+                            ForgetDownload(downloadId);
                             PostMessage(new
                             {
-                                status = "error",
-                                message = $"Download interrupted: {e.DownloadOperation.InterruptReason}",
+                                status = wasCancelled ? "cancelled" : "error",
+                                message = wasCancelled
+                                    ? "Download cancelled by user"
+                                    : $"Download interrupted: {e.DownloadOperation.InterruptReason}",
                                 downloadId
                             });
                             this.Invoke((Action)(() => RemoveTab(tabPage, webView)));
@@ -349,6 +470,7 @@ namespace TauriWebView2Download
                 {
                     try
                     {
+                        restarting.Remove(downloadId);
                         double bytesReceived = e.DownloadOperation.BytesReceived;
                         double totalBytes = e.DownloadOperation.TotalBytesToReceive ?? bytesReceived;
                         float progress = totalBytes > 0 ? (float)(bytesReceived / totalBytes * 100) : 0;
@@ -359,7 +481,9 @@ namespace TauriWebView2Download
                             status = "progress",
                             message = $"Progress: {progress}%",
                             downloadId,
-                            progress
+                            progress,
+                            bytesReceived = e.DownloadOperation.BytesReceived,
+                            totalBytes = e.DownloadOperation.TotalBytesToReceive
                         });
                     }
                     catch (Exception ex)
@@ -377,6 +501,120 @@ namespace TauriWebView2Download
             }
         }
 
+        private void CancelDownload(string downloadId)
+        {
+            if (string.IsNullOrEmpty(downloadId))
+            {
+                PostMessage(new { status = "error", message = "Missing download ID" });
+                return;
+            }
+
+            if (downloadOperations.TryGetValue(downloadId, out CoreWebView2DownloadOperation operation))
+            {
+                // The Interrupted state change posts the acknowledgment
+                cancelRequested.Add(downloadId);
+                if (paused.Remove(downloadId))
+                {
+                    // Already interrupted by the pause, so no further state change will arrive
+                    operation.Cancel();
+                    if (downloadTabs.TryGetValue(downloadId, out Tuple<TabPage, WebView2> pausedEntry))
+                    {
+                        RemoveTab(pausedEntry.Item1, pausedEntry.Item2);
+                    }
+                    ForgetDownload(downloadId);
+                    PostMessage(new { status = "cancelled", message = "Download cancelled by user", downloadId });
+                    return;
+                }
+                operation.Cancel();
+                return;
+            }
+
+            // Still navigating (or already gone): nothing is being written, so drop the tab directly
+            if (downloadTabs.TryGetValue(downloadId, out Tuple<TabPage, WebView2> entry))
+            {
+                RemoveTab(entry.Item1, entry.Item2);
+            }
+            ForgetDownload(downloadId);
+            PostMessage(new { status = "cancelled", message = "Download cancelled by user", downloadId });
+        }
+
+        private void RestartDownload(string downloadId)
+        {
+            if (string.IsNullOrEmpty(downloadId) || !downloadOperations.TryGetValue(downloadId, out CoreWebView2DownloadOperation operation))
+            {
+                PostMessage(new { status = "progress", message = "No transfer to restart", downloadId });
+                return;
+            }
+
+            // Pause + Resume re-requests the file from the current offset instead of starting over
+            restarting.Add(downloadId);
+            if (operation.State == CoreWebView2DownloadState.InProgress)
+            {
+                operation.Pause();
+            }
+
+            if (operation.CanResume)
+            {
+                operation.Resume();
+                PostMessage(new { status = "progress", message = "Download restarted after stall", downloadId, restarted = true });
+            }
+            else
+            {
+                restarting.Remove(downloadId);
+                PostMessage(new { status = "progress", message = "Download cannot be resumed by the host", downloadId });
+            }
+        }
+
+        private void PauseDownload(string downloadId)
+        {
+            if (string.IsNullOrEmpty(downloadId) || !downloadOperations.TryGetValue(downloadId, out CoreWebView2DownloadOperation operation))
+            {
+                PostMessage(new { status = "progress", message = "No transfer to pause", downloadId });
+                return;
+            }
+
+            if (operation.State == CoreWebView2DownloadState.InProgress)
+            {
+                paused.Add(downloadId);
+                operation.Pause();
+            }
+            PostMessage(new { status = "paused", message = "Download paused", downloadId });
+        }
+
+        private void ResumeDownload(string downloadId)
+        {
+            if (string.IsNullOrEmpty(downloadId) || !downloadOperations.TryGetValue(downloadId, out CoreWebView2DownloadOperation operation))
+            {
+                PostMessage(new { status = "progress", message = "No transfer to resume", downloadId });
+                return;
+            }
+
+            if (operation.CanResume)
+            {
+                operation.Resume();
+                paused.Remove(downloadId);
+                PostMessage(new { status = "progress", message = "Download resumed", downloadId, resumed = true });
+            }
+            else
+            {
+                if (downloadTabs.TryGetValue(downloadId, out Tuple<TabPage, WebView2> entry))
+                {
+                    RemoveTab(entry.Item1, entry.Item2);
+                }
+                ForgetDownload(downloadId);
+                PostMessage(new { status = "error", message = "Download cannot be resumed by the host", downloadId });
+            }
+        }
+
+        private void ForgetDownload(string downloadId)
+        {
+            restarting.Remove(downloadId);
+            paused.Remove(downloadId);
+            downloadTabs.Remove(downloadId);
+            downloadOperations.Remove(downloadId);
+            cancelRequested.Remove(downloadId);
+        }
+
         private void RemoveTab(TabPage tabPage, WebView2 webView)
         {
             if (!isClosing && tabPage != null && webView != null)
@@ -387,7 +625,14 @@ namespace TauriWebView2Download
                     webView.Dispose();
                     if (tabControl.TabPages.Count == 0)
                     {
-                        this.Close();
+                        if (stdioMode)
+                        {
+                            Hide();
+                        }
+                        else
+                        {
+                            this.Close();
+                        }
                     }
                 }
                 catch (Exception ex)
@@ -434,6 +679,135 @@ namespace TauriWebView2Download
             }
         }
 
+        private static void LoadCookies(WebView2 webView, dynamic cookies)
+        {
+            if (cookies == null)
+            {
+                return;
+            }
+            var cookieManager = webView.CoreWebView2.CookieManager;
+            foreach (var stored in cookies)
+            {
+                try
+                {
+                    var cookie = cookieManager.CreateCookie(
+                        stored.name.ToString(), stored.value.ToString(), stored.domain.ToString(), stored.path.ToString());
+                    cookie.IsSecure = (bool)stored.secure;
+                    cookie.IsHttpOnly = (bool)stored.httpOnly;
+                    if (stored.expires != null && stored.expires.Type != Newtonsoft.Json.Linq.JTokenType.Null)
+                    {
+                        cookie.Expires = DateTimeOffset.FromUnixTimeSeconds((long)stored.expires).LocalDateTime;
+                    }
+                    cookieManager.AddOrUpdateCookie(cookie);
+                }
+                catch (Exception ex)
+                {
+                    Console.WriteLine($"Failed to load saved cookie: {ex.Message}");
+                }
+            }
+        }
+
+        // Reports the cookies of this download's profile so the app can reuse them for the same host
+        private async Task PostCookies(WebView2 webView, string downloadId)
+        {
+            try
+            {
+                var cookies = await webView.CoreWebView2.CookieManager.GetCookiesAsync(null);
+                var stored = new List<object>();
+                foreach (var cookie in cookies)
+                {
+                    stored.Add(new
+                    {
+                        name = cookie.Name,
+                        value = cookie.Value,
+                        domain = cookie.Domain,
+                        path = cookie.Path,
+                        expires = cookie.IsSession ? (long?)null : new DateTimeOffset(cookie.Expires).ToUnixTimeSeconds(),
+                        secure = cookie.IsSecure,
+                        httpOnly = cookie.IsHttpOnly
+                    });
+                }
+                PostMessage(new { status = "cookies", downloadId, cookies = stored });
+            }
+            catch (Exception ex)
+            {
+                Console.WriteLine($"Failed to read cookies: {ex.Message}");
+            }
+        }
+
+        // Extra headers configured per host in the app, e.g. for mirrors that reject unknown clients
+        private static List<Tuple<string, Dictionary<string, string>>> ParseProviderHeaders(dynamic providers)
+        {
+            var result = new List<Tuple<string, Dictionary<string, string>>>();
+            if (providers == null)
+            {
+                return result;
+            }
+            foreach (var provider in providers)
+            {
+                string host = provider.host?.ToString();
+                if (string.IsNullOrWhiteSpace(host) || provider.headers == null)
+                {
+                    continue;
+                }
+                var headers = new Dictionary<string, string>();
+                foreach (var header in provider.headers)
+                {
+                    headers[header.Name.ToString()] = header.Value?.ToString() ?? "";
+                }
+                result.Add(Tuple.Create(host.Trim().TrimStart('*').TrimStart('.').ToLowerInvariant(), headers));
+            }
+            return result;
+        }
+
+        // "gofile.io" also matches its subdomains
+        private static bool HostMatches(string host, string pattern)
+        {
+            host = host.ToLowerInvariant();
+            return host == pattern || host.EndsWith("." + pattern);
+        }
+
+        private static void PostHello()
+        {
+            Console.Error.WriteLine(JsonConvert.SerializeObject(new
+            {
+                action = "hello",
+                protocolVersion = ProtocolVersion,
+                capabilities = Capabilities,
+                helperVersion = typeof(MainForm).Assembly.GetName().Version.ToString()
+            }));
+        }
+
+        private void StartStdinReader()
+        {
+            Task.Run(() =>
+            {
+                try
+                {
+                    string line;
+                    while (!isClosing && (line = Console.In.ReadLine()) != null)
+                    {
+                        if (string.IsNullOrWhiteSpace(line))
+                        {
+                            continue;
+                        }
+                        string message = line;
+                        this.Invoke((Action)(async () => await ProcessInitialMessage(message)));
+                    }
+                }
+                catch (Exception ex)
+                {
+                    Console.WriteLine($"Stdin reader error: {ex.Message}");
+                }
+
+                // The app closed our stdin, so nobody is left to report to
+                if (!isClosing)
+                {
+                    this.Invoke((Action)(() => this.Close()));
+                }
+            });
+        }
+
         private void StartPipeServer()
         {
             Task.Run(async () =>
@@ -507,6 +881,18 @@ namespace TauriWebView2Download
             Console.WriteLine($"Command-line args: {string.Join(", ", args)}");
             string messageJson = args.Length > 0 ? args[0] : null;
 
+            if (messageJson == "--stdio")
+            {
+                Application.Run(new MainForm(null, stdioMode: true));
+                return;
+            }
+
+            if (IsHelloMessage(messageJson))
+            {
+                PostHello();
+                return;
+            }
+
             try
             {
                 using (var pipeClient = new NamedPipeClientStream(".", PipeName, PipeDirection.Out))
@@ -533,6 +919,24 @@ namespace TauriWebView2Download
             Application.Run(mainForm);
         }
 
+        private static bool IsHelloMessage(string message)
+        {
+            if (string.IsNullOrEmpty(message))
+            {
+                return false;
+            }
+
+            try
+            {
+                dynamic data = JsonConvert.DeserializeObject(message);
+                return data?.action?.ToString() == "hello";
+            }
+            catch
+            {
+                return false;
+            }
+        }
+
         private void InitializeComponent()
         {
             System.ComponentModel.ComponentResourceManager resources = new System.ComponentModel.ComponentResourceManager(typeof(MainForm));
@@ -1,3 +1,69 @@
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+// Files the app needs at runtime; debug symbols and API docs are bundled but never read
+fn is_checked(path: &Path) -> bool {
+    !matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("pdb") | Some("xml")
+    )
+}
+
+fn collect(path: &Path, root: &Path, entries: &mut Vec<serde_json::Value>) {
+    if path.is_dir() {
+        let Ok(read_dir) = fs::read_dir(path) else {
+            return;
+        };
+        let mut children: Vec<_> = read_dir.flatten().map(|e| e.path()).collect();
+        children.sort();
+        for child in children {
+            collect(&child, root, entries);
+        }
+        return;
+    }
+    if !is_checked(path) {
+        return;
+    }
+    let Ok(bytes) = fs::read(path) else {
+        return;
+    };
+    let relative = path
+        .strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/");
+    entries.push(serde_json::json!({
+        "path": relative,
+        "size": bytes.len(),
+        "sha256": hex::encode(Sha256::digest(&bytes)),
+    }));
+}
+
+// Hashes of the bundled sidecar, checked against the installed copies on startup
+fn write_resource_manifest() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let binaries = root.join("binaries");
+    // An empty manifest would silently turn the startup check off
+    assert!(
+        binaries.is_dir(),
+        "binaries/ is missing; the sidecar must be built before the app"
+    );
+    let mut entries = Vec::new();
+    collect(&binaries, root, &mut entries);
+    assert!(!entries.is_empty(), "binaries/ contains no files to check");
+    println!("cargo:rerun-if-changed=binaries");
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    fs::write(
+        Path::new(&out_dir).join("resource-manifest.json"),
+        serde_json::to_string_pretty(&entries).unwrap_or_default(),
+    )
+    .expect("failed to write resource manifest");
+}
+
 fn main() {
+    write_resource_manifest();
     tauri_build::build()
 }
@@ -1,11 +1,93 @@
-use sevenz_rust::decompress_file;
+use crate::archivetools::{self, ArchiveTool, ToolKind};
+use crate::transliterate;
+use sevenz_rust::{decompress_with_extract_fn, default_entry_extract_fn};
+use std::collections::HashMap;
 use std::fmt;
 use std::fs::{self, File};
-use std::io::{self};
-use std::path::Path;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
 use zip::read::ZipArchive;
 
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+// How often an external extractor is checked on, which is also how often its output is measured
+const TOOL_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Everything the app offers to extract; formats without a native extractor need an archive tool
+pub const KNOWN_FORMATS: &[&str] = &[
+    "zip", "7z", "rar", "tar", "tgz", "gz", "tbz2", "bz2", "txz", "xz", "tzst", "zst",
+];
+
+// Bytes read from the archive and written out so far, sampled by extractmonitor
+#[derive(Default)]
+pub struct IoCounters {
+    pub read: AtomicU64,
+    pub written: AtomicU64,
+    // Set when the extractor cannot see archive reads (an external tool)
+    pub reads_unknown: AtomicBool,
+    // Entries left alone because the previous extraction already has them intact
+    pub skipped: AtomicU64,
+}
+
+// Folders an earlier extraction of the same archive left behind: the staging folder of a run that
+// was interrupted and the output folder of one that finished. Each carries the names its files
+// were transliterated to, keyed by their path in the archive.
+#[derive(Default)]
+pub struct ExistingFiles {
+    dirs: Vec<(PathBuf, HashMap<String, String>)>,
+}
+
+impl ExistingFiles {
+    pub fn new(dirs: &[&Path]) -> Self {
+        let dirs = dirs
+            .iter()
+            .filter(|dir| dir.is_dir())
+            .map(|dir| {
+                let renamed = transliterate::load_map(dir)
+                    .map(|map| {
+                        map.entries
+                            .into_iter()
+                            .map(|(romanized, original)| (original, romanized))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                (dir.to_path_buf(), renamed)
+            })
+            .collect();
+        ExistingFiles { dirs }
+    }
+
+    fn candidates(&self, relative: &Path) -> impl Iterator<Item = PathBuf> + '_ {
+        let key = relative.to_string_lossy().replace('\\', "/");
+        self.dirs.iter().map(move |(dir, renamed)| {
+            dir.join(renamed.get(&key).map(String::as_str).unwrap_or(&key))
+        })
+    }
+}
+
+struct CountingReader<'a, R> {
+    inner: R,
+    count: &'a AtomicU64,
+}
+
+impl<R: Read> Read for CountingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for CountingReader<'_, R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
 #[derive(Debug)]
 pub enum ArchiveError {
     Io(String),
@@ -39,9 +121,37 @@ impl From<zip::result::ZipError> for ArchiveError {
     }
 }
 
+// `tools` come from archivetools::detect; they handle formats without a native extractor and
+// archives the native one rejects (e.g. a zip using a compression method it does not implement)
 pub fn unarchive_file_with_progress<F>(
     file_path: &str,
     output_dir: &str,
+    tools: &[ArchiveTool],
+    counters: &IoCounters,
+    progress_callback: F,
+) -> Result<(), ArchiveError>
+where
+    F: Fn(f32),
+{
+    unarchive_file_reusing(
+        file_path,
+        output_dir,
+        &ExistingFiles::default(),
+        tools,
+        counters,
+        progress_callback,
+    )
+}
+
+// Like unarchive_file_with_progress, but entries whose size and CRC match a file in `existing`
+// are not written to `output_dir` again. Only the native zip and 7z extractors can skip; archive
+// tools always extract everything.
+pub fn unarchive_file_reusing<F>(
+    file_path: &str,
+    output_dir: &str,
+    existing: &ExistingFiles,
+    tools: &[ArchiveTool],
+    counters: &IoCounters,
     progress_callback: F,
 ) -> Result<(), ArchiveError>
 where
@@ -65,10 +175,109 @@ where
 
     fs::create_dir_all(output_dir)?;
 
+    let tool = archivetools::tool_for_file(tools, file_path, &extension);
+    let native = match extension.as_str() {
+        "zip" => extract_zip(
+            file_path,
+            output_dir,
+            existing,
+            counters,
+            &progress_callback,
+        ),
+        "7z" => extract_7z(
+            file_path,
+            output_dir,
+            existing,
+            counters,
+            &progress_callback,
+        ),
+        _ => {
+            let tool = tool.ok_or_else(|| {
+                ArchiveError::UnsupportedFormat(format!(
+                    "No program to extract .{} files is installed (7-Zip, unrar or tar)",
+                    extension
+                ))
+            })?;
+            if tool.kind == ToolKind::SevenZip && archivetools::is_compressed_tarball(file_path) {
+                return extract_tarball_twice(
+                    tool,
+                    file_path,
+                    output_dir,
+                    counters,
+                    &progress_callback,
+                );
+            }
+            return extract_with_tool(tool, file_path, output_dir, counters, &progress_callback);
+        }
+    };
+    match (native, tool) {
+        (Err(ArchiveError::InvalidArchive(e)), Some(tool)) => {
+            println!(
+                "Native extraction of {} failed ({}), retrying with {}",
+                file_path, e, tool.path
+            );
+            extract_with_tool(tool, file_path, output_dir, counters, &progress_callback)
+        }
+        (result, _) => result,
+    }
+}
+
+pub fn archive_extension(file_path: &str) -> Option<String> {
+    Path::new(file_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+}
+
+pub fn is_supported_archive(file_path: &str) -> bool {
+    archive_extension(file_path).is_some_and(|ext| KNOWN_FORMATS.contains(&ext.as_str()))
+}
+
+// Sums the uncompressed size recorded in the archive headers without extracting anything
+pub fn estimate_uncompressed_size(file_path: &str) -> Result<u64, ArchiveError> {
+    let path = Path::new(file_path);
+    if !path.exists() {
+        return Err(ArchiveError::FileNotFound(format!(
+            "Archive file does not exist: {}",
+            file_path
+        )));
+    }
+
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .ok_or_else(|| ArchiveError::UnsupportedFormat("No file extension".to_string()))?;
+
     match extension.as_str() {
-        "zip" => extract_zip(file_path, output_dir, progress_callback),
-        "7z" => extract_7z(file_path, output_dir, progress_callback),
-        "rar" => extract_rar(file_path, output_dir, progress_callback),
+        "zip" => {
+            let mut archive = ZipArchive::new(File::open(file_path)?)?;
+            let mut total = 0u64;
+            for i in 0..archive.len() {
+                total += archive.by_index_raw(i)?.size();
+            }
+            Ok(total)
+        }
+        "7z" => {
+            let mut file = File::open(file_path)?;
+            let len = file.metadata()?.len();
+            let archive = sevenz_rust::Archive::read(&mut file, len, &[])
+                .map_err(|e| ArchiveError::InvalidArchive(e.to_string()))?;
+            Ok(archive.files.iter().map(|entry| entry.size()).sum())
+        }
+        "rar" => {
+            let archive = unrar::Archive::new(&file_path)
+                .open_for_listing()
+                .map_err(|e| ArchiveError::InvalidArchive(e.to_string()))?;
+            let mut total = 0u64;
+            for header in archive {
+                let header = header.map_err(|e| ArchiveError::InvalidArchive(e.to_string()))?;
+                if header.is_file() {
+                    total += header.unpacked_size;
+                }
+            }
+            Ok(total)
+        }
         _ => Err(ArchiveError::UnsupportedFormat(format!(
             "Unsupported file format: {}",
             extension
@@ -76,15 +285,61 @@ where
     }
 }
 
+// Reading the old file back is far cheaper than decompressing and writing it again. A match kept
+// somewhere other than `target` means whatever is at `target` is a leftover and must not replace it.
+fn matches_existing(
+    existing: &ExistingFiles,
+    target: &Path,
+    relative: &Path,
+    size: u64,
+    crc: u32,
+) -> bool {
+    if file_matches(target, size, crc) {
+        return true;
+    }
+    if !existing
+        .candidates(relative)
+        .any(|path| path != target && file_matches(&path, size, crc))
+    {
+        return false;
+    }
+    let _ = fs::remove_file(target);
+    true
+}
+
+fn file_matches(path: &Path, size: u64, crc: u32) -> bool {
+    if !fs::metadata(path).is_ok_and(|m| m.is_file() && m.len() == size) {
+        return false;
+    }
+    let Ok(mut file) = File::open(path) else {
+        return false;
+    };
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => hasher.update(&buf[..n]),
+            Err(_) => return false,
+        }
+    }
+    hasher.finalize() == crc
+}
+
 fn extract_zip<F>(
     file_path: &str,
     output_dir: &str,
+    existing: &ExistingFiles,
+    counters: &IoCounters,
     progress_callback: F,
 ) -> Result<(), ArchiveError>
 where
     F: Fn(f32),
 {
-    let file = File::open(file_path)?;
+    let file = CountingReader {
+        inner: File::open(file_path)?,
+        count: &counters.read,
+    };
     let mut archive = ZipArchive::new(file)?;
     let total_files = archive.len() as f32;
 
@@ -93,16 +348,28 @@ where
         let file_path = file.enclosed_name().ok_or_else(|| {
             ArchiveError::InvalidArchive("Invalid file path in archive".to_string())
         })?;
-        let output_path = Path::new(output_dir).join(file_path);
+        let output_path = Path::new(output_dir).join(&file_path);
 
         if file.name().ends_with('/') {
             fs::create_dir_all(&output_path)?;
+        } else if matches_existing(
+            existing,
+            &output_path,
+            &file_path,
+            file.size(),
+            file.crc32(),
+        ) {
+            counters.skipped.fetch_add(1, Ordering::Relaxed);
         } else {
             if let Some(parent) = output_path.parent() {
                 fs::create_dir_all(parent)?;
             }
             let mut outfile = File::create(&output_path)?;
-            io::copy(&mut file, &mut outfile)?;
+            let mut counted = CountingReader {
+                inner: &mut file,
+                count: &counters.written,
+            };
+            io::copy(&mut counted, &mut outfile)?;
         }
 
         // Report progress
@@ -115,6 +382,8 @@ where
 fn extract_7z<F>(
     file_path: &str,
     output_dir: &str,
+    existing: &ExistingFiles,
+    counters: &IoCounters,
     progress_callback: F,
 ) -> Result<(), ArchiveError>
 where
@@ -122,15 +391,57 @@ where
 {
     // sevenz_rust does not support progress callbacks directly
     progress_callback(0.0);
-    decompress_file(file_path, output_dir)
-        .map_err(|e| ArchiveError::InvalidArchive(e.to_string()))?;
+    let file = CountingReader {
+        inner: File::open(file_path)?,
+        count: &counters.read,
+    };
+    decompress_with_extract_fn(file, output_dir, |entry, reader, dest| {
+        if !entry.is_directory()
+            && entry.has_crc
+            && matches_existing(
+                existing,
+                dest,
+                Path::new(entry.name()),
+                entry.size(),
+                entry.crc as u32,
+            )
+        {
+            // Solid archives decode entries back to back, so the skipped one is still read through
+            io::copy(reader, &mut io::sink()).map_err(sevenz_rust::Error::io)?;
+            counters.skipped.fetch_add(1, Ordering::Relaxed);
+            return Ok(true);
+        }
+        let mut counted = CountingReader {
+            inner: reader,
+            count: &counters.written,
+        };
+        default_entry_extract_fn(entry, &mut counted, dest)
+    })
+    .map_err(|e| ArchiveError::InvalidArchive(e.to_string()))?;
     progress_callback(100.0);
     Ok(())
 }
 
-fn extract_rar<F>(
+fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| match entry.file_type() {
+                    Ok(t) if t.is_dir() => dir_size(&entry.path()),
+                    Ok(_) => entry.metadata().map(|m| m.len()).unwrap_or(0),
+                    Err(_) => 0,
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+fn extract_with_tool<F>(
+    tool: &ArchiveTool,
     file_path: &str,
     output_dir: &str,
+    counters: &IoCounters,
     progress_callback: F,
 ) -> Result<(), ArchiveError>
 where
@@ -138,15 +449,68 @@ where
 {
     fs::create_dir_all(output_dir)?;
     progress_callback(0.0);
-    let status = Command::new("unrar")
-        .args(&["x", file_path, output_dir])
-        .status()?;
+    counters.reads_unknown.store(true, Ordering::Relaxed);
+    let initial_size = dir_size(Path::new(output_dir));
+    let mut command = Command::new(&tool.path);
+    command
+        .args(tool.extract_args(file_path, output_dir))
+        .stdin(std::process::Stdio::null());
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let mut child = command.spawn()?;
+    // The tool runs in its own process, so writes are measured by how much the output grows
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        let written = dir_size(Path::new(output_dir)).saturating_sub(initial_size);
+        counters.written.store(written, Ordering::Relaxed);
+        std::thread::sleep(TOOL_POLL_INTERVAL);
+    };
     if status.success() {
         progress_callback(100.0);
         Ok(())
     } else {
-        Err(ArchiveError::InvalidArchive(
-            "RAR extraction failed".to_string(),
-        ))
+        Err(ArchiveError::InvalidArchive(format!(
+            "{} exited with {}",
+            tool.path, status
+        )))
     }
-}
\ No newline at end of file
+}
+
+// 7-Zip turns game.tar.gz into game.tar, so the tar is unpacked into a folder inside the output
+// first and then extracted from there
+fn extract_tarball_twice<F>(
+    tool: &ArchiveTool,
+    file_path: &str,
+    output_dir: &str,
+    counters: &IoCounters,
+    progress_callback: F,
+) -> Result<(), ArchiveError>
+where
+    F: Fn(f32),
+{
+    let inner_dir = Path::new(output_dir).join(".chanomhub-tarball");
+    let _ = fs::remove_dir_all(&inner_dir);
+    let inner_dir_str = inner_dir.to_string_lossy().to_string();
+    let result = extract_with_tool(tool, file_path, &inner_dir_str, counters, |p| {
+        progress_callback(p / 2.0)
+    })
+    .and_then(|_| {
+        let tar = fs::read_dir(&inner_dir)?
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                ArchiveError::InvalidArchive(format!("{} holds no tar archive", file_path))
+            })?;
+        extract_with_tool(tool, &tar.to_string_lossy(), output_dir, counters, |p| {
+            progress_callback(50.0 + p / 2.0)
+        })
+    });
+    let _ = fs::remove_dir_all(&inner_dir);
+    result
+}
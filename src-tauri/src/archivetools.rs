@@ -0,0 +1,434 @@
+use crate::{archiver, helperupdate, httpconfig, offline};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+
+// Portable 7-Zip builds repackaged as zip (so they unpack without 7-Zip) and signed like helper
+// releases. The feed itself is signed too (archive-tools.json.sig, made with `tauri signer sign`),
+// since it names the file inside the package that gets executed.
+const TOOLS_FEED_URL: &str =
+    "https://raw.githubusercontent.com/Chanomhub/Chanomhub-Desktop/main/archive-tools.json";
+const TOOLS_FEED_SIGNATURE_URL: &str =
+    "https://raw.githubusercontent.com/Chanomhub/Chanomhub-Desktop/main/archive-tools.json.sig";
+
+// Names the 7-Zip executable inside a package may have
+const SEVEN_ZIP_BINARIES: &[&str] = &["7z", "7za", "7zz", "7zr"];
+
+const TOOLS_DIR: &str = "archive-tools";
+
+const MARKER_FILE: &str = "installed.json";
+
+// Formats extracted in-process, tools are only a fallback for them
+pub const NATIVE_FORMATS: &[&str] = &["zip", "7z"];
+
+const SEVEN_ZIP_FORMATS: &[&str] = &[
+    "zip", "7z", "rar", "tar", "tgz", "gz", "tbz2", "bz2", "txz", "xz",
+];
+
+// 7za (standalone) has no RAR codec
+const SEVEN_ZIP_STANDALONE_FORMATS: &[&str] =
+    &["zip", "7z", "tar", "tgz", "gz", "tbz2", "bz2", "txz", "xz"];
+
+const UNRAR_FORMATS: &[&str] = &["rar"];
+
+// GNU tar and bsdtar pick the compression from the file (zstd needs the zstd program as well)
+const TAR_FORMATS: &[&str] = &[
+    "tar", "tgz", "gz", "tbz2", "bz2", "txz", "xz", "tzst", "zst",
+];
+
+const TARBALL_SUFFIXES: &[&str] = &[
+    ".tar.gz", ".tar.bz2", ".tar.xz", ".tar.zst", ".tgz", ".tbz2", ".txz", ".tzst",
+];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolKind {
+    #[serde(rename = "7z")]
+    SevenZip,
+    Unrar,
+    Tar,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ArchiveTool {
+    pub kind: ToolKind,
+    pub path: String,
+    // Downloaded by the app rather than found on the system
+    pub managed: bool,
+    pub formats: Vec<String>,
+}
+
+impl ArchiveTool {
+    pub fn handles(&self, extension: &str) -> bool {
+        self.formats.iter().any(|f| f == extension)
+    }
+
+    pub fn extract_args(&self, file_path: &str, output_dir: &str) -> Vec<String> {
+        match self.kind {
+            ToolKind::SevenZip => vec![
+                "x".to_string(),
+                "-y".to_string(),
+                format!("-o{}", output_dir),
+                file_path.to_string(),
+            ],
+            ToolKind::Unrar => vec![
+                "x".to_string(),
+                "-y".to_string(),
+                file_path.to_string(),
+                // unrar treats the last argument as a folder only with a trailing separator
+                format!("{}{}", output_dir, std::path::MAIN_SEPARATOR),
+            ],
+            ToolKind::Tar => vec![
+                "-xf".to_string(),
+                file_path.to_string(),
+                "-C".to_string(),
+                output_dir.to_string(),
+            ],
+        }
+    }
+}
+
+// What the current machine can extract, and with what
+#[derive(Serialize, Clone, Debug)]
+pub struct ArchiveCapabilities {
+    pub native: Vec<String>,
+    pub tools: Vec<ArchiveTool>,
+    // Formats the app knows about but nothing here can extract
+    pub missing: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct ToolsRelease {
+    version: String,
+    // Keyed by "<os>-<arch>" as in std::env::consts, e.g. "windows-x86_64"
+    platforms: HashMap<String, ToolPackage>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct ToolPackage {
+    url: String,
+    sha256: String,
+    signature: String,
+    // Path of the 7-Zip executable inside the zip
+    binary: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct InstalledTools {
+    version: String,
+    binary: String,
+}
+
+fn tools_root(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map(|dir| dir.join(TOOLS_DIR))
+        .map_err(|e| format!("Failed to get app data dir: {}", e))
+}
+
+// Windows also finds "foo" as foo.exe / foo.cmd / foo.bat, like cmd does
+pub fn find_in_path(names: &[&str]) -> Option<PathBuf> {
+    let paths = std::env::var_os("PATH")?;
+    let suffixes: &[&str] = if cfg!(windows) {
+        &["", ".exe", ".cmd", ".bat", ".com"]
+    } else {
+        &[""]
+    };
+    std::env::split_paths(&paths).find_map(|dir| {
+        names.iter().find_map(|name| {
+            suffixes.iter().find_map(|suffix| {
+                let candidate = dir.join(format!("{}{}", name, suffix));
+                candidate.is_file().then_some(candidate)
+            })
+        })
+    })
+}
+
+// Installed but usually not on PATH
+#[cfg(target_os = "windows")]
+fn well_known(kind: ToolKind) -> Option<PathBuf> {
+    let program_files = [
+        std::env::var_os("ProgramFiles"),
+        std::env::var_os("ProgramFiles(x86)"),
+    ];
+    let relative = match kind {
+        ToolKind::SevenZip => "7-Zip\\7z.exe",
+        ToolKind::Unrar => "WinRAR\\UnRAR.exe",
+        ToolKind::Tar => return None,
+    };
+    program_files
+        .into_iter()
+        .flatten()
+        .map(|dir| PathBuf::from(dir).join(relative))
+        .find(|path| path.is_file())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn well_known(_kind: ToolKind) -> Option<PathBuf> {
+    None
+}
+
+fn seven_zip_formats(path: &Path) -> &'static [&'static str] {
+    let name = path
+        .file_stem()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match name.as_str() {
+        "7za" => SEVEN_ZIP_STANDALONE_FORMATS,
+        // 7zr only reads .7z
+        "7zr" => &["7z"],
+        _ => SEVEN_ZIP_FORMATS,
+    }
+}
+
+fn managed_seven_zip(app: &AppHandle) -> Option<PathBuf> {
+    let root = tools_root(app).ok()?;
+    let content = fs::read_to_string(root.join(MARKER_FILE)).ok()?;
+    let installed: InstalledTools = serde_json::from_str(&content).ok()?;
+    let path = root.join(&installed.version).join(&installed.binary);
+    path.is_file().then_some(path)
+}
+
+fn tool(kind: ToolKind, path: PathBuf, managed: bool) -> ArchiveTool {
+    let formats = match kind {
+        ToolKind::SevenZip => seven_zip_formats(&path),
+        ToolKind::Unrar => UNRAR_FORMATS,
+        ToolKind::Tar => TAR_FORMATS,
+    };
+    ArchiveTool {
+        kind,
+        path: path.to_string_lossy().to_string(),
+        managed,
+        formats: formats.iter().map(|f| f.to_string()).collect(),
+    }
+}
+
+// In order of preference: a dedicated tool first, then 7-Zip, then the copy the app manages
+pub fn detect(app: &AppHandle) -> Vec<ArchiveTool> {
+    let mut tools = Vec::new();
+    if let Some(path) = find_in_path(&["unrar"]).or_else(|| well_known(ToolKind::Unrar)) {
+        tools.push(tool(ToolKind::Unrar, path, false));
+    }
+    if let Some(path) =
+        find_in_path(&["7z", "7zz", "7za", "7zr"]).or_else(|| well_known(ToolKind::SevenZip))
+    {
+        tools.push(tool(ToolKind::SevenZip, path, false));
+    }
+    if let Some(path) = find_in_path(&["tar", "bsdtar"]) {
+        tools.push(tool(ToolKind::Tar, path, false));
+    }
+    if let Some(path) = managed_seven_zip(app) {
+        tools.push(tool(ToolKind::SevenZip, path, true));
+    }
+    tools
+}
+
+pub fn tool_for<'a>(tools: &'a [ArchiveTool], extension: &str) -> Option<&'a ArchiveTool> {
+    tools.iter().find(|tool| tool.handles(extension))
+}
+
+// A tar inside gzip/bzip2/xz/zstd. 7-Zip only takes off the outer layer of these.
+pub fn is_compressed_tarball(file_path: &str) -> bool {
+    let name = file_path.to_lowercase();
+    TARBALL_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+// Like tool_for, but tar is preferred for compressed tarballs since it unpacks them in one go
+pub fn tool_for_file<'a>(
+    tools: &'a [ArchiveTool],
+    file_path: &str,
+    extension: &str,
+) -> Option<&'a ArchiveTool> {
+    let tar = tools
+        .iter()
+        .find(|tool| tool.kind == ToolKind::Tar && tool.handles(extension));
+    is_compressed_tarball(file_path)
+        .then_some(tar)
+        .flatten()
+        .or_else(|| tool_for(tools, extension))
+}
+
+pub fn capabilities(app: &AppHandle) -> ArchiveCapabilities {
+    let tools = detect(app);
+    let missing = archiver::KNOWN_FORMATS
+        .iter()
+        .filter(|f| !NATIVE_FORMATS.contains(f) && tool_for(&tools, f).is_none())
+        .map(|f| f.to_string())
+        .collect();
+    ArchiveCapabilities {
+        native: NATIVE_FORMATS.iter().map(|f| f.to_string()).collect(),
+        tools,
+        missing,
+    }
+}
+
+fn platform_key() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+async fn fetch_text(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<String, String> {
+    let settings = httpconfig::settings(app);
+    let response = httpconfig::apply(&settings, client.get(url), url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch archive tools feed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Archive tools feed responded with {}",
+            response.status()
+        ));
+    }
+    response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read archive tools feed: {}", e))
+}
+
+async fn fetch_release(app: &AppHandle, client: &reqwest::Client) -> Result<ToolsRelease, String> {
+    let feed = fetch_text(app, client, TOOLS_FEED_URL).await?;
+    let signature = fetch_text(app, client, TOOLS_FEED_SIGNATURE_URL).await?;
+    helperupdate::verify_signature(app, feed.as_bytes(), &signature)
+        .map_err(|e| format!("Archive tools feed: {}", e))?;
+    serde_json::from_str(&feed).map_err(|e| format!("Failed to parse archive tools feed: {}", e))
+}
+
+// A plain relative path to one of the known 7-Zip executables, so a feed entry cannot point
+// anywhere else
+fn check_binary(binary: &str) -> Result<(), String> {
+    let path = Path::new(binary);
+    let plain = path
+        .components()
+        .all(|c| matches!(c, std::path::Component::Normal(_)));
+    let known = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .is_some_and(|stem| SEVEN_ZIP_BINARIES.contains(&stem.as_str()));
+    if !plain || !known {
+        return Err(format!("Invalid 7-Zip executable in feed: {}", binary));
+    }
+    Ok(())
+}
+
+// Downloads the portable 7-Zip for this platform, checks its hash and signature, and unpacks it
+pub async fn install_seven_zip(app: &AppHandle) -> Result<ArchiveTool, String> {
+    offline::ensure_online(app)?;
+    let client = httpconfig::client(app)?;
+    let release = fetch_release(app, &client).await?;
+    let package = release
+        .platforms
+        .get(&platform_key())
+        .cloned()
+        .ok_or_else(|| format!("No portable 7-Zip is published for {}", platform_key()))?;
+    if release.version.is_empty()
+        || !release
+            .version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+    {
+        return Err(format!("Invalid 7-Zip version: {}", release.version));
+    }
+    check_binary(&package.binary)?;
+    let _ = app.emit(
+        "archive-tool-installing",
+        &serde_json::json!({ "tool": "7z", "version": release.version }),
+    );
+
+    let response = client
+        .get(&package.url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download 7-Zip: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("7-Zip download failed: {}", response.status()));
+    }
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to download 7-Zip: {}", e))?;
+    let digest = hex::encode(Sha256::digest(&bytes));
+    if !digest.eq_ignore_ascii_case(package.sha256.trim()) {
+        return Err("7-Zip package does not match its published SHA-256 hash".to_string());
+    }
+    helperupdate::verify_signature(app, &bytes, &package.signature)
+        .map_err(|e| format!("7-Zip package: {}", e))?;
+
+    let root = tools_root(app)?;
+    let staging = root.join(format!(".staging-{}", release.version));
+    let target = root.join(&release.version);
+    let archive_path = root.join(format!(".7zip-{}.zip", release.version));
+    fs::create_dir_all(&root).map_err(|e| format!("Failed to create {}: {}", root.display(), e))?;
+    fs::write(&archive_path, &bytes).map_err(|e| format!("Failed to save 7-Zip: {}", e))?;
+    let _ = fs::remove_dir_all(&staging);
+    let unpacked = archiver::unarchive_file_with_progress(
+        &archive_path.to_string_lossy(),
+        &staging.to_string_lossy(),
+        &[],
+        &archiver::IoCounters::default(),
+        |_| {},
+    );
+    let _ = fs::remove_file(&archive_path);
+    if let Err(e) = unpacked {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(format!("Failed to unpack 7-Zip: {}", e));
+    }
+
+    let binary = staging.join(&package.binary);
+    if !binary.is_file() {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(format!("7-Zip package is missing {}", package.binary));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to make 7-Zip executable: {}", e))?;
+    }
+    let _ = fs::remove_dir_all(&target);
+    fs::rename(&staging, &target)
+        .map_err(|e| format!("Failed to install 7-Zip {}: {}", release.version, e))?;
+    let marker = InstalledTools {
+        version: release.version.clone(),
+        binary: package.binary.clone(),
+    };
+    fs::write(
+        root.join(MARKER_FILE),
+        serde_json::to_string_pretty(&marker).unwrap_or_default(),
+    )
+    .map_err(|e| format!("Failed to record installed 7-Zip: {}", e))?;
+    println!("Installed portable 7-Zip {}", release.version);
+
+    let installed = tool(ToolKind::SevenZip, target.join(&package.binary), true);
+    let _ = app.emit("archive-tool-installed", &installed);
+    Ok(installed)
+}
+
+// Tools for extracting `file_path`; downloads 7-Zip first when the format needs a tool and none is installed
+pub async fn ensure_for(app: &AppHandle, file_path: &str) -> Result<Vec<ArchiveTool>, String> {
+    let tools = detect(app);
+    let Some(extension) = archiver::archive_extension(file_path) else {
+        return Ok(tools);
+    };
+    if NATIVE_FORMATS.contains(&extension.as_str())
+        || tool_for_file(&tools, file_path, &extension).is_some()
+    {
+        return Ok(tools);
+    }
+    println!(
+        "No extractor for .{} found, installing portable 7-Zip",
+        extension
+    );
+    install_seven_zip(app).await.map_err(|e| {
+        format!(
+            "No program to extract .{} files is installed ({})",
+            extension, e
+        )
+    })?;
+    Ok(detect(app))
+}
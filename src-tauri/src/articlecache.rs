@@ -0,0 +1,227 @@
+use crate::cache;
+use crate::httpconfig;
+use crate::offline;
+use crate::state::ArticleResponse;
+use futures_util::stream::{self, StreamExt};
+use reqwest::StatusCode;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+// Shared by every bulk article lookup so the API sees the same load whatever the caller
+pub const MAX_CONCURRENT_FETCHES: usize = 6;
+
+// The API has no batch endpoint, so many slugs are resolved as parallel single requests
+#[derive(Serialize, Debug, Default)]
+pub struct ArticleBatch {
+    pub articles: HashMap<String, ArticleResponse>,
+    // slug -> error, so one dead article does not fail the whole batch
+    pub failed: HashMap<String, String>,
+}
+
+// Raw response body plus the validators needed to ask the API whether it changed
+#[derive(Serialize, Deserialize)]
+struct CachedArticle {
+    etag: Option<String>,
+    #[serde(rename = "lastModified")]
+    last_modified: Option<String>,
+    #[serde(rename = "fetchedAt")]
+    fetched_at: String,
+    body: serde_json::Value,
+}
+
+// Slugs come from the API and user input, so only a safe subset of characters reaches the file system
+pub fn slug_file_name(slug: &str) -> String {
+    let name: String = slug
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}.json", name)
+}
+
+// Responses fetched with a token can differ per account (paid downloads, hidden articles), so they
+// are kept apart by a hash of the token and never served to a guest or another account
+fn cache_path(app: &AppHandle, slug: &str, token: Option<&str>) -> Option<PathBuf> {
+    let dir = cache::cache_dir(app, "articles")?;
+    let dir = match token {
+        Some(token) => dir
+            .join("users")
+            .join(&hex::encode(Sha256::digest(token.as_bytes()))[..16]),
+        None => dir,
+    };
+    Some(dir.join(slug_file_name(slug)))
+}
+
+fn read_cached(path: &PathBuf) -> Option<CachedArticle> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_cached(path: &PathBuf, cached: &CachedArticle) {
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(path, serde_json::to_string(cached).unwrap_or_default()));
+    if let Err(e) = result {
+        println!("Failed to cache article at {}: {}", path.display(), e);
+    }
+}
+
+// Last copy fetched from the API, for when it cannot be reached
+pub fn cached_body(app: &AppHandle, slug: &str, token: Option<&str>) -> Option<serde_json::Value> {
+    cache_path(app, slug, token)
+        .as_ref()
+        .and_then(read_cached)
+        .map(|cached| cached.body)
+}
+
+pub async fn fetch_article(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    slug: &str,
+    token: Option<String>,
+) -> Result<ArticleResponse, String> {
+    let body = fetch_article_body(app, client, slug, token).await?;
+    serde_json::from_value(body).map_err(|e| format!("Failed to parse response: {}", e))
+}
+
+// Fetches an article, sending the cached ETag / Last-Modified so unchanged articles come back as an empty 304.
+// Returns the raw body, which keeps the fields ArticleResponse does not model (downloads, mirrors, ...).
+// In offline mode only the cached copy is returned.
+pub async fn fetch_article_body(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    slug: &str,
+    token: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let path = cache_path(app, slug, token.as_deref());
+    let cached = path.as_ref().and_then(read_cached);
+    if offline::is_offline(app) {
+        return cached.map(|cached| cached.body).ok_or_else(|| {
+            format!(
+                "{}, and article {} is not cached",
+                offline::OFFLINE_ERROR,
+                slug
+            )
+        });
+    }
+
+    let url = format!("https://api.chanomhub.online/articles/{}", slug);
+    let mut request = httpconfig::apply(&httpconfig::settings(app), client.get(&url), &url);
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+    if let Some(cached) = &cached {
+        if let Some(etag) = &cached.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let _permit = offline::api_permit(app).await?;
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    if response.status() == StatusCode::NOT_MODIFIED
+        && let Some(cached) = cached
+    {
+        return Ok(cached.body);
+    }
+    if !response.status().is_success() {
+        return Err(format!("API request failed: {}", response.status()));
+    }
+
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+    };
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    // Checked before caching so a malformed body is never served from the cache
+    serde_json::from_value::<ArticleResponse>(body.clone())
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    // Without validators a cached copy could never be reused, so it is not worth writing
+    if let Some(path) = path
+        && (etag.is_some() || last_modified.is_some())
+    {
+        write_cached(
+            &path,
+            &CachedArticle {
+                etag,
+                last_modified,
+                fetched_at: chrono::Utc::now().to_rfc3339(),
+                body: body.clone(),
+            },
+        );
+    }
+    Ok(body)
+}
+
+pub async fn fetch_articles(
+    app: &AppHandle,
+    slugs: Vec<String>,
+    token: Option<String>,
+) -> ArticleBatch {
+    let mut unique: Vec<String> = slugs
+        .into_iter()
+        .map(|slug| slug.trim().to_string())
+        .filter(|slug| !slug.is_empty())
+        .collect();
+    unique.sort();
+    unique.dedup();
+
+    let client = httpconfig::client(app).unwrap_or_else(|_| reqwest::Client::new());
+    let mut results = stream::iter(unique)
+        .map(|slug| {
+            let client = &client;
+            let token = token.clone();
+            async move {
+                let result = fetch_article(app, client, &slug, token).await;
+                (slug, result)
+            }
+        })
+        .buffer_unordered(MAX_CONCURRENT_FETCHES);
+
+    let mut batch = ArticleBatch::default();
+    while let Some((slug, result)) = results.next().await {
+        match result {
+            Ok(article) => {
+                batch.articles.insert(slug, article);
+            }
+            Err(e) => {
+                batch.failed.insert(slug, e);
+            }
+        }
+    }
+    if !batch.failed.is_empty() {
+        println!(
+            "Fetched {} articles, {} failed: {:?}",
+            batch.articles.len(),
+            batch.failed.len(),
+            batch.failed
+        );
+    }
+    batch
+}
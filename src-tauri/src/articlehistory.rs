@@ -0,0 +1,252 @@
+use crate::articlecache;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+// Not part of the response cache: clearing the cache should not reset what the user has already seen
+const VERSIONS_DIR: &str = "article-versions";
+
+// Enough to look back over a few builds without the files growing with every edit
+const MAX_VERSIONS: usize = 10;
+
+// Fields that change without the article itself changing
+const IGNORED_KEYS: &[&str] = &[
+    "updatedAt",
+    "viewCount",
+    "favoritesCount",
+    "favorited",
+    "commentsCount",
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct StoredVersion {
+    version: Option<i64>,
+    #[serde(rename = "seenAt")]
+    seen_at: String,
+    body: Value,
+}
+
+// Versions of one article as the user saw them, oldest first
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct ArticleVersions {
+    versions: Vec<StoredVersion>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ArticleChange {
+    // e.g. "title", "downloads[id=12].url", "tags[]"
+    pub path: String,
+    pub kind: ChangeKind,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ArticleChangelog {
+    pub slug: String,
+    // True when the article was never opened before, so there is nothing to compare against
+    #[serde(rename = "firstView")]
+    pub first_view: bool,
+    #[serde(rename = "lastSeenAt")]
+    pub last_seen_at: Option<String>,
+    #[serde(rename = "previousVersion")]
+    pub previous_version: Option<i64>,
+    #[serde(rename = "latestVersion")]
+    pub latest_version: Option<i64>,
+    pub changes: Vec<ArticleChange>,
+}
+
+fn versions_path(app: &AppHandle, slug: &str) -> Option<PathBuf> {
+    let dir = app.path().app_data_dir().ok()?.join(VERSIONS_DIR);
+    Some(dir.join(articlecache::slug_file_name(slug)))
+}
+
+fn load(path: &PathBuf) -> ArticleVersions {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn body_version(body: &Value) -> Option<i64> {
+    body.get("version").and_then(|v| v.as_i64())
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn item_id(item: &Value) -> Option<String> {
+    match item.get("id")? {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+fn change(
+    path: String,
+    kind: ChangeKind,
+    before: Option<&Value>,
+    after: Option<&Value>,
+) -> ArticleChange {
+    ArticleChange {
+        path,
+        kind,
+        before: before.cloned(),
+        after: after.cloned(),
+    }
+}
+
+fn diff(path: &str, before: &Value, after: &Value, changes: &mut Vec<ArticleChange>) {
+    match (before, after) {
+        (Value::Object(old), Value::Object(new)) => {
+            let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for key in keys {
+                if IGNORED_KEYS.contains(&key.as_str()) {
+                    continue;
+                }
+                let key_path = join(path, key);
+                match (old.get(key), new.get(key)) {
+                    (Some(old), Some(new)) => diff(&key_path, old, new, changes),
+                    (None, Some(new)) => {
+                        changes.push(change(key_path, ChangeKind::Added, None, Some(new)))
+                    }
+                    (Some(old), None) => {
+                        changes.push(change(key_path, ChangeKind::Removed, Some(old), None))
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+        // Lists of records (downloads, mirrors) are matched by id so an edited mirror reads as a change
+        (Value::Array(old), Value::Array(new))
+            if old.iter().chain(new).all(|item| item_id(item).is_some()) =>
+        {
+            for item in old {
+                let id = item_id(item).unwrap_or_default();
+                let item_path = format!("{}[id={}]", path, id);
+                match new
+                    .iter()
+                    .find(|n| item_id(n).as_deref() == Some(id.as_str()))
+                {
+                    Some(new_item) => diff(&item_path, item, new_item, changes),
+                    None => changes.push(change(item_path, ChangeKind::Removed, Some(item), None)),
+                }
+            }
+            for item in new {
+                let id = item_id(item).unwrap_or_default();
+                if !old
+                    .iter()
+                    .any(|o| item_id(o).as_deref() == Some(id.as_str()))
+                {
+                    let item_path = format!("{}[id={}]", path, id);
+                    changes.push(change(item_path, ChangeKind::Added, None, Some(item)));
+                }
+            }
+        }
+        // Plain lists (tags, platforms) only report what came and went
+        (Value::Array(old), Value::Array(new)) => {
+            let item_path = format!("{}[]", path);
+            for item in old.iter().filter(|item| !new.contains(item)) {
+                changes.push(change(
+                    item_path.clone(),
+                    ChangeKind::Removed,
+                    Some(item),
+                    None,
+                ));
+            }
+            for item in new.iter().filter(|item| !old.contains(item)) {
+                changes.push(change(
+                    item_path.clone(),
+                    ChangeKind::Added,
+                    None,
+                    Some(item),
+                ));
+            }
+        }
+        _ if before != after => changes.push(change(
+            path.to_string(),
+            ChangeKind::Changed,
+            Some(before),
+            Some(after),
+        )),
+        _ => {}
+    }
+}
+
+// Remembers the article as the user just saw it; an unchanged article only moves the seen time
+pub fn record_view(app: &AppHandle, slug: &str, body: &Value) {
+    let Some(path) = versions_path(app, slug) else {
+        return;
+    };
+    let mut stored = load(&path);
+    let seen_at = chrono::Utc::now().to_rfc3339();
+    let unchanged = stored.versions.last().is_some_and(|last| {
+        let mut changes = Vec::new();
+        diff("", &last.body, body, &mut changes);
+        changes.is_empty()
+    });
+    match stored.versions.last_mut() {
+        Some(last) if unchanged => last.seen_at = seen_at,
+        _ => {
+            stored.versions.push(StoredVersion {
+                version: body_version(body),
+                seen_at,
+                body: body.clone(),
+            });
+            if stored.versions.len() > MAX_VERSIONS {
+                let excess = stored.versions.len() - MAX_VERSIONS;
+                stored.versions.drain(..excess);
+            }
+        }
+    }
+
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, serde_json::to_string(&stored).unwrap_or_default()));
+    if let Err(e) = result {
+        println!("Failed to save article version for {}: {}", slug, e);
+    }
+}
+
+// What changed in the latest fetch since the user last opened the article. Does not mark it as
+// seen, so the changes stay visible until the article is actually opened.
+pub async fn changelog(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    slug: &str,
+    token: Option<String>,
+) -> Result<ArticleChangelog, String> {
+    let latest = articlecache::fetch_article_body(app, client, slug, token).await?;
+    let last_seen = versions_path(app, slug).and_then(|path| load(&path).versions.pop());
+
+    let mut changes = Vec::new();
+    if let Some(last_seen) = &last_seen {
+        diff("", &last_seen.body, &latest, &mut changes);
+    }
+    Ok(ArticleChangelog {
+        slug: slug.to_string(),
+        first_view: last_seen.is_none(),
+        last_seen_at: last_seen.as_ref().map(|v| v.seen_at.clone()),
+        previous_version: last_seen.as_ref().and_then(|v| v.version),
+        latest_version: body_version(&latest),
+        changes,
+    })
+}
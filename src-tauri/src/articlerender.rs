@@ -0,0 +1,224 @@
+use crate::state::{AppState, ArticleResponse};
+use crate::{articlecache, cache, httpconfig};
+use base64::Engine;
+use futures_util::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ArticleRenderSettings {
+    // Download images into the cache and inline them; off leaves only their alt text
+    #[serde(rename = "loadImages")]
+    pub load_images: bool,
+    // Larger images are left out instead of being inlined
+    #[serde(rename = "maxImageMb")]
+    pub max_image_mb: u64,
+    // Links get target="_blank" so they open in the system browser instead of the app window
+    #[serde(rename = "externalLinks")]
+    pub external_links: bool,
+}
+
+impl Default for ArticleRenderSettings {
+    fn default() -> Self {
+        Self {
+            load_images: true,
+            max_image_mb: 10,
+            external_links: true,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderedArticle {
+    pub slug: String,
+    pub title: String,
+    // Sanitized, with every image inlined from the cache; safe to put into the page as-is
+    pub html: String,
+    pub images: usize,
+    // Images that could not be fetched or were too large, rendered without a source
+    pub missing_images: usize,
+    // Served from the article cache because the API could not be reached
+    pub offline: bool,
+}
+
+pub fn settings(app: &AppHandle) -> ArticleRenderSettings {
+    app.state::<Mutex<AppState>>()
+        .lock()
+        .map(|app_state| app_state.article_render_settings.clone())
+        .unwrap_or_default()
+}
+
+// Article content is HTML from the editor, older posts are markdown
+fn to_html(content: &str) -> String {
+    if content.trim_start().starts_with('<') {
+        return content.to_string();
+    }
+    let parser = pulldown_cmark::Parser::new_ext(content, pulldown_cmark::Options::all());
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, parser);
+    html
+}
+
+fn image_urls(html: &str) -> Vec<String> {
+    let document = scraper::Html::parse_fragment(html);
+    let Ok(selector) = scraper::Selector::parse("img[src]") else {
+        return Vec::new();
+    };
+    let mut urls: Vec<String> = document
+        .select(&selector)
+        .filter_map(|img| img.value().attr("src"))
+        .filter(|src| src.starts_with("https://") || src.starts_with("http://"))
+        .map(|src| src.to_string())
+        .collect();
+    urls.sort();
+    urls.dedup();
+    urls
+}
+
+fn image_path(app: &AppHandle, url: &str) -> Option<PathBuf> {
+    Some(cache::cache_dir(app, "images")?.join(hex::encode(Sha1::digest(url.as_bytes()))))
+}
+
+async fn fetch_image(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    url: &str,
+    max_bytes: u64,
+) -> Result<PathBuf, String> {
+    let path = image_path(app, url).ok_or("Failed to get cache dir")?;
+    if path.exists() {
+        return Ok(path);
+    }
+    let response = httpconfig::apply(&httpconfig::settings(app), client.get(url), url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download image: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Image download failed: {}", response.status()));
+    }
+    if response.content_length().is_some_and(|len| len > max_bytes) {
+        return Err("Image is too large".to_string());
+    }
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to download image: {}", e))?;
+    if bytes.len() as u64 > max_bytes {
+        return Err("Image is too large".to_string());
+    }
+    // Only raster formats are inlined; anything else (SVG, HTML error pages) is left out
+    image::guess_format(&bytes).map_err(|e| format!("Not an image: {}", e))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create cache dir: {}", e))?;
+    }
+    fs::write(&path, &bytes).map_err(|e| format!("Failed to cache image: {}", e))?;
+    Ok(path)
+}
+
+fn data_uri(path: &PathBuf, max_bytes: u64) -> Option<String> {
+    let bytes = fs::read(path)
+        .ok()
+        .filter(|b| b.len() as u64 <= max_bytes)?;
+    let mime = image::guess_format(&bytes).ok()?.to_mime_type();
+    Some(format!(
+        "data:{};base64,{}",
+        mime,
+        base64::engine::general_purpose::STANDARD.encode(&bytes)
+    ))
+}
+
+// Downloads what is not cached yet; offline, whatever is already cached is still used
+async fn inline_images(
+    app: &AppHandle,
+    urls: Vec<String>,
+    max_bytes: u64,
+) -> HashMap<String, String> {
+    let client = httpconfig::client(app).unwrap_or_else(|_| reqwest::Client::new());
+    stream::iter(urls)
+        .map(|url| {
+            let client = &client;
+            async move {
+                match fetch_image(app, client, &url, max_bytes).await {
+                    Ok(path) => data_uri(&path, max_bytes).map(|uri| (url, uri)),
+                    Err(e) => {
+                        println!("Article image {} not inlined: {}", url, e);
+                        None
+                    }
+                }
+            }
+        })
+        .buffer_unordered(articlecache::MAX_CONCURRENT_FETCHES)
+        .filter_map(|inlined| async move { inlined })
+        .collect()
+        .await
+}
+
+// Scripts, styles, frames and event handlers are dropped; images only keep a cached data: source
+fn sanitize(
+    html: &str,
+    images: HashMap<String, String>,
+    settings: &ArticleRenderSettings,
+) -> String {
+    let mut builder = ammonia::Builder::default();
+    builder
+        .url_schemes(["http", "https", "mailto"].into())
+        .url_relative(ammonia::UrlRelative::Deny)
+        .attribute_filter(
+            move |element, attribute, value| match (element, attribute) {
+                ("img", "src") => images.get(value).map(|uri| Cow::Owned(uri.clone())),
+                _ => Some(Cow::Borrowed(value)),
+            },
+        );
+    if settings.external_links {
+        builder.set_tag_attribute_value("a", "target", "_blank");
+    }
+    builder.clean(html).to_string()
+}
+
+async fn article(app: &AppHandle, slug: &str) -> Result<(ArticleResponse, bool), String> {
+    let token = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .token
+        .clone();
+    let client = httpconfig::client(app)?;
+    match articlecache::fetch_article(app, &client, slug, token.clone()).await {
+        Ok(article) => Ok((article, false)),
+        Err(e) => {
+            let cached = articlecache::cached_body(app, slug, token.as_deref()).ok_or(e)?;
+            let article = serde_json::from_value(cached)
+                .map_err(|e| format!("Failed to parse cached article: {}", e))?;
+            Ok((article, true))
+        }
+    }
+}
+
+pub async fn render(app: &AppHandle, slug: &str) -> Result<RenderedArticle, String> {
+    let settings = settings(app);
+    let (article, offline) = article(app, slug).await?;
+    let html = to_html(&article.content);
+    let urls = image_urls(&html);
+    let images = if settings.load_images {
+        inline_images(app, urls.clone(), settings.max_image_mb * 1024 * 1024).await
+    } else {
+        HashMap::new()
+    };
+    let missing_images = urls.len() - images.len();
+    Ok(RenderedArticle {
+        slug: article.slug,
+        title: article.title,
+        html: sanitize(&html, images, &settings),
+        images: urls.len(),
+        missing_images,
+        offline,
+    })
+}
@@ -0,0 +1,96 @@
+use crate::downloader;
+use crate::state::AppState;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+
+// Games launched with the setting on, and the downloads they paused.
+// Downloads come back only when the last of those games has exited.
+#[derive(Default)]
+pub struct AutoPause {
+    running_games: usize,
+    paused: Vec<String>,
+}
+
+fn is_enabled(app: &AppHandle) -> bool {
+    app.state::<Mutex<AppState>>()
+        .lock()
+        .is_ok_and(|app_state| app_state.download_settings.pause_while_playing)
+}
+
+async fn pause_all(app: &AppHandle, game_id: &str) {
+    let paused = downloader::pause_running(app, "autopause").await;
+    if paused.is_empty() {
+        return;
+    }
+
+    println!(
+        "Paused {} downloads while {} is running",
+        paused.len(),
+        game_id
+    );
+    let _ = app.emit(
+        "downloads-auto-paused",
+        &serde_json::json!({
+            "gameId": game_id,
+            "ids": paused
+        }),
+    );
+    if let Ok(mut auto_pause) = app.state::<Mutex<AutoPause>>().lock() {
+        auto_pause.paused.extend(paused);
+    }
+}
+
+// Cancelled, failed or resumed by hand while the game was running are left alone
+async fn resume_all(app: &AppHandle, ids: Vec<String>) {
+    let resumed = downloader::unpause_all(app, ids, "autopause").await;
+    if resumed.is_empty() {
+        return;
+    }
+    println!("Resumed {} downloads after the game exited", resumed.len());
+    let _ = app.emit(
+        "downloads-auto-resumed",
+        &serde_json::json!({
+            "ids": resumed
+        }),
+    );
+}
+
+// Pauses downloads for the first running game and resumes them once `exited` fires for the last one
+pub fn game_started(app: AppHandle, game_id: String, exited: CancellationToken) {
+    if !is_enabled(&app) {
+        return;
+    }
+    let first = match app.state::<Mutex<AutoPause>>().lock() {
+        Ok(mut auto_pause) => {
+            auto_pause.running_games += 1;
+            auto_pause.running_games == 1
+        }
+        Err(e) => {
+            println!("Failed to lock auto pause state: {}", e);
+            return;
+        }
+    };
+
+    tauri::async_runtime::spawn(async move {
+        if first {
+            pause_all(&app, &game_id).await;
+        }
+        exited.cancelled().await;
+
+        let ids = match app.state::<Mutex<AutoPause>>().lock() {
+            Ok(mut auto_pause) => {
+                auto_pause.running_games = auto_pause.running_games.saturating_sub(1);
+                if auto_pause.running_games > 0 {
+                    return;
+                }
+                std::mem::take(&mut auto_pause.paused)
+            }
+            Err(e) => {
+                println!("Failed to lock auto pause state: {}", e);
+                return;
+            }
+        };
+        resume_all(&app, ids).await;
+    });
+}
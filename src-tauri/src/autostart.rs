@@ -0,0 +1,139 @@
+use std::path::PathBuf;
+
+// Passed by the login entry so a normal launch from the menu always shows the window
+pub const AUTOSTART_ARG: &str = "--autostart";
+
+const ENTRY_NAME: &str = "Chanomhub";
+
+#[cfg(target_os = "windows")]
+const RUN_KEY: &str = "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Run";
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+fn current_exe() -> Result<PathBuf, String> {
+    std::env::current_exe().map_err(|e| format!("Failed to get executable path: {}", e))
+}
+
+pub fn launched_by_autostart() -> bool {
+    std::env::args().any(|arg| arg == AUTOSTART_ARG)
+}
+
+// Registry Run key under HKCU, no admin rights needed
+#[cfg(target_os = "windows")]
+pub fn set_enabled(enabled: bool) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    use std::process::Command as StdCommand;
+
+    let mut command = StdCommand::new("reg");
+    if enabled {
+        let value = format!("\"{}\" {}", current_exe()?.display(), AUTOSTART_ARG);
+        command.args([
+            "add", RUN_KEY, "/v", ENTRY_NAME, "/t", "REG_SZ", "/d", &value, "/f",
+        ]);
+    } else {
+        if !is_enabled() {
+            return Ok(());
+        }
+        command.args(["delete", RUN_KEY, "/v", ENTRY_NAME, "/f"]);
+    }
+    let output = command
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("Failed to run reg: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to update autostart entry: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+pub fn is_enabled() -> bool {
+    use std::os::windows::process::CommandExt;
+    use std::process::Command as StdCommand;
+
+    StdCommand::new("reg")
+        .args(["query", RUN_KEY, "/v", ENTRY_NAME])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "macos")]
+fn entry_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to get home dir")?;
+    Ok(home
+        .join("Library/LaunchAgents")
+        .join("com.chanomhub.app.plist"))
+}
+
+#[cfg(target_os = "macos")]
+fn entry_contents() -> Result<String, String> {
+    Ok(format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>com.chanomhub.app</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+        current_exe()?.display(),
+        AUTOSTART_ARG
+    ))
+}
+
+// XDG autostart entry, picked up by every mainstream desktop environment
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn entry_path() -> Result<PathBuf, String> {
+    let config = dirs::config_dir().ok_or("Failed to get config dir")?;
+    Ok(config.join("autostart").join("chanomhub.desktop"))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn entry_contents() -> Result<String, String> {
+    // AppImages run from a temporary mount, so the entry must point at the image itself
+    let exe = std::env::var_os("APPIMAGE")
+        .map(PathBuf::from)
+        .map_or_else(current_exe, Ok)?;
+    Ok(format!(
+        "[Desktop Entry]\nType=Application\nName={}\nExec=\"{}\" {}\nX-GNOME-Autostart-enabled=true\nNoDisplay=true\n",
+        ENTRY_NAME,
+        exe.display(),
+        AUTOSTART_ARG
+    ))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn set_enabled(enabled: bool) -> Result<(), String> {
+    let path = entry_path()?;
+    if enabled {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create autostart dir: {}", e))?;
+        }
+        std::fs::write(&path, entry_contents()?)
+            .map_err(|e| format!("Failed to write autostart entry: {}", e))?;
+    } else if path.exists() {
+        std::fs::remove_file(&path)
+            .map_err(|e| format!("Failed to remove autostart entry: {}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn is_enabled() -> bool {
+    entry_path().is_ok_and(|p| p.exists())
+}
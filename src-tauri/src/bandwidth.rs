@@ -0,0 +1,310 @@
+use crate::downloader;
+use crate::state::{self, AppState};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+const USAGE_FILE: &str = "bandwidth_usage.json";
+
+// Progress arrives several times a second; the total is written out at most this often
+const SAVE_INTERVAL: Duration = Duration::from_secs(15);
+
+// Two years is enough to compare against an ISP bill
+const MAX_MONTHS: usize = 24;
+
+const WARNING_THRESHOLDS: &[u8] = &[80, 100];
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct BandwidthSettings {
+    // None means no cap, only accounting
+    #[serde(rename = "monthlyCapBytes")]
+    pub monthly_cap_bytes: Option<u64>,
+    // Pause helper downloads and refuse new ones once the cap is reached, instead of only warning
+    #[serde(rename = "enforceCap")]
+    pub enforce_cap: bool,
+}
+
+impl BandwidthSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.monthly_cap_bytes == Some(0) {
+            return Err("Monthly cap must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+struct MonthUsage {
+    bytes: u64,
+    // Thresholds already announced this month
+    #[serde(default)]
+    warned: Vec<u8>,
+}
+
+// Calendar months in local time ("2024-05"), as ISPs bill them
+#[derive(Default)]
+pub struct BandwidthTracker {
+    months: BTreeMap<String, MonthUsage>,
+    // Bytes already counted per running download, since the helper reports running totals
+    counted: HashMap<String, u64>,
+    last_saved: Option<Instant>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MonthlyBandwidth {
+    pub month: String,
+    pub bytes: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct BandwidthUsage {
+    pub month: String,
+    #[serde(rename = "usedBytes")]
+    pub used_bytes: u64,
+    #[serde(rename = "capBytes")]
+    pub cap_bytes: Option<u64>,
+    pub percent: Option<f64>,
+    #[serde(rename = "capEnforced")]
+    pub cap_enforced: bool,
+    // Newest first
+    pub history: Vec<MonthlyBandwidth>,
+}
+
+fn usage_path(app: &AppHandle) -> Option<PathBuf> {
+    state::get_config_dir(app).map(|dir| dir.join(USAGE_FILE))
+}
+
+fn current_month() -> String {
+    chrono::Local::now().format("%Y-%m").to_string()
+}
+
+pub fn load_tracker(app: &AppHandle) -> BandwidthTracker {
+    let months = usage_path(app)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    BandwidthTracker {
+        months,
+        ..Default::default()
+    }
+}
+
+fn save(app: &AppHandle, tracker: &mut BandwidthTracker) {
+    tracker.last_saved = Some(Instant::now());
+    let Some(path) = usage_path(app) else {
+        return;
+    };
+    let result = serde_json::to_string_pretty(&tracker.months)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        println!("Failed to save bandwidth usage: {}", e);
+    }
+}
+
+fn settings(app: &AppHandle) -> BandwidthSettings {
+    app.state::<Mutex<AppState>>()
+        .lock()
+        .map(|app_state| app_state.bandwidth_settings.clone())
+        .unwrap_or_default()
+}
+
+// Adds to this month's total and returns the thresholds crossed for the first time
+fn add(app: &AppHandle, tracker: &mut BandwidthTracker, bytes: u64, cap: Option<u64>) -> Vec<u8> {
+    let month = current_month();
+    let usage = tracker.months.entry(month).or_default();
+    usage.bytes += bytes;
+
+    let mut crossed = Vec::new();
+    if let Some(cap) = cap {
+        let percent = usage.bytes as f64 / cap as f64 * 100.0;
+        for &threshold in WARNING_THRESHOLDS {
+            if percent >= threshold as f64 && !usage.warned.contains(&threshold) {
+                usage.warned.push(threshold);
+                crossed.push(threshold);
+            }
+        }
+    }
+
+    while tracker.months.len() > MAX_MONTHS {
+        tracker.months.pop_first();
+    }
+    if !crossed.is_empty()
+        || tracker
+            .last_saved
+            .is_none_or(|at| at.elapsed() >= SAVE_INTERVAL)
+    {
+        save(app, tracker);
+    }
+    crossed
+}
+
+fn warn(app: &AppHandle, thresholds: Vec<u8>, settings: &BandwidthSettings) {
+    let Some(threshold) = thresholds.into_iter().max() else {
+        return;
+    };
+    let cap = settings.monthly_cap_bytes.unwrap_or_default();
+    let used = app
+        .state::<Mutex<BandwidthTracker>>()
+        .lock()
+        .ok()
+        .and_then(|tracker| tracker.months.get(&current_month()).map(|u| u.bytes))
+        .unwrap_or_default();
+    println!(
+        "Monthly download usage reached {}% ({} of {} bytes)",
+        threshold, used, cap
+    );
+    let _ = app.emit(
+        "bandwidth-warning",
+        &serde_json::json!({
+            "threshold": threshold,
+            "usedBytes": used,
+            "capBytes": cap,
+            "enforced": settings.enforce_cap
+        }),
+    );
+
+    let gb = |bytes: u64| bytes as f64 / 1024.0 / 1024.0 / 1024.0;
+    let body = if threshold >= 100 && settings.enforce_cap {
+        format!(
+            "You have used {:.1} of {:.1} GB this month. Downloads are paused until next month or until the cap is raised.",
+            gb(used),
+            gb(cap)
+        )
+    } else {
+        format!(
+            "You have used {:.1} of {:.1} GB this month.",
+            gb(used),
+            gb(cap)
+        )
+    };
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(format!("Download cap {}% used", threshold))
+        .body(body)
+        .show()
+    {
+        println!("Failed to show notification: {}", e);
+    }
+
+    if threshold >= 100 && settings.enforce_cap {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let paused = downloader::pause_running(&app, "bandwidth").await;
+            println!("Download cap reached, paused {} downloads", paused.len());
+        });
+    }
+}
+
+// Handles a running total from the helper's progress messages
+pub fn record_progress(app: &AppHandle, download_id: &str, bytes_received: u64) {
+    let settings = settings(app);
+    let crossed = {
+        let tracker = app.state::<Mutex<BandwidthTracker>>();
+        let Ok(mut tracker) = tracker.lock() else {
+            return;
+        };
+        let counted = tracker
+            .counted
+            .insert(download_id.to_string(), bytes_received)
+            .unwrap_or(0);
+        // A lower total means the transfer restarted from zero, and those bytes are downloaded again
+        let delta = if bytes_received >= counted {
+            bytes_received - counted
+        } else {
+            bytes_received
+        };
+        if delta == 0 {
+            return;
+        }
+        add(app, &mut tracker, delta, settings.monthly_cap_bytes)
+    };
+    warn(app, crossed, &settings);
+}
+
+// Counts whatever progress did not report (all of it for browser downloads) once the file is there
+pub fn record_completed(app: &AppHandle, download_id: &str, file_size: Option<u64>) {
+    let settings = settings(app);
+    let crossed = {
+        let tracker = app.state::<Mutex<BandwidthTracker>>();
+        let Ok(mut tracker) = tracker.lock() else {
+            return;
+        };
+        let counted = tracker.counted.remove(download_id).unwrap_or(0);
+        let remaining = file_size.unwrap_or(0).saturating_sub(counted);
+        let crossed = add(app, &mut tracker, remaining, settings.monthly_cap_bytes);
+        save(app, &mut tracker);
+        crossed
+    };
+    warn(app, crossed, &settings);
+}
+
+// Failed or cancelled: the bytes already counted stay counted, since they were transferred
+pub fn forget(app: &AppHandle, download_id: &str) {
+    let tracker = app.state::<Mutex<BandwidthTracker>>();
+    if let Ok(mut tracker) = tracker.lock() {
+        tracker.counted.remove(download_id);
+        save(app, &mut tracker);
+    }
+}
+
+// Refuses new downloads once an enforced cap is used up
+pub fn ensure_under_cap(app: &AppHandle) -> Result<(), String> {
+    let settings = settings(app);
+    let Some(cap) = settings.monthly_cap_bytes.filter(|_| settings.enforce_cap) else {
+        return Ok(());
+    };
+    let used = app
+        .state::<Mutex<BandwidthTracker>>()
+        .lock()
+        .map_err(|e| format!("Failed to lock bandwidth usage: {}", e))?
+        .months
+        .get(&current_month())
+        .map(|usage| usage.bytes)
+        .unwrap_or_default();
+    if used >= cap {
+        return Err(
+            "Monthly download cap reached; raise the cap or wait until next month".to_string(),
+        );
+    }
+    Ok(())
+}
+
+pub fn usage(app: &AppHandle) -> Result<BandwidthUsage, String> {
+    let settings = settings(app);
+    let tracker = app.state::<Mutex<BandwidthTracker>>();
+    let tracker = tracker
+        .lock()
+        .map_err(|e| format!("Failed to lock bandwidth usage: {}", e))?;
+    let month = current_month();
+    let used_bytes = tracker
+        .months
+        .get(&month)
+        .map(|usage| usage.bytes)
+        .unwrap_or_default();
+    Ok(BandwidthUsage {
+        month,
+        used_bytes,
+        cap_bytes: settings.monthly_cap_bytes,
+        percent: settings
+            .monthly_cap_bytes
+            .map(|cap| (used_bytes as f64 / cap as f64 * 1000.0).round() / 10.0),
+        cap_enforced: settings.enforce_cap && settings.monthly_cap_bytes.is_some(),
+        history: tracker
+            .months
+            .iter()
+            .rev()
+            .map(|(month, usage)| MonthlyBandwidth {
+                month: month.clone(),
+                bytes: usage.bytes,
+            })
+            .collect(),
+    })
+}
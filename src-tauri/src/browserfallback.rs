@@ -0,0 +1,158 @@
+use crate::ActiveDownloads;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// Long enough for a slow host with a captcha and a countdown page
+const WATCH_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+// Names browsers use while a download is still in progress
+const PARTIAL_SUFFIXES: &[&str] = &[".crdownload", ".part", ".partial", ".download", ".tmp"];
+
+pub fn watch_dir() -> Result<PathBuf, String> {
+    dirs::download_dir().ok_or_else(|| "Could not find the system Downloads folder".to_string())
+}
+
+fn is_partial(name: &str) -> bool {
+    let name = name.to_lowercase();
+    PARTIAL_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+// Exact name, or the "name (1).zip" a browser picks when the name is already taken
+fn name_matches(found: &str, expected: &str) -> bool {
+    let (found, expected) = (found.to_lowercase(), expected.to_lowercase());
+    if found == expected {
+        return true;
+    }
+    let (found, expected) = (Path::new(&found), Path::new(&expected));
+    if found.extension() != expected.extension() {
+        return false;
+    }
+    let (Some(found_stem), Some(expected_stem)) = (
+        found.file_stem().and_then(|s| s.to_str()),
+        expected.file_stem().and_then(|s| s.to_str()),
+    ) else {
+        return false;
+    };
+    found_stem
+        .strip_prefix(expected_stem)
+        .map(|rest| rest.trim())
+        .and_then(|rest| rest.strip_prefix('('))
+        .and_then(|rest| rest.strip_suffix(')'))
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+// A finished file that appeared after the handoff and matches the expected name and/or size
+fn find_candidate(
+    dir: &Path,
+    filename: Option<&str>,
+    expected_size: Option<u64>,
+    since: SystemTime,
+) -> Option<(PathBuf, u64)> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let metadata = entry.metadata().ok()?;
+            if !metadata.is_file() || is_partial(&name) {
+                return None;
+            }
+            // Browsers keep the server's timestamp on some platforms, so creation time counts too
+            let appeared = metadata
+                .created()
+                .ok()
+                .into_iter()
+                .chain(metadata.modified().ok())
+                .max()?;
+            if appeared < since {
+                return None;
+            }
+            if filename.is_some_and(|expected| !name_matches(&name, expected)) {
+                return None;
+            }
+            if expected_size.is_some_and(|size| size != metadata.len()) {
+                return None;
+            }
+            Some((entry.path(), metadata.len()))
+        })
+        .max_by_key(|(_, size)| *size)
+}
+
+async fn finish(app: &AppHandle, response: serde_json::Value) {
+    if let Some(id) = response.get("downloadId").and_then(|id| id.as_str())
+        && let Ok(mut downloads) = app.state::<RwLock<ActiveDownloads>>().write()
+    {
+        downloads.tokens.remove(id);
+    }
+    let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+    if let Err(e) = crate::webview2_response(response, app.clone(), active_downloads).await {
+        println!("Failed to register browser download: {}", e);
+    }
+}
+
+// Polls the Downloads folder until the file shows up and stops growing, then hands it to the
+// same completion path as helper downloads (notification, extraction rule, and so on)
+pub fn watch(
+    app: AppHandle,
+    download_id: String,
+    dir: PathBuf,
+    filename: Option<String>,
+    expected_size: Option<u64>,
+    token: CancellationToken,
+) {
+    // Small slack for file systems with coarse timestamps
+    let since = SystemTime::now() - Duration::from_secs(5);
+    tauri::async_runtime::spawn(async move {
+        let started = Instant::now();
+        let mut last_seen: Option<(PathBuf, u64)> = None;
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => return,
+                _ = interval.tick() => {}
+            }
+
+            if started.elapsed() > WATCH_TIMEOUT {
+                let message = format!(
+                    "No matching file appeared in {} within {} minutes",
+                    dir.display(),
+                    WATCH_TIMEOUT.as_secs() / 60
+                );
+                let response = serde_json::json!({
+                    "status": "error",
+                    "message": message,
+                    "downloadId": download_id
+                });
+                finish(&app, response).await;
+                return;
+            }
+
+            let candidate = find_candidate(&dir, filename.as_deref(), expected_size, since);
+            // Same size on two polls in a row means the browser is done writing
+            if let Some((path, size)) = &candidate
+                && *size > 0
+                && last_seen.as_ref() == Some(&(path.clone(), *size))
+            {
+                println!(
+                    "Browser download found for {}: {}",
+                    download_id,
+                    path.display()
+                );
+                let response = serde_json::json!({
+                    "status": "success",
+                    "downloadId": download_id,
+                    "path": path.to_string_lossy(),
+                    "filename": path.file_name().map(|n| n.to_string_lossy().to_string())
+                });
+                finish(&app, response).await;
+                return;
+            }
+            last_seen = candidate;
+        }
+    });
+}
@@ -0,0 +1,291 @@
+use crate::state::{
+    AppState, DownloadedGameInfo, save_active_downloads_to_file, save_state_to_file,
+};
+use crate::{ActiveDownloads, DownloadInfo, archiver, diskio, gamelinks, timeline, undo};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::{Mutex, RwLock};
+use tauri::{AppHandle, Emitter, Manager};
+
+#[derive(Serialize, Clone, Debug)]
+pub struct BulkItemResult {
+    pub id: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+// One "bulk-progress" stream per batch, so the UI can show a single aggregate progress bar
+fn emit_progress(
+    app: &AppHandle,
+    operation: &str,
+    total: usize,
+    results: &[BulkItemResult],
+    current: Option<&str>,
+) {
+    let _ = app.emit(
+        "bulk-progress",
+        &serde_json::json!({
+            "operation": operation,
+            "total": total,
+            "completed": results.len(),
+            "failed": results.iter().filter(|r| !r.success).count(),
+            "currentId": current,
+            "progress": if total == 0 { 100.0 } else { results.len() as f32 / total as f32 * 100.0 },
+            "done": current.is_none(),
+            "results": if current.is_none() { Some(results) } else { None }
+        }),
+    );
+}
+
+async fn run<F, Fut>(
+    app: &AppHandle,
+    operation: &str,
+    ids: Vec<String>,
+    mut op: F,
+) -> Vec<BulkItemResult>
+where
+    F: FnMut(String) -> Fut,
+    Fut: std::future::Future<Output = Result<(), String>>,
+{
+    println!("Bulk {} started for {} items", operation, ids.len());
+    let total = ids.len();
+    let mut results = Vec::with_capacity(total);
+    for id in ids {
+        emit_progress(app, operation, total, &results, Some(&id));
+        let result = op(id.clone()).await;
+        if let Err(e) = &result {
+            println!("Bulk {} failed for {}: {}", operation, id, e);
+        }
+        results.push(BulkItemResult {
+            id,
+            success: result.is_ok(),
+            error: result.err(),
+        });
+    }
+    emit_progress(app, operation, total, &results, None);
+    println!(
+        "Bulk {} finished: {} of {} succeeded",
+        operation,
+        results.iter().filter(|r| r.success).count(),
+        total
+    );
+    results
+}
+
+fn download_path(app: &AppHandle, id: &str) -> Result<String, String> {
+    let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+    let downloads = active_downloads
+        .read()
+        .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+    let download = downloads
+        .downloads
+        .get(id)
+        .ok_or_else(|| format!("Download not found: {}", id))?;
+    download
+        .path
+        .clone()
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| format!("Download {} has no file yet", id))
+}
+
+pub async fn extract(app: &AppHandle, ids: Vec<String>) -> Vec<BulkItemResult> {
+    run(app, "extract", ids, |id| async move {
+        let path = download_path(app, &id)?;
+        let output_dir = format!("{}_extracted", path);
+        crate::extract_download(path, output_dir, id, app.clone(), false).await
+    })
+    .await
+}
+
+// Files go to the trash and the removed entries into `journal`, so the batch can be undone for a while
+async fn delete_one(
+    app: &AppHandle,
+    id: &str,
+    delete_files: bool,
+    journal: &Mutex<undo::JournalEntry>,
+) -> Result<(), String> {
+    let in_progress = {
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        let downloads = active_downloads
+            .read()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        downloads.tokens.contains_key(id)
+    };
+    if in_progress {
+        crate::cancel_active_download(id.to_string(), app.clone()).await?;
+    }
+
+    let removed = {
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        let mut downloads = active_downloads
+            .write()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        let removed = downloads.downloads.remove(id);
+        save_active_downloads_to_file(app, &downloads)?;
+        removed
+    };
+
+    let game = {
+        let state = app.state::<Mutex<AppState>>();
+        let mut app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        let games = app_state.games.get_or_insert_with(Vec::new);
+        let game = games
+            .iter()
+            .position(|g| g.id == id)
+            .map(|i| games.remove(i));
+        save_state_to_file(app, &app_state)?;
+        game
+    };
+
+    if removed.is_none() && game.is_none() {
+        return Err(format!("Download not found: {}", id));
+    }
+
+    let mut journal = journal
+        .lock()
+        .map_err(|e| format!("Failed to lock undo journal: {}", e))?;
+    let paths = if delete_files {
+        paths_of(removed.as_ref(), game.as_ref())
+    } else {
+        Vec::new()
+    };
+    journal.downloads.extend(removed);
+    journal.games.extend(game);
+    for path in paths {
+        undo::trash(&mut journal, Path::new(&path))?;
+    }
+    Ok(())
+}
+
+// Archive and extracted folder of a download and/or its saved game entry
+fn paths_of(download: Option<&DownloadInfo>, game: Option<&DownloadedGameInfo>) -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+    if let Some(download) = download {
+        paths.extend(download.path.clone());
+        paths.extend(download.extracted_path.clone());
+    }
+    if let Some(game) = game {
+        paths.push(game.path.clone());
+        paths.extend(game.extracted_path.clone());
+    }
+    paths.retain(|p| !p.is_empty());
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+// What `delete` would remove, shown to the user before the confirmation token is used
+pub fn delete_summary(
+    app: &AppHandle,
+    ids: &[String],
+    delete_files: bool,
+) -> Result<serde_json::Value, String> {
+    let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+    let downloads = active_downloads
+        .read()
+        .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+    let state = app.state::<Mutex<AppState>>();
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+
+    let items: Vec<serde_json::Value> = ids
+        .iter()
+        .map(|id| {
+            let download = downloads.downloads.get(id);
+            let game = app_state
+                .games
+                .as_ref()
+                .and_then(|games| games.iter().find(|g| &g.id == id));
+            let name = game
+                .map(gamelinks::display_name)
+                .or_else(|| download.map(|d| d.filename.clone()));
+            let paths = if delete_files {
+                paths_of(download, game)
+            } else {
+                Vec::new()
+            };
+            serde_json::json!({
+                "id": id,
+                "name": name,
+                "found": download.is_some() || game.is_some(),
+                "paths": paths
+            })
+        })
+        .collect();
+    Ok(serde_json::json!({
+        "deleteFiles": delete_files,
+        "items": items
+    }))
+}
+
+pub async fn delete(app: &AppHandle, ids: Vec<String>, delete_files: bool) -> Vec<BulkItemResult> {
+    let journal = Mutex::new(undo::new_entry("delete_downloads"));
+    let results = run(app, "delete", ids, |id| {
+        let journal = &journal;
+        async move { delete_one(app, &id, delete_files, journal).await }
+    })
+    .await;
+    if let Ok(journal) = journal.into_inner() {
+        undo::record(app, journal);
+    }
+
+    if let Ok(app_state) = app.state::<Mutex<AppState>>().lock()
+        && let Err(e) = gamelinks::sync(&app_state)
+    {
+        println!("Failed to update games folder links: {}", e);
+    }
+    results
+}
+
+// Re-checks that the file is still there, complete and readable, and that the extracted folder still exists
+fn verify_one(app: &AppHandle, id: &str) -> Result<(), String> {
+    let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+    let mut downloads = active_downloads
+        .write()
+        .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+    let download = downloads
+        .downloads
+        .get_mut(id)
+        .ok_or_else(|| format!("Download not found: {}", id))?;
+
+    let mut problem = None;
+    if let Some(extracted_path) = download.extracted_path.clone()
+        && !Path::new(&extracted_path).exists()
+    {
+        download.extracted = false;
+        download.extracted_path = None;
+        download.extraction_status = Some("idle".to_string());
+        download.extraction_progress = Some(0.0);
+        problem = Some(format!("Extracted folder is missing: {}", extracted_path));
+        timeline::record(app, id, "extraction", "idle", "verify", problem.as_deref());
+    }
+
+    match download.path.clone().filter(|p| !p.is_empty()) {
+        None => problem = problem.or(Some("Download has no file yet".to_string())),
+        Some(path) => {
+            if let Err(e) = diskio::ensure_not_partial(&path) {
+                problem = Some(e);
+            } else if !Path::new(&path).exists() {
+                problem = Some(format!("File is missing: {}", path));
+            } else if archiver::is_supported_archive(&path) {
+                match archiver::estimate_uncompressed_size(&path) {
+                    Ok(size) => download.uncompressed_size = Some(size),
+                    Err(e) => problem = Some(format!("Archive is unreadable: {}", e)),
+                }
+            }
+        }
+    }
+
+    save_active_downloads_to_file(app, &downloads)?;
+    match problem {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+pub async fn verify(app: &AppHandle, ids: Vec<String>) -> Vec<BulkItemResult> {
+    run(app, "verify", ids, |id| async move { verify_one(app, &id) }).await
+}
@@ -0,0 +1,302 @@
+use crate::httpconfig;
+use crate::state::{AppState, save_state_to_file};
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+pub const CACHE_KINDS: &[&str] = &[
+    "icons", "covers", "articles", "images", "sounds", "logs", "temp",
+];
+
+// Freshly extracted icons are not referenced until the launch config is saved, so GC leaves them alone for a while
+const ICON_GC_GRACE: Duration = Duration::from_secs(10 * 60);
+
+const DEFAULT_ICON_NAME: &str = "default.png";
+
+// Edge length of icons generated from cover art
+const COVER_ICON_SIZE: u32 = 256;
+
+// Sizes Windows picks from for shortcuts, Explorer views and the taskbar
+const ICO_SIZES: &[u32] = &[16, 24, 32, 48, 64, 128, 256];
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CacheCategory {
+    pub kind: String,
+    pub path: String,
+    pub bytes: u64,
+    pub files: u64,
+}
+
+// Where each kind of disposable app data lives
+pub fn cache_dir(app: &AppHandle, kind: &str) -> Option<PathBuf> {
+    let path = app.path();
+    match kind {
+        "icons" => path.app_data_dir().ok().map(|d| d.join("icons")),
+        "covers" => path.app_cache_dir().ok().map(|d| d.join("covers")),
+        "articles" => path.app_cache_dir().ok().map(|d| d.join("articles")),
+        // Images inlined into rendered articles
+        "images" => path.app_cache_dir().ok().map(|d| d.join("images")),
+        // Generated built-in sound cues
+        "sounds" => path.app_cache_dir().ok().map(|d| d.join("sounds")),
+        "logs" => path.app_log_dir().ok(),
+        // Staging area for extractions in progress
+        "temp" => Some(std::env::temp_dir().join("chanomhub")),
+        _ => None,
+    }
+}
+
+fn dir_usage(dir: &Path) -> (u64, u64) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return (0, 0);
+    };
+    let mut bytes = 0;
+    let mut files = 0;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let Ok(metadata) = fs::symlink_metadata(entry.path()) else {
+            continue;
+        };
+        if metadata.is_dir() {
+            let (b, f) = dir_usage(&entry.path());
+            bytes += b;
+            files += f;
+        } else if metadata.is_file() {
+            bytes += metadata.len();
+            files += 1;
+        }
+    }
+    (bytes, files)
+}
+
+pub fn get_cache_stats(app: &AppHandle) -> Vec<CacheCategory> {
+    CACHE_KINDS
+        .iter()
+        .filter_map(|kind| {
+            let dir = cache_dir(app, kind)?;
+            let (bytes, files) = dir_usage(&dir);
+            Some(CacheCategory {
+                kind: kind.to_string(),
+                path: dir.to_string_lossy().to_string(),
+                bytes,
+                files,
+            })
+        })
+        .collect()
+}
+
+// Icons are referenced from the library, so games pointing into the folder fall back to the default icon
+fn forget_icons(app: &AppHandle, icons_dir: &Path) -> Result<(), String> {
+    let state = app.state::<Mutex<AppState>>();
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    let mut changed = false;
+    for game in app_state.games.iter_mut().flatten() {
+        if game
+            .icon_path
+            .as_ref()
+            .is_some_and(|p| Path::new(p).starts_with(icons_dir))
+        {
+            game.icon_path = None;
+            changed = true;
+        }
+    }
+    if changed {
+        save_state_to_file(app, &app_state)?;
+    }
+    Ok(())
+}
+
+// Deletes the contents of the given cache kinds and returns the number of bytes freed
+pub fn clear_cache(app: &AppHandle, kinds: &[String]) -> Result<u64, String> {
+    let mut freed = 0;
+    for kind in kinds {
+        let dir = cache_dir(app, kind).ok_or_else(|| format!("Unknown cache kind: {}", kind))?;
+        if !dir.exists() {
+            continue;
+        }
+
+        let entries =
+            fs::read_dir(&dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let (bytes, _) = if path.is_dir() {
+                dir_usage(&path)
+            } else {
+                (entry.metadata().map(|m| m.len()).unwrap_or(0), 1)
+            };
+            let result = if path.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            match result {
+                Ok(()) => freed += bytes,
+                // Files held open (e.g. the current log) are skipped
+                Err(e) => println!("Failed to remove {}: {}", path.display(), e),
+            }
+        }
+
+        if kind == "icons" {
+            forget_icons(app, &dir)?;
+        }
+        println!("Cleared {} cache at {}", kind, dir.display());
+    }
+    Ok(freed)
+}
+
+fn icons_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = cache_dir(app, "icons").ok_or("Failed to get app data dir")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create icons dir: {}", e))?;
+    Ok(dir)
+}
+
+// Icons are named after the executable's SHA-1, so extracting the same game again reuses the file
+pub fn icon_path_for(app: &AppHandle, executable_path: &str) -> Result<PathBuf, String> {
+    let mut file =
+        File::open(executable_path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut hasher = Sha1::new();
+    io::copy(&mut file, &mut hasher).map_err(|e| format!("Failed to hash executable: {}", e))?;
+    let hash = hex::encode(hasher.finalize());
+    Ok(icons_dir(app)?.join(format!("{}.png", hash)))
+}
+
+// Shared copy of the bundled fallback icon
+pub fn default_icon_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(icons_dir(app)?.join(DEFAULT_ICON_NAME))
+}
+
+// Builds a square icon from the article cover, for executables without a usable icon (e.g. packed exes)
+pub async fn cover_icon(app: &AppHandle, cover_url: &str) -> Result<PathBuf, String> {
+    let icon_path = icons_dir(app)?.join(format!(
+        "cover-{}.png",
+        hex::encode(Sha1::digest(cover_url.as_bytes()))
+    ));
+    if icon_path.exists() {
+        return Ok(icon_path);
+    }
+
+    let settings = httpconfig::settings(app);
+    let client = httpconfig::client_builder(&settings)
+        .build()
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+    let response = httpconfig::apply(&settings, client.get(cover_url), cover_url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download cover: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Cover download failed: {}", response.status()));
+    }
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to download cover: {}", e))?;
+    let cover =
+        image::load_from_memory(&bytes).map_err(|e| format!("Failed to decode cover: {}", e))?;
+
+    // Covers are wide banners, so the middle is kept rather than squashing the whole image
+    let side = cover.width().min(cover.height());
+    let x = (cover.width() - side) / 2;
+    let y = (cover.height() - side) / 2;
+    cover
+        .crop_imm(x, y, side, side)
+        .resize_exact(
+            COVER_ICON_SIZE,
+            COVER_ICON_SIZE,
+            image::imageops::FilterType::Lanczos3,
+        )
+        .save_with_format(&icon_path, image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to save icon: {}", e))?;
+    println!("Created icon from cover art: {:?}", icon_path);
+    Ok(icon_path)
+}
+
+// Multi-size .ico next to a PNG icon, so shortcuts don't rely on Windows downscaling a single 256px image
+pub fn ico_from_png(image_path: &str) -> Result<PathBuf, String> {
+    let source = Path::new(image_path);
+    let ico_path = source.with_extension("ico");
+    let up_to_date = match (fs::metadata(source), fs::metadata(&ico_path)) {
+        (Ok(src), Ok(ico)) => matches!((src.modified(), ico.modified()), (Ok(s), Ok(i)) if i >= s),
+        _ => false,
+    };
+    if up_to_date {
+        return Ok(ico_path);
+    }
+
+    let image = image::open(source).map_err(|e| format!("Failed to open image: {}", e))?;
+    // Non-square art is padded rather than stretched
+    let side = image.width().max(image.height());
+    let mut square = image::RgbaImage::new(side, side);
+    image::imageops::overlay(
+        &mut square,
+        &image.to_rgba8(),
+        i64::from((side - image.width()) / 2),
+        i64::from((side - image.height()) / 2),
+    );
+
+    let mut icon_dir = ico::IconDir::new(ico::ResourceType::Icon);
+    for size in ICO_SIZES {
+        let resized =
+            image::imageops::resize(&square, *size, *size, image::imageops::FilterType::Lanczos3);
+        let icon_image = ico::IconImage::from_rgba_data(*size, *size, resized.into_raw());
+        let entry = ico::IconDirEntry::encode(&icon_image)
+            .map_err(|e| format!("Failed to encode {}px icon: {}", size, e))?;
+        icon_dir.add_entry(entry);
+    }
+
+    let file = File::create(&ico_path).map_err(|e| format!("Failed to create ico file: {}", e))?;
+    icon_dir
+        .write(file)
+        .map_err(|e| format!("Failed to write ico file: {}", e))?;
+    println!("Generated {:?}", ico_path);
+    Ok(ico_path)
+}
+
+// Removes icons that no game references anymore
+pub fn gc_icons(app: &AppHandle, app_state: &AppState) -> Result<usize, String> {
+    let Some(dir) = cache_dir(app, "icons") else {
+        return Ok(0);
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(0);
+    };
+
+    let referenced: HashSet<PathBuf> = app_state
+        .games
+        .iter()
+        .flatten()
+        .filter_map(|g| g.icon_path.as_ref())
+        .map(PathBuf::from)
+        .flat_map(|png| [png.with_extension("ico"), png])
+        .collect();
+
+    let mut removed = 0;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if !path.is_file() || referenced.contains(&path) {
+            continue;
+        }
+        let recent = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_none_or(|age| age < ICON_GC_GRACE);
+        if recent {
+            continue;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => removed += 1,
+            Err(e) => println!("Failed to remove unused icon {}: {}", path.display(), e),
+        }
+    }
+    if removed > 0 {
+        println!("Removed {} unused icons from {}", removed, dir.display());
+    }
+    Ok(removed)
+}
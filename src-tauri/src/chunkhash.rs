@@ -0,0 +1,234 @@
+use crate::state::{self, AppState};
+use crate::timeline;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+
+const MANIFEST_DIR: &str = "chunk_manifests";
+
+// Below this a manifest would hold more hashes than it saves in re-downloading
+const MIN_CHUNK_SIZE: u64 = 1024 * 1024;
+
+// SHA-256 of every `chunkSize` bytes of the file, in order; the last chunk may be shorter
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkManifest {
+    pub chunk_size: u64,
+    pub chunks: Vec<String>,
+}
+
+impl ChunkManifest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.chunk_size < MIN_CHUNK_SIZE {
+            return Err(format!(
+                "Chunk size must be at least {} bytes",
+                MIN_CHUNK_SIZE
+            ));
+        }
+        if self.chunks.is_empty() {
+            return Err("Chunk manifest has no chunks".to_string());
+        }
+        if let Some(bad) = self
+            .chunks
+            .iter()
+            .position(|h| h.len() != 64 || hex::decode(h).is_err())
+        {
+            return Err(format!("Chunk {} is not a SHA-256 hash", bad + 1));
+        }
+        Ok(())
+    }
+
+    fn chunk_start(&self, index: usize) -> u64 {
+        index as u64 * self.chunk_size
+    }
+}
+
+// A chunk whose bytes did not match the manifest; everything from `start` is fetched again
+#[derive(Debug)]
+pub struct CorruptChunk {
+    pub index: usize,
+    pub start: u64,
+}
+
+impl CorruptChunk {
+    pub fn message(&self) -> String {
+        format!(
+            "Chunk {} (from byte {}) failed verification",
+            self.index + 1,
+            self.start
+        )
+    }
+}
+
+fn manifest_path(app: &AppHandle, download_id: &str) -> Option<PathBuf> {
+    let name: String = download_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let dir = state::get_config_dir(app)?.join(MANIFEST_DIR);
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join(format!("{}.json", name)))
+}
+
+// Kept on disk by download id, so the manifest survives queueing, restarts and mirror switches
+pub fn save(app: &AppHandle, download_id: &str, manifest: &ChunkManifest) -> Result<(), String> {
+    manifest.validate()?;
+    let path = manifest_path(app, download_id).ok_or("Failed to get config dir")?;
+    let json = serde_json::to_string(manifest)
+        .map_err(|e| format!("Failed to serialize chunk manifest: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to save chunk manifest: {}", e))
+}
+
+pub fn load(app: &AppHandle, download_id: &str) -> Option<ChunkManifest> {
+    let content = fs::read_to_string(manifest_path(app, download_id)?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn forget(app: &AppHandle, download_id: &str) {
+    if let Some(path) = manifest_path(app, download_id) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+fn enabled(app: &AppHandle) -> bool {
+    app.state::<Mutex<AppState>>()
+        .lock()
+        .map(|app_state| app_state.download_settings.verify_chunks)
+        .unwrap_or(true)
+}
+
+// Manifest to check the transfer against, when there is one and checking is turned on
+pub fn for_download(app: &AppHandle, download_id: &str) -> Option<Arc<ChunkManifest>> {
+    if !enabled(app) {
+        return None;
+    }
+    load(app, download_id).map(Arc::new)
+}
+
+// Segment size rounded up to whole chunks, so every chunk is written by a single segment
+pub fn align(manifest: Option<&ChunkManifest>, size: u64) -> u64 {
+    match manifest {
+        Some(manifest) => size.div_ceil(manifest.chunk_size) * manifest.chunk_size,
+        None => size,
+    }
+}
+
+// Hashes bytes as they are written to one range of the file and checks each completed chunk
+pub struct ChunkVerifier {
+    manifest: Arc<ChunkManifest>,
+    index: usize,
+    // None while inside a chunk that started before this range and cannot be checked from here
+    hasher: Option<Sha256>,
+    pos: u64,
+}
+
+impl ChunkVerifier {
+    // Starts at `from` inside the range beginning at `range_start`. Bytes of the current chunk
+    // already on disk are read back so the chunk can still be checked as a whole.
+    pub fn resume(
+        manifest: Arc<ChunkManifest>,
+        part_path: &Path,
+        range_start: u64,
+        from: u64,
+    ) -> Result<Self, String> {
+        let index = (from / manifest.chunk_size) as usize;
+        let chunk_start = manifest.chunk_start(index);
+        let mut verifier = Self {
+            manifest,
+            index,
+            hasher: None,
+            pos: from,
+        };
+        if chunk_start < range_start {
+            return Ok(verifier);
+        }
+        let mut hasher = Sha256::new();
+        if from > chunk_start {
+            let mut file = File::open(part_path)
+                .map_err(|e| format!("Failed to open {}: {}", part_path.display(), e))?;
+            file.seek(SeekFrom::Start(chunk_start))
+                .map_err(|e| format!("Failed to seek in {}: {}", part_path.display(), e))?;
+            let mut head = file.take(from - chunk_start);
+            std::io::copy(&mut head, &mut hasher)
+                .map_err(|e| format!("Failed to read {}: {}", part_path.display(), e))?;
+        }
+        verifier.hasher = Some(hasher);
+        Ok(verifier)
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) -> Result<(), CorruptChunk> {
+        while !data.is_empty() {
+            let chunk_end = self.manifest.chunk_start(self.index + 1);
+            let take = data.len().min((chunk_end - self.pos) as usize);
+            if let Some(hasher) = &mut self.hasher {
+                hasher.update(&data[..take]);
+            }
+            self.pos += take as u64;
+            data = &data[take..];
+            if self.pos == chunk_end {
+                self.check()?;
+                self.index += 1;
+                self.hasher = Some(Sha256::new());
+            }
+        }
+        Ok(())
+    }
+
+    // Checks the file's last chunk, which ends with the file rather than on a chunk boundary
+    pub fn finish(&mut self) -> Result<(), CorruptChunk> {
+        if self.index + 1 == self.manifest.chunks.len()
+            && self.pos > self.manifest.chunk_start(self.index)
+        {
+            self.check()?;
+            self.hasher = None;
+        }
+        Ok(())
+    }
+
+    fn check(&mut self) -> Result<(), CorruptChunk> {
+        let Some(hasher) = self.hasher.take() else {
+            return Ok(());
+        };
+        // Past the end of the manifest there is nothing to compare with
+        let Some(expected) = self.manifest.chunks.get(self.index) else {
+            return Ok(());
+        };
+        if hex::encode(hasher.finalize()).eq_ignore_ascii_case(expected) {
+            return Ok(());
+        }
+        Err(CorruptChunk {
+            index: self.index,
+            start: self.manifest.chunk_start(self.index),
+        })
+    }
+}
+
+pub fn report_corrupt(app: &AppHandle, download_id: &str, chunk: &CorruptChunk) {
+    println!("Download {}: {}", download_id, chunk.message());
+    timeline::record(
+        app,
+        download_id,
+        "event",
+        "chunk-corrupt",
+        "native",
+        Some(&chunk.message()),
+    );
+    let _ = app.emit(
+        "download-chunk-corrupt",
+        &serde_json::json!({
+            "id": download_id,
+            "chunk": chunk.index,
+            "offset": chunk.start
+        }),
+    );
+}
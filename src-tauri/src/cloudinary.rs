@@ -2,7 +2,7 @@ use crate::state::CloudinaryConfig;
 use chrono;
 use hex;
 use image::ImageReader;
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
 use std::collections::HashMap;
@@ -10,6 +10,7 @@ use std::fs;
 use std::io::Cursor;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
 use tokio::time;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -226,18 +227,36 @@ pub async fn upload_to_cloudinary(
         form = form.text("public_id", id.clone());
     }
 
-    let timestamp = chrono::Utc::now().timestamp().to_string();
-    form = form
-        .text("api_key", cloudinary_config.api_key.clone())
-        .text("timestamp", timestamp.clone())
-        .text(
-            "signature",
-            generate_signature(
-                &cloudinary_config.api_secret,
-                &public_id.unwrap_or_default(),
-                &timestamp,
-            ),
-        );
+    let upload_preset = cloudinary_config
+        .upload_preset
+        .clone()
+        .filter(|p| !p.trim().is_empty());
+    if let Some(preset) = &upload_preset {
+        form = form.text("upload_preset", preset.clone());
+    }
+
+    // preset แบบ unsigned ไม่ต้องใช้ api_secret เลย จึงไม่ต้องเก็บ secret ไว้ในเครื่อง
+    if cloudinary_config.api_secret.is_empty() {
+        if upload_preset.is_none() {
+            return Err("Cloudinary needs either an API secret or an upload preset".to_string());
+        }
+    } else {
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        form = form
+            .text("api_key", cloudinary_config.api_key.clone())
+            .text("timestamp", timestamp.clone())
+            .text(
+                "signature",
+                generate_signature(
+                    &cloudinary_config.api_secret,
+                    &[
+                        ("public_id", &public_id.unwrap_or_default()),
+                        ("upload_preset", &upload_preset.unwrap_or_default()),
+                        ("timestamp", &timestamp),
+                    ],
+                ),
+            );
+    }
 
     let response = client
         .post(format!(
@@ -294,15 +313,13 @@ fn convert_gif_to_webp(input_path: &str) -> Result<String, String> {
     Ok(output_path)
 }
 
-fn generate_signature(api_secret: &str, public_id: &str, timestamp: &str) -> String {
-    let mut signature_params = Vec::new();
-
-    // Only add public_id if it's not empty
-    if !public_id.is_empty() {
-        signature_params.push(format!("public_id={}", public_id));
-    }
-
-    signature_params.push(format!("timestamp={}", timestamp));
+// ใช้กับทุกพารามิเตอร์ที่ส่งไป (ยกเว้น file, api_key) ค่าว่างไม่นับรวม
+fn generate_signature(api_secret: &str, params: &[(&str, &str)]) -> String {
+    let mut signature_params: Vec<String> = params
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
 
     // Sort parameters alphabetically as per Cloudinary requirements
     signature_params.sort();
@@ -316,4 +333,341 @@ fn generate_signature(api_secret: &str, public_id: &str, timestamp: &str) -> Str
     let result = hasher.finalize();
 
     hex::encode(result)
-}
\ No newline at end of file
+}
+
+// ขนาดย่อสำหรับหน้ารวมรูป: กว้างไม่เกิน 480px ให้ Cloudinary เลือกฟอร์แมตและคุณภาพเอง
+const THUMBNAIL_TRANSFORMATION: &str = "c_limit,w_480,q_auto,f_auto";
+
+// รูปย่อไม่ต้องอัปโหลดซ้ำ ใส่ transformation ใน URL แล้ว Cloudinary สร้างให้ตอนเรียกครั้งแรก
+pub fn thumbnail_url(url: &str) -> Option<String> {
+    let (base, path) = url.split_once("/image/upload/")?;
+    Some(format!(
+        "{}/image/upload/{}/{}",
+        base, THUMBNAIL_TRANSFORMATION, path
+    ))
+}
+
+// รายการไฟล์ที่อัปโหลดไว้ (Admin API) ใช้จัดการไฟล์เก่าจากในแอปแทนหน้าเว็บ Cloudinary
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UploadedAsset {
+    pub public_id: String,
+    pub secure_url: String,
+    #[serde(default)]
+    pub format: Option<String>,
+    #[serde(default)]
+    pub bytes: u64,
+    #[serde(default)]
+    pub width: Option<u32>,
+    #[serde(default)]
+    pub height: Option<u32>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UploadPage {
+    #[serde(default)]
+    pub resources: Vec<UploadedAsset>,
+    // ส่งกลับมาเป็น page ถัดไป ไม่มีแปลว่าหน้าสุดท้าย
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+const UPLOADS_PER_PAGE: u32 = 50;
+
+fn admin_url(cloudinary_config: &CloudinaryConfig) -> Result<String, String> {
+    // Admin API ใช้ได้เฉพาะเมื่อมี key และ secret ใช้ preset อย่างเดียวไม่พอ
+    if cloudinary_config.api_key.is_empty() || cloudinary_config.api_secret.is_empty() {
+        return Err("Managing uploads needs the Cloudinary API key and secret".to_string());
+    }
+    Ok(format!(
+        "https://api.cloudinary.com/v1_1/{}/resources/image/upload",
+        cloudinary_config.cloud_name
+    ))
+}
+
+// Admin API ตอบ error เป็น {"error": {"message": ...}}
+async fn admin_error(response: reqwest::Response) -> String {
+    let status = response.status();
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    let message = body
+        .get("error")
+        .and_then(|e| e.get("message"))
+        .and_then(|m| m.as_str())
+        .unwrap_or("unknown error");
+    format!("Cloudinary request failed ({}): {}", status, message)
+}
+
+// page คือ next_cursor จากหน้าก่อนหน้า
+pub async fn list_uploads(
+    prefix: Option<String>,
+    page: Option<String>,
+    client: &Client,
+    cloudinary_config: &CloudinaryConfig,
+) -> Result<UploadPage, String> {
+    let mut query = vec![("max_results", UPLOADS_PER_PAGE.to_string())];
+    if let Some(prefix) = prefix.filter(|p| !p.is_empty()) {
+        query.push(("prefix", prefix));
+    }
+    if let Some(cursor) = page.filter(|c| !c.is_empty()) {
+        query.push(("next_cursor", cursor));
+    }
+    let response = client
+        .get(admin_url(cloudinary_config)?)
+        .basic_auth(
+            &cloudinary_config.api_key,
+            Some(&cloudinary_config.api_secret),
+        )
+        .query(&query)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    if !response.status().is_success() {
+        return Err(admin_error(response).await);
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))
+}
+
+pub async fn delete_upload(
+    public_id: String,
+    client: &Client,
+    cloudinary_config: &CloudinaryConfig,
+) -> Result<(), String> {
+    let response = client
+        .delete(admin_url(cloudinary_config)?)
+        .basic_auth(
+            &cloudinary_config.api_key,
+            Some(&cloudinary_config.api_secret),
+        )
+        .query(&[("public_ids[]", &public_id)])
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    if !response.status().is_success() {
+        return Err(admin_error(response).await);
+    }
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    // ตอบ 200 เสมอ ผลจริงอยู่ใน deleted: {public_id: "deleted" | "not_found"}
+    match body
+        .get("deleted")
+        .and_then(|d| d.get(&public_id))
+        .and_then(|r| r.as_str())
+    {
+        Some("deleted") => {}
+        Some(result) => return Err(format!("Failed to delete {}: {}", public_id, result)),
+        None => {
+            return Err(format!(
+                "Failed to delete {}: unexpected response",
+                public_id
+            ));
+        }
+    }
+
+    // ไม่ให้แคชคืน URL ของไฟล์ที่ลบไปแล้วถ้าอัปโหลดไฟล์เดิมอีกครั้ง
+    if let Ok(mut cache) = IMAGE_CACHE.lock() {
+        let marker = format!("/{}.", public_id);
+        cache.retain(|_, entry| !entry.url.contains(&marker));
+    }
+    println!("Deleted Cloudinary asset: {}", public_id);
+    Ok(())
+}
+
+// วิดีโอส่งเป็นชิ้น Cloudinary รับชิ้นละไม่ต่ำกว่า 5 MB ยกเว้นชิ้นสุดท้าย
+const VIDEO_CHUNK_SIZE: u64 = 20 * 1024 * 1024;
+// แปลงเป็น mp4 h264 ที่เล่นได้ทุกเบราว์เซอร์ ใช้ทั้งเป็น eager และใน URL ของ trailer
+const TRAILER_TRANSFORMATION: &str = "vc_h264,q_auto";
+const TRANSCODE_POLL_INTERVAL: Duration = Duration::from_secs(10);
+const TRANSCODE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Serialize, Clone, Debug)]
+pub struct VideoUpload {
+    pub upload_id: String,
+    pub public_id: String,
+    // ไฟล์ต้นฉบับ ใช้ได้ทันที
+    pub secure_url: String,
+    // ไฟล์ที่แปลงแล้ว ใช้ได้เมื่อมี event "video-ready"
+    pub trailer_url: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct VideoUploadResponse {
+    secure_url: String,
+    public_id: String,
+}
+
+fn video_form(
+    chunk: Vec<u8>,
+    file_name: &str,
+    params: &[(&str, &str)],
+    cloudinary_config: &CloudinaryConfig,
+) -> Result<reqwest::multipart::Form, String> {
+    let file_part = reqwest::multipart::Part::bytes(chunk)
+        .file_name(file_name.to_string())
+        .mime_str("application/octet-stream")
+        .map_err(|e| format!("Failed to set MIME type: {}", e))?;
+    let mut form = reqwest::multipart::Form::new().part("file", file_part);
+    for (key, value) in params.iter().filter(|(_, v)| !v.is_empty()) {
+        form = form.text(key.to_string(), value.to_string());
+    }
+    if !cloudinary_config.api_secret.is_empty() {
+        form = form
+            .text("api_key", cloudinary_config.api_key.clone())
+            .text(
+                "signature",
+                generate_signature(&cloudinary_config.api_secret, params),
+            );
+    }
+    Ok(form)
+}
+
+// อัปโหลดวิดีโอทีละชิ้น (กลับมาต่อฝั่ง Cloudinary ด้วย X-Unique-Upload-Id) แล้วรอการแปลงไฟล์ในเบื้องหลัง
+pub async fn upload_video(
+    app: &AppHandle,
+    file_path: String,
+    public_id: Option<String>,
+    cloudinary_config: &CloudinaryConfig,
+) -> Result<VideoUpload, String> {
+    let upload_preset = cloudinary_config
+        .upload_preset
+        .clone()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_default();
+    let signed = !cloudinary_config.api_secret.is_empty();
+    if !signed && upload_preset.is_empty() {
+        return Err("Cloudinary needs either an API secret or an upload preset".to_string());
+    }
+
+    let total = fs::metadata(&file_path)
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+    if total == 0 {
+        return Err("Video file is empty".to_string());
+    }
+    let file_name = std::path::Path::new(&file_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "video".to_string());
+    let timestamp = chrono::Utc::now().timestamp().to_string();
+    let upload_id = format!(
+        "{}-{}",
+        timestamp,
+        &hex::encode(Sha1::digest(file_path.as_bytes()))[..8]
+    );
+
+    // preset แบบ unsigned สั่ง eager ไม่ได้ trailer จะถูกแปลงตอนเรียก URL ครั้งแรกแทน
+    let eager = format!("{}/mp4", TRAILER_TRANSFORMATION);
+    let public_id = public_id.unwrap_or_default();
+    let mut params: Vec<(&str, &str)> =
+        vec![("public_id", &public_id), ("upload_preset", &upload_preset)];
+    if signed {
+        params.extend([
+            ("eager", eager.as_str()),
+            ("eager_async", "true"),
+            ("timestamp", &timestamp),
+        ]);
+    }
+
+    let client = Client::new();
+    let url = format!(
+        "https://api.cloudinary.com/v1_1/{}/video/upload",
+        cloudinary_config.cloud_name
+    );
+    let mut file =
+        fs::File::open(&file_path).map_err(|e| format!("Failed to open video: {}", e))?;
+    let mut offset = 0;
+    let mut result = None;
+    while offset < total {
+        let size = VIDEO_CHUNK_SIZE.min(total - offset);
+        let mut chunk = vec![0u8; size as usize];
+        std::io::Read::read_exact(&mut file, &mut chunk)
+            .map_err(|e| format!("Failed to read video: {}", e))?;
+        let response = client
+            .post(&url)
+            .header("X-Unique-Upload-Id", &upload_id)
+            .header(
+                "Content-Range",
+                format!("bytes {}-{}/{}", offset, offset + size - 1, total),
+            )
+            .multipart(video_form(chunk, &file_name, &params, cloudinary_config)?)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request: {}", e))?;
+        if !response.status().is_success() {
+            return Err(admin_error(response).await);
+        }
+        offset += size;
+        let _ = app.emit(
+            "video-upload-progress",
+            &serde_json::json!({
+                "uploadId": upload_id,
+                "sent": offset,
+                "total": total
+            }),
+        );
+        // มีแค่คำตอบของชิ้นสุดท้ายที่เป็นข้อมูลไฟล์
+        if offset == total {
+            result = Some(
+                response
+                    .json::<VideoUploadResponse>()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?,
+            );
+        }
+    }
+    let result = result.ok_or("Cloudinary did not confirm the upload")?;
+    println!("Uploaded video {} to {}", file_path, result.secure_url);
+
+    let upload = VideoUpload {
+        upload_id,
+        trailer_url: format!(
+            "https://res.cloudinary.com/{}/video/upload/{}/{}.mp4",
+            cloudinary_config.cloud_name, TRAILER_TRANSFORMATION, result.public_id
+        ),
+        public_id: result.public_id,
+        secure_url: result.secure_url,
+    };
+    tauri::async_runtime::spawn(watch_transcode(app.clone(), upload.clone()));
+    Ok(upload)
+}
+
+// Cloudinary ตอบ 423 ระหว่างที่ยังแปลงไฟล์อยู่ ได้ 200 เมื่อ trailer พร้อมใช้
+async fn watch_transcode(app: AppHandle, upload: VideoUpload) {
+    let client = Client::new();
+    let started = Instant::now();
+    let error = loop {
+        if started.elapsed() > TRANSCODE_TIMEOUT {
+            break "Transcoding did not finish in time".to_string();
+        }
+        match client.head(&upload.trailer_url).send().await {
+            Ok(response) if response.status().is_success() => {
+                println!("Trailer ready: {}", upload.trailer_url);
+                let _ = app.emit("video-ready", &upload);
+                return;
+            }
+            Ok(response)
+                if matches!(
+                    response.status(),
+                    StatusCode::LOCKED | StatusCode::NOT_FOUND
+                ) => {}
+            Ok(response) => break format!("Transcoding failed: {}", response.status()),
+            // เน็ตหลุดชั่วคราวไม่ถือว่าแปลงไฟล์ล้มเหลว
+            Err(e) => println!("Failed to check trailer status: {}", e),
+        }
+        time::sleep(TRANSCODE_POLL_INTERVAL).await;
+    };
+    println!("Trailer {} failed: {}", upload.public_id, error);
+    let _ = app.emit(
+        "video-failed",
+        &serde_json::json!({
+            "uploadId": upload.upload_id,
+            "publicId": upload.public_id,
+            "error": error
+        }),
+    );
+}
@@ -0,0 +1,261 @@
+use crate::archivetools;
+use crate::launchguard::{LaunchCheck, LaunchCheckReport};
+use crate::state::LaunchConfig;
+use std::path::Path;
+
+// Handled by the shell itself, so there is nothing to find in PATH
+const SHELL_BUILTINS: &[&str] = &[
+    "cd", "export", "exec", "set", "unset", "source", ".", "true", "false", "echo", "test", "[",
+    "time", "wait", "eval", "read", "trap", "umask", "ulimit", // cmd.exe
+    "start", "call", "pushd", "popd", "rem", "if", "for", "exit", "chdir",
+];
+
+// Run the rest of the command line, so the word after them is a program too
+const WRAPPERS: &[&str] = &[
+    "env",
+    "exec",
+    "nohup",
+    "time",
+    "gamemoderun",
+    "mangohud",
+    "prime-run",
+];
+
+const SEPARATORS: &[&str] = &[";", "&&", "||", "|", "&"];
+
+// (pattern, what it does); matched against the raw command
+const DANGEROUS_PATTERNS: &[(&str, &str)] = &[
+    (
+        r"\brm\s+-[a-zA-Z]*(r[a-zA-Z]*f|f[a-zA-Z]*r)[a-zA-Z]*\s+(/|~|\$HOME)(\s|/?\*|$)",
+        "recursively deletes the root or home folder",
+    ),
+    (r"\bmkfs(\.\w+)?\b", "formats a file system"),
+    (r"\bdd\b.*\bof=/dev/", "writes directly to a device"),
+    (r">\s*/dev/(sd|nvme|hd|mmcblk)", "overwrites a disk device"),
+    (
+        r"\b(curl|wget)\b[^|]*\|\s*(sudo\s+)?(ba|z|da)?sh\b",
+        "runs a script downloaded from the internet",
+    ),
+    (r"\b(sudo|pkexec|doas)\b", "asks for administrator rights"),
+    (r":\(\)\s*\{", "looks like a fork bomb"),
+    (
+        r"\bchmod\s+-R\s+0?777\s+/(\s|$)",
+        "makes the whole system writable by everyone",
+    ),
+    (r"(?i)\bformat\s+[a-z]:", "formats a drive"),
+    (
+        r"(?i)\b(rd|rmdir|del)\s+(/\w\s+)*[a-z]:\\?\*?(\s|$)",
+        "deletes a whole drive",
+    ),
+];
+
+fn check(check: &str, severity: &str, message: String) -> LaunchCheck {
+    LaunchCheck {
+        check: check.to_string(),
+        severity: severity.to_string(),
+        message,
+        details: None,
+    }
+}
+
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+// Programs the command line starts: the first word of every pipeline stage, and what wrappers run
+fn programs(words: &[String]) -> Vec<String> {
+    let mut programs = Vec::new();
+    let mut expect_program = true;
+    for word in words {
+        let (word, ends_stage) = match word.strip_suffix(';') {
+            Some(stripped) => (stripped, true),
+            None => (word.as_str(), false),
+        };
+        if SEPARATORS.contains(&word) {
+            expect_program = true;
+            continue;
+        }
+        if expect_program && !word.is_empty() && !is_assignment(word) && !word.starts_with('-') {
+            programs.push(word.to_string());
+            expect_program = WRAPPERS.contains(&word);
+        }
+        if ends_stage {
+            expect_program = true;
+        }
+    }
+    programs
+}
+
+fn check_program(stage: &str, program: &str) -> Option<LaunchCheck> {
+    if SHELL_BUILTINS.contains(&program) || program.contains('$') {
+        return None;
+    }
+    if program.contains('/') || program.contains('\\') {
+        return (!Path::new(program).exists()).then(|| {
+            check(
+                "binary",
+                "block",
+                format!("{}: {} does not exist", stage, program),
+            )
+        });
+    }
+    match archivetools::find_in_path(&[program]) {
+        Some(_) => None,
+        None => Some(check(
+            "binary",
+            "block",
+            format!("{}: {} was not found in PATH", stage, program),
+        )),
+    }
+}
+
+// cmd.exe only knows double quotes, and & or | end a command even without spaces around them
+fn split_cmd(command: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in command.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '&' | '|' if !quoted => {
+                words.extend((!current.is_empty()).then(|| std::mem::take(&mut current)));
+                words.push(c.to_string());
+            }
+            c if c.is_whitespace() && !quoted => {
+                words.extend((!current.is_empty()).then(|| std::mem::take(&mut current)));
+            }
+            c => current.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    words.extend((!current.is_empty()).then_some(current));
+    Some(words)
+}
+
+// Split the way the shell launch_game uses will split it
+fn split_words(command: &str) -> Option<Vec<String>> {
+    if cfg!(target_os = "windows") {
+        split_cmd(command)
+    } else {
+        shlex::split(command)
+    }
+}
+
+fn check_command(stage: &str, command: &str, checks: &mut Vec<LaunchCheck>) {
+    let Some(words) = split_words(command) else {
+        checks.push(check(
+            "parse",
+            "block",
+            format!("{}: unbalanced quotes or a trailing backslash", stage),
+        ));
+        return;
+    };
+    if words.is_empty() {
+        checks.push(check("parse", "block", format!("{} is empty", stage)));
+        return;
+    }
+    for program in programs(&words) {
+        checks.extend(check_program(stage, &program));
+    }
+    for (pattern, description) in DANGEROUS_PATTERNS {
+        if regex::Regex::new(pattern).is_ok_and(|re| re.is_match(command)) {
+            checks.push(check(
+                "danger",
+                "warn",
+                format!("{}: this command {}", stage, description),
+            ));
+        }
+    }
+    if command.contains("{exe}") || command.contains("{dir}") {
+        checks.push(check(
+            "parse",
+            "warn",
+            format!(
+                "{}: template variables are only filled in when applying a template",
+                stage
+            ),
+        ));
+    }
+}
+
+// Dry run of what launch_game would do with the config, without starting anything
+pub fn validate(launch_config: &LaunchConfig) -> LaunchCheckReport {
+    let mut checks = Vec::new();
+    let executable = Path::new(&launch_config.executable_path);
+    if launch_config.executable_path.trim().is_empty() {
+        checks.push(check(
+            "executable",
+            "block",
+            "No executable selected".to_string(),
+        ));
+    } else if !executable.is_file() {
+        checks.push(check(
+            "executable",
+            "block",
+            format!("{} does not exist", launch_config.executable_path),
+        ));
+    }
+
+    match launch_config.launch_method.as_str() {
+        "direct" if !cfg!(target_os = "windows") => checks.push(check(
+            "method",
+            "block",
+            "Direct launch only supported on Windows".to_string(),
+        )),
+        "wine" if cfg!(target_os = "windows") => checks.push(check(
+            "method",
+            "block",
+            "Wine not needed on Windows".to_string(),
+        )),
+        "wine" => checks.extend(check_program("Launch method", "wine")),
+        "python" => checks.extend(check_program("Launch method", "python3")),
+        "custom" => match launch_config
+            .custom_command
+            .as_deref()
+            .filter(|c| !c.trim().is_empty())
+        {
+            Some(command) => check_command("Custom command", command, &mut checks),
+            None => checks.push(check(
+                "parse",
+                "block",
+                "Custom command not provided".to_string(),
+            )),
+        },
+        "direct" => {}
+        other => checks.push(check(
+            "method",
+            "block",
+            format!("Invalid launch method: {}", other),
+        )),
+    }
+
+    for (stage, command) in [
+        ("Pre-launch hook", &launch_config.pre_launch),
+        ("Post-exit hook", &launch_config.post_exit),
+    ] {
+        if let Some(command) = command.as_deref().filter(|c| !c.trim().is_empty()) {
+            check_command(stage, command, &mut checks);
+        }
+    }
+
+    if let Some(remapper) = &launch_config.input_remapper
+        && let Err(e) = remapper.validate()
+    {
+        checks.push(check("inputRemapper", "block", e));
+    }
+    if launch_config.elevate && !cfg!(target_os = "windows") {
+        checks.extend(check_program("Run as administrator", "pkexec"));
+        if launch_config.launch_method == "wine" {
+            checks.push(check(
+                "elevate",
+                "warn",
+                "Wine running as root uses root's prefix instead of yours".to_string(),
+            ));
+        }
+    }
+    LaunchCheckReport::from_checks(checks)
+}
@@ -0,0 +1,117 @@
+use crate::httpconfig;
+use crate::launchtemplates::{self, LaunchTemplate};
+use crate::offline;
+use crate::state::{AppState, LaunchConfig};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+const API_BASE: &str = "https://api.chanomhub.online/api";
+
+// A crowd-sourced tip for running one game, e.g. "works with wine-ge-8" or "needs Japanese locale"
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CompatNote {
+    pub id: i64,
+    pub text: String,
+    // Short machine-readable labels such as "wine-ge-8", "ja-locale", "gamemode"
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub platform: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    // Upvotes minus downvotes
+    #[serde(default)]
+    pub score: i64,
+    #[serde(rename = "createdAt", default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub suggested: Option<SuggestedLaunch>,
+}
+
+// The part of a LaunchConfig a note can suggest. The executable always stays the user's own.
+// Commands may use the same variables as launch templates ({exe}, {dir}, ...).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SuggestedLaunch {
+    #[serde(rename = "launchMethod")]
+    pub launch_method: String,
+    #[serde(rename = "customCommand", default)]
+    pub custom_command: Option<String>,
+    #[serde(rename = "preLaunch", default)]
+    pub pre_launch: Option<String>,
+    #[serde(rename = "postExit", default)]
+    pub post_exit: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NotesResponse {
+    #[serde(default)]
+    notes: Vec<CompatNote>,
+}
+
+// Notes for the platform this app runs on first, then by score
+fn sort_for_platform(notes: &mut [CompatNote]) {
+    let platform = std::env::consts::OS;
+    notes.sort_by_key(|note| {
+        let matches = note
+            .platform
+            .as_deref()
+            .is_none_or(|p| p.eq_ignore_ascii_case(platform));
+        (!matches, -note.score)
+    });
+}
+
+pub async fn fetch_notes(
+    app: &AppHandle,
+    slug: &str,
+    token: Option<String>,
+) -> Result<Vec<CompatNote>, String> {
+    let client = httpconfig::client(app)?;
+    let url = format!("{}/articles/{}/compat-notes", API_BASE, slug);
+    let mut request = httpconfig::apply(&httpconfig::settings(app), client.get(&url), &url)
+        .header("accept", "application/json");
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+    let _permit = offline::api_permit(app).await?;
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    // No notes yet is not an error
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(Vec::new());
+    }
+    if !response.status().is_success() {
+        return Err(format!("API request failed: {}", response.status()));
+    }
+    let mut notes = response
+        .json::<NotesResponse>()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?
+        .notes;
+    sort_for_platform(&mut notes);
+    Ok(notes)
+}
+
+// Resolved like a one-off launch template, so notes and templates resolve variables the same way.
+// Returns the config the note would give the game and the game's current one; nothing is saved.
+pub fn preview(
+    app_state: &AppState,
+    game_id: &str,
+    note: &CompatNote,
+    executable_path: Option<String>,
+) -> Result<(LaunchConfig, Option<LaunchConfig>), String> {
+    let suggested = note
+        .suggested
+        .as_ref()
+        .ok_or("This note has no launch settings to apply")?;
+    let template = LaunchTemplate {
+        name: format!("Compatibility note {}", note.id),
+        launch_method: suggested.launch_method.clone(),
+        custom_command: suggested.custom_command.clone(),
+        pre_launch: suggested.pre_launch.clone(),
+        post_exit: suggested.post_exit.clone(),
+        ..Default::default()
+    };
+    launchtemplates::preview(app_state, game_id, &template, executable_path)
+}
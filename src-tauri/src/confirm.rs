@@ -0,0 +1,108 @@
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+// Long enough to read the summary, short enough that a stale token is useless
+const TOKEN_TTL: Duration = Duration::from_secs(2 * 60);
+
+struct PendingConfirmation {
+    action: String,
+    // Hash of the exact arguments, so a token cannot authorize a different (bigger) operation
+    fingerprint: String,
+    expires_at: Instant,
+}
+
+// Tokens handed out by the first call of a destructive command, consumed by the second
+#[derive(Default)]
+pub struct Confirmations(HashMap<String, PendingConfirmation>);
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ConfirmationRequest {
+    pub action: String,
+    pub token: String,
+    pub summary: serde_json::Value,
+    #[serde(rename = "expiresInSecs")]
+    pub expires_in_secs: u64,
+}
+
+// What a guarded command returns: either the summary to confirm or the result of the action
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "status")]
+pub enum Guarded<T> {
+    #[serde(rename = "confirmationRequired")]
+    ConfirmationRequired(ConfirmationRequest),
+    #[serde(rename = "done")]
+    Done { result: T },
+}
+
+pub fn fingerprint(args: &serde_json::Value) -> String {
+    hex::encode(Sha1::digest(args.to_string().as_bytes()))
+}
+
+fn new_token(action: &str, fingerprint: &str) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let seed = format!("{}:{}:{}:{:?}", action, fingerprint, nanos, Instant::now());
+    hex::encode(Sha1::digest(seed.as_bytes()))
+}
+
+pub fn issue(
+    app: &AppHandle,
+    action: &str,
+    fingerprint: String,
+    summary: serde_json::Value,
+) -> Result<ConfirmationRequest, String> {
+    let confirmations = app.state::<Mutex<Confirmations>>();
+    let mut confirmations = confirmations
+        .lock()
+        .map_err(|e| format!("Failed to lock confirmations: {}", e))?;
+    let now = Instant::now();
+    confirmations
+        .0
+        .retain(|_, pending| pending.expires_at > now);
+
+    let token = new_token(action, &fingerprint);
+    confirmations.0.insert(
+        token.clone(),
+        PendingConfirmation {
+            action: action.to_string(),
+            fingerprint,
+            expires_at: now + TOKEN_TTL,
+        },
+    );
+    Ok(ConfirmationRequest {
+        action: action.to_string(),
+        token,
+        summary,
+        expires_in_secs: TOKEN_TTL.as_secs(),
+    })
+}
+
+// Tokens are single use: a failed check still burns the token
+pub fn consume(
+    app: &AppHandle,
+    token: &str,
+    action: &str,
+    fingerprint: &str,
+) -> Result<(), String> {
+    let confirmations = app.state::<Mutex<Confirmations>>();
+    let mut confirmations = confirmations
+        .lock()
+        .map_err(|e| format!("Failed to lock confirmations: {}", e))?;
+    let pending = confirmations
+        .0
+        .remove(token)
+        .ok_or("Confirmation token is unknown or was already used")?;
+    if pending.expires_at <= Instant::now() {
+        return Err("Confirmation token has expired".to_string());
+    }
+    if pending.action != action || pending.fingerprint != fingerprint {
+        return Err("Confirmation token was issued for a different operation".to_string());
+    }
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use crate::{ActiveDownloads, integrity, state};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use tauri::{AppHandle, Manager};
+
+const JAR_FILE: &str = "cookie_jar.json";
+
+// Hosts check Referer against the page that linked the file; used when the caller knows only the slug
+const ARTICLE_PAGE_BASE: &str = "https://chanomhub.online/articles";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StoredCookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    pub path: String,
+    // Unix seconds; None for session cookies, which are kept until the jar is cleared
+    pub expires: Option<i64>,
+    #[serde(default)]
+    pub secure: bool,
+    #[serde(rename = "httpOnly", default)]
+    pub http_only: bool,
+}
+
+// Cookies the helper saw while downloading, keyed by the host of the download URL.
+// Each download runs in a fresh WebView2 profile, so without this every visit starts logged out.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CookieJar {
+    pub providers: HashMap<String, Vec<StoredCookie>>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CookieProvider {
+    pub host: String,
+    pub cookies: usize,
+}
+
+fn jar_path(app: &AppHandle) -> Option<PathBuf> {
+    state::get_config_dir(app).map(|dir| dir.join(JAR_FILE))
+}
+
+fn is_expired(cookie: &StoredCookie, now: i64) -> bool {
+    cookie.expires.is_some_and(|expires| expires <= now)
+}
+
+pub fn provider_key(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    let host = url.host_str()?.to_ascii_lowercase();
+    Some(host.trim_start_matches("www.").to_string())
+}
+
+pub fn article_referer(slug: &str) -> String {
+    format!("{}/{}", ARTICLE_PAGE_BASE, slug)
+}
+
+pub fn load_jar(app: &AppHandle) -> CookieJar {
+    let Some(path) = jar_path(app) else {
+        return CookieJar::default();
+    };
+    match integrity::read_verified(app, &path) {
+        Ok(Some(content)) => serde_json::from_str(&content).unwrap_or_default(),
+        Ok(None) => CookieJar::default(),
+        Err(e) => {
+            println!("Failed to load cookie jar: {}", e);
+            CookieJar::default()
+        }
+    }
+}
+
+fn save_jar(app: &AppHandle, jar: &CookieJar) {
+    let Some(path) = jar_path(app) else {
+        return;
+    };
+    let result = serde_json::to_string_pretty(jar)
+        .map_err(|e| e.to_string())
+        .and_then(|json| integrity::write_signed(app, &path, json.as_bytes()));
+    if let Err(e) = result {
+        println!("Failed to save cookie jar: {}", e);
+    }
+}
+
+// Cookies to preload before the helper opens a download URL
+pub fn cookies_for(app: &AppHandle, url: &str) -> Vec<StoredCookie> {
+    let Some(key) = provider_key(url) else {
+        return Vec::new();
+    };
+    let now = chrono::Utc::now().timestamp();
+    app.state::<Mutex<CookieJar>>()
+        .lock()
+        .ok()
+        .and_then(|jar| jar.providers.get(&key).cloned())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|cookie| !is_expired(cookie, now))
+        .collect()
+}
+
+// Handles the "cookies" message the helper sends once a transfer has started
+pub fn record_from_helper(app: &AppHandle, message: &serde_json::Value) {
+    let Some(download_id) = message.get("downloadId").and_then(|id| id.as_str()) else {
+        return;
+    };
+    let url = app
+        .state::<RwLock<ActiveDownloads>>()
+        .read()
+        .ok()
+        .and_then(|downloads| downloads.downloads.get(download_id).map(|d| d.url.clone()));
+    let Some(key) = url.as_deref().and_then(provider_key) else {
+        return;
+    };
+    let Some(cookies) = message
+        .get("cookies")
+        .cloned()
+        .and_then(|c| serde_json::from_value::<Vec<StoredCookie>>(c).ok())
+    else {
+        return;
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    let cookies: Vec<StoredCookie> = cookies
+        .into_iter()
+        .filter(|cookie| !is_expired(cookie, now))
+        .collect();
+    let jar = app.state::<Mutex<CookieJar>>();
+    let Ok(mut jar) = jar.lock() else {
+        return;
+    };
+    println!("Saved {} cookies for {}", cookies.len(), key);
+    if cookies.is_empty() {
+        jar.providers.remove(&key);
+    } else {
+        jar.providers.insert(key, cookies);
+    }
+    save_jar(app, &jar);
+}
+
+pub fn providers(app: &AppHandle) -> Result<Vec<CookieProvider>, String> {
+    let jar = app.state::<Mutex<CookieJar>>();
+    let jar = jar
+        .lock()
+        .map_err(|e| format!("Failed to lock cookie jar: {}", e))?;
+    let mut providers: Vec<CookieProvider> = jar
+        .providers
+        .iter()
+        .map(|(host, cookies)| CookieProvider {
+            host: host.clone(),
+            cookies: cookies.len(),
+        })
+        .collect();
+    providers.sort_by(|a, b| a.host.cmp(&b.host));
+    Ok(providers)
+}
+
+// Clears one provider, or the whole jar when no host is given
+pub fn clear(app: &AppHandle, host: Option<String>) -> Result<(), String> {
+    let jar = app.state::<Mutex<CookieJar>>();
+    let mut jar = jar
+        .lock()
+        .map_err(|e| format!("Failed to lock cookie jar: {}", e))?;
+    match host {
+        Some(host) => {
+            jar.providers
+                .remove(host.trim().to_ascii_lowercase().trim_start_matches("www."));
+        }
+        None => jar.providers.clear(),
+    }
+    save_jar(app, &jar);
+    Ok(())
+}
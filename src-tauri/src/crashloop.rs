@@ -0,0 +1,173 @@
+use crate::state::LaunchConfig;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::ExitStatus;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+// This many abnormal exits inside the window counts as a crash loop
+const CRASH_THRESHOLD: usize = 3;
+const CRASH_WINDOW: Duration = Duration::from_secs(10 * 60);
+const LOG_TAIL_LINES: usize = 20;
+
+// NTSTATUS codes Windows reports when a runtime DLL is missing or the wrong architecture
+const STATUS_DLL_NOT_FOUND: i32 = 0xC0000135_u32 as i32;
+const STATUS_INVALID_IMAGE_FORMAT: i32 = 0xC000007B_u32 as i32;
+
+// Folders mod loaders install into, relative to the game folder
+const MOD_FOLDERS: &[&str] = &[
+    "mods",
+    "Mods",
+    "BepInEx",
+    "www/mods",
+    "game/mods",
+    "plugins",
+];
+
+// Recent abnormal exits per game id, kept until the app restarts
+#[derive(Default)]
+pub struct CrashHistory {
+    crashes: HashMap<String, Vec<(Instant, Option<i32>, String)>>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Remediation {
+    pub id: String,
+    pub title: String,
+    pub detail: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashLoopReport {
+    pub game_id: String,
+    pub crashes: usize,
+    pub window_minutes: u64,
+    // Newest last
+    pub exit_statuses: Vec<String>,
+    pub launch_method: String,
+    pub log_tail: Vec<String>,
+    pub suggestions: Vec<Remediation>,
+}
+
+fn remediation(id: &str, title: &str, detail: &str) -> Remediation {
+    Remediation {
+        id: id.to_string(),
+        title: title.to_string(),
+        detail: detail.to_string(),
+    }
+}
+
+fn log_tail(log_path: Option<&Path>) -> Vec<String> {
+    let Some(contents) = log_path.and_then(|p| std::fs::read_to_string(p).ok()) else {
+        return Vec::new();
+    };
+    let lines: Vec<&str> = contents.lines().collect();
+    lines[lines.len().saturating_sub(LOG_TAIL_LINES)..]
+        .iter()
+        .map(|l| l.to_string())
+        .collect()
+}
+
+fn suggestions(launch_config: &LaunchConfig, codes: &[Option<i32>]) -> Vec<Remediation> {
+    let mut suggestions = vec![remediation(
+        "verifyFiles",
+        "Verify game files",
+        "A damaged or incomplete extraction is the most common cause of repeated crashes.",
+    )];
+    if codes
+        .iter()
+        .flatten()
+        .any(|c| *c == STATUS_DLL_NOT_FOUND || *c == STATUS_INVALID_IMAGE_FORMAT)
+    {
+        suggestions.push(remediation(
+            "reinstallRuntime",
+            "Reinstall runtimes",
+            "The game could not load a required DLL. Reinstall the Visual C++ Redistributable and DirectX runtime.",
+        ));
+    }
+    if let Some(graphics) = &launch_config.wine_graphics
+        && launch_config.launch_method == "wine"
+        && (graphics.dxvk || graphics.vkd3d)
+    {
+        suggestions.push(remediation(
+            "disableWineGraphics",
+            "Turn off DXVK / VKD3D",
+            "Try the game with Wine's own Direct3D to rule out a translation layer problem.",
+        ));
+    }
+    let game_dir = Path::new(&launch_config.executable_path).parent();
+    if let Some(folder) = game_dir.and_then(|dir| {
+        MOD_FOLDERS
+            .iter()
+            .find(|f| dir.join(f).read_dir().is_ok_and(|mut d| d.next().is_some()))
+    }) {
+        suggestions.push(remediation(
+            "disableMods",
+            "Disable mods",
+            &format!(
+                "The game folder has mods in {}. Move them out and try again.",
+                folder
+            ),
+        ));
+    }
+    if launch_config.launch_method == "custom"
+        || launch_config
+            .pre_launch
+            .as_deref()
+            .is_some_and(|c| !c.trim().is_empty())
+    {
+        suggestions.push(remediation(
+            "checkLaunchConfig",
+            "Check the launch command",
+            "A custom command or pre-launch hook runs before the game. Try the default launch method.",
+        ));
+    }
+    suggestions
+}
+
+// Called by watch_for_exit; emits `game-crash-loop` once the threshold is reached
+pub fn record_exit(
+    app: &AppHandle,
+    game_id: &str,
+    status: &ExitStatus,
+    launch_config: &LaunchConfig,
+    log_path: Option<&Path>,
+) {
+    if status.success() {
+        return;
+    }
+    let now = Instant::now();
+    let exits = {
+        let history = app.state::<Mutex<CrashHistory>>();
+        let Ok(mut history) = history.lock() else {
+            return;
+        };
+        let exits = history.crashes.entry(game_id.to_string()).or_default();
+        exits.retain(|(at, _, _)| now.duration_since(*at) < CRASH_WINDOW);
+        exits.push((now, status.code(), status.to_string()));
+        if exits.len() < CRASH_THRESHOLD {
+            return;
+        }
+        // Reported once per loop; the next report needs another full run of crashes
+        std::mem::take(exits)
+    };
+
+    let codes: Vec<Option<i32>> = exits.iter().map(|(_, code, _)| *code).collect();
+    let report = CrashLoopReport {
+        game_id: game_id.to_string(),
+        crashes: exits.len(),
+        window_minutes: CRASH_WINDOW.as_secs() / 60,
+        exit_statuses: exits.into_iter().map(|(_, _, status)| status).collect(),
+        launch_method: launch_config.launch_method.clone(),
+        log_tail: log_tail(log_path),
+        suggestions: suggestions(launch_config, &codes),
+    };
+    println!(
+        "{} crashed {} times in {} minutes",
+        game_id, report.crashes, report.window_minutes
+    );
+    let _ = app.emit("game-crash-loop", &report);
+}
@@ -0,0 +1,225 @@
+use crate::state::AppState;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+
+// Roughly what a game's asset folder looks like to the scanner
+const PROBE_FILES: usize = 300;
+const PROBE_FILE_SIZE: usize = 4 * 1024;
+const PROBE_DIR_NAME: &str = ".chanomhub-io-probe";
+
+// Unscanned disks manage thousands of small files a second; well below this, the scanner is the bottleneck
+const SLOW_FILES_PER_SEC: f64 = 400.0;
+
+// Typical file count of an extracted game, used to turn the measurement into a wait the user understands
+const TYPICAL_GAME_FILES: u64 = 10_000;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct WriteProbe {
+    pub files: usize,
+    pub millis: u64,
+    #[serde(rename = "filesPerSec")]
+    pub files_per_sec: f64,
+    // Seconds spent just creating the files of a typical game at this rate
+    #[serde(rename = "estimatedSecsPerGame")]
+    pub estimated_secs_per_game: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DefenderStatus {
+    // Defender only exists on Windows; everything else is None/false elsewhere
+    pub supported: bool,
+    #[serde(rename = "realtimeProtection")]
+    pub realtime_protection: Option<bool>,
+    #[serde(rename = "libraryDir")]
+    pub library_dir: Option<String>,
+    // Exclusion added through the app; the exclusion list itself needs admin rights to read
+    #[serde(rename = "excludedPath")]
+    pub excluded_path: Option<String>,
+    pub probe: Option<WriteProbe>,
+    pub warning: Option<String>,
+}
+
+fn library_dir(app: &AppHandle) -> Result<(Option<String>, Option<String>), String> {
+    let app_state = app.state::<Mutex<AppState>>();
+    let app_state = app_state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok((
+        app_state.download_dir.clone(),
+        app_state.defender_exclusion.clone(),
+    ))
+}
+
+// Writes and deletes a burst of small files, the pattern real-time scanning slows down the most
+fn probe_small_files(dir: &Path) -> Result<WriteProbe, String> {
+    let probe_dir = dir.join(PROBE_DIR_NAME);
+    fs::create_dir_all(&probe_dir)
+        .map_err(|e| format!("Failed to create {}: {}", probe_dir.display(), e))?;
+    let data = vec![0x5au8; PROBE_FILE_SIZE];
+    let started = Instant::now();
+    let result = (0..PROBE_FILES).try_for_each(|i| {
+        fs::write(probe_dir.join(format!("probe-{}.dat", i)), &data)
+            .map_err(|e| format!("Failed to write probe file: {}", e))
+    });
+    let elapsed = started.elapsed();
+    let _ = fs::remove_dir_all(&probe_dir);
+    result?;
+
+    let files_per_sec = PROBE_FILES as f64 / elapsed.as_secs_f64().max(0.001);
+    Ok(WriteProbe {
+        files: PROBE_FILES,
+        millis: elapsed.as_millis() as u64,
+        files_per_sec: files_per_sec.round(),
+        estimated_secs_per_game: (TYPICAL_GAME_FILES as f64 / files_per_sec).round() as u64,
+    })
+}
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+#[cfg(target_os = "windows")]
+fn run_powershell(script: &str) -> Result<std::process::Output, String> {
+    use std::os::windows::process::CommandExt;
+
+    std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("Failed to run PowerShell: {}", e))
+}
+
+#[cfg(target_os = "windows")]
+fn realtime_protection() -> Option<bool> {
+    let output = run_powershell("(Get-MpComputerStatus).RealTimeProtectionEnabled").ok()?;
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "True" => Some(true),
+        "False" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn realtime_protection() -> Option<bool> {
+    None
+}
+
+// Defender cmdlets need admin rights, so the command runs in an elevated PowerShell (UAC prompt).
+// The inner command is passed encoded, which sidesteps quoting of arbitrary paths.
+#[cfg(target_os = "windows")]
+fn run_elevated(command: &str) -> Result<(), String> {
+    use base64::Engine;
+
+    let utf16: Vec<u8> = command
+        .encode_utf16()
+        .flat_map(|unit| unit.to_le_bytes())
+        .collect();
+    let encoded = base64::engine::general_purpose::STANDARD.encode(utf16);
+    let script = format!(
+        "$p = Start-Process powershell -Verb RunAs -Wait -PassThru -WindowStyle Hidden -ArgumentList '-NoProfile -NonInteractive -EncodedCommand {}'; exit $p.ExitCode",
+        encoded
+    );
+    let output = run_powershell(&script)?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // Declining the UAC prompt ends up here as well
+        Err(format!(
+            "Windows Defender did not accept the change: {}",
+            stderr
+                .lines()
+                .next()
+                .unwrap_or("elevation was cancelled")
+                .trim()
+        ))
+    }
+}
+
+fn quote(path: &str) -> String {
+    format!("'{}'", path.replace('\'', "''"))
+}
+
+pub fn exclusion_commands(path: &str) -> (String, String) {
+    (
+        format!("Add-MpPreference -ExclusionPath {}", quote(path)),
+        format!("Remove-MpPreference -ExclusionPath {}", quote(path)),
+    )
+}
+
+// Measures small-file throughput in the library and says whether real-time scanning is the likely cause
+pub fn status(app: &AppHandle) -> Result<DefenderStatus, String> {
+    let (library_dir, excluded_path) = library_dir(app)?;
+    let realtime_protection = realtime_protection();
+    let probe = library_dir
+        .as_deref()
+        .map(Path::new)
+        .filter(|dir| dir.is_dir())
+        .map(probe_small_files)
+        .transpose()?;
+
+    let excluded = excluded_path.is_some() && excluded_path == library_dir;
+    let slowed = |probe: &&WriteProbe| {
+        realtime_protection == Some(true) && !excluded && probe.files_per_sec < SLOW_FILES_PER_SEC
+    };
+    let warning = probe.as_ref().filter(slowed).map(|probe| {
+        format!(
+            "Real-time protection is slowing down the library: {} files/s, \
+             about {} s spent per {} files extracted",
+            probe.files_per_sec, probe.estimated_secs_per_game, TYPICAL_GAME_FILES
+        )
+    });
+    Ok(DefenderStatus {
+        supported: cfg!(target_os = "windows"),
+        realtime_protection,
+        library_dir,
+        excluded_path,
+        probe,
+        warning,
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn save_exclusion(app: &AppHandle, path: Option<String>) -> Result<(), String> {
+    let app_state = app.state::<Mutex<AppState>>();
+    let mut app_state = app_state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    app_state.defender_exclusion = path;
+    crate::state::save_state_to_file(app, &app_state)
+}
+
+// Registers the library folder as an exclusion and remembers it so it can be reverted later
+#[cfg(target_os = "windows")]
+pub fn add_exclusion(app: &AppHandle) -> Result<String, String> {
+    let (library_dir, _) = library_dir(app)?;
+    let path = library_dir.ok_or("No library folder is set")?;
+    let (add, _) = exclusion_commands(&path);
+    run_elevated(&add)?;
+    println!("Added Windows Defender exclusion for {}", path);
+    save_exclusion(app, Some(path.clone()))?;
+    Ok(path)
+}
+
+#[cfg(target_os = "windows")]
+pub fn remove_exclusion(app: &AppHandle) -> Result<(), String> {
+    let (_, excluded_path) = library_dir(app)?;
+    let path = excluded_path.ok_or("No exclusion was added by Chanomhub")?;
+    let (_, remove) = exclusion_commands(&path);
+    run_elevated(&remove)?;
+    println!("Removed Windows Defender exclusion for {}", path);
+    save_exclusion(app, None)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn add_exclusion(_app: &AppHandle) -> Result<String, String> {
+    Err("Windows Defender exclusions are only available on Windows".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn remove_exclusion(_app: &AppHandle) -> Result<(), String> {
+    Err("Windows Defender exclusions are only available on Windows".to_string())
+}
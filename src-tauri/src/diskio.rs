@@ -0,0 +1,364 @@
+use crate::state::DownloadSettings;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+// Downloads are written under this suffix and only renamed once complete, so a
+// half-written file is never mistaken for a finished archive
+pub const PART_SUFFIX: &str = ".part";
+
+// Errors from a failed space check start with this, the details come with disk-space-warning
+pub const NOT_ENOUGH_SPACE: &str = "Not enough disk space";
+
+pub fn is_part_file(path: &str) -> bool {
+    path.to_lowercase().ends_with(PART_SUFFIX)
+}
+
+// "game.tar.gz" -> ("game", ".tar.gz"), so numbering goes before the whole extension
+fn split_extension(name: &str) -> (&str, &str) {
+    let lower = name.to_lowercase();
+    let at = lower
+        .find(".tar.")
+        .filter(|at| *at > 0)
+        .or_else(|| lower.rfind('.').filter(|at| *at > 0))
+        .unwrap_or(name.len());
+    name.split_at(at)
+}
+
+fn name_taken(dir: &Path, name: &str, taken: &[PathBuf]) -> bool {
+    let path = dir.join(name);
+    let part = dir.join(format!("{}{}", name, PART_SUFFIX));
+    path.exists() || part.exists() || taken.contains(&part)
+}
+
+// `name`, or "name (1).ext", "name (2).ext", ... when the file, its .part or a running
+// download's .part (`taken`) already uses it, so two downloads never share a file
+pub fn unique_download_name(dir: &Path, name: &str, taken: &[PathBuf]) -> String {
+    if !name_taken(dir, name, taken) {
+        return name.to_string();
+    }
+    let (stem, extension) = split_extension(name);
+    (1..)
+        .map(|n| format!("{} ({}){}", stem, n, extension))
+        .find(|candidate| !name_taken(dir, candidate, taken))
+        .unwrap_or_else(|| name.to_string())
+}
+
+// Moves a finished `.part` file to its final name (same directory, so a plain rename). An
+// existing file is never replaced; the download gets the next free name instead.
+pub fn finalize_part_file(part_path: &str) -> Result<String, String> {
+    if !is_part_file(part_path) {
+        return Ok(part_path.to_string());
+    }
+
+    if !Path::new(part_path).exists() {
+        return Err(format!("Downloaded file not found: {}", part_path));
+    }
+    let mut final_path = PathBuf::from(&part_path[..part_path.len() - PART_SUFFIX.len()]);
+    if final_path.exists() {
+        let dir = final_path.parent().unwrap_or(Path::new("."));
+        let name = final_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let free = unique_download_name(dir, &name, &[]);
+        println!(
+            "{} already exists, saving the download as {}",
+            final_path.display(),
+            free
+        );
+        final_path = dir.join(free);
+    }
+
+    fs::rename(part_path, &final_path).map_err(|e| {
+        format!(
+            "Failed to finalize download {}: {}",
+            final_path.display(),
+            e
+        )
+    })?;
+    println!(
+        "Finalized download: {} -> {}",
+        part_path,
+        final_path.display()
+    );
+    Ok(final_path.to_string_lossy().to_string())
+}
+
+pub fn ensure_not_partial(path: &str) -> Result<(), String> {
+    if is_part_file(path) {
+        return Err(format!(
+            "{} is an incomplete download and cannot be used until it finishes",
+            path
+        ));
+    }
+    Ok(())
+}
+
+const MIN_BUFFER_MB: u64 = 4;
+const MAX_BUFFER_MB: u64 = 16;
+// Removable drives get a small buffer and an fsync every few MB so yanking the stick loses little
+const SAFE_BUFFER_MB: u64 = 4;
+const SAFE_FSYNC_INTERVAL_MB: u64 = 8;
+
+const MB: u64 = 1024 * 1024;
+
+#[derive(Clone, Copy, Debug)]
+pub struct WritePolicy {
+    pub buffer_size: usize,
+    // Bytes written between fsyncs (0 = only when the file is finished)
+    pub fsync_every: u64,
+}
+
+impl WritePolicy {
+    pub fn for_target(settings: &DownloadSettings, target: &Path) -> Self {
+        let safe = match settings.safe_write_mode.as_str() {
+            "always" => true,
+            "never" => false,
+            _ => is_removable_path(target),
+        };
+
+        if safe {
+            println!("Using safe write mode for {:?}", target);
+            return Self {
+                buffer_size: (SAFE_BUFFER_MB * MB) as usize,
+                fsync_every: SAFE_FSYNC_INTERVAL_MB * MB,
+            };
+        }
+
+        Self {
+            buffer_size: (settings.write_buffer_mb.clamp(MIN_BUFFER_MB, MAX_BUFFER_MB) * MB)
+                as usize,
+            fsync_every: settings.fsync_interval_mb * MB,
+        }
+    }
+}
+
+// Buffered file writer that batches small network chunks into large sequential writes
+pub struct DownloadWriter {
+    writer: BufWriter<File>,
+    policy: WritePolicy,
+    unsynced: u64,
+    written: u64,
+}
+
+impl DownloadWriter {
+    // Creates the file after checking that `expected_len` bytes fit. Nothing is reserved: a
+    // single-stream download resumes from the file's length, so the file may only ever hold bytes
+    // that arrived. Split downloads reserve their full size and track progress in their own record.
+    pub fn create(
+        path: &Path,
+        policy: WritePolicy,
+        expected_len: Option<u64>,
+    ) -> Result<Self, String> {
+        if let Some(len) = expected_len {
+            ensure_space_for(path, len)?;
+        }
+
+        let file = File::create(path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        Ok(Self {
+            writer: BufWriter::with_capacity(policy.buffer_size, file),
+            policy,
+            unsynced: 0,
+            written: 0,
+        })
+    }
+
+    // Continues a partial file; `written` counts only what this writer adds
+    pub fn append(path: &Path, policy: WritePolicy) -> Result<Self, String> {
+        let file = fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        Ok(Self {
+            writer: BufWriter::with_capacity(policy.buffer_size, file),
+            policy,
+            unsynced: 0,
+            written: 0,
+        })
+    }
+
+    pub fn write_chunk(&mut self, data: &[u8]) -> io::Result<()> {
+        self.writer.write_all(data)?;
+        self.written += data.len() as u64;
+        self.unsynced += data.len() as u64;
+
+        if self.policy.fsync_every > 0 && self.unsynced >= self.policy.fsync_every {
+            self.writer.flush()?;
+            self.writer.get_ref().sync_data()?;
+            self.unsynced = 0;
+        }
+        Ok(())
+    }
+
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    // Flushes the buffer and makes sure everything is on disk before the file is finalized
+    pub fn finish(mut self) -> io::Result<u64> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_all()?;
+        Ok(self.written)
+    }
+}
+
+// What a download or extraction would have run out of part-way
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SpaceShortfall {
+    pub path: String,
+    pub needed: u64,
+    pub available: u64,
+    pub missing: u64,
+}
+
+impl SpaceShortfall {
+    pub fn message(&self) -> String {
+        format!(
+            "{} in {}: {} bytes needed, {} bytes available",
+            NOT_ENOUGH_SPACE, self.path, self.needed, self.available
+        )
+    }
+}
+
+// None when `len` bytes fit on the volume holding `path` (or its parent, if it does not exist yet)
+pub fn shortfall(path: &Path, len: u64) -> Result<Option<SpaceShortfall>, String> {
+    let dir = if path.is_dir() {
+        path
+    } else {
+        path.parent().unwrap_or(path)
+    };
+    let available = fs2::available_space(dir)
+        .map_err(|e| format!("Failed to query free space for {}: {}", dir.display(), e))?;
+    Ok((available < len).then(|| SpaceShortfall {
+        path: dir.to_string_lossy().to_string(),
+        needed: len,
+        available,
+        missing: len - available,
+    }))
+}
+
+pub fn ensure_space_for(path: &Path, len: u64) -> Result<(), String> {
+    match shortfall(path, len)? {
+        Some(shortfall) => Err(shortfall.message()),
+        None => Ok(()),
+    }
+}
+
+// Checked before a download or extraction starts writing. A shortfall is sent as
+// disk-space-warning so the UI can explain it; `stage` is "download" or "extraction". When the
+// free space cannot be read the work goes ahead rather than failing on a guess.
+pub fn preflight(
+    app: &AppHandle,
+    id: &str,
+    stage: &str,
+    path: &Path,
+    len: u64,
+) -> Result<(), String> {
+    let shortfall = match shortfall(path, len) {
+        Ok(Some(shortfall)) => shortfall,
+        Ok(None) => return Ok(()),
+        Err(e) => {
+            println!("{}", e);
+            return Ok(());
+        }
+    };
+    println!("{} for {} of {}", shortfall.message(), stage, id);
+    let _ = app.emit(
+        "disk-space-warning",
+        &serde_json::json!({
+            "id": id,
+            "stage": stage,
+            "shortfall": shortfall
+        }),
+    );
+    Err(shortfall.message())
+}
+
+pub fn is_removable_path(path: &Path) -> bool {
+    #[cfg(target_os = "windows")]
+    {
+        use std::path::Component;
+        let Some(Component::Prefix(prefix)) = path.components().next() else {
+            return false;
+        };
+        let drive = prefix.as_os_str().to_string_lossy().to_string();
+        match std::process::Command::new("fsutil")
+            .args(["fsinfo", "drivetype", &drive])
+            .output()
+        {
+            Ok(output) => String::from_utf8_lossy(&output.stdout).contains("Removable"),
+            Err(_) => false,
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        path.starts_with("/Volumes")
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        path.starts_with("/media") || path.starts_with("/run/media")
+    }
+}
+
+pub fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+// Moves everything inside `from` into `to`, falling back to copy + delete when they are on different drives.
+// Links are dropped rather than followed; the games folder recreates its own.
+pub fn move_dir_contents(from: &Path, to: &Path) -> Result<usize, String> {
+    fs::create_dir_all(to).map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+    let entries =
+        fs::read_dir(from).map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+
+    let mut moved = 0;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let source = entry.path();
+        let target = to.join(entry.file_name());
+        if target.exists() {
+            return Err(format!("{} already exists", target.display()));
+        }
+
+        let is_link = fs::symlink_metadata(&source)
+            .map(|m| m.file_type().is_symlink())
+            .unwrap_or(false);
+        if fs::rename(&source, &target).is_err() {
+            if !is_link {
+                copy_recursive(&source, &target).map_err(|e| {
+                    format!(
+                        "Failed to copy {} to {}: {}",
+                        source.display(),
+                        target.display(),
+                        e
+                    )
+                })?;
+            }
+            if source.is_dir() && !is_link {
+                fs::remove_dir_all(&source)
+            } else if is_link && cfg!(target_os = "windows") {
+                fs::remove_dir(&source)
+            } else {
+                fs::remove_file(&source)
+            }
+            .map_err(|e| format!("Failed to remove {}: {}", source.display(), e))?;
+        }
+        moved += 1;
+    }
+    Ok(moved)
+}
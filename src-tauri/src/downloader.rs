@@ -0,0 +1,758 @@
+use crate::chunkhash::{self, ChunkManifest};
+use crate::nativedownload::{self, Transfer};
+use crate::scheduler::{self, Priority};
+use crate::state::{AppState, ArticleMeta, save_active_downloads_to_file};
+use crate::{
+    ActiveDownloads, DownloadInfo, bandwidth, cookiejar, diskio, offline, segmented, sidecar,
+    timeline,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+
+// Matches the host itself and its subdomains ("mega.nz" covers "www.mega.nz")
+fn needs_browser(app: &AppHandle, url: &str) -> bool {
+    let Some(host) = url::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+    else {
+        return true;
+    };
+    let hosts = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .map(|app_state| app_state.download_settings.browser_hosts.clone())
+        .unwrap_or_default();
+    hosts
+        .iter()
+        .map(|h| h.trim().to_lowercase())
+        .filter(|h| !h.is_empty())
+        .any(|h| host == h || host.ends_with(&format!(".{}", h)))
+}
+
+// Downloads with the app's own HTTP client, and hands the link to the WebView2 helper only when
+// the host needs a browser: listed in browserHosts, or answering with a page instead of the file.
+// Returns the provider that took the download ("native" or "webview2"), or "queued" in offline mode
+// or while every download slot is taken.
+pub async fn start(
+    app: &AppHandle,
+    url: String,
+    filename: String,
+    download_id: String,
+    article: Option<ArticleMeta>,
+    referer: Option<String>,
+    options: TransferOptions,
+) -> Result<String, String> {
+    if offline::is_offline(app) {
+        offline::queue_download(
+            app,
+            offline::QueuedDownload {
+                download_id,
+                url,
+                filename,
+                article,
+                referer,
+                queued_at: chrono::Utc::now().to_rfc3339(),
+                mirrors: Vec::new(),
+                options,
+            },
+        )?;
+        return Ok(offline::QUEUED_PROVIDER.to_string());
+    }
+    if scheduler::queue_if_full(
+        app,
+        &url,
+        &filename,
+        &download_id,
+        &article,
+        &referer,
+        options,
+    )? {
+        return Ok(scheduler::QUEUED.to_string());
+    }
+    let web = url.starts_with("http://") || url.starts_with("https://");
+    if !web || needs_browser(app, &url) {
+        crate::start_webview2_download(
+            url,
+            filename,
+            download_id.clone(),
+            article,
+            referer,
+            app.clone(),
+            app.state::<RwLock<ActiveDownloads>>(),
+        )
+        .await?;
+        if options.priority != Priority::default() {
+            scheduler::set_priority(app, &download_id, options.priority)?;
+        }
+        return Ok("webview2".to_string());
+    }
+    println!(
+        "Starting native download: id={}, url={}, filename={}",
+        download_id, url, filename
+    );
+    bandwidth::ensure_under_cap(app)?;
+    // The name comes from the page; only its last component is used so it stays in the folder
+    let file_name = Path::new(&filename)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid file name: {}", filename))?;
+    let article = article.filter(|a| !a.slug.is_empty());
+    let referer = referer.filter(|r| !r.trim().is_empty()).or_else(|| {
+        article
+            .as_ref()
+            .map(|a| cookiejar::article_referer(&a.slug))
+    });
+
+    let save_folder = crate::get_download_dir(app.clone())?;
+    std::fs::create_dir_all(&save_folder)
+        .map_err(|e| format!("Failed to create save folder: {}", e))?;
+
+    let token = CancellationToken::new();
+    let (file_name, part_path) = {
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        let mut downloads = active_downloads
+            .write()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        // Picked under the lock, so two downloads with the same name never share a .part file
+        let taken: Vec<PathBuf> = downloads
+            .downloads
+            .values()
+            .filter(|d| d.id != download_id)
+            .filter_map(|d| d.part_path.as_deref().map(PathBuf::from))
+            .collect();
+        let file_name = diskio::unique_download_name(Path::new(&save_folder), &file_name, &taken);
+        let part_path =
+            Path::new(&save_folder).join(format!("{}{}", file_name, diskio::PART_SUFFIX));
+        downloads.downloads.insert(
+            download_id.clone(),
+            DownloadInfo {
+                id: download_id.clone(),
+                filename: file_name.clone(),
+                url: url.clone(),
+                progress: 0.0,
+                status: "downloading".to_string(),
+                provider: Some("native".to_string()),
+                extraction_status: Some("idle".to_string()),
+                extraction_progress: Some(0.0),
+                article: article.clone(),
+                referer: referer.clone(),
+                started_at: Some(chrono::Utc::now().to_rfc3339()),
+                source_url: Some(url.clone()),
+                part_path: Some(part_path.to_string_lossy().to_string()),
+                priority: options.priority,
+                segments: options.segments,
+                ..Default::default()
+            },
+        );
+        downloads.tokens.insert(download_id.clone(), token.clone());
+        save_active_downloads_to_file(app, &downloads)?;
+        (file_name, part_path)
+    };
+    timeline::record(
+        app,
+        &download_id,
+        "download",
+        "starting",
+        "user",
+        Some(&url),
+    );
+
+    if let Some(meta) = article
+        .clone()
+        .filter(|a| a.title.is_none() || a.cover_image.is_none())
+    {
+        tauri::async_runtime::spawn(crate::fill_article_meta(
+            app.clone(),
+            download_id.clone(),
+            meta,
+        ));
+    }
+
+    let transfer = Transfer {
+        url: url.clone(),
+        part_path,
+        filename: file_name,
+        referer,
+        status: "downloading".to_string(),
+    };
+    spawn(app.clone(), download_id, transfer, token, url, article);
+    Ok("native".to_string())
+}
+
+// `page_url` is what the WebView2 helper gets when the host turns out to need a browser
+fn spawn(
+    app: AppHandle,
+    download_id: String,
+    transfer: Transfer,
+    token: CancellationToken,
+    page_url: String,
+    article: Option<ArticleMeta>,
+) {
+    tauri::async_runtime::spawn(async move {
+        // The transfer checks every chunk against the manifest when there is one
+        let chunks_verified = chunkhash::for_download(&app, &download_id).is_some();
+        let status = match nativedownload::run(&app, &download_id, &transfer, &token).await {
+            Ok(()) => serde_json::json!({
+                "status": "success",
+                "downloadId": download_id,
+                "chunksVerified": chunks_verified,
+                "path": transfer.part_path.to_string_lossy(),
+                "filename": transfer.filename
+            }),
+            // cancel_active_download reports the cancellation itself
+            Err(_) if token.is_cancelled() => return,
+            Err(e) if e == nativedownload::NEEDS_BROWSER => {
+                println!(
+                    "{} needs a browser, handing download {} to WebView2",
+                    page_url, download_id
+                );
+                let started = crate::start_webview2_download(
+                    page_url,
+                    transfer.filename.clone(),
+                    download_id.clone(),
+                    article,
+                    transfer.referer.clone(),
+                    app.clone(),
+                    app.state::<RwLock<ActiveDownloads>>(),
+                )
+                .await;
+                match started {
+                    Ok(()) => return,
+                    Err(e) => serde_json::json!({
+                        "status": "error",
+                        "message": e,
+                        "downloadId": download_id
+                    }),
+                }
+            }
+            Err(e) => serde_json::json!({
+                "status": "error",
+                "message": e,
+                "downloadId": download_id
+            }),
+        };
+        nativedownload::report(&app, status).await;
+    });
+}
+
+// Continues an interrupted or failed download from its .part file with a Range request. The
+// file's own address is used when the helper reported one; hosts that need a browser and never
+// gave one are started again through WebView2.
+pub async fn resume(app: &AppHandle, download_id: &str) -> Result<String, String> {
+    offline::ensure_online(app)?;
+    let download = app
+        .state::<RwLock<ActiveDownloads>>()
+        .read()
+        .map_err(|e| format!("Failed to lock active downloads: {}", e))?
+        .downloads
+        .get(download_id)
+        .cloned()
+        .ok_or_else(|| format!("No download found for id: {}", download_id))?;
+    let resumable = match download.status.as_str() {
+        "interrupted" | "failed" => true,
+        "paused" => download.provider.as_deref() == Some("native"),
+        _ => false,
+    };
+    if !resumable {
+        return Err(format!(
+            "Download is {}, nothing to resume",
+            download.status
+        ));
+    }
+    let url = match download.source_url.clone() {
+        Some(url) => url,
+        None if needs_browser(app, &download.url) => {
+            let options = options_of(&download);
+            return start(
+                app,
+                download.url,
+                download.filename,
+                download.id,
+                download.article,
+                download.referer,
+                options,
+            )
+            .await;
+        }
+        None => download.url.clone(),
+    };
+    bandwidth::ensure_under_cap(app)?;
+    let part_path = match &download.part_path {
+        Some(path) => PathBuf::from(path),
+        None => Path::new(&crate::get_download_dir(app.clone())?).join(format!(
+            "{}{}",
+            download.filename,
+            diskio::PART_SUFFIX
+        )),
+    };
+    // A split download's .part file is full size from the start
+    let offset = segmented::downloaded(&part_path)
+        .unwrap_or_else(|| std::fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0));
+
+    let token = CancellationToken::new();
+    {
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        let mut downloads = active_downloads
+            .write()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        if let Some(entry) = downloads.downloads.get_mut(download_id) {
+            entry.provider = Some("native".to_string());
+            entry.status = "downloading".to_string();
+            entry.error = None;
+            entry.part_path = Some(part_path.to_string_lossy().to_string());
+            entry.bytes_downloaded = Some(offset);
+        }
+        downloads
+            .tokens
+            .insert(download_id.to_string(), token.clone());
+        save_active_downloads_to_file(app, &downloads)?;
+    }
+    let detail = format!("from byte {}", offset);
+    timeline::record(
+        app,
+        download_id,
+        "download",
+        "resuming",
+        "user",
+        Some(&detail),
+    );
+    println!("Resuming download {} from byte {}", download_id, offset);
+
+    let transfer = Transfer {
+        url,
+        part_path,
+        filename: download.filename,
+        referer: download.referer,
+        status: "downloading".to_string(),
+    };
+    spawn(
+        app.clone(),
+        download_id.to_string(),
+        transfer,
+        token,
+        download.url,
+        download.article,
+    );
+    Ok("native".to_string())
+}
+
+fn provider(app: &AppHandle, download_id: &str) -> Result<Option<String>, String> {
+    Ok(app
+        .state::<RwLock<ActiveDownloads>>()
+        .read()
+        .map_err(|e| format!("Failed to lock active downloads: {}", e))?
+        .downloads
+        .get(download_id)
+        .ok_or_else(|| format!("No download found for id: {}", download_id))?
+        .provider
+        .clone())
+}
+
+// WebView2 transfers are paused in the helper. A native transfer is stopped instead; its .part
+// file stays and unpause() continues it with a Range request. `source` ends up in the timeline.
+pub async fn pause(app: &AppHandle, download_id: &str, source: &str) -> Result<(), String> {
+    match provider(app, download_id)?.as_deref() {
+        Some("webview2") => sidecar::pause_download(app, download_id).await,
+        Some("native") => {
+            {
+                let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+                let mut downloads = active_downloads
+                    .write()
+                    .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+                if let Some(token) = downloads.tokens.remove(download_id) {
+                    token.cancel();
+                }
+                if let Some(download) = downloads.downloads.get_mut(download_id) {
+                    download.status = "paused".to_string();
+                }
+                save_active_downloads_to_file(app, &downloads)?;
+            }
+            timeline::record(app, download_id, "download", "paused", source, None);
+            let _ = app.emit("download-paused", &serde_json::json!({ "id": download_id }));
+            Ok(())
+        }
+        _ => Err("This download cannot be paused".to_string()),
+    }
+}
+
+pub async fn unpause(app: &AppHandle, download_id: &str) -> Result<(), String> {
+    match provider(app, download_id)?.as_deref() {
+        Some("native") => resume(app, download_id).await.map(|_| ()),
+        _ => {
+            sidecar::resume_download(app, download_id).await?;
+            // The helper only reports progress again once bytes arrive
+            let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+            let mut downloads = active_downloads
+                .write()
+                .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+            if let Some(download) = downloads.downloads.get_mut(download_id)
+                && download.status == "paused"
+            {
+                download.status = "downloading".to_string();
+            }
+            save_active_downloads_to_file(app, &downloads)
+        }
+    }
+}
+
+fn set_paused_at(app: &AppHandle, download_id: &str, at: Option<String>) -> Result<(), String> {
+    let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+    let mut downloads = active_downloads
+        .write()
+        .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+    if let Some(download) = downloads.downloads.get_mut(download_id) {
+        if at.is_some() {
+            download.status = "paused".to_string();
+        }
+        download.paused_at = at;
+    }
+    // A stopped native transfer gives up its token; cancel_active_download needs one
+    downloads
+        .tokens
+        .entry(download_id.to_string())
+        .or_insert_with(CancellationToken::new);
+    save_active_downloads_to_file(app, &downloads)
+}
+
+// Pause the user asked for. Unlike the automatic ones it is kept across restarts, and the
+// scheduler leaves the download alone until resume_paused(). A queued download is simply held back.
+pub async fn pause_by_user(app: &AppHandle, download_id: &str) -> Result<(), String> {
+    let status = app
+        .state::<RwLock<ActiveDownloads>>()
+        .read()
+        .map_err(|e| format!("Failed to lock active downloads: {}", e))?
+        .downloads
+        .get(download_id)
+        .map(|d| d.status.clone())
+        .ok_or_else(|| format!("No download found for id: {}", download_id))?;
+    match status.as_str() {
+        scheduler::QUEUED => {
+            timeline::record(app, download_id, "download", "paused", "user", None);
+            let _ = app.emit("download-paused", &serde_json::json!({ "id": download_id }));
+        }
+        "starting" | "downloading" => pause(app, download_id, "user").await?,
+        // Paused by the scheduler, battery or idle rules; from now on only the user resumes it
+        "paused" => {}
+        _ => return Err(format!("Download is {}, nothing to pause", status)),
+    }
+    // The helper confirms its pause later; the slot is free from now on
+    set_paused_at(app, download_id, Some(chrono::Utc::now().to_rfc3339()))?;
+    scheduler::forget(app, download_id);
+    scheduler::spawn_fill(app);
+    println!("Paused download {}", download_id);
+    Ok(())
+}
+
+// Continues a paused download. Without a free slot it waits for one like a queued download,
+// keeping its place by priority. Returns the provider, or "queued".
+pub async fn resume_paused(app: &AppHandle, download_id: &str) -> Result<String, String> {
+    let download = app
+        .state::<RwLock<ActiveDownloads>>()
+        .read()
+        .map_err(|e| format!("Failed to lock active downloads: {}", e))?
+        .downloads
+        .get(download_id)
+        .cloned()
+        .ok_or_else(|| format!("No download found for id: {}", download_id))?;
+    if download.status != "paused" {
+        return Err(format!(
+            "Download is {}, nothing to resume",
+            download.status
+        ));
+    }
+    offline::ensure_online(app)?;
+    set_paused_at(app, download_id, None)?;
+    timeline::record(app, download_id, "event", "resumed", "user", None);
+    // Paused before it ever got a slot
+    if download.provider.is_none() {
+        let options = options_of(&download);
+        return start(
+            app,
+            download.url,
+            download.filename,
+            download.id,
+            download.article,
+            download.referer,
+            options,
+        )
+        .await;
+    }
+    if scheduler::wait_for_slot(app, download_id)? {
+        return Ok(scheduler::QUEUED.to_string());
+    }
+    unpause(app, download_id).await?;
+    Ok(download.provider.unwrap_or_default())
+}
+
+// Pausable transfers that are moving right now
+fn running(app: &AppHandle) -> Vec<String> {
+    match app.state::<RwLock<ActiveDownloads>>().read() {
+        Ok(downloads) => downloads
+            .downloads
+            .values()
+            .filter(|d| matches!(d.provider.as_deref(), Some("webview2" | "native")))
+            .filter(|d| d.status == "downloading")
+            .map(|d| d.id.clone())
+            .collect(),
+        Err(e) => {
+            println!("Failed to lock active downloads: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+// Pauses every running transfer and returns the ones that were paused
+pub async fn pause_running(app: &AppHandle, source: &str) -> Vec<String> {
+    let mut paused = Vec::new();
+    for download_id in running(app) {
+        match pause(app, &download_id, source).await {
+            Ok(()) => paused.push(download_id),
+            Err(e) => println!("Failed to pause download {}: {}", download_id, e),
+        }
+    }
+    paused
+}
+
+// Resumes what pause_running() paused, skipping anything cancelled, resumed or paused by hand since
+pub async fn unpause_all(app: &AppHandle, ids: Vec<String>, source: &str) -> Vec<String> {
+    let mut resumed = Vec::new();
+    for download_id in ids {
+        let still_paused = app
+            .state::<RwLock<ActiveDownloads>>()
+            .read()
+            .is_ok_and(|downloads| {
+                downloads
+                    .downloads
+                    .get(&download_id)
+                    .is_some_and(|d| d.status == "paused" && d.paused_at.is_none())
+            });
+        if !still_paused {
+            continue;
+        }
+        // While the pause lasted other downloads may have taken the slots; the scheduler continues it
+        match scheduler::wait_for_slot(app, &download_id) {
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(e) => println!("{}", e),
+        }
+        match unpause(app, &download_id).await {
+            Ok(()) => {
+                timeline::record(app, &download_id, "event", "resumed", source, None);
+                resumed.push(download_id);
+            }
+            Err(e) => println!("Failed to resume download {}: {}", download_id, e),
+        }
+    }
+    resumed
+}
+
+// Per-download choices that stay with it through the queue, mirror switches and resumes
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+#[serde(default)]
+pub struct TransferOptions {
+    pub priority: Priority,
+    // Parallel connections for large files on hosts that accept ranges; None uses the setting
+    pub segments: Option<u8>,
+}
+
+pub fn options_of(download: &DownloadInfo) -> TransferOptions {
+    TransferOptions {
+        priority: download.priority,
+        segments: download.segments,
+    }
+}
+
+// Optional extras for start_download
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct StartOptions {
+    // Fallback links for the same file, tried in order when the previous one fails
+    pub mirrors: Vec<String>,
+    // Per-chunk hashes to check the file against while it downloads
+    #[serde(rename = "chunkManifest")]
+    pub chunk_manifest: Option<ChunkManifest>,
+    #[serde(flatten)]
+    pub transfer: TransferOptions,
+}
+
+// Links still to try for a download, with what is needed to start it again on the next one
+pub struct MirrorJob {
+    filename: String,
+    article: Option<ArticleMeta>,
+    referer: Option<String>,
+    remaining: VecDeque<String>,
+}
+
+// Mirror lists by download id, dropped once a download completes, is cancelled or runs out of links
+#[derive(Default)]
+pub struct MirrorQueue(HashMap<String, MirrorJob>);
+
+fn next_mirror(app: &AppHandle, download_id: &str) -> Option<String> {
+    let queue = app.state::<Mutex<MirrorQueue>>();
+    let mut queue = queue.lock().ok()?;
+    let job = queue.0.get_mut(download_id)?;
+    let next = job.remaining.pop_front();
+    if next.is_none() {
+        queue.0.remove(download_id);
+    }
+    next
+}
+
+pub fn forget_mirrors(app: &AppHandle, download_id: &str) {
+    if let Ok(mut queue) = app.state::<Mutex<MirrorQueue>>().lock() {
+        queue.0.remove(download_id);
+    }
+}
+
+// Like start(), with fallback links tried in order when one fails, whether it fails to start or
+// partway through. DownloadInfo.url and provider end up naming the link and engine that worked.
+pub async fn start_mirrored(
+    app: &AppHandle,
+    urls: Vec<String>,
+    filename: String,
+    download_id: String,
+    article: Option<ArticleMeta>,
+    referer: Option<String>,
+    options: TransferOptions,
+) -> Result<String, String> {
+    let mut remaining: VecDeque<String> = VecDeque::new();
+    for url in urls.iter().map(|u| u.trim()).filter(|u| !u.is_empty()) {
+        if !remaining.iter().any(|u| u == url) {
+            remaining.push_back(url.to_string());
+        }
+    }
+    let mut url = remaining.pop_front().ok_or("No download links given")?;
+    if offline::is_offline(app) {
+        offline::queue_download(
+            app,
+            offline::QueuedDownload {
+                download_id,
+                url,
+                filename,
+                article,
+                referer,
+                queued_at: chrono::Utc::now().to_rfc3339(),
+                mirrors: remaining.into(),
+                options,
+            },
+        )?;
+        return Ok(offline::QUEUED_PROVIDER.to_string());
+    }
+    if remaining.is_empty() {
+        forget_mirrors(app, &download_id);
+    } else {
+        let job = MirrorJob {
+            filename: filename.clone(),
+            article: article.clone(),
+            referer: referer.clone(),
+            remaining,
+        };
+        app.state::<Mutex<MirrorQueue>>()
+            .lock()
+            .map_err(|e| format!("Failed to lock mirror queue: {}", e))?
+            .0
+            .insert(download_id.clone(), job);
+    }
+    loop {
+        let started = start(
+            app,
+            url.clone(),
+            filename.clone(),
+            download_id.clone(),
+            article.clone(),
+            referer.clone(),
+            options,
+        )
+        .await;
+        match started {
+            Err(e) => match next_mirror(app, &download_id) {
+                Some(next) => {
+                    println!("Mirror {} failed to start: {}, trying {}", url, e, next);
+                    url = next;
+                }
+                None => return Err(e),
+            },
+            started => return started,
+        }
+    }
+}
+
+// Called when a transfer fails; moves the download to its next mirror. Returns false when no
+// mirror is left, in which case the failure is reported as usual.
+pub fn try_next_mirror(app: &AppHandle, download_id: &str, error: Option<&str>) -> bool {
+    let Some(url) = next_mirror(app, download_id) else {
+        return false;
+    };
+    let job = app
+        .state::<Mutex<MirrorQueue>>()
+        .lock()
+        .ok()
+        .and_then(|queue| {
+            queue.0.get(download_id).map(|job| {
+                (
+                    job.filename.clone(),
+                    job.article.clone(),
+                    job.referer.clone(),
+                )
+            })
+        });
+    let Some((filename, article, referer)) = job else {
+        return false;
+    };
+    println!(
+        "Download {} failed ({}), switching to mirror {}",
+        download_id,
+        error.unwrap_or("unknown error"),
+        url
+    );
+    timeline::record(
+        app,
+        download_id,
+        "event",
+        "switching mirror",
+        "mirror",
+        Some(&url),
+    );
+    let _ = app.emit(
+        "download-mirror-switched",
+        &serde_json::json!({ "id": download_id, "url": url, "error": error }),
+    );
+    let app = app.clone();
+    let download_id = download_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        // Read here rather than above, where the status handler still holds the downloads lock
+        let options = app
+            .state::<RwLock<ActiveDownloads>>()
+            .read()
+            .ok()
+            .and_then(|downloads| downloads.downloads.get(&download_id).map(options_of))
+            .unwrap_or_default();
+        let started = start(
+            &app,
+            url,
+            filename,
+            download_id.clone(),
+            article,
+            referer,
+            options,
+        )
+        .await;
+        // Reported like any other failure, which moves on to the mirror after this one
+        if let Err(e) = started {
+            nativedownload::report(
+                &app,
+                serde_json::json!({
+                    "status": "error",
+                    "message": e,
+                    "downloadId": download_id
+                }),
+            )
+            .await;
+        }
+    });
+    true
+}
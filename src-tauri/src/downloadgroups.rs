@@ -0,0 +1,376 @@
+use crate::state::save_active_downloads_to_file;
+use crate::{ActiveDownloads, DownloadInfo, bulk, diskio, timeline};
+use regex::Regex;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
+use tauri::{AppHandle, Emitter, Manager};
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SplitKind {
+    // name.part1.rar, name.part2.rar: the first volume pulls in the rest when extracted
+    RarVolumes,
+    // name.7z.001, name.7z.002: a plain byte split that has to be joined before extracting
+    Numbered,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SplitPart {
+    // File name without the part suffix, e.g. "game" for game.part2.rar or "game.7z" for game.7z.002
+    pub base: String,
+    pub number: u32,
+    pub kind: SplitKind,
+}
+
+static SPLIT_PATTERNS: LazyLock<Vec<(Regex, SplitKind)>> = LazyLock::new(|| {
+    [
+        (r"(?i)^(.+)\.part0*(\d+)\.rar$", SplitKind::RarVolumes),
+        (
+            r"(?i)^(.+\.(?:7z|zip|rar|tar))\.0*(\d+)$",
+            SplitKind::Numbered,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(pattern, kind)| Regex::new(pattern).ok().map(|regex| (regex, kind)))
+    .collect()
+});
+
+pub fn split_part(filename: &str) -> Option<SplitPart> {
+    SPLIT_PATTERNS.iter().find_map(|(pattern, kind)| {
+        let captures = pattern.captures(filename)?;
+        Some(SplitPart {
+            base: captures[1].to_string(),
+            number: captures[2].parse().ok()?,
+            kind: *kind,
+        })
+    })
+}
+
+// Every download of one article, shown as a single entry with combined progress
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadGroup {
+    pub slug: String,
+    pub title: Option<String>,
+    pub cover_image: Option<String>,
+    // Part order for split releases, file name order otherwise
+    pub download_ids: Vec<String>,
+    pub completed: usize,
+    pub failed: usize,
+    // Mean of the members' progress
+    pub progress: f32,
+    // "downloading", "failed", "completed" or "extracted"
+    pub status: String,
+    // Set when every member is a volume of the same split archive
+    pub split: Option<SplitKind>,
+}
+
+// The first member's part when every member is a volume of the same archive
+fn split_of(members: &[&DownloadInfo]) -> Option<SplitPart> {
+    let first = split_part(&members.first()?.filename)?;
+    members
+        .iter()
+        .all(|d| {
+            split_part(&d.filename).is_some_and(|p| p.kind == first.kind && p.base == first.base)
+        })
+        .then_some(first)
+}
+
+fn part_number(download: &DownloadInfo) -> u32 {
+    split_part(&download.filename)
+        .map(|p| p.number)
+        .unwrap_or_default()
+}
+
+fn group(slug: &str, mut members: Vec<&DownloadInfo>) -> DownloadGroup {
+    members.sort_by(|a, b| {
+        part_number(a)
+            .cmp(&part_number(b))
+            .then_with(|| a.filename.cmp(&b.filename))
+    });
+    let article = members.iter().find_map(|d| d.article.as_ref());
+    let completed = members.iter().filter(|d| d.status == "completed").count();
+    let failed = members
+        .iter()
+        .filter(|d| matches!(d.status.as_str(), "failed" | "cancelled" | "interrupted"))
+        .count();
+    let progress = members
+        .iter()
+        .map(|d| {
+            if d.status == "completed" {
+                100.0
+            } else {
+                d.progress
+            }
+        })
+        .sum::<f32>()
+        / members.len() as f32;
+    let status = if failed > 0 {
+        "failed"
+    } else if completed < members.len() {
+        "downloading"
+    } else if members.iter().all(|d| d.extracted) {
+        "extracted"
+    } else {
+        "completed"
+    };
+    DownloadGroup {
+        slug: slug.to_string(),
+        title: article.and_then(|a| a.title.clone()),
+        cover_image: article.and_then(|a| a.cover_image.clone()),
+        download_ids: members.iter().map(|d| d.id.clone()).collect(),
+        completed,
+        failed,
+        progress,
+        status: status.to_string(),
+        split: split_of(&members).map(|p| p.kind),
+    }
+}
+
+// Only articles with more than one download; a single file is already its own entry
+pub fn list(downloads: &ActiveDownloads) -> Vec<DownloadGroup> {
+    let mut by_slug: Vec<(&str, Vec<&DownloadInfo>)> = Vec::new();
+    for download in downloads.downloads.values() {
+        let Some(slug) = download.article.as_ref().map(|a| a.slug.as_str()) else {
+            continue;
+        };
+        match by_slug.iter_mut().find(|(s, _)| *s == slug) {
+            Some((_, members)) => members.push(download),
+            None => by_slug.push((slug, vec![download])),
+        }
+    }
+    let mut groups: Vec<DownloadGroup> = by_slug
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(slug, members)| group(slug, members))
+        .collect();
+    groups.sort_by(|a, b| a.slug.cmp(&b.slug));
+    groups
+}
+
+struct Member {
+    id: String,
+    path: String,
+    number: u32,
+}
+
+fn members(app: &AppHandle, ids: &[String]) -> Result<(Vec<Member>, Option<SplitPart>), String> {
+    let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+    let downloads = active_downloads
+        .read()
+        .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+    let infos = ids
+        .iter()
+        .map(|id| {
+            downloads
+                .downloads
+                .get(id)
+                .ok_or_else(|| format!("Download not found: {}", id))
+        })
+        .collect::<Result<Vec<&DownloadInfo>, String>>()?;
+    if infos.is_empty() {
+        return Err("No downloads to extract".to_string());
+    }
+    let unfinished = infos.iter().filter(|d| d.status != "completed").count();
+    if unfinished > 0 {
+        return Err(format!(
+            "{} of {} parts have not finished downloading",
+            unfinished,
+            infos.len()
+        ));
+    }
+    let split = split_of(&infos);
+    let mut members = infos
+        .iter()
+        .map(|d| {
+            Ok(Member {
+                id: d.id.clone(),
+                path: d
+                    .path
+                    .clone()
+                    .filter(|p| !p.is_empty())
+                    .ok_or_else(|| format!("Download {} has no file yet", d.id))?,
+                number: part_number(d),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    members.sort_by_key(|m| m.number);
+    Ok((members, split))
+}
+
+// Part numbers have to run 1..=n with nothing missing or doubled
+fn check_sequence(members: &[Member]) -> Result<(), String> {
+    for (i, member) in members.iter().enumerate() {
+        let expected = i as u32 + 1;
+        if member.number != expected {
+            return Err(format!("Part {} is missing", expected));
+        }
+    }
+    Ok(())
+}
+
+fn join_parts(parts: &[PathBuf], target: &Path) -> Result<(), String> {
+    let total = parts
+        .iter()
+        .map(|p| std::fs::metadata(p).map(|m| m.len()))
+        .sum::<io::Result<u64>>()
+        .map_err(|e| format!("Failed to read part size: {}", e))?;
+    diskio::ensure_space_for(target, total)?;
+    // Never truncates an existing file, e.g. the user's own archive with the same name
+    let mut output = BufWriter::new(
+        File::options()
+            .write(true)
+            .create_new(true)
+            .open(target)
+            .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?,
+    );
+    let result = parts
+        .iter()
+        .try_for_each(|part| {
+            let mut input = File::open(part)
+                .map_err(|e| format!("Failed to open {}: {}", part.display(), e))?;
+            io::copy(&mut input, &mut output)
+                .map(|_| ())
+                .map_err(|e| format!("Failed to join {}: {}", part.display(), e))
+        })
+        .and_then(|_| {
+            output
+                .flush()
+                .map_err(|e| format!("Failed to write {}: {}", target.display(), e))
+        });
+    // The file was created above, so a half-joined one is ours to remove
+    if result.is_err() {
+        drop(output);
+        let _ = std::fs::remove_file(target);
+    }
+    result
+}
+
+fn mark_extracted(app: &AppHandle, members: &[Member], output_dir: &str) -> Result<(), String> {
+    {
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        let mut downloads = active_downloads
+            .write()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        for member in members {
+            if let Some(download) = downloads.downloads.get_mut(&member.id) {
+                download.extraction_status = Some("completed".to_string());
+                download.extraction_progress = Some(100.0);
+                download.extracted = true;
+                download.extracted_path = Some(output_dir.to_string());
+            }
+        }
+        save_active_downloads_to_file(app, &downloads)?;
+    }
+    for member in members.iter().skip(1) {
+        timeline::record(
+            app,
+            &member.id,
+            "extraction",
+            "completed",
+            "extraction",
+            Some("extracted together with part 1"),
+        );
+    }
+    Ok(())
+}
+
+pub async fn extract(app: &AppHandle, slug: &str) -> Result<(), String> {
+    let ids: Vec<String> = {
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        let downloads = active_downloads
+            .read()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        downloads
+            .downloads
+            .values()
+            .filter(|d| d.article.as_ref().is_some_and(|a| a.slug == slug))
+            .map(|d| d.id.clone())
+            .collect()
+    };
+    if ids.is_empty() {
+        return Err(format!("No downloads found for {}", slug));
+    }
+    extract_downloads(app, &ids).await
+}
+
+// Split releases are joined (or opened from their first volume) and extracted once;
+// separate archives are extracted one after another
+pub async fn extract_downloads(app: &AppHandle, ids: &[String]) -> Result<(), String> {
+    let (members, split) = members(app, ids)?;
+    let Some(SplitPart { base, kind, .. }) = split else {
+        let ids = members.into_iter().map(|m| m.id).collect();
+        let failed: Vec<String> = bulk::extract(app, ids)
+            .await
+            .into_iter()
+            .filter_map(|r| r.error.map(|e| format!("{}: {}", r.id, e)))
+            .collect();
+        if failed.is_empty() {
+            return Ok(());
+        }
+        return Err(failed.join("; "));
+    };
+    check_sequence(&members)?;
+
+    let first = &members[0];
+    let dir = Path::new(&first.path)
+        .parent()
+        .ok_or_else(|| format!("{} has no parent folder", first.path))?;
+    // Archive tools only find the other volumes next to the first one
+    if members
+        .iter()
+        .any(|m| Path::new(&m.path).parent() != Some(dir))
+    {
+        return Err("All parts need to be in the same folder".to_string());
+    }
+
+    let archive = match kind {
+        SplitKind::RarVolumes => PathBuf::from(&first.path),
+        SplitKind::Numbered => {
+            // "game.7z" is joined into "game.joined.7z" (or the next free name), keeping the
+            // extension the archive tools go by
+            let joined_name = match base.rsplit_once('.') {
+                Some((stem, extension)) => format!("{}.joined.{}", stem, extension),
+                None => format!("{}.joined", base),
+            };
+            let joined = dir.join(diskio::unique_download_name(dir, &joined_name, &[]));
+            let _ = app.emit(
+                "extraction-progress",
+                &serde_json::json!({
+                    "downloadId": first.id,
+                    "status": "joining",
+                    "progress": 0.0
+                }),
+            );
+            let parts: Vec<PathBuf> = members.iter().map(|m| PathBuf::from(&m.path)).collect();
+            let target = joined.clone();
+            tauri::async_runtime::spawn_blocking(move || join_parts(&parts, &target))
+                .await
+                .map_err(|e| format!("Joining parts panicked: {}", e))??;
+            println!("Joined {} parts into {}", members.len(), joined.display());
+            joined
+        }
+    };
+    let archive_path = archive.to_string_lossy().to_string();
+    let output_dir = format!("{}_extracted", dir.join(&base).to_string_lossy());
+    let result = crate::extract_download(
+        archive_path.clone(),
+        output_dir.clone(),
+        first.id.clone(),
+        app.clone(),
+        true,
+    )
+    .await;
+    // The joined copy only doubles the disk use; the parts stay as downloaded
+    if kind == SplitKind::Numbered
+        && let Err(e) = std::fs::remove_file(&archive)
+    {
+        println!("Failed to remove joined archive {}: {}", archive_path, e);
+    }
+    result?;
+    mark_extracted(app, &members, &output_dir)
+}
@@ -0,0 +1,217 @@
+use crate::DownloadInfo;
+use crate::state;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+const HISTORY_FILE: &str = "download_history.json";
+
+// Years of normal use; the oldest entries go first
+const MAX_ENTRIES: usize = 10_000;
+
+const CSV_HEADER: &str = "id,filename,status,provider,host,sizeBytes,startedAt,finishedAt,durationSecs,averageBytesPerSec";
+
+// One finished transfer. Unlike the downloads list, this survives the user clearing finished downloads.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HistoryEntry {
+    pub id: String,
+    pub filename: String,
+    // "completed" or "failed"
+    pub status: String,
+    pub provider: Option<String>,
+    // Host only, so an export never carries signed download links
+    pub host: Option<String>,
+    #[serde(rename = "sizeBytes")]
+    pub size_bytes: Option<u64>,
+    #[serde(rename = "startedAt")]
+    pub started_at: Option<String>,
+    #[serde(rename = "finishedAt")]
+    pub finished_at: String,
+    // Wall clock time, paused time included
+    #[serde(rename = "durationSecs")]
+    pub duration_secs: Option<u64>,
+    #[serde(rename = "averageBytesPerSec")]
+    pub average_bytes_per_sec: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct DownloadHistory {
+    pub entries: Vec<HistoryEntry>,
+}
+
+// Either bound may be a date ("2024-05-01", inclusive) or an RFC 3339 timestamp
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct DateRange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+fn history_path(app: &AppHandle) -> Option<PathBuf> {
+    state::get_config_dir(app).map(|dir| dir.join(HISTORY_FILE))
+}
+
+pub fn load_history(app: &AppHandle) -> DownloadHistory {
+    history_path(app)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_history(app: &AppHandle, history: &DownloadHistory) {
+    let Some(path) = history_path(app) else {
+        return;
+    };
+    let result = serde_json::to_string(history)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        println!("Failed to save download history: {}", e);
+    }
+}
+
+// Called once a download reaches "completed" or "failed"
+pub fn record(app: &AppHandle, download: &DownloadInfo) {
+    let finished = Utc::now();
+    let size_bytes = download
+        .path
+        .as_ref()
+        .and_then(|path| fs::metadata(path).ok())
+        .map(|metadata| metadata.len());
+    let duration_secs = download
+        .started_at
+        .as_deref()
+        .and_then(|started| DateTime::parse_from_rfc3339(started).ok())
+        .map(|started| {
+            (finished - started.with_timezone(&Utc))
+                .num_seconds()
+                .max(0) as u64
+        });
+    let average_bytes_per_sec = match (size_bytes, duration_secs) {
+        (Some(size), Some(secs)) if secs > 0 && download.status == "completed" => Some(size / secs),
+        _ => None,
+    };
+    let entry = HistoryEntry {
+        id: download.id.clone(),
+        filename: download.filename.clone(),
+        status: download.status.clone(),
+        provider: download.provider.clone(),
+        host: url::Url::parse(&download.url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_string())),
+        size_bytes,
+        started_at: download.started_at.clone(),
+        finished_at: finished.to_rfc3339(),
+        duration_secs,
+        average_bytes_per_sec,
+    };
+
+    let history = app.state::<Mutex<DownloadHistory>>();
+    let Ok(mut history) = history.lock() else {
+        return;
+    };
+    // A restarted download finishes again under the same id
+    history.entries.retain(|e| e.id != entry.id);
+    history.entries.push(entry);
+    if history.entries.len() > MAX_ENTRIES {
+        let excess = history.entries.len() - MAX_ENTRIES;
+        history.entries.drain(..excess);
+    }
+    save_history(app, &history);
+}
+
+fn parse_bound(value: &str, end_of_day: bool) -> Result<DateTime<Utc>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date: {}", value))?;
+    let time = if end_of_day {
+        date.and_hms_opt(23, 59, 59)
+    } else {
+        date.and_hms_opt(0, 0, 0)
+    };
+    time.map(|t| t.and_utc())
+        .ok_or_else(|| format!("Invalid date: {}", value))
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_csv(entries: &[&HistoryEntry]) -> String {
+    let optional = |value: Option<u64>| value.map(|v| v.to_string()).unwrap_or_default();
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    for entry in entries {
+        let fields = [
+            csv_field(&entry.id),
+            csv_field(&entry.filename),
+            csv_field(&entry.status),
+            csv_field(entry.provider.as_deref().unwrap_or_default()),
+            csv_field(entry.host.as_deref().unwrap_or_default()),
+            optional(entry.size_bytes),
+            csv_field(entry.started_at.as_deref().unwrap_or_default()),
+            csv_field(&entry.finished_at),
+            optional(entry.duration_secs),
+            optional(entry.average_bytes_per_sec),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+// Writes the history finished within the range to `path` and returns how many entries it holds
+pub fn export(
+    app: &AppHandle,
+    format: &str,
+    range: &DateRange,
+    path: &str,
+) -> Result<usize, String> {
+    let from = range
+        .from
+        .as_deref()
+        .map(|from| parse_bound(from, false))
+        .transpose()?;
+    let to = range
+        .to
+        .as_deref()
+        .map(|to| parse_bound(to, true))
+        .transpose()?;
+
+    let history = app.state::<Mutex<DownloadHistory>>();
+    let history = history
+        .lock()
+        .map_err(|e| format!("Failed to lock download history: {}", e))?;
+    let entries: Vec<&HistoryEntry> = history
+        .entries
+        .iter()
+        .filter(|entry| {
+            let Ok(finished) = DateTime::parse_from_rfc3339(&entry.finished_at) else {
+                return false;
+            };
+            from.is_none_or(|from| finished >= from) && to.is_none_or(|to| finished <= to)
+        })
+        .collect();
+
+    let content = match format {
+        "csv" => to_csv(&entries),
+        "json" => serde_json::to_string_pretty(&entries)
+            .map_err(|e| format!("Failed to serialize download history: {}", e))?,
+        _ => return Err(format!("Unsupported export format: {}", format)),
+    };
+    fs::write(path, content).map_err(|e| format!("Failed to write export: {}", e))?;
+    println!(
+        "Exported {} download history entries to {}",
+        entries.len(),
+        path
+    );
+    Ok(entries.len())
+}
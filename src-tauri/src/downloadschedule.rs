@@ -0,0 +1,163 @@
+use crate::downloader::{self, TransferOptions};
+use crate::state::{self, ArticleMeta};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+const SCHEDULE_FILE: &str = "scheduled_downloads.json";
+
+// Start times are checked this often, so a download starts at most this late
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+// Held for every read-modify-write of SCHEDULE_FILE, so a command and the due check cannot
+// overwrite each other's changes
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+// Download that starts by itself at `scheduled_at`, e.g. at night when the ISP does not throttle
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledDownload {
+    pub download_id: String,
+    pub url: String,
+    pub filename: String,
+    #[serde(default)]
+    pub article: Option<ArticleMeta>,
+    #[serde(default)]
+    pub referer: Option<String>,
+    // RFC 3339; one already past starts on the next check, also right after the app starts
+    pub scheduled_at: String,
+    // Fallback links tried after `url`, see downloader::start_mirrored
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+    #[serde(flatten)]
+    pub options: TransferOptions,
+}
+
+impl ScheduledDownload {
+    fn due_at(&self) -> Result<DateTime<Utc>, String> {
+        DateTime::parse_from_rfc3339(&self.scheduled_at)
+            .map(|at| at.with_timezone(&Utc))
+            .map_err(|e| format!("Invalid start time {}: {}", self.scheduled_at, e))
+    }
+}
+
+fn schedule_path(app: &AppHandle) -> Option<PathBuf> {
+    state::get_config_dir(app).map(|dir| dir.join(SCHEDULE_FILE))
+}
+
+// Soonest first
+pub fn load(app: &AppHandle) -> Vec<ScheduledDownload> {
+    let mut scheduled: Vec<ScheduledDownload> = schedule_path(app)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    scheduled.sort_by_key(|d| d.due_at().ok());
+    scheduled
+}
+
+fn save(app: &AppHandle, scheduled: &[ScheduledDownload]) -> Result<(), String> {
+    let path = schedule_path(app).ok_or("Failed to get config dir")?;
+    let json = serde_json::to_string_pretty(scheduled)
+        .map_err(|e| format!("Failed to serialize scheduled downloads: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to save scheduled downloads: {}", e))
+}
+
+// Scheduling the same id again moves it to the new time
+pub fn schedule(app: &AppHandle, download: ScheduledDownload) -> Result<(), String> {
+    let due_at = download.due_at()?;
+    let _guard = FILE_LOCK
+        .lock()
+        .map_err(|e| format!("Failed to lock scheduled downloads: {}", e))?;
+    let mut scheduled = load(app);
+    scheduled.retain(|d| d.download_id != download.download_id);
+    println!(
+        "Scheduled download id={} for {}",
+        download.download_id,
+        due_at.to_rfc3339()
+    );
+    let _ = app.emit("download-scheduled", &download);
+    scheduled.push(download);
+    save(app, &scheduled)
+}
+
+pub fn unschedule(app: &AppHandle, download_id: &str) -> Result<bool, String> {
+    let _guard = FILE_LOCK
+        .lock()
+        .map_err(|e| format!("Failed to lock scheduled downloads: {}", e))?;
+    let mut scheduled = load(app);
+    let before = scheduled.len();
+    scheduled.retain(|d| d.download_id != download_id);
+    if scheduled.len() == before {
+        return Ok(false);
+    }
+    save(app, &scheduled)?;
+    let _ = app.emit(
+        "download-unscheduled",
+        &serde_json::json!({ "id": download_id }),
+    );
+    Ok(true)
+}
+
+// Taken off the schedule before they start, so a slow start cannot fire the same download twice
+fn take_due(app: &AppHandle) -> Option<Vec<ScheduledDownload>> {
+    let _guard = FILE_LOCK.lock().ok()?;
+    let now = Utc::now();
+    let (due, waiting): (Vec<_>, Vec<_>) = load(app)
+        .into_iter()
+        .partition(|d| d.due_at().ok().is_none_or(|at| at <= now));
+    if due.is_empty() {
+        return None;
+    }
+    if let Err(e) = save(app, &waiting) {
+        println!("{}", e);
+        return None;
+    }
+    Some(due)
+}
+
+// Starts everything that is due. From here the download goes through downloader::start like any
+// other, so slot limits, offline mode and the usual progress events apply.
+async fn start_due(app: &AppHandle) {
+    let Some(due) = take_due(app) else {
+        return;
+    };
+    for download in due {
+        let download_id = download.download_id.clone();
+        let urls = std::iter::once(download.url)
+            .chain(download.mirrors)
+            .collect();
+        let result = downloader::start_mirrored(
+            app,
+            urls,
+            download.filename,
+            download.download_id,
+            download.article,
+            download.referer,
+            download.options,
+        )
+        .await;
+        match result {
+            Ok(provider) => println!("Started scheduled download {} ({})", download_id, provider),
+            Err(e) => {
+                println!("Failed to start scheduled download {}: {}", download_id, e);
+                let _ = app.emit(
+                    "download-error",
+                    &serde_json::json!({ "id": download_id, "error": e }),
+                );
+            }
+        }
+    }
+}
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            start_due(&app).await;
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
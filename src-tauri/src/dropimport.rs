@@ -0,0 +1,169 @@
+use crate::state::{AppState, ArticleMeta, save_active_downloads_to_file};
+use crate::{ActiveDownloads, archiver, articlecache, diskio, httpconfig};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use tauri::{AppHandle, Emitter, Manager};
+
+fn file_stem(filename: &str) -> String {
+    Path::new(filename)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| filename.to_string())
+}
+
+// Same archive downloaded before (or re-dropped), so its article is already known
+fn known_article(app: &AppHandle, filename: &str) -> Option<ArticleMeta> {
+    if let Ok(downloads) = app.state::<RwLock<ActiveDownloads>>().read()
+        && let Some(article) = downloads
+            .downloads
+            .values()
+            .filter(|d| d.filename.eq_ignore_ascii_case(filename))
+            .find_map(|d| d.article.clone())
+    {
+        return Some(article);
+    }
+
+    let app_state = app.state::<Mutex<AppState>>();
+    let app_state = app_state.lock().ok()?;
+    app_state
+        .games
+        .iter()
+        .flatten()
+        .filter(|g| g.filename.eq_ignore_ascii_case(filename))
+        .find_map(|g| g.article.clone())
+}
+
+// "My_Game v1.2.zip" -> "my-game"; version suffixes never appear in article slugs
+fn guess_slug(filename: &str) -> Option<String> {
+    let version = regex::Regex::new(r"(?i)[\s_\-.]*v?\d+(\.\d+)+[a-z]?\s*$").ok()?;
+    let stem = file_stem(filename);
+    let stem = version.replace(&stem, "");
+    let slug = stem
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    (!slug.is_empty()).then_some(slug)
+}
+
+async fn infer_article(app: &AppHandle, filename: &str) -> Option<ArticleMeta> {
+    if let Some(article) = known_article(app, filename) {
+        return Some(article);
+    }
+
+    let slug = guess_slug(filename)?;
+    let token = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .ok()
+        .and_then(|app_state| app_state.token.clone());
+    let client = httpconfig::client(app).ok()?;
+    match articlecache::fetch_article(app, &client, &slug, token).await {
+        Ok(article) => Some(ArticleMeta {
+            slug,
+            title: Some(article.title),
+            cover_image: article.main_image,
+            version: article.version,
+        }),
+        Err(_) => {
+            println!("No article found for dropped file {}", filename);
+            None
+        }
+    }
+}
+
+// `extract_now` skips the prompt, for "Extract with Chanomhub" from the file manager
+pub async fn import_archive(
+    app: &AppHandle,
+    download_id: String,
+    path: &Path,
+    extract_now: bool,
+) -> Result<(), String> {
+    let path_str = path.to_string_lossy().to_string();
+    let filename = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or("Dropped path has no file name")?;
+    diskio::ensure_not_partial(&path_str)?;
+
+    crate::register_manual_download(
+        download_id.clone(),
+        filename.clone(),
+        path_str.clone(),
+        app.state::<RwLock<ActiveDownloads>>(),
+        app.clone(),
+    )?;
+
+    let article = infer_article(app, &filename).await;
+    if article.is_some() {
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        let mut downloads = active_downloads
+            .write()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        if let Some(download) = downloads.downloads.get_mut(&download_id) {
+            download.article = article.clone();
+        }
+        save_active_downloads_to_file(app, &downloads)?;
+    }
+
+    let _ = app.emit(
+        "download-imported",
+        &serde_json::json!({
+            "id": download_id,
+            "filename": filename,
+            "path": path_str,
+            "article": article
+        }),
+    );
+
+    // วางไฟล์เองถือว่าต้องการแตกไฟล์ ถ้าไม่ได้ตั้งเป็น auto ก็ถามก่อน
+    let auto = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .map(|app_state| app_state.extraction_settings.action_for(&path_str) == "auto")
+        .unwrap_or(false);
+    if extract_now {
+        let output_dir = format!("{}_extracted", path_str);
+        crate::extract_download(path_str, output_dir, download_id, app.clone(), true).await?;
+    } else if auto {
+        crate::apply_extraction_rule(app, &download_id, &path_str, &filename);
+    } else {
+        let _ = app.emit(
+            "extraction-prompt",
+            &serde_json::json!({
+                "id": download_id,
+                "filename": filename,
+                "path": path_str
+            }),
+        );
+    }
+    Ok(())
+}
+
+// Registers archives dropped onto a window as completed downloads
+pub async fn import_dropped(app: AppHandle, paths: Vec<PathBuf>) {
+    let stamp = chrono::Utc::now().timestamp_millis();
+    for (index, path) in paths.iter().enumerate() {
+        if !path.is_file() || !archiver::is_supported_archive(&path.to_string_lossy()) {
+            println!("Ignoring dropped file: {}", path.display());
+            continue;
+        }
+        let download_id = format!("drop_{}_{}", stamp, index);
+        println!(
+            "Importing dropped archive {} as {}",
+            path.display(),
+            download_id
+        );
+        if let Err(e) = import_archive(&app, download_id.clone(), path, false).await {
+            println!("Failed to import dropped file {}: {}", path.display(), e);
+            let _ = app.emit(
+                "download-import-failed",
+                &serde_json::json!({
+                    "path": path.to_string_lossy(),
+                    "error": e
+                }),
+            );
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command as StdCommand, Stdio};
+
+// Printed by the wrapper once the user has allowed the elevation and the game is starting
+const STARTED_MARKER: &str = "chanomhub-elevated-started";
+
+// ERROR_CANCELLED, what Start-Process reports when the UAC prompt is declined
+#[cfg(target_os = "windows")]
+const DECLINED_EXIT_CODES: &[i32] = &[1223];
+
+// pkexec: 126 when the dialog was dismissed, 127 when not authorized
+#[cfg(not(target_os = "windows"))]
+const DECLINED_EXIT_CODES: &[i32] = &[126, 127];
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+fn working_dir(command: &StdCommand) -> String {
+    command
+        .get_current_dir()
+        .or_else(|| std::path::Path::new(command.get_program()).parent())
+        .map(|dir| dir.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "windows")]
+fn ps_quote(value: &std::ffi::OsStr) -> String {
+    format!("'{}'", value.to_string_lossy().replace('\'', "''"))
+}
+
+// Start-Process -Verb RunAs is ShellExecute "runas". The PowerShell host stays alive until the game
+// exits, so session tracking and exit hooks work as usual.
+#[cfg(target_os = "windows")]
+pub fn wrap(command: StdCommand) -> Result<StdCommand, String> {
+    use std::ffi::OsStr;
+    use std::os::windows::process::CommandExt;
+
+    if command.get_envs().next().is_some() {
+        // ShellExecute starts the elevated process with a fresh environment
+        println!("Environment overrides are not passed to elevated launches");
+    }
+    // Arguments already wrapped in quotes (the raw cmd /C line) are passed through as they are
+    let arguments: Vec<String> = command
+        .get_args()
+        .map(|arg| {
+            let arg = arg.to_string_lossy();
+            if arg.starts_with('"') || !arg.contains([' ', '\t']) {
+                arg.to_string()
+            } else {
+                format!("\"{}\"", arg)
+            }
+        })
+        .collect();
+    let argument_list = if arguments.is_empty() {
+        String::new()
+    } else {
+        format!(
+            " -ArgumentList {}",
+            ps_quote(OsStr::new(&arguments.join(" ")))
+        )
+    };
+    let script = format!(
+        "try {{ $p = Start-Process -FilePath {} -WorkingDirectory {}{} -Verb RunAs -PassThru -ErrorAction Stop }} \
+         catch {{ [Console]::Error.WriteLine($_.Exception.Message); exit 1223 }}; \
+         Write-Output '{}'; $p.WaitForExit(); exit $p.ExitCode",
+        ps_quote(command.get_program()),
+        ps_quote(OsStr::new(&working_dir(&command))),
+        argument_list,
+        STARTED_MARKER
+    );
+    let mut wrapped = StdCommand::new("powershell");
+    wrapped
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .creation_flags(CREATE_NO_WINDOW)
+        .stdout(Stdio::piped());
+    Ok(wrapped)
+}
+
+// pkexec clears the environment and the working directory, so both are handed over explicitly
+#[cfg(not(target_os = "windows"))]
+pub fn wrap(command: StdCommand) -> Result<StdCommand, String> {
+    if crate::archivetools::find_in_path(&["pkexec"]).is_none() {
+        return Err(
+            "Running as administrator needs pkexec (polkit), which is not installed".into(),
+        );
+    }
+    let mut wrapped = StdCommand::new("pkexec");
+    wrapped.args([
+        "/bin/sh",
+        "-c",
+        &format!(
+            "cd \"$1\" || exit 1; shift; echo {}; exec \"$@\" >&2",
+            STARTED_MARKER
+        ),
+        "sh",
+    ]);
+    wrapped.arg(working_dir(&command)).arg("env");
+    for name in [
+        "DISPLAY",
+        "XAUTHORITY",
+        "WAYLAND_DISPLAY",
+        "XDG_RUNTIME_DIR",
+    ] {
+        if let Some(value) = std::env::var_os(name) {
+            let mut pair = std::ffi::OsString::from(format!("{}=", name));
+            pair.push(value);
+            wrapped.arg(pair);
+        }
+    }
+    for (name, value) in command.get_envs() {
+        if let Some(value) = value {
+            let mut pair = name.to_os_string();
+            pair.push("=");
+            pair.push(value);
+            wrapped.arg(pair);
+        }
+    }
+    wrapped
+        .arg(command.get_program())
+        .args(command.get_args())
+        .stdout(Stdio::piped());
+    Ok(wrapped)
+}
+
+// Blocks until the prompt is answered: Ok once the game is starting, Err when it was declined
+pub fn wait_until_started(child: &mut Child) -> Result<(), String> {
+    let Some(stdout) = child.stdout.take() else {
+        return Ok(());
+    };
+    let mut line = String::new();
+    let _ = BufReader::new(stdout).read_line(&mut line);
+    if line.trim() == STARTED_MARKER {
+        return Ok(());
+    }
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for elevation: {}", e))?;
+    match status.code() {
+        Some(code) if DECLINED_EXIT_CODES.contains(&code) => {
+            Err("Administrator permission was declined, so the game was not started".to_string())
+        }
+        _ => Err(format!(
+            "Could not start the game as administrator ({})",
+            status
+        )),
+    }
+}
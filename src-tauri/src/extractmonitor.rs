@@ -0,0 +1,187 @@
+use crate::archiver::IoCounters;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+
+// Read and write rates over the last sample. High reads with low writes points at the disk,
+// low rates on both while a core is busy points at decompression.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct Throughput {
+    // None when the extractor cannot see archive reads (RAR goes through the external unrar)
+    #[serde(rename = "readMbPerSec")]
+    pub read_mb_per_sec: Option<f64>,
+    #[serde(rename = "writtenMbPerSec")]
+    pub written_mb_per_sec: f64,
+    #[serde(rename = "readBytes")]
+    pub read_bytes: u64,
+    #[serde(rename = "writtenBytes")]
+    pub written_bytes: u64,
+}
+
+// Samples the extractor's counters once a second and emits "extraction-throughput" until dropped
+pub struct ExtractionMonitor {
+    counters: Arc<IoCounters>,
+    latest: Arc<Mutex<Throughput>>,
+    done: Arc<AtomicBool>,
+}
+
+fn rate(bytes: u64, elapsed: Duration) -> f64 {
+    let mb_per_sec = bytes as f64 / BYTES_PER_MB / elapsed.as_secs_f64();
+    (mb_per_sec * 10.0).round() / 10.0
+}
+
+impl ExtractionMonitor {
+    pub fn start(app: AppHandle, download_id: String) -> Self {
+        let monitor = Self {
+            counters: Arc::new(IoCounters::default()),
+            latest: Arc::new(Mutex::new(Throughput::default())),
+            done: Arc::new(AtomicBool::new(false)),
+        };
+        let counters = monitor.counters.clone();
+        let latest = monitor.latest.clone();
+        let done = monitor.done.clone();
+
+        tauri::async_runtime::spawn(async move {
+            let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+            interval.tick().await;
+            let (mut last_read, mut last_written, mut last_at) = (0, 0, Instant::now());
+            loop {
+                interval.tick().await;
+                if done.load(Ordering::Relaxed) {
+                    break;
+                }
+                let read = counters.read.load(Ordering::Relaxed);
+                let written = counters.written.load(Ordering::Relaxed);
+                let elapsed = last_at.elapsed();
+                let throughput = Throughput {
+                    read_mb_per_sec: (!counters.reads_unknown.load(Ordering::Relaxed))
+                        .then(|| rate(read.saturating_sub(last_read), elapsed)),
+                    written_mb_per_sec: rate(written.saturating_sub(last_written), elapsed),
+                    read_bytes: read,
+                    written_bytes: written,
+                };
+                (last_read, last_written, last_at) = (read, written, Instant::now());
+
+                if let Ok(mut latest) = latest.lock() {
+                    *latest = throughput.clone();
+                }
+                let _ = app.emit(
+                    "extraction-throughput",
+                    &serde_json::json!({
+                        "downloadId": download_id,
+                        "throughput": throughput
+                    }),
+                );
+            }
+        });
+        monitor
+    }
+
+    pub fn counters(&self) -> Arc<IoCounters> {
+        self.counters.clone()
+    }
+
+    // For the progress callback, which runs on the extraction thread
+    pub fn latest_handle(&self) -> Arc<Mutex<Throughput>> {
+        self.latest.clone()
+    }
+}
+
+impl Drop for ExtractionMonitor {
+    fn drop(&mut self) {
+        self.done.store(true, Ordering::Relaxed);
+    }
+}
+
+// Keeps the extraction thread at idle/background IO priority until dropped, so the rest of the
+// system stays responsive. Processes started from the thread (unrar on Linux) inherit it.
+pub struct LowPriorityIo {
+    #[cfg(target_os = "linux")]
+    previous: libc::c_long,
+}
+
+#[cfg(target_os = "linux")]
+const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+
+#[cfg(target_os = "linux")]
+const IOPRIO_CLASS_IDLE: libc::c_long = 3;
+
+#[cfg(target_os = "linux")]
+const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+
+#[cfg(target_os = "linux")]
+pub fn lower_io_priority() -> LowPriorityIo {
+    // who = 0 with IOPRIO_WHO_PROCESS targets the calling thread
+    let previous = unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, 0) };
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            0,
+            IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+        )
+    };
+    if result != 0 {
+        println!(
+            "Failed to lower IO priority: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+    LowPriorityIo { previous }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for LowPriorityIo {
+    fn drop(&mut self) {
+        if self.previous >= 0 {
+            unsafe {
+                libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, self.previous);
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+const THREAD_MODE_BACKGROUND_BEGIN: i32 = 0x0001_0000;
+
+#[cfg(target_os = "windows")]
+const THREAD_MODE_BACKGROUND_END: i32 = 0x0002_0000;
+
+#[cfg(target_os = "windows")]
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn GetCurrentThread() -> *mut std::ffi::c_void;
+    fn SetThreadPriority(thread: *mut std::ffi::c_void, priority: i32) -> i32;
+}
+
+// Background mode lowers both the CPU and the IO priority of the thread
+#[cfg(target_os = "windows")]
+pub fn lower_io_priority() -> LowPriorityIo {
+    if unsafe { SetThreadPriority(GetCurrentThread(), THREAD_MODE_BACKGROUND_BEGIN) } == 0 {
+        println!(
+            "Failed to lower IO priority: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+    LowPriorityIo {}
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for LowPriorityIo {
+    fn drop(&mut self) {
+        unsafe {
+            SetThreadPriority(GetCurrentThread(), THREAD_MODE_BACKGROUND_END);
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+pub fn lower_io_priority() -> LowPriorityIo {
+    LowPriorityIo {}
+}
@@ -0,0 +1,333 @@
+use crate::state::{self, AppState};
+use crate::{httpconfig, offline, poller};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+// Site-wide feed of new and updated articles
+pub const HUB_FEED_URL: &str = "https://chanomhub.online/rss.xml";
+
+const SEEN_FILE: &str = "feed_seen.json";
+
+// Feeds usually list their last 10-50 items, so this covers many feeds without growing forever
+const MAX_SEEN_ITEMS: usize = 5000;
+
+const MIN_POLL_MINUTES: u64 = 15;
+
+static POLLING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Feed {
+    pub url: String,
+    // Display name; the feed's own title is used when empty
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct FeedSettings {
+    // Follow HUB_FEED_URL in addition to the feeds below
+    #[serde(rename = "hubFeed")]
+    pub hub_feed: bool,
+    pub feeds: Vec<Feed>,
+    // 0 leaves feeds to check_feeds only
+    #[serde(rename = "pollIntervalMinutes")]
+    pub poll_interval_minutes: u64,
+    #[serde(rename = "showNotifications")]
+    pub show_notifications: bool,
+}
+
+impl Default for FeedSettings {
+    fn default() -> Self {
+        Self {
+            hub_feed: false,
+            feeds: Vec::new(),
+            poll_interval_minutes: 60,
+            show_notifications: true,
+        }
+    }
+}
+
+impl FeedSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        poller::validate_interval(self.poll_interval_minutes, MIN_POLL_MINUTES)?;
+        for feed in &self.feeds {
+            let url = url::Url::parse(&feed.url)
+                .map_err(|e| format!("Invalid feed URL {}: {}", feed.url, e))?;
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err(format!("Feed URL must use http or https: {}", feed.url));
+            }
+        }
+        Ok(())
+    }
+
+    fn all_feeds(&self) -> Vec<Feed> {
+        let hub = self.hub_feed.then(|| Feed {
+            url: HUB_FEED_URL.to_string(),
+            name: "Chanomhub".to_string(),
+        });
+        hub.into_iter()
+            .chain(self.feeds.iter().cloned())
+            .filter(|f| !f.url.trim().is_empty())
+            .collect()
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedItem {
+    pub feed_url: String,
+    pub feed_name: String,
+    // <guid> / <id>, or the link when the feed has neither
+    pub id: String,
+    pub title: String,
+    pub link: Option<String>,
+    // As written in the feed (RFC 822 for RSS, RFC 3339 for Atom)
+    pub published: Option<String>,
+    pub summary: Option<String>,
+}
+
+// Items already announced; saved to SEEN_FILE after every check
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SeenFeedItems {
+    // Feeds whose current items were recorded without a notification, so subscribing to a feed
+    // does not announce everything it lists
+    seeded: HashSet<String>,
+    // "<feed url> <item id>"
+    items: VecDeque<String>,
+}
+
+impl SeenFeedItems {
+    fn contains(&self, key: &str) -> bool {
+        self.items.iter().any(|k| k == key)
+    }
+
+    fn insert(&mut self, key: String) {
+        if self.contains(&key) {
+            return;
+        }
+        if self.items.len() >= MAX_SEEN_ITEMS {
+            self.items.pop_front();
+        }
+        self.items.push_back(key);
+    }
+}
+
+fn item_key(item: &FeedItem) -> String {
+    format!("{} {}", item.feed_url, item.id)
+}
+
+fn seen_path(app: &AppHandle) -> Option<PathBuf> {
+    state::get_config_dir(app).map(|dir| dir.join(SEEN_FILE))
+}
+
+pub fn load_seen(app: &AppHandle) -> SeenFeedItems {
+    seen_path(app)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_seen(app: &AppHandle, seen: &SeenFeedItems) {
+    let Some(path) = seen_path(app) else {
+        return;
+    };
+    let result = serde_json::to_string(seen)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        println!("Failed to save seen feed items: {}", e);
+    }
+}
+
+fn settings(app: &AppHandle) -> FeedSettings {
+    app.state::<Mutex<AppState>>()
+        .lock()
+        .map(|app_state| app_state.feed_settings.clone())
+        .unwrap_or_default()
+}
+
+fn child<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &str,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children()
+        .find(|c| c.is_element() && c.tag_name().name() == name)
+}
+
+fn child_text(node: roxmltree::Node, names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| child(node, name))
+        .find_map(|c| c.text())
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+// Atom puts the URL in href; the page link is the one without rel or with rel="alternate"
+fn atom_link(entry: roxmltree::Node) -> Option<String> {
+    entry
+        .children()
+        .filter(|c| c.is_element() && c.tag_name().name() == "link")
+        .find(|l| matches!(l.attribute("rel"), None | Some("alternate")))
+        .and_then(|l| l.attribute("href"))
+        .map(|href| href.to_string())
+}
+
+// RSS 2.0, RSS 1.0 (RDF) and Atom
+pub fn parse(feed: &Feed, xml: &str) -> Result<Vec<FeedItem>, String> {
+    let document =
+        roxmltree::Document::parse(xml).map_err(|e| format!("Failed to parse feed: {}", e))?;
+    let root = document.root_element();
+    let (channel, entries, atom) = match root.tag_name().name() {
+        "rss" => {
+            let channel = child(root, "channel").ok_or("RSS feed has no channel")?;
+            (channel, channel, false)
+        }
+        // RSS 1.0 keeps its items next to the channel rather than inside it
+        "RDF" => (child(root, "channel").unwrap_or(root), root, false),
+        "feed" => (root, root, true),
+        other => return Err(format!("Not an RSS or Atom feed: <{}>", other)),
+    };
+    let feed_name = if feed.name.trim().is_empty() {
+        child_text(channel, &["title"]).unwrap_or_else(|| feed.url.clone())
+    } else {
+        feed.name.clone()
+    };
+
+    let entry_tag = if atom { "entry" } else { "item" };
+    Ok(entries
+        .children()
+        .filter(|c| c.is_element() && c.tag_name().name() == entry_tag)
+        .filter_map(|entry| {
+            let link = if atom {
+                atom_link(entry)
+            } else {
+                child_text(entry, &["link"])
+            };
+            let title = child_text(entry, &["title"]).unwrap_or_default();
+            let id = child_text(entry, &["guid", "id"])
+                .or_else(|| link.clone())
+                .or_else(|| (!title.is_empty()).then(|| title.clone()))?;
+            Some(FeedItem {
+                feed_url: feed.url.clone(),
+                feed_name: feed_name.clone(),
+                id,
+                title,
+                link,
+                published: child_text(entry, &["pubDate", "published", "updated", "date"]),
+                summary: child_text(entry, &["description", "summary"]),
+            })
+        })
+        .collect())
+}
+
+pub async fn fetch(app: &AppHandle, feed: &Feed) -> Result<Vec<FeedItem>, String> {
+    offline::ensure_online(app)?;
+    let client = httpconfig::client(app)?;
+    let response = httpconfig::apply(&httpconfig::settings(app), client.get(&feed.url), &feed.url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch feed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Feed request failed: {}", response.status()));
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read feed: {}", e))?;
+    parse(feed, &body)
+}
+
+fn announce(app: &AppHandle, items: &[FeedItem], show_notifications: bool) {
+    for item in items {
+        let _ = app.emit("feed-item", item);
+    }
+    if !show_notifications || items.is_empty() {
+        return;
+    }
+    let (title, body) = match items {
+        [item] => (item.title.clone(), format!("New in {}", item.feed_name)),
+        _ => (
+            format!("{} new feed items", items.len()),
+            items
+                .iter()
+                .map(|i| i.title.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+    };
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        println!("Failed to show notification: {}", e);
+    }
+}
+
+// Looks for items that appeared since the last check and announces them
+pub async fn check(app: &AppHandle) -> Result<Vec<FeedItem>, String> {
+    if POLLING.swap(true, Ordering::SeqCst) {
+        return Err("A feed check is already running".to_string());
+    }
+    let result = poll(app).await;
+    POLLING.store(false, Ordering::SeqCst);
+    result
+}
+
+async fn poll(app: &AppHandle) -> Result<Vec<FeedItem>, String> {
+    let settings = settings(app);
+    let feeds = settings.all_feeds();
+
+    // Fetched before taking the lock on the seen items
+    let mut results = Vec::new();
+    for feed in &feeds {
+        match fetch(app, feed).await {
+            Ok(items) => results.push((feed, items)),
+            Err(e) => println!("Feed check for {} failed: {}", feed.url, e),
+        }
+    }
+
+    let seen = app.state::<Mutex<SeenFeedItems>>();
+    let mut seen = seen
+        .lock()
+        .map_err(|e| format!("Failed to lock seen feed items: {}", e))?;
+    let urls: HashSet<String> = feeds.iter().map(|f| f.url.clone()).collect();
+    seen.seeded.retain(|url| urls.contains(url));
+
+    let mut new_items: Vec<FeedItem> = Vec::new();
+    for (feed, items) in results {
+        let seeding = seen.seeded.insert(feed.url.clone());
+        for item in items {
+            let key = item_key(&item);
+            if seen.contains(&key) {
+                continue;
+            }
+            // Recorded right away, so an item listed twice in one feed is announced once
+            seen.insert(key);
+            if !seeding {
+                new_items.push(item);
+            }
+        }
+    }
+    save_seen(app, &seen);
+    drop(seen);
+
+    if !new_items.is_empty() {
+        println!("Found {} new feed items", new_items.len());
+    }
+    announce(app, &new_items, settings.show_notifications);
+    Ok(new_items)
+}
+
+// Minutes between background checks for poller, 0 while polling is off or there is no feed
+pub fn poll_interval(app: &AppHandle) -> u64 {
+    let settings = settings(app);
+    if settings.all_feeds().is_empty() {
+        return 0;
+    }
+    settings.poll_interval_minutes
+}
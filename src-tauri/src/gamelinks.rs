@@ -0,0 +1,190 @@
+use crate::state::{AppState, DownloadedGameInfo};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_LINKS_DIR_NAME: &str = "Games";
+
+const ARCHIVE_EXTENSIONS: &[&str] = &[".zip", ".7z", ".rar"];
+
+pub fn links_root(app_state: &AppState) -> Option<PathBuf> {
+    if let Some(dir) = app_state
+        .library_settings
+        .games_links_dir
+        .as_ref()
+        .filter(|d| !d.is_empty())
+    {
+        return Some(PathBuf::from(dir));
+    }
+    app_state
+        .download_dir
+        .as_ref()
+        .map(|dir| Path::new(dir).join(DEFAULT_LINKS_DIR_NAME))
+}
+
+// Readable folder name for a game: the article title when known, otherwise
+// the filename without its archive extension ("Some Game v1.2.zip" -> "Some Game v1.2")
+pub fn display_name(game: &DownloadedGameInfo) -> String {
+    let mut name = game.filename.as_str();
+    if let Some(title) = game
+        .article
+        .as_ref()
+        .and_then(|a| a.title.as_deref())
+        .filter(|t| !t.trim().is_empty())
+    {
+        name = title;
+    } else {
+        for ext in ARCHIVE_EXTENSIONS {
+            if name.to_lowercase().ends_with(ext) {
+                name = &name[..name.len() - ext.len()];
+                break;
+            }
+        }
+    }
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim().trim_end_matches('.');
+    if cleaned.is_empty() {
+        game.id.clone()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+pub fn is_link(path: &Path) -> bool {
+    fs::symlink_metadata(path)
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false)
+}
+
+// Junctions read back as `\\?\C:\...`, so both sides are resolved before comparing. A target that
+// no longer exists is compared with the verbatim prefix stripped instead.
+pub fn links_to(link: &Path, target: &Path) -> bool {
+    let Ok(current) = fs::read_link(link) else {
+        return false;
+    };
+    let resolve = |path: &Path| {
+        fs::canonicalize(path).unwrap_or_else(|_| {
+            let text = path.to_string_lossy();
+            PathBuf::from(text.strip_prefix(r"\\?\").unwrap_or(&text))
+        })
+    };
+    resolve(&current) == resolve(target)
+}
+
+fn remove_link(path: &Path) -> Result<(), String> {
+    // Directory symlinks and junctions are removed as directories on Windows
+    #[cfg(target_os = "windows")]
+    let result = fs::remove_dir(path);
+    #[cfg(not(target_os = "windows"))]
+    let result = fs::remove_file(path);
+    result.map_err(|e| format!("Failed to remove link {}: {}", path.display(), e))
+}
+
+pub fn create_link(target: &Path, link: &Path) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        // Symlinks need developer mode or admin rights, junctions work for every user
+        if std::os::windows::fs::symlink_dir(target, link).is_ok() {
+            return Ok(());
+        }
+        let output = std::process::Command::new("cmd")
+            .arg("/C")
+            .arg("mklink")
+            .arg("/J")
+            .arg(link)
+            .arg(target)
+            .output()
+            .map_err(|e| format!("Failed to run mklink: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to create junction {}: {}",
+                link.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        std::os::unix::fs::symlink(target, link)
+            .map_err(|e| format!("Failed to create link {}: {}", link.display(), e))
+    }
+}
+
+// Rebuilds the `Games/<Title>` tree so it points at every extracted game.
+// Only links are ever created or removed; real files in the folder are left alone.
+pub fn sync(app_state: &AppState) -> Result<usize, String> {
+    if !app_state.library_settings.games_links_enabled {
+        return Ok(0);
+    }
+    let root = links_root(app_state).ok_or("Download directory not set")?;
+    fs::create_dir_all(&root)
+        .map_err(|e| format!("Failed to create games folder {}: {}", root.display(), e))?;
+
+    let mut wanted: Vec<(String, PathBuf)> = Vec::new();
+    let mut used: HashSet<String> = HashSet::new();
+    for game in app_state.games.iter().flatten() {
+        let Some(target) = game
+            .extracted_path
+            .as_ref()
+            .filter(|p| !p.is_empty() && Path::new(p).is_dir())
+        else {
+            continue;
+        };
+
+        let base = display_name(game);
+        let mut name = base.clone();
+        let mut n = 2;
+        while !used.insert(name.to_lowercase()) {
+            name = format!("{} ({})", base, n);
+            n += 1;
+        }
+        wanted.push((name, PathBuf::from(target)));
+    }
+
+    // Drop links for games that are gone or whose target moved
+    let entries = fs::read_dir(&root)
+        .map_err(|e| format!("Failed to read games folder {}: {}", root.display(), e))?;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if !is_link(&path) {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let keep = wanted
+            .iter()
+            .any(|(n, target)| *n == name && links_to(&path, target));
+        if !keep {
+            remove_link(&path)?;
+        }
+    }
+
+    let mut linked = 0;
+    for (name, target) in &wanted {
+        let link = root.join(name);
+        if is_link(&link) {
+            linked += 1;
+            continue;
+        }
+        if link.exists() {
+            println!(
+                "Skipping game link {}: a real file or folder already uses that name",
+                link.display()
+            );
+            continue;
+        }
+        create_link(target, &link)?;
+        linked += 1;
+    }
+
+    println!("Games folder {} has {} links", root.display(), linked);
+    Ok(linked)
+}
@@ -0,0 +1,307 @@
+use crate::launchhooks::append_log;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::process::Command as StdCommand;
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio_util::sync::CancellationToken;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+// Launchers and Wine can take a while before the real window shows up
+const DEFAULT_WAIT_SECS: u64 = 60;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum WindowMode {
+    // Left as the game opened it
+    #[default]
+    Normal,
+    Maximized,
+    // Frame removed and stretched over the whole monitor
+    Borderless,
+}
+
+// Applied once the game's first visible window appears
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WindowActions {
+    pub mode: WindowMode,
+    // Monitor name as reported by the OS; unset keeps the window where it opened
+    pub monitor: Option<String>,
+    pub always_on_top: bool,
+    pub wait_secs: Option<u64>,
+}
+
+impl WindowActions {
+    fn is_active(&self) -> bool {
+        self.mode != WindowMode::Normal || self.monitor.is_some() || self.always_on_top
+    }
+}
+
+// Physical top-left corner of a monitor
+#[derive(Clone, Copy, Debug)]
+struct MonitorArea {
+    x: i32,
+    y: i32,
+}
+
+fn monitor_area(app: &AppHandle, name: &str) -> Option<MonitorArea> {
+    let monitors = app.available_monitors().ok()?;
+    let monitor = monitors
+        .iter()
+        .find(|m| m.name().is_some_and(|n| n == name))?;
+    Some(MonitorArea {
+        x: monitor.position().x,
+        y: monitor.position().y,
+    })
+}
+
+// Runs in the background after the game is spawned; gives up when the game exits first
+pub fn apply_after_launch(
+    app: AppHandle,
+    pid: u32,
+    actions: WindowActions,
+    log_path: Option<PathBuf>,
+    exited: CancellationToken,
+) {
+    if !actions.is_active() {
+        return;
+    }
+    let area = actions.monitor.as_deref().and_then(|name| {
+        let area = monitor_area(&app, name);
+        if area.is_none() {
+            append_log(
+                log_path.as_deref(),
+                &format!("monitor {} not found, window stays where it opens", name),
+            );
+        }
+        area
+    });
+    tauri::async_runtime::spawn(async move {
+        let wait = Duration::from_secs(actions.wait_secs.unwrap_or(DEFAULT_WAIT_SECS));
+        let result = tokio::select! {
+            _ = exited.cancelled() => return,
+            result = tauri::async_runtime::spawn_blocking(move || apply(pid, &actions, area, wait)) => result,
+        };
+        let message = match result {
+            Ok(Ok(())) => "window actions applied".to_string(),
+            Ok(Err(e)) => format!("Failed to apply window actions: {}", e),
+            Err(e) => format!("Failed to apply window actions: {}", e),
+        };
+        println!("{}", message);
+        append_log(log_path.as_deref(), &message);
+    });
+}
+
+// The spawned process is often a launcher or Wine, so its children are searched too
+#[cfg(not(target_os = "windows"))]
+fn process_tree(pid: u32) -> Vec<u32> {
+    let mut parents = Vec::new();
+    for entry in std::fs::read_dir("/proc").into_iter().flatten().flatten() {
+        let Ok(stat) = std::fs::read_to_string(entry.path().join("stat")) else {
+            continue;
+        };
+        // pid (comm) state ppid ...; comm may contain spaces
+        let Some((head, rest)) = stat.rsplit_once(") ") else {
+            continue;
+        };
+        let child = head
+            .split_once(' ')
+            .and_then(|(p, _)| p.parse::<u32>().ok());
+        let parent = rest.split(' ').nth(1).and_then(|p| p.parse::<u32>().ok());
+        if let (Some(child), Some(parent)) = (child, parent) {
+            parents.push((child, parent));
+        }
+    }
+    let mut tree = vec![pid];
+    let mut i = 0;
+    while i < tree.len() {
+        let current = tree[i];
+        tree.extend(
+            parents
+                .iter()
+                .filter(|(_, parent)| *parent == current)
+                .map(|(child, _)| *child),
+        );
+        i += 1;
+    }
+    tree
+}
+
+#[cfg(not(target_os = "windows"))]
+fn xdotool(args: &[&str]) -> Result<String, String> {
+    let output = StdCommand::new("xdotool")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run xdotool: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "xdotool {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn find_window(pid: u32) -> Option<String> {
+    process_tree(pid).into_iter().find_map(|pid| {
+        xdotool(&["search", "--onlyvisible", "--pid", &pid.to_string()])
+            .ok()?
+            .lines()
+            .next()
+            .map(str::to_string)
+    })
+}
+
+// Window states go through the window manager (EWMH), so this needs X11 or XWayland
+#[cfg(not(target_os = "windows"))]
+fn apply(
+    pid: u32,
+    actions: &WindowActions,
+    area: Option<MonitorArea>,
+    wait: Duration,
+) -> Result<(), String> {
+    if crate::archivetools::find_in_path(&["xdotool"]).is_none() {
+        return Err("xdotool is not installed".to_string());
+    }
+    let started = std::time::Instant::now();
+    let proc_dir = PathBuf::from(format!("/proc/{}", pid));
+    let window = loop {
+        if let Some(window) = find_window(pid) {
+            break window;
+        }
+        if started.elapsed() >= wait || !proc_dir.exists() {
+            return Err("the game window did not appear".to_string());
+        }
+        std::thread::sleep(Duration::from_secs(1));
+    };
+
+    if let Some(area) = area {
+        xdotool(&[
+            "windowmove",
+            &window,
+            &area.x.to_string(),
+            &area.y.to_string(),
+        ])?;
+    }
+    match actions.mode {
+        WindowMode::Normal => {}
+        WindowMode::Maximized => {
+            xdotool(&["windowstate", "--add", "MAXIMIZED_VERT", &window])?;
+            xdotool(&["windowstate", "--add", "MAXIMIZED_HORZ", &window])?;
+        }
+        WindowMode::Borderless => {
+            xdotool(&["windowstate", "--add", "FULLSCREEN", &window])?;
+        }
+    }
+    if actions.always_on_top {
+        xdotool(&["windowstate", "--add", "ABOVE", &window])?;
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+const WINDOW_SCRIPT: &str = r#"
+Add-Type -AssemblyName System.Windows.Forms
+Add-Type @"
+using System;
+using System.Runtime.InteropServices;
+public static class GameWindow {
+    delegate bool EnumProc(IntPtr hwnd, IntPtr lParam);
+    [DllImport("user32.dll")] static extern bool EnumWindows(EnumProc proc, IntPtr lParam);
+    [DllImport("user32.dll")] static extern uint GetWindowThreadProcessId(IntPtr hwnd, out uint pid);
+    [DllImport("user32.dll")] static extern bool IsWindowVisible(IntPtr hwnd);
+    [DllImport("user32.dll")] public static extern bool ShowWindow(IntPtr hwnd, int cmd);
+    [DllImport("user32.dll")] public static extern bool SetWindowPos(IntPtr hwnd, IntPtr after, int x, int y, int cx, int cy, uint flags);
+    [DllImport("user32.dll")] public static extern int GetWindowLong(IntPtr hwnd, int index);
+    [DllImport("user32.dll")] public static extern int SetWindowLong(IntPtr hwnd, int index, int value);
+    public static IntPtr Find(uint[] pids) {
+        IntPtr found = IntPtr.Zero;
+        EnumWindows((hwnd, lParam) => {
+            uint pid;
+            GetWindowThreadProcessId(hwnd, out pid);
+            if (IsWindowVisible(hwnd) && Array.IndexOf(pids, pid) >= 0) { found = hwnd; return false; }
+            return true;
+        }, IntPtr.Zero);
+        return found;
+    }
+}
+"@
+function Get-Tree($root) {
+    $all = Get-CimInstance Win32_Process | Select-Object ProcessId, ParentProcessId
+    $tree = @([uint32]$root)
+    for ($i = 0; $i -lt $tree.Count; $i++) {
+        $tree += $all | Where-Object { $_.ParentProcessId -eq $tree[$i] } | ForEach-Object { [uint32]$_.ProcessId }
+    }
+    return ,$tree
+}
+$deadline = (Get-Date).AddSeconds($Wait)
+$hwnd = [IntPtr]::Zero
+while ($hwnd -eq [IntPtr]::Zero) {
+    if (-not (Get-Process -Id $Root -ErrorAction SilentlyContinue) -or (Get-Date) -gt $deadline) {
+        [Console]::Error.WriteLine('the game window did not appear'); exit 2
+    }
+    $hwnd = [GameWindow]::Find([uint32[]](Get-Tree $Root))
+    if ($hwnd -eq [IntPtr]::Zero) { Start-Sleep -Seconds 1 }
+}
+if ($HasArea) {
+    [void][GameWindow]::SetWindowPos($hwnd, [IntPtr]::Zero, $X, $Y, 0, 0, 0x0005)
+}
+$bounds = [System.Windows.Forms.Screen]::FromHandle($hwnd).Bounds
+switch ($Mode) {
+    'maximized' { [void][GameWindow]::ShowWindow($hwnd, 3) }
+    'borderless' {
+        $style = [GameWindow]::GetWindowLong($hwnd, -16) -band (-bnot 0x00C40000)
+        [void][GameWindow]::SetWindowLong($hwnd, -16, $style)
+        [void][GameWindow]::SetWindowPos($hwnd, [IntPtr]::Zero, $bounds.X, $bounds.Y, $bounds.Width, $bounds.Height, 0x0024)
+    }
+}
+if ($Topmost) {
+    [void][GameWindow]::SetWindowPos($hwnd, [IntPtr](-1), 0, 0, 0, 0, 0x0003)
+}
+"#;
+
+// user32 calls through PowerShell; borderless uses the monitor the window ended up on
+#[cfg(target_os = "windows")]
+fn apply(
+    pid: u32,
+    actions: &WindowActions,
+    area: Option<MonitorArea>,
+    wait: Duration,
+) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+
+    let mode = match actions.mode {
+        WindowMode::Normal => "normal",
+        WindowMode::Maximized => "maximized",
+        WindowMode::Borderless => "borderless",
+    };
+    let (has_area, area) = match area {
+        Some(area) => (true, area),
+        None => (false, MonitorArea { x: 0, y: 0 }),
+    };
+    let script = format!(
+        "$Root = {}; $Wait = {}; $Mode = '{}'; $HasArea = ${}; $X = {}; $Y = {}; $Topmost = ${};{}",
+        pid,
+        wait.as_secs(),
+        mode,
+        has_area,
+        area.x,
+        area.y,
+        actions.always_on_top,
+        WINDOW_SCRIPT
+    );
+    let output = StdCommand::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("Failed to run PowerShell: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
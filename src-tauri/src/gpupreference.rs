@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+// Same key the Settings > Display > Graphics page writes, per user so no admin rights are needed
+#[cfg(target_os = "windows")]
+const GPU_PREFERENCES_KEY: &str = "HKCU\\Software\\Microsoft\\DirectX\\UserGpuPreferences";
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+// Laptops with a discrete GPU hand unknown programs to the integrated one unless told otherwise
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum GpuPreference {
+    // Let Windows decide; removes the entry
+    Default,
+    PowerSaving,
+    HighPerformance,
+}
+
+impl GpuPreference {
+    #[cfg(target_os = "windows")]
+    fn registry_data(self) -> Option<&'static str> {
+        match self {
+            GpuPreference::Default => None,
+            GpuPreference::PowerSaving => Some("GpuPreference=1;"),
+            GpuPreference::HighPerformance => Some("GpuPreference=2;"),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn reg(args: &[&str]) -> Result<std::process::Output, String> {
+    use std::os::windows::process::CommandExt;
+    use std::process::Command as StdCommand;
+
+    StdCommand::new("reg")
+        .args(args)
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("Failed to run reg: {}", e))
+}
+
+// The value name is the full executable path, so moving the game needs the preference written again
+#[cfg(target_os = "windows")]
+pub fn set(executable_path: &str, preference: GpuPreference) -> Result<(), String> {
+    let output = match preference.registry_data() {
+        Some(data) => reg(&[
+            "add",
+            GPU_PREFERENCES_KEY,
+            "/v",
+            executable_path,
+            "/t",
+            "REG_SZ",
+            "/d",
+            data,
+            "/f",
+        ])?,
+        None => {
+            if current(executable_path) == GpuPreference::Default {
+                return Ok(());
+            }
+            reg(&["delete", GPU_PREFERENCES_KEY, "/v", executable_path, "/f"])?
+        }
+    };
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to update GPU preference: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    println!(
+        "Set GPU preference for {} to {:?}",
+        executable_path, preference
+    );
+    Ok(())
+}
+
+// What Windows will use for the executable, whoever set it
+#[cfg(target_os = "windows")]
+pub fn current(executable_path: &str) -> GpuPreference {
+    let Ok(output) = reg(&["query", GPU_PREFERENCES_KEY, "/v", executable_path]) else {
+        return GpuPreference::Default;
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        GpuPreference::Default
+    } else if stdout.contains("GpuPreference=2;") {
+        GpuPreference::HighPerformance
+    } else if stdout.contains("GpuPreference=1;") {
+        GpuPreference::PowerSaving
+    } else {
+        GpuPreference::Default
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn set(_executable_path: &str, preference: GpuPreference) -> Result<(), String> {
+    if preference == GpuPreference::Default {
+        return Ok(());
+    }
+    Err("GPU preference is only available on Windows".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn current(_executable_path: &str) -> GpuPreference {
+    GpuPreference::Default
+}
@@ -0,0 +1,237 @@
+use crate::state::{AppState, save_state_to_file};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::sync::Mutex;
+use tauri::ipc::Invoke;
+use tauri::{AppHandle, Manager, Runtime};
+
+// Commands that only read state (or open things for the user) and keep working in guest mode.
+// Everything else is rejected by the backend, whatever the frontend shows.
+const ALLOWED_IN_GUEST_MODE: &[&str] = &[
+    "echo_test",
+    "get_cloudinary_config",
+    "get_ui_prefs",
+    "get_startup_settings",
+    "take_opened_manifest",
+    "get_extract_context_menu",
+    "get_telemetry_settings",
+    "get_pending_telemetry",
+    "get_session_limit",
+    "get_launch_guards",
+    "check_launch_requirements",
+    "validate_launch_config",
+    "open_downloads_window",
+    "get_download_settings",
+    "get_extraction_settings",
+    "get_original_filename",
+    "get_library_settings",
+    "fetch_article_by_slug",
+    "fetch_articles_by_slugs",
+    "get_download_dir",
+    "get_legacy_download_dir",
+    "suggest_download_locations",
+    "check_api_connectivity",
+    "get_current_user",
+    "validate_token",
+    "check_compat_layers",
+    "verify_config_exists",
+    "show_download_notification",
+    "open_directory",
+    "get_active_downloads",
+    "get_cache_stats",
+    "check_path_exists",
+    "get_saved_games",
+    "get_sidecar_info",
+    "is_directory",
+    "launch_game",
+    "launch_game_by_slug",
+    "get_guest_mode",
+    "get_integrity_report",
+    "get_request_settings",
+    "get_default_user_agent",
+    "get_cookie_providers",
+    "get_download_speed_history",
+    "check_helper_update",
+    "check_installation",
+    "get_article_changelog",
+    "get_subscription_settings",
+    "get_feed_settings",
+    "get_sound_settings",
+    "get_now_playing",
+    "get_network_settings",
+    "get_offline_queue",
+    "get_scheduled_downloads",
+    "get_jobs",
+    "preview_feed",
+    "get_bandwidth_usage",
+    "get_bandwidth_settings",
+    "get_defender_status",
+    "get_archive_capabilities",
+    "get_compat_notes",
+    "get_launch_templates",
+    "get_gpu_preference",
+    "get_wine_graphics_status",
+    "get_launch_entries",
+    "get_download_timeline",
+    "get_download_groups",
+    "get_retention_settings",
+    "preview_retention_cleanup",
+    "render_article",
+    "get_article_render_settings",
+    "exit_guest_mode",
+];
+
+const MIN_PIN_LEN: usize = 4;
+
+// Wrong PINs allowed before exit_guest_mode starts making the guest wait
+const FREE_PIN_ATTEMPTS: u32 = 3;
+// The wait doubles from this with every further wrong PIN, up to MAX_PIN_LOCKOUT_SECS
+const PIN_LOCKOUT_SECS: i64 = 30;
+const MAX_PIN_LOCKOUT_SECS: i64 = 60 * 60;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct GuestModeSettings {
+    // Persisted so restarting the app does not leave guest mode
+    pub active: bool,
+    #[serde(rename = "pinHash")]
+    pub pin_hash: Option<String>,
+    pub salt: Option<String>,
+    // Persisted too, so restarting the app does not reset the wait after wrong PINs
+    #[serde(rename = "failedAttempts")]
+    pub failed_attempts: u32,
+    // RFC 3339
+    #[serde(rename = "lockedUntil")]
+    pub locked_until: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GuestModeStatus {
+    pub active: bool,
+    #[serde(rename = "pinSet")]
+    pub pin_set: bool,
+}
+
+fn hash_pin(salt: &str, pin: &str) -> String {
+    hex::encode(Sha1::digest(format!("{}:{}", salt, pin).as_bytes()))
+}
+
+fn pin_matches(settings: &GuestModeSettings, pin: &str) -> bool {
+    match (&settings.pin_hash, &settings.salt) {
+        (Some(hash), Some(salt)) => hash_pin(salt, pin) == *hash,
+        _ => false,
+    }
+}
+
+fn lockout_secs(failed_attempts: u32) -> i64 {
+    if failed_attempts < FREE_PIN_ATTEMPTS {
+        return 0;
+    }
+    let doublings = (failed_attempts - FREE_PIN_ATTEMPTS).min(16);
+    (PIN_LOCKOUT_SECS << doublings).min(MAX_PIN_LOCKOUT_SECS)
+}
+
+fn is_active<R: Runtime>(app: &AppHandle<R>) -> bool {
+    app.state::<Mutex<AppState>>()
+        .lock()
+        .is_ok_and(|app_state| app_state.guest_mode.active)
+}
+
+fn allow<R: Runtime>(invoke: &Invoke<R>) -> bool {
+    let command = invoke.message.command();
+    ALLOWED_IN_GUEST_MODE.contains(&command)
+        || !is_active(invoke.message.webview_ref().app_handle())
+}
+
+// Wraps the command handler so guest mode is enforced by the backend, not by hidden buttons
+pub fn guard<R: Runtime>(
+    commands: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        if !allow(&invoke) {
+            let message = format!(
+                "{} is not available in guest mode",
+                invoke.message.command()
+            );
+            invoke.resolver.reject(message);
+            return true;
+        }
+        commands(invoke)
+    }
+}
+
+pub fn status(app_state: &AppState) -> GuestModeStatus {
+    GuestModeStatus {
+        active: app_state.guest_mode.active,
+        pin_set: app_state.guest_mode.pin_hash.is_some(),
+    }
+}
+
+pub fn set_pin(
+    app: &AppHandle,
+    app_state: &mut AppState,
+    current_pin: Option<String>,
+    new_pin: String,
+) -> Result<(), String> {
+    if app_state.guest_mode.pin_hash.is_some()
+        && !current_pin.is_some_and(|pin| pin_matches(&app_state.guest_mode, &pin))
+    {
+        return Err("Current PIN is incorrect".to_string());
+    }
+    let new_pin = new_pin.trim();
+    if new_pin.len() < MIN_PIN_LEN || !new_pin.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("PIN must be at least {} digits", MIN_PIN_LEN));
+    }
+    let salt = hex::encode(Sha1::digest(
+        format!("{:?}{}", std::time::SystemTime::now(), std::process::id()).as_bytes(),
+    ));
+    app_state.guest_mode.pin_hash = Some(hash_pin(&salt, new_pin));
+    app_state.guest_mode.salt = Some(salt);
+    save_state_to_file(app, app_state)
+}
+
+pub fn enter(app: &AppHandle, app_state: &mut AppState) -> Result<(), String> {
+    if app_state.guest_mode.pin_hash.is_none() {
+        return Err("Set a PIN before enabling guest mode".to_string());
+    }
+    app_state.guest_mode.active = true;
+    println!("Guest mode enabled");
+    save_state_to_file(app, app_state)
+}
+
+pub fn exit(app: &AppHandle, app_state: &mut AppState, pin: &str) -> Result<(), String> {
+    let now = chrono::Utc::now();
+    let locked_until = app_state
+        .guest_mode
+        .locked_until
+        .as_deref()
+        .and_then(|at| chrono::DateTime::parse_from_rfc3339(at).ok());
+    if let Some(until) = locked_until
+        && until > now
+    {
+        return Err(format!(
+            "Too many incorrect PINs, try again in {} seconds",
+            (until.with_timezone(&chrono::Utc) - now)
+                .num_seconds()
+                .max(1)
+        ));
+    }
+    if !pin_matches(&app_state.guest_mode, pin.trim()) {
+        let guest_mode = &mut app_state.guest_mode;
+        guest_mode.failed_attempts = guest_mode.failed_attempts.saturating_add(1);
+        let wait = lockout_secs(guest_mode.failed_attempts);
+        guest_mode.locked_until =
+            (wait > 0).then(|| (now + chrono::Duration::seconds(wait)).to_rfc3339());
+        println!(
+            "Incorrect guest mode PIN ({} in a row)",
+            guest_mode.failed_attempts
+        );
+        save_state_to_file(app, app_state)?;
+        return Err("Incorrect PIN".to_string());
+    }
+    app_state.guest_mode.failed_attempts = 0;
+    app_state.guest_mode.locked_until = None;
+    app_state.guest_mode.active = false;
+    println!("Guest mode disabled");
+    save_state_to_file(app, app_state)
+}
@@ -0,0 +1,324 @@
+use crate::sidecar::{self, HELPER_BINARY_NAME, PROTOCOL_VERSION};
+use crate::{ActiveDownloads, httpconfig, offline};
+use base64::Engine;
+use minisign_verify::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::{AppHandle, Emitter, Manager};
+
+// Published next to the app's own latest.json, so the helper can be fixed without a full app release
+const FEED_URL: &str =
+    "https://raw.githubusercontent.com/Chanomhub/Chanomhub-Desktop/main/helper-latest.json";
+
+const HELPER_DIR: &str = "webview2-helper";
+
+const MARKER_FILE: &str = "installed.json";
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct HelperRelease {
+    pub version: String,
+    #[serde(rename = "protocolVersion")]
+    pub protocol_version: u32,
+    #[serde(default)]
+    pub notes: Option<String>,
+    pub files: Vec<HelperFile>,
+}
+
+// Every file of the helper folder (the exe and its WebView2/Json.NET dlls)
+#[derive(Deserialize, Clone, Debug)]
+pub struct HelperFile {
+    pub name: String,
+    pub url: String,
+    pub sha256: String,
+    // Minisign signature in the same base64 form the app updater uses
+    pub signature: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct InstalledHelper {
+    version: String,
+    // The bundled helper wins again once the app itself is updated
+    #[serde(rename = "appVersion")]
+    app_version: String,
+    #[serde(rename = "installedAt")]
+    installed_at: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct HelperUpdateStatus {
+    #[serde(rename = "currentVersion")]
+    pub current_version: Option<String>,
+    #[serde(rename = "latestVersion")]
+    pub latest_version: String,
+    #[serde(rename = "updateAvailable")]
+    pub update_available: bool,
+    // False when the new helper speaks a different protocol and needs an app update instead
+    pub compatible: bool,
+    pub notes: Option<String>,
+}
+
+fn helper_root(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map(|dir| dir.join(HELPER_DIR))
+        .map_err(|e| format!("Failed to get app data dir: {}", e))
+}
+
+fn read_marker(root: &Path) -> Option<InstalledHelper> {
+    let content = fs::read_to_string(root.join(MARKER_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+// Path of a helper installed through the update channel, if it is still newer than the bundled one
+pub fn installed_helper_path(app: &AppHandle) -> Option<PathBuf> {
+    let root = helper_root(app).ok()?;
+    let installed = read_marker(&root)?;
+    if installed.app_version != app.package_info().version.to_string() {
+        println!(
+            "Ignoring WebView2 helper {} installed under app {}",
+            installed.version, installed.app_version
+        );
+        return None;
+    }
+    let path = root.join(&installed.version).join(HELPER_BINARY_NAME);
+    path.exists().then_some(path)
+}
+
+// Numeric comparison of dotted versions ("1.2.0" vs "1.10.0.0"), missing parts count as zero
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |v: &str| -> Vec<u64> {
+        v.trim_start_matches('v')
+            .split('.')
+            .map(|p| p.parse().unwrap_or(0))
+            .collect()
+    };
+    let (a, b) = (parts(a), parts(b));
+    (0..a.len().max(b.len()))
+        .map(|i| {
+            a.get(i)
+                .copied()
+                .unwrap_or(0)
+                .cmp(&b.get(i).copied().unwrap_or(0))
+        })
+        .find(|o| o.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+async fn fetch_release(app: &AppHandle) -> Result<HelperRelease, String> {
+    offline::ensure_online(app)?;
+    let response = httpconfig::client(app)?
+        .get(FEED_URL)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch helper release feed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Helper release feed responded with {}",
+            response.status()
+        ));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse helper release feed: {}", e))
+}
+
+pub async fn check(app: &AppHandle) -> Result<HelperUpdateStatus, String> {
+    let release = fetch_release(app).await?;
+    let current_version = sidecar::ensure_running(app)
+        .await
+        .ok()
+        .and_then(|info| info.helper_version);
+    let compatible = release.protocol_version == PROTOCOL_VERSION;
+    let newer = current_version
+        .as_deref()
+        .is_none_or(|current| compare_versions(&release.version, current).is_gt());
+    // The helper only runs on Windows, and a feed without files is the placeholder in the repo
+    let installable = cfg!(target_os = "windows") && !release.files.is_empty();
+    Ok(HelperUpdateStatus {
+        current_version,
+        latest_version: release.version,
+        update_available: installable && compatible && newer,
+        compatible,
+        notes: release.notes,
+    })
+}
+
+// Same key and signature format as the app updater (plugins.updater.pubkey in tauri.conf.json)
+pub fn verify_signature(app: &AppHandle, data: &[u8], signature: &str) -> Result<(), String> {
+    let decode = |b64: &str| -> Result<String, String> {
+        base64::engine::general_purpose::STANDARD
+            .decode(b64.trim())
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(|| "not valid base64".to_string())
+    };
+    let pubkey = app
+        .config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|updater| updater.get("pubkey"))
+        .and_then(|key| key.as_str())
+        .ok_or("No update signing key is configured")?;
+    let public_key = decode(pubkey)
+        .and_then(|key| PublicKey::decode(&key).map_err(|e| e.to_string()))
+        .map_err(|e| format!("Update signing key is invalid: {}", e))?;
+    let signature = decode(signature)
+        .and_then(|sig| Signature::decode(&sig).map_err(|e| e.to_string()))
+        .map_err(|e| format!("Signature is invalid: {}", e))?;
+    public_key
+        .verify(data, &signature, true)
+        .map_err(|e| format!("Signature does not match: {}", e))
+}
+
+async fn download_verified(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    file: &HelperFile,
+) -> Result<Vec<u8>, String> {
+    let response = client
+        .get(&file.url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", file.name, e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Download of {} failed: {}",
+            file.name,
+            response.status()
+        ));
+    }
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", file.name, e))?;
+
+    let digest = hex::encode(Sha256::digest(&bytes));
+    if !digest.eq_ignore_ascii_case(file.sha256.trim()) {
+        return Err(format!(
+            "{} does not match its published SHA-256 hash",
+            file.name
+        ));
+    }
+    verify_signature(app, &bytes, &file.signature).map_err(|e| format!("{}: {}", file.name, e))?;
+    Ok(bytes.to_vec())
+}
+
+// Downloads every file into a staging folder and only switches over once all of them verified
+pub async fn install(app: &AppHandle) -> Result<String, String> {
+    if !cfg!(target_os = "windows") {
+        return Err("The WebView2 helper is only used on Windows".to_string());
+    }
+    let release = fetch_release(app).await?;
+    if release.protocol_version != PROTOCOL_VERSION {
+        return Err(format!(
+            "Helper {} speaks protocol v{} but this app requires v{}; update the app instead",
+            release.version, release.protocol_version, PROTOCOL_VERSION
+        ));
+    }
+    // The version names the install folder
+    if release.version.is_empty()
+        || !release
+            .version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+    {
+        return Err(format!("Invalid helper version: {}", release.version));
+    }
+    if !release.files.iter().any(|f| f.name == HELPER_BINARY_NAME) {
+        return Err(format!("Helper release is missing {}", HELPER_BINARY_NAME));
+    }
+    // Names come from the feed, so anything that is not a plain file name is refused
+    if let Some(file) = release
+        .files
+        .iter()
+        .find(|f| Path::new(&f.name).file_name() != Some(std::ffi::OsStr::new(&f.name)))
+    {
+        return Err(format!(
+            "Invalid file name in helper release: {}",
+            file.name
+        ));
+    }
+
+    let root = helper_root(app)?;
+    let staging = root.join(format!(".staging-{}", release.version));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)
+            .map_err(|e| format!("Failed to clear {}: {}", staging.display(), e))?;
+    }
+    fs::create_dir_all(&staging)
+        .map_err(|e| format!("Failed to create {}: {}", staging.display(), e))?;
+
+    let client = httpconfig::client(app)?;
+    for file in &release.files {
+        let bytes = match download_verified(app, &client, file).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&staging);
+                return Err(e);
+            }
+        };
+        fs::write(staging.join(&file.name), bytes)
+            .map_err(|e| format!("Failed to write {}: {}", file.name, e))?;
+    }
+
+    let target = root.join(&release.version);
+    if target.exists() {
+        fs::remove_dir_all(&target)
+            .map_err(|e| format!("Failed to replace {}: {}", target.display(), e))?;
+    }
+    fs::rename(&staging, &target)
+        .map_err(|e| format!("Failed to install helper {}: {}", release.version, e))?;
+    let marker = InstalledHelper {
+        version: release.version.clone(),
+        app_version: app.package_info().version.to_string(),
+        installed_at: chrono::Utc::now().to_rfc3339(),
+    };
+    fs::write(
+        root.join(MARKER_FILE),
+        serde_json::to_string_pretty(&marker).unwrap_or_default(),
+    )
+    .map_err(|e| format!("Failed to record installed helper: {}", e))?;
+    println!("Installed WebView2 helper {}", release.version);
+
+    // Older versions that are not running any more; a busy one is removed on the next update
+    if let Ok(entries) = fs::read_dir(&root) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir()
+                && path != target
+                && !entry.file_name().to_string_lossy().starts_with('.')
+            {
+                let _ = fs::remove_dir_all(&path);
+            }
+        }
+    }
+
+    // The running helper is swapped right away unless it still owns a transfer
+    let busy = app
+        .state::<RwLock<ActiveDownloads>>()
+        .read()
+        .map(|downloads| {
+            downloads.downloads.values().any(|d| {
+                d.provider.as_deref() == Some("webview2")
+                    && matches!(d.status.as_str(), "starting" | "downloading" | "paused")
+            })
+        })
+        .unwrap_or(true);
+    if !busy {
+        sidecar::shutdown(app);
+    }
+    let _ = app.emit(
+        "helper-updated",
+        &serde_json::json!({
+            "version": release.version,
+            "appliesOnNextStart": busy
+        }),
+    );
+    Ok(release.version)
+}
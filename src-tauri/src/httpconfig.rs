@@ -0,0 +1,131 @@
+use crate::state::AppState;
+use reqwest::header::{HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct RequestSettings {
+    // Empty means the built-in default from default_user_agent
+    #[serde(rename = "userAgent")]
+    pub user_agent: String,
+
+    #[serde(rename = "providerHeaders")]
+    pub provider_headers: Vec<ProviderHeaders>,
+}
+
+// Extra headers for one host and its subdomains, e.g. "gofile.io" also covers "store1.gofile.io"
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ProviderHeaders {
+    pub host: String,
+    pub headers: HashMap<String, String>,
+}
+
+// Some mirrors reject reqwest's empty User-Agent, so requests identify as the app instead
+pub fn default_user_agent() -> String {
+    format!(
+        "Chanomhub-Desktop/{} ({})",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS
+    )
+}
+
+impl RequestSettings {
+    pub fn effective_user_agent(&self) -> String {
+        let custom = self.user_agent.trim();
+        if custom.is_empty() {
+            default_user_agent()
+        } else {
+            custom.to_string()
+        }
+    }
+
+    // Headers of every provider whose host matches the URL, later entries winning on conflicts
+    pub fn headers_for(&self, url: &str) -> Vec<(String, String)> {
+        let Some(host) = url::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_ascii_lowercase()))
+        else {
+            return Vec::new();
+        };
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for provider in self
+            .provider_headers
+            .iter()
+            .filter(|p| host_matches(&host, &p.host))
+        {
+            for (name, value) in &provider.headers {
+                headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+                headers.push((name.clone(), value.clone()));
+            }
+        }
+        headers
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.user_agent.trim().is_empty() {
+            HeaderValue::from_str(self.user_agent.trim())
+                .map_err(|_| "User-Agent contains invalid characters".to_string())?;
+        }
+        for provider in &self.provider_headers {
+            if normalize_host(&provider.host).is_empty() {
+                return Err("Provider host cannot be empty".to_string());
+            }
+            for (name, value) in &provider.headers {
+                HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+                    format!("Invalid header name \"{}\" for {}", name, provider.host)
+                })?;
+                HeaderValue::from_str(value).map_err(|_| {
+                    format!("Invalid value for header {} on {}", name, provider.host)
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn normalize_host(host: &str) -> String {
+    host.trim()
+        .trim_start_matches("*.")
+        .trim_start_matches('.')
+        .to_ascii_lowercase()
+}
+
+fn host_matches(host: &str, pattern: &str) -> bool {
+    let pattern = normalize_host(pattern);
+    !pattern.is_empty() && (host == pattern || host.ends_with(&format!(".{}", pattern)))
+}
+
+pub fn settings(app: &AppHandle) -> RequestSettings {
+    app.state::<Mutex<AppState>>()
+        .lock()
+        .map(|app_state| app_state.request_settings.clone())
+        .unwrap_or_default()
+}
+
+// Starting point for every client talking to the API or mirrors, so they all send the same User-Agent
+pub fn client_builder(settings: &RequestSettings) -> reqwest::ClientBuilder {
+    reqwest::Client::builder().user_agent(settings.effective_user_agent())
+}
+
+pub fn client(app: &AppHandle) -> Result<reqwest::Client, String> {
+    client_builder(&settings(app))
+        .build()
+        .map_err(|e| format!("Failed to create client: {}", e))
+}
+
+pub fn apply(
+    settings: &RequestSettings,
+    request: reqwest::RequestBuilder,
+    url: &str,
+) -> reqwest::RequestBuilder {
+    settings
+        .headers_for(url)
+        .into_iter()
+        .fold(request, |request, (name, value)| {
+            request.header(name, value)
+        })
+}
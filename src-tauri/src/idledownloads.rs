@@ -0,0 +1,184 @@
+use crate::downloader;
+use crate::state::AppState;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+// Downloads this mode paused because the user was active; only these are resumed by it
+#[derive(Default)]
+pub struct IdleDownloads {
+    paused: Vec<String>,
+    // Logged once, so a platform without an idle API does not fill the log
+    unsupported_logged: bool,
+}
+
+#[cfg(target_os = "windows")]
+#[repr(C)]
+struct LastInputInfo {
+    cb_size: u32,
+    dw_time: u32,
+}
+
+#[cfg(target_os = "windows")]
+#[link(name = "user32")]
+unsafe extern "system" {
+    fn GetLastInputInfo(info: *mut LastInputInfo) -> i32;
+}
+
+#[cfg(target_os = "windows")]
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn GetTickCount() -> u32;
+}
+
+#[cfg(target_os = "windows")]
+pub fn idle_time() -> Option<Duration> {
+    let mut info = LastInputInfo {
+        cb_size: std::mem::size_of::<LastInputInfo>() as u32,
+        dw_time: 0,
+    };
+    if unsafe { GetLastInputInfo(&mut info) } == 0 {
+        return None;
+    }
+    // Both counters wrap after 49 days, the difference stays right
+    let now = unsafe { GetTickCount() };
+    Some(Duration::from_millis(u64::from(
+        now.wrapping_sub(info.dw_time),
+    )))
+}
+
+// HIDIdleTime is in nanoseconds
+#[cfg(target_os = "macos")]
+pub fn idle_time() -> Option<Duration> {
+    let output = std::process::Command::new("ioreg")
+        .args(["-c", "IOHIDSystem", "-d", "4"])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.contains("\"HIDIdleTime\""))
+        .and_then(|line| line.rsplit('=').next())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_nanos)
+}
+
+// X11 through xprintidle, GNOME (also on Wayland) through Mutter's idle monitor
+#[cfg(target_os = "linux")]
+pub fn idle_time() -> Option<Duration> {
+    let run = |program: &str, args: &[&str]| -> Option<String> {
+        let output = std::process::Command::new(program)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())?;
+        Some(String::from_utf8_lossy(&output.stdout).to_string())
+    };
+    if let Some(ms) = run("xprintidle", &[]).and_then(|out| out.trim().parse::<u64>().ok()) {
+        return Some(Duration::from_millis(ms));
+    }
+    // Prints "(uint64 12345,)"
+    run(
+        "gdbus",
+        &[
+            "call",
+            "--session",
+            "--dest",
+            "org.gnome.Mutter.IdleMonitor",
+            "--object-path",
+            "/org/gnome/Mutter/IdleMonitor/Core",
+            "--method",
+            "org.gnome.Mutter.IdleMonitor.GetIdletime",
+        ],
+    )
+    .and_then(|out| {
+        out.split_whitespace()
+            .nth(1)
+            .map(|n| n.trim_end_matches([',', ')']).to_string())
+    })
+    .and_then(|n| n.parse::<u64>().ok())
+    .map(Duration::from_millis)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+pub fn idle_time() -> Option<Duration> {
+    None
+}
+
+// Downloads that start while the user is active are caught on the next check
+async fn pause_running(app: &AppHandle) {
+    let paused = downloader::pause_running(app, "idle").await;
+    if paused.is_empty() {
+        return;
+    }
+    println!("Paused {} downloads until the system is idle", paused.len());
+    let _ = app.emit(
+        "downloads-idle-paused",
+        &serde_json::json!({ "ids": paused }),
+    );
+    if let Ok(mut idle) = app.state::<Mutex<IdleDownloads>>().lock() {
+        idle.paused.extend(paused);
+    }
+}
+
+async fn resume_paused(app: &AppHandle) {
+    let ids = match app.state::<Mutex<IdleDownloads>>().lock() {
+        Ok(mut idle) => std::mem::take(&mut idle.paused),
+        Err(e) => {
+            println!("Failed to lock idle downloads: {}", e);
+            return;
+        }
+    };
+    let resumed = downloader::unpause_all(app, ids, "idle").await;
+    if resumed.is_empty() {
+        return;
+    }
+    println!(
+        "Resumed {} downloads while the system is idle",
+        resumed.len()
+    );
+    let _ = app.emit(
+        "downloads-idle-resumed",
+        &serde_json::json!({ "ids": resumed }),
+    );
+}
+
+// Checks input idle time in the background; the setting is re-read every round, and turning it
+// off gives back everything it paused
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            let (enabled, minutes) = match app.state::<Mutex<AppState>>().lock() {
+                Ok(app_state) => (
+                    app_state.download_settings.idle_only,
+                    app_state.download_settings.idle_minutes,
+                ),
+                Err(_) => continue,
+            };
+            if !enabled {
+                resume_paused(&app).await;
+                continue;
+            }
+            let Some(idle) = tauri::async_runtime::spawn_blocking(idle_time)
+                .await
+                .ok()
+                .flatten()
+            else {
+                if let Ok(mut idle) = app.state::<Mutex<IdleDownloads>>().lock()
+                    && !idle.unsupported_logged
+                {
+                    idle.unsupported_logged = true;
+                    println!("Idle time is not available on this system, downloads keep running");
+                }
+                continue;
+            };
+            if idle >= Duration::from_secs(minutes * 60) {
+                resume_paused(&app).await;
+            } else {
+                pause_running(&app).await;
+            }
+        }
+    });
+}
@@ -0,0 +1,250 @@
+use crate::launchhooks::append_log;
+use crate::state::LaunchConfig;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command as StdCommand, Stdio};
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+// Holds the HidHide CLI path while controllers are cloaked, so a crash or forced exit before the game
+// closes does not leave them hidden from every other program
+const CLOAK_MARKER: &str = "hidhide-cloaked";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RemapperKind {
+    // Runs with a profile for as long as the game does
+    #[default]
+    Antimicrox,
+    // Windows only: hides the physical controller while the game runs, so only the remapped one is seen
+    Hidhide,
+    // Any other program, started with `args` and closed with the game
+    Custom,
+}
+
+impl RemapperKind {
+    fn name(self) -> &'static str {
+        match self {
+            RemapperKind::Antimicrox => "AntiMicroX",
+            RemapperKind::Hidhide => "HidHide",
+            RemapperKind::Custom => "Input remapper",
+        }
+    }
+}
+
+// Input remapper started alongside a game and closed when it exits
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct InputRemapper {
+    pub enabled: bool,
+    pub kind: RemapperKind,
+    // Overrides the usual install location; required for custom
+    pub program: Option<String>,
+    // antimicrox profile (.amgp) to load
+    pub profile: Option<String>,
+    pub args: Vec<String>,
+}
+
+impl InputRemapper {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.kind == RemapperKind::Custom
+            && self.program.as_deref().is_none_or(|p| p.trim().is_empty())
+        {
+            return Err("A custom input remapper needs a program".to_string());
+        }
+        if self.kind == RemapperKind::Hidhide && !cfg!(target_os = "windows") {
+            return Err("HidHide is only available on Windows".to_string());
+        }
+        Ok(())
+    }
+}
+
+// What has to be undone once the game exits
+pub enum RemapperHandle {
+    Process(Child),
+    Cloaked {
+        program: PathBuf,
+        marker: Option<PathBuf>,
+    },
+}
+
+fn cloak_marker(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_local_data_dir()
+        .ok()
+        .map(|dir| dir.join(CLOAK_MARKER))
+}
+
+#[cfg(target_os = "windows")]
+fn default_program(kind: RemapperKind) -> Option<PathBuf> {
+    let relative = match kind {
+        RemapperKind::Antimicrox => "AntiMicroX\\bin\\antimicrox.exe",
+        RemapperKind::Hidhide => "Nefarius Software Solutions\\HidHide\\x64\\HidHideCLI.exe",
+        RemapperKind::Custom => return None,
+    };
+    std::env::var_os("ProgramFiles")
+        .map(|dir| PathBuf::from(dir).join(relative))
+        .filter(|path| path.is_file())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn default_program(kind: RemapperKind) -> Option<PathBuf> {
+    match kind {
+        // Resolved through PATH
+        RemapperKind::Antimicrox => Some(PathBuf::from("antimicrox")),
+        RemapperKind::Hidhide | RemapperKind::Custom => None,
+    }
+}
+
+fn program(remapper: &InputRemapper) -> Result<PathBuf, String> {
+    remapper
+        .program
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| default_program(remapper.kind))
+        .ok_or_else(|| {
+            format!(
+                "{} was not found; set its program path",
+                remapper.kind.name()
+            )
+        })
+}
+
+fn run_hidhide(program: &Path, arg: &str) -> Result<(), String> {
+    let mut command = StdCommand::new(program);
+    command.arg(arg);
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run HidHide: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "HidHide {} failed: {}",
+            arg,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+// Started before the game so the controller is already remapped when it opens its input devices
+pub fn start(
+    app: &AppHandle,
+    launch_config: &LaunchConfig,
+    log_path: Option<&Path>,
+) -> Result<Option<RemapperHandle>, String> {
+    let Some(remapper) = launch_config.input_remapper.as_ref().filter(|r| r.enabled) else {
+        return Ok(None);
+    };
+    remapper.validate()?;
+    let program = program(remapper)?;
+
+    if remapper.kind == RemapperKind::Hidhide {
+        // Recorded first, so cloaking that succeeds is always undone on the next start
+        let marker = cloak_marker(app);
+        if let Some(marker) = &marker {
+            let written = marker
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|()| std::fs::write(marker, program.to_string_lossy().as_bytes()));
+            if let Err(e) = written {
+                println!("Failed to record HidHide cloaking: {}", e);
+            }
+        }
+        if let Err(e) = run_hidhide(&program, "--cloak-on") {
+            if let Some(marker) = &marker {
+                let _ = std::fs::remove_file(marker);
+            }
+            return Err(e);
+        }
+        append_log(log_path, "HidHide cloaking enabled");
+        return Ok(Some(RemapperHandle::Cloaked { program, marker }));
+    }
+
+    let mut command = StdCommand::new(&program);
+    if remapper.kind == RemapperKind::Antimicrox {
+        command.arg("--hidden");
+        if let Some(profile) = remapper.profile.as_deref().filter(|p| !p.is_empty()) {
+            command.arg("--profile").arg(profile);
+        }
+    }
+    let child = command
+        .args(&remapper.args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", program.display(), e))?;
+    append_log(
+        log_path,
+        &format!(
+            "started input remapper {} (pid {})",
+            program.display(),
+            child.id()
+        ),
+    );
+    Ok(Some(RemapperHandle::Process(child)))
+}
+
+pub fn stop(handle: RemapperHandle, log_path: Option<&Path>) {
+    match handle {
+        RemapperHandle::Process(mut child) => {
+            // An antimicrox that was already running takes over the profile and this one exits by itself
+            if matches!(child.try_wait(), Ok(None)) {
+                let _ = child.kill();
+            }
+            let _ = child.wait();
+            append_log(log_path, "input remapper closed");
+        }
+        RemapperHandle::Cloaked { program, marker } => match run_hidhide(&program, "--cloak-off") {
+            Ok(()) => {
+                if let Some(marker) = marker {
+                    let _ = std::fs::remove_file(marker);
+                }
+                append_log(log_path, "HidHide cloaking disabled");
+            }
+            Err(e) => {
+                println!("{}", e);
+                append_log(log_path, &e);
+            }
+        },
+    }
+}
+
+// Turns off cloaking left on by a session that never reached the game's exit
+pub fn restore_cloak(app: &AppHandle) {
+    let Some(marker) = cloak_marker(app) else {
+        return;
+    };
+    let Ok(program) = std::fs::read_to_string(&marker) else {
+        return;
+    };
+    match run_hidhide(Path::new(program.trim()), "--cloak-off") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&marker);
+            println!("Turned off HidHide cloaking left over from the last session");
+        }
+        Err(e) => println!("Failed to turn off leftover HidHide cloaking: {}", e),
+    }
+}
+
+// Closes the remapper once `exited` fires
+pub fn stop_on_exit(handle: RemapperHandle, log_path: Option<PathBuf>, exited: CancellationToken) {
+    tauri::async_runtime::spawn(async move {
+        exited.cancelled().await;
+        let _ =
+            tauri::async_runtime::spawn_blocking(move || stop(handle, log_path.as_deref())).await;
+    });
+}
@@ -0,0 +1,244 @@
+use crate::{httpconfig, offline, sidecar};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+// Written by build.rs from the files that went into the bundle
+const MANIFEST: &str = include_str!(concat!(env!("OUT_DIR"), "/resource-manifest.json"));
+
+// The repository holds the same files the installer bundles; the release tag is tried first
+const SOURCE_URL: &str = "https://raw.githubusercontent.com/Chanomhub/Chanomhub-Desktop";
+
+// Result of the last check, kept for the UI until the app restarts
+static LAST_REPORT: Mutex<Option<InstallationReport>> = Mutex::new(None);
+
+#[derive(Deserialize, Clone, Debug)]
+struct ManifestEntry {
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceProblem {
+    // Relative to the resource folder, e.g. "binaries/Release/Newtonsoft.Json.dll"
+    pub path: String,
+    // "missing" or "modified"
+    pub reason: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallationReport {
+    pub checked: usize,
+    pub problems: Vec<ResourceProblem>,
+    pub checked_at: String,
+}
+
+fn manifest() -> Vec<ManifestEntry> {
+    serde_json::from_str(MANIFEST).unwrap_or_default()
+}
+
+fn resource_path(app: &AppHandle, relative: &str) -> Result<PathBuf, String> {
+    let root = app
+        .path()
+        .resource_dir()
+        .map_err(|e| format!("Failed to get resource dir: {}", e))?;
+    Ok(relative.split('/').fold(root, |path, part| path.join(part)))
+}
+
+fn matches(path: &Path, entry: &ManifestEntry) -> Result<bool, std::io::Error> {
+    // Size first, so a truncated file is not hashed
+    if fs::metadata(path)?.len() != entry.size {
+        return Ok(false);
+    }
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()).eq_ignore_ascii_case(&entry.sha256))
+}
+
+pub fn verify(app: &AppHandle) -> Result<InstallationReport, String> {
+    let entries = manifest();
+    let mut problems = Vec::new();
+    for entry in &entries {
+        let path = resource_path(app, &entry.path)?;
+        let reason = match matches(&path, entry) {
+            Ok(true) => continue,
+            Ok(false) => "modified",
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => "missing",
+            // Locked or unreadable (often an antivirus holding it) counts as damaged as well
+            Err(e) => {
+                println!("Failed to check {}: {}", path.display(), e);
+                "modified"
+            }
+        };
+        problems.push(ResourceProblem {
+            path: entry.path.clone(),
+            reason: reason.to_string(),
+        });
+    }
+    let report = InstallationReport {
+        checked: entries.len(),
+        problems,
+        checked_at: chrono::Utc::now().to_rfc3339(),
+    };
+    if let Ok(mut last) = LAST_REPORT.lock() {
+        *last = Some(report.clone());
+    }
+    Ok(report)
+}
+
+pub fn last_report() -> Option<InstallationReport> {
+    LAST_REPORT.lock().ok().and_then(|last| last.clone())
+}
+
+async fn download(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    entry: &ManifestEntry,
+) -> Result<Vec<u8>, String> {
+    let version = app.package_info().version.to_string();
+    let mut last_error = String::new();
+    for reference in [format!("v{}", version), "main".to_string()] {
+        let url = format!("{}/{}/src-tauri/{}", SOURCE_URL, reference, entry.path);
+        let response = match client.get(&url).send().await {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                last_error = format!("{} responded with {}", url, response.status());
+                continue;
+            }
+            Err(e) => {
+                last_error = format!("Failed to download {}: {}", entry.path, e);
+                continue;
+            }
+        };
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to download {}: {}", entry.path, e))?;
+        // Only the exact file this build shipped with is accepted
+        if hex::encode(Sha256::digest(&bytes)).eq_ignore_ascii_case(&entry.sha256) {
+            return Ok(bytes.to_vec());
+        }
+        last_error = format!(
+            "{} from {} does not match this build",
+            entry.path, reference
+        );
+    }
+    Err(last_error)
+}
+
+// Downloads every missing or altered file again and checks the installation once more
+pub async fn repair(app: &AppHandle) -> Result<InstallationReport, String> {
+    let report = verify(app)?;
+    if report.problems.is_empty() {
+        return Ok(report);
+    }
+    // Windows will not replace files of a running helper
+    if report
+        .problems
+        .iter()
+        .any(|p| p.path.starts_with("binaries/"))
+    {
+        sidecar::shutdown(app);
+    }
+
+    let entries = manifest();
+    offline::ensure_online(app)?;
+    let client = httpconfig::client(app)?;
+    for problem in &report.problems {
+        let Some(entry) = entries.iter().find(|e| e.path == problem.path) else {
+            continue;
+        };
+        let bytes = download(app, &client, entry).await?;
+        let target = resource_path(app, &entry.path)?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let incoming = target.with_extension("repair");
+        fs::write(&incoming, bytes)
+            .and_then(|_| fs::rename(&incoming, &target))
+            .map_err(|e| {
+                let _ = fs::remove_file(&incoming);
+                format!("Failed to restore {}: {}", target.display(), e)
+            })?;
+        println!("Restored {}", entry.path);
+    }
+
+    let report = verify(app)?;
+    let _ = app.emit("installation-checked", &report);
+    Ok(report)
+}
+
+fn ask_to_repair(app: &AppHandle, report: &InstallationReport) -> bool {
+    let files: Vec<String> = report
+        .problems
+        .iter()
+        .take(5)
+        .map(|p| format!("{} ({})", p.path, p.reason))
+        .collect();
+    let more = report.problems.len().saturating_sub(files.len());
+    app.dialog()
+        .message(format!(
+            "Some files of the installation are missing or damaged, possibly removed by an antivirus:\n\n{}{}\n\nDownload them again now?",
+            files.join("\n"),
+            if more > 0 {
+                format!("\n...and {} more", more)
+            } else {
+                String::new()
+            }
+        ))
+        .title("Chanomhub")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Repair".to_string(),
+            "Later".to_string(),
+        ))
+        .blocking_show()
+}
+
+// Checks the bundled files in the background after startup and offers a repair when any is off
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let check_app = app.clone();
+        let report = match tauri::async_runtime::spawn_blocking(move || verify(&check_app)).await {
+            Ok(Ok(report)) => report,
+            Ok(Err(e)) => {
+                println!("Installation check failed: {}", e);
+                return;
+            }
+            Err(e) => {
+                println!("Installation check panicked: {}", e);
+                return;
+            }
+        };
+        let _ = app.emit("installation-checked", &report);
+        if report.problems.is_empty() {
+            return;
+        }
+        println!(
+            "{} of {} bundled files are missing or altered",
+            report.problems.len(),
+            report.checked
+        );
+        let dialog_app = app.clone();
+        let accepted =
+            tauri::async_runtime::spawn_blocking(move || ask_to_repair(&dialog_app, &report))
+                .await
+                .unwrap_or(false);
+        if accepted && let Err(e) = repair(&app).await {
+            println!("Installation repair failed: {}", e);
+            let _ = app.emit(
+                "installation-repair-failed",
+                &serde_json::json!({ "error": e }),
+            );
+        }
+    });
+}
@@ -0,0 +1,205 @@
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Manager};
+
+const KEY_FILE: &str = "state.key";
+
+const KEY_LEN: usize = 32;
+
+// A key generated on this run cannot check signatures written with a lost one,
+// so existing files are trusted once and re-signed instead of all failing
+struct SigningKey {
+    bytes: Vec<u8>,
+    fresh: bool,
+}
+
+static KEY: OnceLock<SigningKey> = OnceLock::new();
+
+// Failures found while loading, kept for the UI until the app restarts
+static FAILURES: Mutex<Vec<IntegrityFailure>> = Mutex::new(Vec::new());
+
+#[derive(Serialize, Clone, Debug)]
+pub struct IntegrityFailure {
+    pub file: String,
+    pub reason: String,
+    #[serde(rename = "restoredFromBackup")]
+    pub restored_from_backup: bool,
+    #[serde(rename = "detectedAt")]
+    pub detected_at: String,
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+// Kept outside the config dir so copying or editing that folder does not carry the key along
+fn load_key(app: &AppHandle) -> SigningKey {
+    let path = app
+        .path()
+        .app_local_data_dir()
+        .ok()
+        .map(|dir| dir.join(KEY_FILE));
+    if let Some(bytes) = path
+        .as_ref()
+        .and_then(|p| fs::read(p).ok())
+        .filter(|b| b.len() == KEY_LEN)
+    {
+        return SigningKey {
+            bytes,
+            fresh: false,
+        };
+    }
+
+    let mut bytes = vec![0u8; KEY_LEN];
+    if let Err(e) = getrandom::getrandom(&mut bytes) {
+        println!("Failed to generate state signing key: {}", e);
+    }
+    if let Some(path) = path {
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, &bytes));
+        if let Err(e) = result {
+            println!("Failed to save state signing key: {}", e);
+        }
+    }
+    SigningKey { bytes, fresh: true }
+}
+
+fn key(app: &AppHandle) -> &'static SigningKey {
+    KEY.get_or_init(|| load_key(app))
+}
+
+fn sign(app: &AppHandle, contents: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(&key(app).bytes).expect("HMAC accepts keys of any length");
+    mac.update(contents);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn verify(app: &AppHandle, contents: &[u8], signature: &str) -> bool {
+    let Ok(expected) = hex::decode(signature.trim()) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(&key(app).bytes) else {
+        return false;
+    };
+    mac.update(contents);
+    mac.verify_slice(&expected).is_ok()
+}
+
+fn is_valid(app: &AppHandle, path: &Path) -> bool {
+    match (fs::read(path), fs::read_to_string(sibling(path, ".sig"))) {
+        (Ok(contents), Ok(signature)) => verify(app, &contents, &signature),
+        _ => false,
+    }
+}
+
+fn record_failure(path: &Path, reason: &str, restored_from_backup: bool) {
+    let file = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string());
+    println!(
+        "Integrity check failed for {}: {} (restored from backup: {})",
+        file, reason, restored_from_backup
+    );
+    if let Ok(mut failures) = FAILURES.lock() {
+        failures.push(IntegrityFailure {
+            file,
+            reason: reason.to_string(),
+            restored_from_backup,
+            detected_at: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+}
+
+pub fn failures() -> Vec<IntegrityFailure> {
+    FAILURES.lock().map(|f| f.clone()).unwrap_or_default()
+}
+
+// The previous good version becomes the backup, then the file and its signature are written to temp
+// files and renamed into place, signature first. A crash between the two renames leaves a signature
+// that does not match, and the next read falls back to the backup.
+pub fn write_signed(app: &AppHandle, path: &Path, contents: &[u8]) -> Result<(), String> {
+    let sig_path = sibling(path, ".sig");
+    if is_valid(app, path) {
+        let _ = fs::copy(path, sibling(path, ".bak"));
+        let _ = fs::copy(&sig_path, sibling(path, ".bak.sig"));
+    }
+
+    let tmp_path = sibling(path, ".tmp");
+    let tmp_sig_path = sibling(path, ".sig.tmp");
+    fs::write(&tmp_path, contents)
+        .map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+    fs::write(&tmp_sig_path, sign(app, contents))
+        .map_err(|e| format!("Failed to write {}: {}", tmp_sig_path.display(), e))?;
+    fs::rename(&tmp_sig_path, &sig_path)
+        .map_err(|e| format!("Failed to replace {}: {}", sig_path.display(), e))?;
+    fs::rename(&tmp_path, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+fn restore_backup(app: &AppHandle, path: &Path) -> Option<String> {
+    let backup = sibling(path, ".bak");
+    if !is_valid(app, &backup) {
+        return None;
+    }
+    let contents = fs::read_to_string(&backup).ok()?;
+    fs::copy(&backup, path).ok()?;
+    fs::copy(sibling(path, ".bak.sig"), sibling(path, ".sig")).ok()?;
+    Some(contents)
+}
+
+// Returns None when the file does not exist. A file that fails its signature is replaced by the
+// last good backup; Err means neither could be trusted.
+pub fn read_verified(app: &AppHandle, path: &Path) -> Result<Option<String>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents =
+        fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let reason = match fs::read_to_string(sibling(path, ".sig")) {
+        // Written by a version without signatures, or the key was just regenerated
+        Err(_) if !sibling(path, ".bak.sig").exists() => None,
+        Ok(_) if key(app).fresh => None,
+        Err(_) => Some("signature file is missing"),
+        Ok(signature) if verify(app, &contents, &signature) => {
+            return String::from_utf8(contents)
+                .map(Some)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e));
+        }
+        Ok(_) => Some("signature does not match the contents"),
+    };
+
+    let Some(reason) = reason else {
+        let text = String::from_utf8(contents)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        write_signed(app, path, text.as_bytes())?;
+        return Ok(Some(text));
+    };
+
+    match restore_backup(app, path) {
+        Some(contents) => {
+            record_failure(path, reason, true);
+            Ok(Some(contents))
+        }
+        None => {
+            // Kept aside for manual recovery, since the caller falls back to defaults
+            let corrupt = sibling(path, ".corrupt");
+            let _ = fs::rename(path, &corrupt);
+            record_failure(path, reason, false);
+            Err(format!(
+                "{} failed its integrity check and no valid backup exists (moved to {})",
+                path.display(),
+                corrupt.display()
+            ))
+        }
+    }
+}
@@ -0,0 +1,385 @@
+use crate::DownloadInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+// Progress within a stage is sent at most this often; stage and status changes always go out
+const EMIT_INTERVAL: Duration = Duration::from_millis(250);
+
+// How deep exe detection looks below the extraction folder
+const MAX_SCAN_DEPTH: usize = 4;
+
+// Bundled helpers that are never the game itself
+const NOT_THE_GAME: &[&str] = &[
+    "unins",
+    "uninstall",
+    "crashhandler",
+    "crashreport",
+    "vc_redist",
+    "vcredist",
+    "dxsetup",
+    "dxwebsetup",
+    "notification_helper",
+    "setup",
+];
+
+// Ordered steps from link to playable game
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Stage {
+    Download,
+    Verify,
+    Extract,
+    DetectExe,
+    Ready,
+}
+
+impl Stage {
+    const ALL: [Stage; 5] = [
+        Stage::Download,
+        Stage::Verify,
+        Stage::Extract,
+        Stage::DetectExe,
+        Stage::Ready,
+    ];
+
+    // Share of the job's overall progress
+    fn weight(self) -> f64 {
+        match self {
+            Stage::Download => 70.0,
+            Stage::Verify => 5.0,
+            Stage::Extract => 20.0,
+            Stage::DetectExe => 5.0,
+            Stage::Ready => 0.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum StageStatus {
+    Pending,
+    Running,
+    // Needs the user, e.g. the extraction rule is "ask"
+    Waiting,
+    Paused,
+    Done,
+    Skipped,
+    Failed,
+    Cancelled,
+}
+
+impl StageStatus {
+    fn finished(self) -> bool {
+        matches!(self, StageStatus::Done | StageStatus::Skipped)
+    }
+
+    fn stops_job(self) -> bool {
+        matches!(self, StageStatus::Failed | StageStatus::Cancelled)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StageProgress {
+    pub stage: Stage,
+    pub status: StageStatus,
+    pub progress: f64,
+}
+
+// One download and everything that happens to it afterwards, under the download's id
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub id: String,
+    pub title: String,
+    // First stage that is not finished yet
+    pub stage: Stage,
+    pub status: StageStatus,
+    // 0-100 across all stages
+    pub progress: f64,
+    pub stages: Vec<StageProgress>,
+    // Found after extraction, most likely first
+    pub executables: Vec<String>,
+    pub error: Option<String>,
+    pub updated_at: String,
+    #[serde(skip)]
+    last_emit: Option<Instant>,
+}
+
+impl Job {
+    fn new(id: &str, title: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            title: title.to_string(),
+            stage: Stage::Download,
+            status: StageStatus::Pending,
+            progress: 0.0,
+            stages: Stage::ALL
+                .iter()
+                .map(|stage| StageProgress {
+                    stage: *stage,
+                    status: StageStatus::Pending,
+                    progress: 0.0,
+                })
+                .collect(),
+            executables: Vec::new(),
+            error: None,
+            updated_at: String::new(),
+            last_emit: None,
+        }
+    }
+
+    fn refresh(&mut self) {
+        let current = self
+            .stages
+            .iter()
+            .find(|s| s.status.stops_job())
+            .or_else(|| self.stages.iter().find(|s| !s.status.finished()))
+            .or_else(|| self.stages.last());
+        if let Some(current) = current {
+            self.stage = current.stage;
+            self.status = current.status;
+        }
+        self.progress = self
+            .stages
+            .iter()
+            .map(|s| match s.status {
+                StageStatus::Done | StageStatus::Skipped => s.stage.weight(),
+                _ => s.stage.weight() * s.progress / 100.0,
+            })
+            .sum::<f64>()
+            .min(100.0);
+        // Ready has nothing of its own to do; it completes with the stages before it
+        let ready = self
+            .stages
+            .iter()
+            .rev()
+            .skip(1)
+            .all(|s| s.status.finished());
+        if let Some(last) = self.stages.last_mut() {
+            // Extracting again takes a ready job back to that stage
+            last.status = if ready {
+                StageStatus::Done
+            } else {
+                StageStatus::Pending
+            };
+            last.progress = if ready { 100.0 } else { 0.0 };
+        }
+        if ready {
+            self.stage = Stage::Ready;
+            self.status = StageStatus::Done;
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Jobs(HashMap<String, Job>);
+
+// Updates one stage of a job, creating the job on first use, and sends `job-progress`
+pub fn update(
+    app: &AppHandle,
+    id: &str,
+    title: &str,
+    stage: Stage,
+    status: StageStatus,
+    progress: f64,
+) {
+    change(app, id, title, |job| {
+        if let Some(entry) = job.stages.iter_mut().find(|s| s.stage == stage) {
+            entry.status = status;
+            entry.progress = match status {
+                StageStatus::Done | StageStatus::Skipped => 100.0,
+                _ => progress.clamp(0.0, 100.0),
+            };
+        }
+        if status == StageStatus::Running {
+            job.error = None;
+        }
+    });
+}
+
+pub fn fail(app: &AppHandle, id: &str, title: &str, stage: Stage, error: &str) {
+    change(app, id, title, |job| {
+        if let Some(entry) = job.stages.iter_mut().find(|s| s.stage == stage) {
+            entry.status = StageStatus::Failed;
+        }
+        job.error = Some(error.to_string());
+    });
+}
+
+fn change(app: &AppHandle, id: &str, title: &str, apply: impl FnOnce(&mut Job)) {
+    let jobs = app.state::<Mutex<Jobs>>();
+    let Ok(mut jobs) = jobs.lock() else {
+        return;
+    };
+    let job = jobs
+        .0
+        .entry(id.to_string())
+        .or_insert_with(|| Job::new(id, title));
+    let before = (job.stage, job.status);
+    apply(job);
+    job.refresh();
+    let changed = before != (job.stage, job.status);
+    if !changed && job.last_emit.is_some_and(|at| at.elapsed() < EMIT_INTERVAL) {
+        return;
+    }
+    job.updated_at = chrono::Utc::now().to_rfc3339();
+    job.last_emit = Some(Instant::now());
+    let _ = app.emit("job-progress", &*job);
+}
+
+// Follows the download's own status; called whenever webview2_response has handled a message
+pub fn track_download(app: &AppHandle, download: &DownloadInfo) {
+    let (id, title) = (download.id.as_str(), download.filename.as_str());
+    let progress = f64::from(download.progress);
+    match download.status.as_str() {
+        "queued" => update(app, id, title, Stage::Download, StageStatus::Pending, 0.0),
+        "starting" | "downloading" | "transferring" => update(
+            app,
+            id,
+            title,
+            Stage::Download,
+            StageStatus::Running,
+            progress,
+        ),
+        "paused" => update(
+            app,
+            id,
+            title,
+            Stage::Download,
+            StageStatus::Paused,
+            progress,
+        ),
+        "cancelled" => update(app, id, title, Stage::Download, StageStatus::Cancelled, 0.0),
+        "completed" => {
+            update(app, id, title, Stage::Download, StageStatus::Done, 100.0);
+            // Only a chunk manifest checks the bytes themselves; without one there is nothing
+            // to verify against, and a broken archive shows up when it is extracted
+            let verify = if download.chunks_verified {
+                StageStatus::Done
+            } else {
+                StageStatus::Skipped
+            };
+            update(app, id, title, Stage::Verify, verify, 100.0);
+        }
+        "failed" | "unknown" => fail(
+            app,
+            id,
+            title,
+            Stage::Download,
+            download.error.as_deref().unwrap_or("Download failed"),
+        ),
+        _ => {}
+    }
+}
+
+// Nothing to extract, so there is no folder to look for the game in either
+pub fn skip_extraction(app: &AppHandle, id: &str, title: &str) {
+    update(app, id, title, Stage::Extract, StageStatus::Skipped, 100.0);
+    update(
+        app,
+        id,
+        title,
+        Stage::DetectExe,
+        StageStatus::Skipped,
+        100.0,
+    );
+}
+
+// Looks for the game's executable in the extracted folder and finishes the job
+pub async fn detect_executables(app: &AppHandle, id: &str, title: &str, dir: &Path) {
+    update(app, id, title, Stage::DetectExe, StageStatus::Running, 0.0);
+    let scan_dir = dir.to_path_buf();
+    let found = tauri::async_runtime::spawn_blocking(move || find_executables(&scan_dir))
+        .await
+        .unwrap_or_default();
+    println!(
+        "Found {} executable candidates in {}",
+        found.len(),
+        dir.display()
+    );
+    change(app, id, title, |job| {
+        job.executables = found;
+        if let Some(entry) = job.stages.iter_mut().find(|s| s.stage == Stage::DetectExe) {
+            entry.status = StageStatus::Done;
+            entry.progress = 100.0;
+        }
+    });
+}
+
+// Shallowest first, then the largest file, which is usually the game rather than a launcher
+fn find_executables(dir: &Path) -> Vec<String> {
+    let mut found: Vec<(usize, u64, PathBuf)> = Vec::new();
+    let mut pending = vec![(dir.to_path_buf(), 0)];
+    while let Some((current, depth)) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&current) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let extension = path
+                .extension()
+                .map(|e| e.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let executable = crate::EXECUTABLE_EXTENSIONS
+                .iter()
+                .any(|e| e.eq_ignore_ascii_case(&extension));
+            // A macOS .app bundle is a folder
+            if metadata.is_dir() && !executable {
+                if depth < MAX_SCAN_DEPTH {
+                    pending.push((path, depth + 1));
+                }
+                continue;
+            }
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if executable && !NOT_THE_GAME.iter().any(|skip| name.contains(skip)) {
+                found.push((depth, metadata.len(), path));
+            }
+        }
+    }
+    found.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+    found
+        .into_iter()
+        .map(|(_, _, path)| path.to_string_lossy().to_string())
+        .collect()
+}
+
+pub fn list(app: &AppHandle) -> Vec<Job> {
+    let mut jobs: Vec<Job> = app
+        .state::<Mutex<Jobs>>()
+        .lock()
+        .map(|jobs| jobs.0.values().cloned().collect())
+        .unwrap_or_default();
+    jobs.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    jobs
+}
+
+fn is_finished(job: &Job) -> bool {
+    job.stage == Stage::Ready || job.status.stops_job()
+}
+
+// Jobs that clear_finished would remove
+pub fn finished(app: &AppHandle) -> Vec<Job> {
+    list(app).into_iter().filter(is_finished).collect()
+}
+
+// Drops jobs that are ready, failed or cancelled; returns how many were removed
+pub fn clear_finished(app: &AppHandle) -> usize {
+    let jobs = app.state::<Mutex<Jobs>>();
+    let Ok(mut jobs) = jobs.lock() else {
+        return 0;
+    };
+    let before = jobs.0.len();
+    jobs.0.retain(|_, job| !is_finished(job));
+    before - jobs.0.len()
+}
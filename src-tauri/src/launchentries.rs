@@ -0,0 +1,105 @@
+use crate::state::{AppState, DownloadedGameInfo, LaunchConfig};
+use serde::{Deserialize, Serialize};
+
+const MAX_NAME_LEN: usize = 64;
+
+// Extra entry point of an install (config tool, patcher, ...), shown next to the main launch config
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LaunchEntry {
+    pub name: String,
+    #[serde(rename = "launchConfig")]
+    pub launch_config: LaunchConfig,
+}
+
+fn validate_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Launch entry name is required".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(format!(
+            "Launch entry name is longer than {} characters",
+            MAX_NAME_LEN
+        ));
+    }
+    Ok(name)
+}
+
+fn find_game<'a>(
+    app_state: &'a mut AppState,
+    game_id: &str,
+) -> Result<&'a mut DownloadedGameInfo, String> {
+    app_state
+        .games
+        .iter_mut()
+        .flatten()
+        .find(|g| g.id == game_id)
+        .ok_or_else(|| format!("Game with id {} not found", game_id))
+}
+
+// Adds the entry, or replaces the one with the same name (names are matched case-insensitively)
+pub fn save(
+    app_state: &mut AppState,
+    game_id: &str,
+    name: &str,
+    launch_config: LaunchConfig,
+) -> Result<LaunchEntry, String> {
+    let name = validate_name(name)?;
+    if let Some(remapper) = &launch_config.input_remapper {
+        remapper.validate()?;
+    }
+    let game = find_game(app_state, game_id)?;
+    let entry = LaunchEntry {
+        name: name.to_string(),
+        launch_config,
+    };
+    match game
+        .launch_entries
+        .iter_mut()
+        .find(|e| e.name.eq_ignore_ascii_case(name))
+    {
+        Some(existing) => *existing = entry.clone(),
+        None => game.launch_entries.push(entry.clone()),
+    }
+    Ok(entry)
+}
+
+// The stored config of an entry, so a save can tell which commands and paths changed
+pub fn existing(app_state: &AppState, game_id: &str, name: &str) -> Option<LaunchConfig> {
+    app_state
+        .games
+        .iter()
+        .flatten()
+        .find(|g| g.id == game_id)?
+        .launch_entries
+        .iter()
+        .find(|e| e.name.eq_ignore_ascii_case(name.trim()))
+        .map(|e| e.launch_config.clone())
+}
+
+pub fn delete(app_state: &mut AppState, game_id: &str, name: &str) -> Result<(), String> {
+    let game = find_game(app_state, game_id)?;
+    let before = game.launch_entries.len();
+    game.launch_entries
+        .retain(|e| !e.name.eq_ignore_ascii_case(name.trim()));
+    if game.launch_entries.len() == before {
+        return Err(format!("Launch entry {} not found", name));
+    }
+    Ok(())
+}
+
+// No name (or an empty one) is the game's main launch config
+pub fn resolve(
+    game: &DownloadedGameInfo,
+    entry_name: Option<&str>,
+) -> Result<Option<LaunchConfig>, String> {
+    match entry_name.map(str::trim).filter(|n| !n.is_empty()) {
+        None => Ok(game.launch_config.clone()),
+        Some(name) => game
+            .launch_entries
+            .iter()
+            .find(|e| e.name.eq_ignore_ascii_case(name))
+            .map(|e| Some(e.launch_config.clone()))
+            .ok_or_else(|| format!("Launch entry {} not found", name)),
+    }
+}
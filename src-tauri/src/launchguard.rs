@@ -0,0 +1,303 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command as StdCommand;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LaunchGuards {
+    // Free space needed on the game drive and the drive holding saves
+    #[serde(rename = "minFreeDiskMb")]
+    pub min_free_disk_mb: u64,
+
+    // "off", "warn" or "block"
+    #[serde(rename = "lowDiskAction")]
+    pub low_disk_action: String,
+
+    #[serde(rename = "batterySaverAction")]
+    pub battery_saver_action: String,
+}
+
+impl Default for LaunchGuards {
+    fn default() -> Self {
+        Self {
+            min_free_disk_mb: 1024,
+            low_disk_action: "warn".to_string(),
+            battery_saver_action: "off".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LaunchCheck {
+    pub check: String,
+    // "ok", "warn" or "block"
+    pub severity: String,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LaunchCheckReport {
+    #[serde(rename = "canLaunch")]
+    pub can_launch: bool,
+    #[serde(rename = "hasWarnings")]
+    pub has_warnings: bool,
+    pub checks: Vec<LaunchCheck>,
+}
+
+impl LaunchCheckReport {
+    pub fn from_checks(checks: Vec<LaunchCheck>) -> Self {
+        LaunchCheckReport {
+            can_launch: !checks.iter().any(|c| c.severity == "block"),
+            has_warnings: checks.iter().any(|c| c.severity == "warn"),
+            checks,
+        }
+    }
+
+    // Message for launch_game to return when a guard blocks the launch
+    pub fn blocked_reason(&self) -> Option<String> {
+        let reasons: Vec<&str> = self
+            .checks
+            .iter()
+            .filter(|c| c.severity == "block")
+            .map(|c| c.message.as_str())
+            .collect();
+        if reasons.is_empty() {
+            None
+        } else {
+            Some(format!("Launch blocked: {}", reasons.join("; ")))
+        }
+    }
+}
+
+fn failed_severity(action: &str) -> &'static str {
+    match action {
+        "block" => "block",
+        _ => "warn",
+    }
+}
+
+// Game folder plus the folder most engines write saves to; the drive with the least room decides
+fn check_disk(executable_path: &str, guards: &LaunchGuards) -> Option<LaunchCheck> {
+    if guards.low_disk_action == "off" {
+        return None;
+    }
+    let mut paths: Vec<PathBuf> = Path::new(executable_path)
+        .parent()
+        .map(Path::to_path_buf)
+        .into_iter()
+        .collect();
+    paths.extend(dirs::document_dir());
+
+    let lowest = paths
+        .iter()
+        .filter_map(|path| fs2::available_space(path).ok().map(|free| (path, free)))
+        .min_by_key(|(_, free)| *free);
+    let Some((path, free)) = lowest else {
+        return Some(LaunchCheck {
+            check: "disk".to_string(),
+            severity: "ok".to_string(),
+            message: "Could not determine free disk space".to_string(),
+            details: None,
+        });
+    };
+
+    let free_mb = free / (1024 * 1024);
+    let details = Some(serde_json::json!({
+        "path": path.to_string_lossy(),
+        "freeMb": free_mb,
+        "minFreeMb": guards.min_free_disk_mb
+    }));
+    Some(if free_mb < guards.min_free_disk_mb {
+        LaunchCheck {
+            check: "disk".to_string(),
+            severity: failed_severity(&guards.low_disk_action).to_string(),
+            message: format!(
+                "Only {} MB free on {}; the game may fail to write saves",
+                free_mb,
+                path.display()
+            ),
+            details,
+        }
+    } else {
+        LaunchCheck {
+            check: "disk".to_string(),
+            severity: "ok".to_string(),
+            message: format!("{} MB free", free_mb),
+            details,
+        }
+    })
+}
+
+// Power source and charge, shared with the battery pause of downloads
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerStatus {
+    pub on_battery: bool,
+    // None when the system does not report it (desktops, some drivers)
+    pub percent: Option<u8>,
+}
+
+#[cfg(target_os = "windows")]
+#[repr(C)]
+#[derive(Default)]
+struct SystemPowerStatus {
+    ac_line_status: u8,
+    battery_flag: u8,
+    battery_life_percent: u8,
+    system_status_flag: u8,
+    battery_life_time: u32,
+    battery_full_life_time: u32,
+}
+
+#[cfg(target_os = "windows")]
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+}
+
+#[cfg(target_os = "windows")]
+pub fn power_status() -> Option<PowerStatus> {
+    let mut status = SystemPowerStatus::default();
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return None;
+    }
+    // 255 means unknown for both fields
+    Some(PowerStatus {
+        on_battery: status.ac_line_status == 0,
+        percent: (status.battery_life_percent <= 100).then_some(status.battery_life_percent),
+    })
+}
+
+#[cfg(target_os = "linux")]
+pub fn power_status() -> Option<PowerStatus> {
+    let read = |path: std::path::PathBuf| {
+        std::fs::read_to_string(path)
+            .map(|s| s.trim().to_string())
+            .unwrap_or_default()
+    };
+    let mut status = PowerStatus::default();
+    for entry in std::fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let dir = entry.path();
+        if read(dir.join("type")) != "Battery" || read(dir.join("scope")) == "Device" {
+            continue;
+        }
+        if read(dir.join("status")) == "Discharging" {
+            status.on_battery = true;
+        }
+        if let Ok(percent) = read(dir.join("capacity")).parse::<u8>() {
+            status.percent = Some(status.percent.map_or(percent, |p| p.min(percent)));
+        }
+    }
+    Some(status)
+}
+
+// "Now drawing from 'Battery Power'" and "-InternalBattery-0 (id=...)	85%; discharging; ..."
+#[cfg(target_os = "macos")]
+pub fn power_status() -> Option<PowerStatus> {
+    let output = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let percent = text
+        .split(|c: char| c.is_whitespace() || c == ';')
+        .find_map(|part| part.strip_suffix('%'))
+        .and_then(|p| p.parse::<u8>().ok());
+    Some(PowerStatus {
+        on_battery: text.contains("'Battery Power'"),
+        percent,
+    })
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+pub fn power_status() -> Option<PowerStatus> {
+    None
+}
+
+// None when the platform does not tell us
+#[cfg(target_os = "windows")]
+fn battery_saver_on() -> Option<bool> {
+    use std::os::windows::process::CommandExt;
+
+    let output = StdCommand::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "[Windows.System.Power.PowerManager,Windows.System.Power,ContentType=WindowsRuntime]::EnergySaverStatus",
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "On" => Some(true),
+        "Off" | "Disabled" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn battery_saver_on() -> Option<bool> {
+    let output = StdCommand::new("pmset").arg("-g").output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let low_power = text
+        .lines()
+        .find(|line| line.trim_start().starts_with("lowpowermode"))?;
+    Some(low_power.trim_end().ends_with('1'))
+}
+
+// power-profiles-daemon when it is running, otherwise the firmware profile; a low battery alone
+// does not mean the system is throttling
+#[cfg(target_os = "linux")]
+fn battery_saver_on() -> Option<bool> {
+    if let Ok(output) = StdCommand::new("powerprofilesctl").arg("get").output()
+        && output.status.success()
+    {
+        return Some(String::from_utf8_lossy(&output.stdout).trim() == "power-saver");
+    }
+
+    let profile = std::fs::read_to_string("/sys/firmware/acpi/platform_profile").ok()?;
+    Some(profile.trim() == "low-power")
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn battery_saver_on() -> Option<bool> {
+    None
+}
+
+fn check_battery(guards: &LaunchGuards) -> Option<LaunchCheck> {
+    if guards.battery_saver_action == "off" {
+        return None;
+    }
+    Some(match battery_saver_on() {
+        Some(true) => LaunchCheck {
+            check: "battery".to_string(),
+            severity: failed_severity(&guards.battery_saver_action).to_string(),
+            message: "Battery saver is on; the game may run slowly".to_string(),
+            details: None,
+        },
+        Some(false) => LaunchCheck {
+            check: "battery".to_string(),
+            severity: "ok".to_string(),
+            message: "Battery saver is off".to_string(),
+            details: None,
+        },
+        None => LaunchCheck {
+            check: "battery".to_string(),
+            severity: "ok".to_string(),
+            message: "Battery saver state is unknown".to_string(),
+            details: None,
+        },
+    })
+}
+
+pub fn run_checks(executable_path: &str, guards: &LaunchGuards) -> LaunchCheckReport {
+    let checks: Vec<LaunchCheck> = check_disk(executable_path, guards)
+        .into_iter()
+        .chain(check_battery(guards))
+        .collect();
+    LaunchCheckReport::from_checks(checks)
+}
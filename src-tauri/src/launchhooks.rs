@@ -0,0 +1,264 @@
+use crate::crashloop;
+use crate::state::LaunchConfig;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+
+const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 30;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+// One log per game under the app log dir, so it goes away with "clear logs"
+pub fn game_log_path(app: &AppHandle, game_id: &str) -> Option<PathBuf> {
+    let name: String = game_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let dir = app.path().app_log_dir().ok()?.join("games");
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join(format!("{}.log", name)))
+}
+
+pub fn append_log(log_path: Option<&Path>, text: &str) {
+    let Some(log_path) = log_path else {
+        return;
+    };
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)
+        .and_then(|mut file| {
+            writeln!(
+                file,
+                "[{}] {}",
+                chrono::Local::now().to_rfc3339(),
+                text.trim_end()
+            )
+        });
+    if let Err(e) = result {
+        println!("Failed to write game log {}: {}", log_path.display(), e);
+    }
+}
+
+// cmd.exe does not follow the quoting Rust applies to arguments, so the command line is passed
+// through untouched; with /S cmd only strips the outer pair of quotes
+#[cfg(target_os = "windows")]
+fn cmd_line(command: &str) -> String {
+    format!("\"{}\"", command)
+}
+
+fn shell_command(command: &str) -> tokio::process::Command {
+    #[cfg(target_os = "windows")]
+    {
+        let mut cmd = tokio::process::Command::new("cmd");
+        cmd.args(["/D", "/S", "/C"]).raw_arg(cmd_line(command));
+        cmd
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    }
+}
+
+// Same shell as the hooks, for the "custom" launch method
+pub fn std_shell_command(command: &str) -> std::process::Command {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+
+        let mut cmd = std::process::Command::new("cmd");
+        cmd.args(["/D", "/S", "/C"]).raw_arg(cmd_line(command));
+        // The game opens its own window; this only hides the console cmd would add
+        cmd.creation_flags(CREATE_NO_WINDOW);
+        cmd
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let mut cmd = std::process::Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    }
+}
+
+// Runs a hook in the game folder and logs its output; the process is killed when the timeout hits
+pub async fn run_hook(
+    stage: &str,
+    command: &str,
+    working_dir: Option<&Path>,
+    timeout_secs: Option<u64>,
+    log_path: Option<&Path>,
+) -> Result<(), String> {
+    let timeout = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS));
+    append_log(log_path, &format!("{}: {}", stage, command));
+
+    let mut cmd = shell_command(command);
+    cmd.kill_on_drop(true);
+    if let Some(dir) = working_dir {
+        cmd.current_dir(dir);
+    }
+
+    let output = match tokio::time::timeout(timeout, cmd.output()).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            let message = format!("Failed to run {} command: {}", stage, e);
+            append_log(log_path, &message);
+            return Err(message);
+        }
+        Err(_) => {
+            let message = format!("{} command timed out after {}s", stage, timeout.as_secs());
+            append_log(log_path, &message);
+            return Err(message);
+        }
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stdout.trim().is_empty() {
+        append_log(log_path, &format!("{} stdout:\n{}", stage, stdout));
+    }
+    if !stderr.trim().is_empty() {
+        append_log(log_path, &format!("{} stderr:\n{}", stage, stderr));
+    }
+    append_log(
+        log_path,
+        &format!("{} exited with {}", stage, output.status),
+    );
+
+    if !output.status.success() {
+        return Err(format!("{} command failed with {}", stage, output.status));
+    }
+    Ok(())
+}
+
+// Started and left running with no time limit; it is not killed when the game exits either
+fn spawn_detached(
+    stage: &str,
+    command: &str,
+    working_dir: Option<&Path>,
+    log_path: Option<&Path>,
+) -> Result<(), String> {
+    append_log(log_path, &format!("{} (detached): {}", stage, command));
+    let mut cmd = shell_command(command);
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if let Some(dir) = working_dir {
+        cmd.current_dir(dir);
+    }
+    match cmd.spawn() {
+        Ok(child) => {
+            append_log(
+                log_path,
+                &format!("{} started (pid {:?})", stage, child.id()),
+            );
+            Ok(())
+        }
+        Err(e) => {
+            let message = format!("Failed to run {} command: {}", stage, e);
+            append_log(log_path, &message);
+            Err(message)
+        }
+    }
+}
+
+fn working_dir(launch_config: &LaunchConfig) -> Option<PathBuf> {
+    Path::new(&launch_config.executable_path)
+        .parent()
+        .map(Path::to_path_buf)
+}
+
+pub async fn run_pre_launch(
+    launch_config: &LaunchConfig,
+    log_path: Option<&Path>,
+) -> Result<(), String> {
+    let Some(command) = launch_config
+        .pre_launch
+        .as_deref()
+        .filter(|c| !c.trim().is_empty())
+    else {
+        return Ok(());
+    };
+    if launch_config.pre_launch_detached {
+        return spawn_detached(
+            "pre-launch",
+            command,
+            working_dir(launch_config).as_deref(),
+            log_path,
+        );
+    }
+    run_hook(
+        "pre-launch",
+        command,
+        working_dir(launch_config).as_deref(),
+        launch_config.hook_timeout_secs,
+        log_path,
+    )
+    .await
+}
+
+// Waits for the game to exit on a blocking thread, cancels `exited`, then runs the post-exit hook
+pub fn watch_for_exit(
+    app: AppHandle,
+    game_id: String,
+    mut child: std::process::Child,
+    launch_config: LaunchConfig,
+    log_path: Option<PathBuf>,
+    exited: CancellationToken,
+) {
+    tauri::async_runtime::spawn(async move {
+        let status = tauri::async_runtime::spawn_blocking(move || child.wait()).await;
+        exited.cancel();
+        match status {
+            Ok(Ok(status)) => {
+                append_log(log_path.as_deref(), &format!("game exited with {}", status));
+                crashloop::record_exit(
+                    &app,
+                    &game_id,
+                    &status,
+                    &launch_config,
+                    log_path.as_deref(),
+                );
+            }
+            Ok(Err(e)) => append_log(
+                log_path.as_deref(),
+                &format!("Failed to wait for game: {}", e),
+            ),
+            Err(e) => append_log(
+                log_path.as_deref(),
+                &format!("Failed to wait for game: {}", e),
+            ),
+        }
+
+        let Some(command) = launch_config
+            .post_exit
+            .as_deref()
+            .filter(|c| !c.trim().is_empty())
+        else {
+            return;
+        };
+        if let Err(e) = run_hook(
+            "post-exit",
+            command,
+            working_dir(&launch_config).as_deref(),
+            launch_config.hook_timeout_secs,
+            log_path.as_deref(),
+        )
+        .await
+        {
+            println!("{}", e);
+        }
+    });
+}
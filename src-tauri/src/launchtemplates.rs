@@ -0,0 +1,242 @@
+use crate::gamelinks;
+use crate::portablesaves::PortableSaves;
+use crate::state::{AppState, DownloadedGameInfo, LaunchConfig};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// Methods launch_game knows
+const LAUNCH_METHODS: &[&str] = &["direct", "python", "wine", "custom"];
+
+// Replaced at apply time, quoted for the shell each command runs in
+const VARIABLES: &[&str] = &["{exe}", "{dir}", "{name}", "{id}"];
+
+// A launch setup shared by many games, e.g. "Wine + locale emu + gamemode".
+// The executable is never part of it; each game keeps its own.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct LaunchTemplate {
+    // Empty when creating; assigned on save
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "launchMethod")]
+    pub launch_method: String,
+    #[serde(rename = "customCommand")]
+    pub custom_command: Option<String>,
+    #[serde(rename = "preLaunch")]
+    pub pre_launch: Option<String>,
+    #[serde(rename = "postExit")]
+    pub post_exit: Option<String>,
+    // Unset fields leave the game's own value alone
+    #[serde(rename = "hookTimeoutSecs")]
+    pub hook_timeout_secs: Option<u64>,
+    #[serde(rename = "portableSaves")]
+    pub portable_saves: Option<PortableSaves>,
+}
+
+impl LaunchTemplate {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Template name is required".to_string());
+        }
+        if !LAUNCH_METHODS.contains(&self.launch_method.as_str()) {
+            return Err(format!("Unknown launch method: {}", self.launch_method));
+        }
+        if self.launch_method == "custom"
+            && self
+                .custom_command
+                .as_deref()
+                .is_none_or(|c| c.trim().is_empty())
+        {
+            return Err("A custom launch method needs a command".to_string());
+        }
+        for command in [&self.custom_command, &self.pre_launch, &self.post_exit]
+            .into_iter()
+            .flatten()
+        {
+            if let Some(unknown) = unknown_variable(command) {
+                return Err(format!(
+                    "Unknown variable {} (available: {})",
+                    unknown,
+                    VARIABLES.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Only the commands, unresolved, for pathscope::ensure_commands_allowed
+    pub fn commands(&self) -> LaunchConfig {
+        LaunchConfig {
+            launch_method: self.launch_method.clone(),
+            custom_command: self.custom_command.clone(),
+            pre_launch: self.pre_launch.clone(),
+            post_exit: self.post_exit.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+// First `{word}` that is not a known variable; other braces (shell, JSON) are left alone
+fn unknown_variable(command: &str) -> Option<String> {
+    let mut rest = command;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            break;
+        };
+        let word = &after[..end];
+        if !word.is_empty() && word.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            let variable = format!("{{{}}}", word);
+            if !VARIABLES.contains(&variable.as_str()) {
+                return Some(variable);
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    None
+}
+
+// Custom commands and hooks run through `cmd /C` on Windows. Inside double quotes cmd takes
+// & | ^ < > literally, but `"` ends the quotes and %VAR% / !VAR! still expand there.
+#[cfg(target_os = "windows")]
+const CMD_UNQUOTABLE: &[char] = &['"', '%', '!', '\r', '\n'];
+
+#[cfg(target_os = "windows")]
+fn shell_quote(value: &str) -> Result<String, String> {
+    if let Some(c) = value.chars().find(|c| CMD_UNQUOTABLE.contains(c)) {
+        return Err(format!(
+            "{} contains {:?}, which cannot be passed to cmd safely",
+            value, c
+        ));
+    }
+    Ok(format!("\"{}\"", value))
+}
+
+// Single-quoted for `sh -c`
+#[cfg(not(target_os = "windows"))]
+fn shell_quote(value: &str) -> Result<String, String> {
+    Ok(format!("'{}'", value.replace('\'', "'\\''")))
+}
+
+// The name comes from the article title, so characters cmd cannot quote are dropped rather than
+// refusing the whole template
+#[cfg(target_os = "windows")]
+fn quotable_name(name: &str) -> String {
+    name.replace(CMD_UNQUOTABLE, "")
+}
+
+#[cfg(not(target_os = "windows"))]
+fn quotable_name(name: &str) -> String {
+    name.to_string()
+}
+
+pub fn resolve_variables(
+    command: &str,
+    game: &DownloadedGameInfo,
+    executable_path: &str,
+) -> Result<String, String> {
+    let dir = Path::new(executable_path)
+        .parent()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = quotable_name(&gamelinks::display_name(game));
+    Ok(command
+        .replace("{exe}", &shell_quote(executable_path)?)
+        .replace("{dir}", &shell_quote(&dir)?)
+        .replace("{name}", &shell_quote(&name)?)
+        .replace("{id}", &shell_quote(&game.id)?))
+}
+
+// Creates or replaces a template and returns it with its id
+pub fn save(
+    app_state: &mut AppState,
+    mut template: LaunchTemplate,
+) -> Result<LaunchTemplate, String> {
+    template.validate()?;
+    template.name = template.name.trim().to_string();
+    if template.id.is_empty() {
+        template.id = format!("template-{}", chrono::Utc::now().timestamp_millis());
+    }
+    let templates = &mut app_state.launch_templates;
+    match templates.iter_mut().find(|t| t.id == template.id) {
+        Some(existing) => *existing = template.clone(),
+        None => templates.push(template.clone()),
+    }
+    Ok(template)
+}
+
+pub fn delete(app_state: &mut AppState, template_id: &str) -> Result<(), String> {
+    let before = app_state.launch_templates.len();
+    app_state.launch_templates.retain(|t| t.id != template_id);
+    if app_state.launch_templates.len() == before {
+        return Err(format!("Template {} not found", template_id));
+    }
+    Ok(())
+}
+
+pub fn find(app_state: &AppState, template_id: &str) -> Result<LaunchTemplate, String> {
+    app_state
+        .launch_templates
+        .iter()
+        .find(|t| t.id == template_id)
+        .cloned()
+        .ok_or_else(|| format!("Template {} not found", template_id))
+}
+
+pub fn store(
+    app_state: &mut AppState,
+    game_id: &str,
+    launch_config: LaunchConfig,
+) -> Result<(), String> {
+    let game = app_state
+        .games
+        .iter_mut()
+        .flatten()
+        .find(|g| g.id == game_id)
+        .ok_or_else(|| format!("Game with id {} not found", game_id))?;
+    game.launch_config = Some(launch_config);
+    Ok(())
+}
+
+// The config the template would give the game, and the game's current one, without changing either
+pub fn preview(
+    app_state: &AppState,
+    game_id: &str,
+    template: &LaunchTemplate,
+    executable_path: Option<String>,
+) -> Result<(LaunchConfig, Option<LaunchConfig>), String> {
+    template.validate()?;
+    let game = app_state
+        .games
+        .iter()
+        .flatten()
+        .find(|g| g.id == game_id)
+        .ok_or_else(|| format!("Game with id {} not found", game_id))?;
+    let current = game.launch_config.clone();
+    let executable_path = executable_path
+        .or_else(|| current.as_ref().map(|c| c.executable_path.clone()))
+        .ok_or("Choose the game's executable before applying a template")?;
+    let resolve = |command: &Option<String>| {
+        command
+            .as_deref()
+            .map(|command| resolve_variables(command, game, &executable_path))
+            .transpose()
+    };
+
+    // Everything the template does not set, including fields added later, stays the game's own
+    let base = current.clone().unwrap_or_default();
+    let launch_config = LaunchConfig {
+        launch_method: template.launch_method.clone(),
+        custom_command: resolve(&template.custom_command)?,
+        pre_launch: resolve(&template.pre_launch)?,
+        post_exit: resolve(&template.post_exit)?,
+        hook_timeout_secs: template.hook_timeout_secs.or(base.hook_timeout_secs),
+        portable_saves: template
+            .portable_saves
+            .clone()
+            .or_else(|| base.portable_saves.clone()),
+        executable_path,
+        ..base
+    };
+    Ok((launch_config, current))
+}
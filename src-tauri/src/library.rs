@@ -0,0 +1,180 @@
+use crate::httpconfig::{self, RequestSettings};
+use crate::state::{AppState, ArticleMeta, save_state_to_file};
+use crate::{ActiveDownloads, articlecache};
+use futures_util::stream::{self, StreamExt};
+use serde::Serialize;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+const MIRROR_CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct LibraryMetadataUpdate {
+    pub id: String,
+    pub slug: String,
+    pub ok: bool,
+    pub error: Option<String>,
+    pub title: Option<String>,
+    #[serde(rename = "coverImage")]
+    pub cover_image: Option<String>,
+    #[serde(rename = "coverChanged")]
+    pub cover_changed: bool,
+    #[serde(rename = "installedVersion")]
+    pub installed_version: Option<i64>,
+    #[serde(rename = "latestVersion")]
+    pub latest_version: Option<i64>,
+    #[serde(rename = "updateAvailable")]
+    pub update_available: bool,
+    // None when there is no mirror URL on record for the game
+    #[serde(rename = "mirrorAlive")]
+    pub mirror_alive: Option<bool>,
+}
+
+struct RefreshJob {
+    id: String,
+    article: ArticleMeta,
+    mirror: Option<String>,
+}
+
+async fn check_mirror(client: &reqwest::Client, settings: &RequestSettings, url: &str) -> bool {
+    match httpconfig::apply(settings, client.head(url), url)
+        .send()
+        .await
+    {
+        // Some hosts refuse HEAD, which still proves the link resolves
+        Ok(response) => {
+            response.status().is_success()
+                || response.status().is_redirection()
+                || response.status() == reqwest::StatusCode::METHOD_NOT_ALLOWED
+        }
+        Err(e) => {
+            println!("Mirror check failed for {}: {}", url, e);
+            false
+        }
+    }
+}
+
+async fn refresh_one(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    token: Option<String>,
+    job: RefreshJob,
+) -> LibraryMetadataUpdate {
+    let mut update = LibraryMetadataUpdate {
+        id: job.id,
+        slug: job.article.slug.clone(),
+        installed_version: job.article.version,
+        ..Default::default()
+    };
+    if let Some(url) = &job.mirror {
+        update.mirror_alive = Some(check_mirror(client, &httpconfig::settings(app), url).await);
+    }
+
+    match articlecache::fetch_article(app, client, &job.article.slug, token).await {
+        Ok(article) => {
+            update.ok = true;
+            update.cover_changed =
+                article.main_image.is_some() && article.main_image != job.article.cover_image;
+            update.update_available = matches!(
+                (job.article.version, article.version),
+                (Some(installed), Some(latest)) if latest > installed
+            );
+            update.title = Some(article.title);
+            update.cover_image = article.main_image;
+            update.latest_version = article.version;
+        }
+        Err(e) => update.error = Some(e),
+    }
+    update
+}
+
+// Writes refreshed titles and covers back into the library. The installed version is left as the
+// download recorded it; filling it from update.latest_version would make every later refresh compare
+// the latest version against itself and never report an update.
+fn apply_updates(app: &AppHandle, updates: &[LibraryMetadataUpdate]) -> Result<(), String> {
+    let state = app.state::<Mutex<AppState>>();
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    for game in app_state.games.iter_mut().flatten() {
+        let Some(update) = updates.iter().find(|u| u.ok && u.id == game.id) else {
+            continue;
+        };
+        if let Some(article) = game.article.as_mut() {
+            article.title = update.title.clone().or(article.title.take());
+            article.cover_image = update.cover_image.clone().or(article.cover_image.take());
+        }
+    }
+    save_state_to_file(app, &app_state)
+}
+
+// Refreshes article data for every game in the library, emitting each result as it arrives
+pub async fn refresh_library_metadata(
+    app: &AppHandle,
+) -> Result<Vec<LibraryMetadataUpdate>, String> {
+    let (token, games) = {
+        let state = app.state::<Mutex<AppState>>();
+        let app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        (
+            app_state.token.clone(),
+            app_state.games.clone().unwrap_or_default(),
+        )
+    };
+    let mirrors: Vec<(String, String)> = {
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        let downloads = active_downloads
+            .read()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        downloads
+            .downloads
+            .values()
+            .filter(|d| !d.url.is_empty())
+            .map(|d| (d.id.clone(), d.url.clone()))
+            .collect()
+    };
+
+    let jobs: Vec<RefreshJob> = games
+        .into_iter()
+        .filter_map(|game| {
+            let article = game.article.filter(|a| !a.slug.is_empty())?;
+            let mirror = mirrors
+                .iter()
+                .find(|(id, _)| id == &game.id)
+                .map(|(_, url)| url.clone());
+            Some(RefreshJob {
+                id: game.id,
+                article,
+                mirror,
+            })
+        })
+        .collect();
+    let total = jobs.len();
+    println!("Refreshing metadata for {} games", total);
+
+    let client = httpconfig::client_builder(&httpconfig::settings(app))
+        .timeout(MIRROR_CHECK_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+    let mut results = stream::iter(jobs)
+        .map(|job| refresh_one(app, &client, token.clone(), job))
+        .buffer_unordered(articlecache::MAX_CONCURRENT_FETCHES);
+
+    let mut updates = Vec::with_capacity(total);
+    while let Some(update) = results.next().await {
+        let _ = app.emit(
+            "library-metadata-progress",
+            &serde_json::json!({
+                "done": updates.len() + 1,
+                "total": total,
+                "update": update
+            }),
+        );
+        updates.push(update);
+    }
+
+    apply_updates(app, &updates)?;
+    Ok(updates)
+}
@@ -4,29 +4,103 @@
 )]
 
 mod archiver;
+mod archivetools;
+mod articlecache;
+mod articlehistory;
+mod articlerender;
+mod autopause;
+mod autostart;
+mod bandwidth;
+mod browserfallback;
+mod bulk;
+mod cache;
+mod chunkhash;
 mod cloudinary;
+mod commandcheck;
+mod compatnotes;
+mod confirm;
+mod cookiejar;
+mod crashloop;
+mod defender;
+mod diskio;
+mod downloader;
+mod downloadgroups;
+mod downloadhistory;
+mod downloadschedule;
+mod dropimport;
+mod elevate;
+mod extractmonitor;
+mod feeds;
+mod gamelinks;
+mod gamewindow;
+mod gpupreference;
+mod guest;
+mod helperupdate;
+mod httpconfig;
+mod idledownloads;
+mod inputremap;
+mod installcheck;
+mod integrity;
+mod jobs;
+mod launchentries;
+mod launchguard;
+mod launchhooks;
+mod launchtemplates;
+mod library;
+mod milestones;
+mod motw;
+mod nativedownload;
+mod nowplaying;
+mod offline;
+mod pathscope;
+mod poller;
+mod portablesaves;
+mod powerpause;
+mod retention;
+mod runninggames;
+mod scheduler;
+mod screenshotsync;
+mod segmented;
+mod session;
+mod settingsio;
+mod setup;
+mod shellopen;
+mod sidecar;
+mod sleepguard;
+mod soundcues;
+mod speedhistory;
+mod splitqueue;
+mod staging;
 mod state;
+mod subscriptions;
+mod telemetry;
+mod timeline;
+mod transliterate;
+mod undo;
+mod watchdog;
+mod windows;
+mod windowstate;
+mod winegraphics;
 
 use crate::state::{
-    AppState, ArticleResponse, CloudinaryConfig, DownloadedGameInfo, LaunchConfig,
-    cleanup_active_downloads, save_active_downloads_to_file,
-    save_state_to_file,
+    AppState, ArticleMeta, ArticleResponse, CloudinaryConfig, DownloadSettings, DownloadedGameInfo,
+    ExtractionSettings, LaunchConfig, LibrarySettings, StartupSettings, TelemetrySettings, UiPrefs,
+    cleanup_active_downloads, save_active_downloads_to_file, save_state_to_file,
 };
 use ico::IconDir;
 use image::DynamicImage;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::{self, File};
-use std::path::{Path};
+use std::path::{Path, PathBuf};
 use std::process::Command as StdCommand;
 use std::sync::{Mutex, RwLock};
 use tauri::{AppHandle, Emitter, Manager, State};
 use tauri_plugin_dialog::DialogExt;
 use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_opener::OpenerExt;
 use tauri_plugin_shell::ShellExt;
-use tauri_plugin_shell::process::CommandEvent;
 use tokio_util::sync::CancellationToken;
-use uuid::Uuid;
 
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct ActiveDownloads {
@@ -36,7 +110,9 @@ pub struct ActiveDownloads {
     pub tokens: HashMap<String, CancellationToken>,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
+// Fields missing from files written by older versions fall back to their defaults
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
 pub struct DownloadInfo {
     id: String,
     filename: String,
@@ -51,6 +127,32 @@ pub struct DownloadInfo {
     extracted_path: Option<String>,
     extraction_status: Option<String>, // เพิ่ม: idle, extracting, completed, failed
     extraction_progress: Option<f32>,  // เพิ่ม: ความคืบหน้า (0.0 - 100.0)
+    #[serde(default)]
+    uncompressed_size: Option<u64>, // ขนาดหลังแตกไฟล์ (อ่านจาก header ของ archive)
+    #[serde(default)]
+    article: Option<ArticleMeta>, // บทความต้นทาง (slug, ชื่อ, ภาพปก)
+    #[serde(default)]
+    referer: Option<String>, // หน้าที่ลิงก์มา ส่งเป็น Referer ตอนเริ่ม/เริ่มใหม่
+    #[serde(default)]
+    started_at: Option<String>, // เวลาที่เริ่ม ใช้คำนวณระยะเวลา/ความเร็วเฉลี่ยในประวัติ
+    #[serde(default)]
+    source_url: Option<String>, // ลิงก์ไฟล์จริงที่ helper ได้มาหลังผ่านหน้าโฮสต์ ใช้ย้ายไป native engine
+    #[serde(default)]
+    part_path: Option<String>, // ไฟล์ .part ที่กำลังเขียนอยู่
+    #[serde(default)]
+    bytes_downloaded: Option<u64>, // จำนวนไบต์ที่อยู่ในไฟล์ .part แล้ว ใช้โหลดต่อด้วย Range
+    #[serde(default)]
+    total_bytes: Option<u64>, // ขนาดไฟล์เต็มถ้าโฮสต์บอก
+    #[serde(default)]
+    priority: scheduler::Priority, // high / normal / low ใช้จัดลำดับคิวและแบ่งความเร็ว
+    #[serde(default)]
+    queued_at: Option<String>, // เวลาที่เข้าคิวรอ slot ว่าง
+    #[serde(default)]
+    segments: Option<u8>, // จำนวน connection ที่แบ่งโหลดพร้อมกัน ไม่ระบุใช้ค่าจากการตั้งค่า
+    #[serde(default)]
+    paused_at: Option<String>, // เวลาที่ผู้ใช้กดหยุดพัก ค้างสถานะ paused ไว้แม้ปิดแอป
+    #[serde(default)]
+    chunks_verified: bool, // ทุก chunk ตรงกับ hash ใน chunk manifest
 }
 
 #[tauri::command]
@@ -69,6 +171,44 @@ async fn unarchive_file(
     download_id: String, // เพิ่มเพื่อระบุไฟล์ที่กำลังแตก
     app: AppHandle,
 ) -> Result<(), String> {
+    extract_download(file_path, output_dir, download_id, app, true).await
+}
+
+// แตกไฟล์และอัปเดตสถานะของ download (notify = false ใช้กับงานแบบกลุ่มเพื่อไม่ให้แจ้งเตือนทีละไฟล์)
+async fn extract_download(
+    file_path: String,
+    output_dir: String,
+    download_id: String,
+    app: AppHandle,
+    notify: bool,
+) -> Result<(), String> {
+    diskio::ensure_not_partial(&file_path)?;
+
+    let (transliterate_filenames, low_priority_io, staging_dir) = {
+        let app_state = app
+            .state::<Mutex<AppState>>()
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?
+            .extraction_settings
+            .clone();
+        (
+            app_state.transliterate_filenames,
+            app_state.low_priority_io,
+            app_state.staging_dir,
+        )
+    };
+
+    // ตรวจพื้นที่ว่างก่อนแตกไฟล์ ขนาดหลังแตกอ่านจาก header ของ archive (อ่านไม่ได้ก็แตกต่อตามเดิม)
+    if let Ok(needed) = archiver::estimate_uncompressed_size(&file_path) {
+        staging::preflight(
+            &app,
+            &download_id,
+            staging_dir.as_deref(),
+            Path::new(&output_dir),
+            needed,
+        )?;
+    }
+
     // ส่งสถานะเริ่มต้น
     app.emit(
         "extraction-progress",
@@ -81,6 +221,18 @@ async fn unarchive_file(
     .map_err(|e| format!("Failed to emit extraction progress: {}", e))?;
 
     // อัปเดตสถานะใน active downloads
+    let label = std::path::Path::new(&file_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| file_path.clone());
+    jobs::update(
+        &app,
+        &download_id,
+        &label,
+        jobs::Stage::Extract,
+        jobs::StageStatus::Running,
+        0.0,
+    );
     {
         let active_downloads = app.state::<RwLock<ActiveDownloads>>();
         let mut downloads = active_downloads
@@ -92,20 +244,95 @@ async fn unarchive_file(
         }
         save_active_downloads_to_file(&app, &downloads)?;
     }
+    timeline::record(
+        &app,
+        &download_id,
+        "extraction",
+        "extracting",
+        "extraction",
+        Some(&output_dir),
+    );
 
     // เรียกฟังก์ชันแตกไฟล์
-    let result = archiver::unarchive_file_with_progress(&file_path, &output_dir, |progress| {
-        // ส่งความคืบหน้า (ถ้า library รองรับ)
-        app.emit(
-            "extraction-progress",
-            &serde_json::json!({
-                "downloadId": download_id,
-                "status": "extracting",
-                "progress": progress
-            }),
-        )
-        .ok();
-    });
+    // ฟอร์แมตที่แตกเองไม่ได้ใช้ 7-Zip/unrar/tar ในเครื่อง ถ้าไม่มีเลยจะโหลด 7-Zip แบบพกพามาให้
+    let tools = archivetools::ensure_for(&app, &file_path).await;
+
+    // แตกลงโฟลเดอร์พักก่อน แล้วค่อยย้ายไปที่ปลายทางเมื่อเสร็จ ถ้าล้มเหลวจะไม่เหลือไฟล์แตกไม่ครบที่ปลายทาง
+    let staging = staging::prepare(staging_dir.as_deref(), Path::new(&output_dir), &download_id)?;
+
+    // แตกไฟล์ใน thread แยก พร้อมวัดความเร็วอ่าน/เขียนดิสก์ทุกวินาที
+    let monitor = extractmonitor::ExtractionMonitor::start(app.clone(), download_id.clone());
+    let result = {
+        let (app, download_id, label) = (app.clone(), download_id.clone(), label.clone());
+        let (file_path, output_dir, staging) =
+            (file_path.clone(), output_dir.clone(), staging.clone());
+        let (counters, throughput) = (monitor.counters(), monitor.latest_handle());
+        tauri::async_runtime::spawn_blocking(move || {
+            let tools = tools?;
+            let _priority = low_priority_io.then(extractmonitor::lower_io_priority);
+            // แตกซ้ำ (เช่นหลังแตกไม่สำเร็จหรือแอปปิดกลางคัน) ข้ามไฟล์ที่ขนาดและ CRC ตรงกับที่อยู่ในโฟลเดอร์พักหรือปลายทางแล้ว
+            let existing =
+                archiver::ExistingFiles::new(&[staging.as_path(), Path::new(&output_dir)]);
+            archiver::unarchive_file_reusing(
+                &file_path,
+                &staging.to_string_lossy(),
+                &existing,
+                &tools,
+                &counters,
+                |progress| {
+                    // ใช้แบตเตอรี่และเหลือน้อย: หยุดรอตรงนี้จนกลับมาเสียบไฟ
+                    powerpause::wait_while_held();
+                    // ส่งความคืบหน้า (ถ้า library รองรับ)
+                    let throughput = throughput.lock().map(|t| t.clone()).unwrap_or_default();
+                    jobs::update(
+                        &app,
+                        &download_id,
+                        &label,
+                        jobs::Stage::Extract,
+                        jobs::StageStatus::Running,
+                        f64::from(progress),
+                    );
+                    milestones::emit_progress(
+                        &app,
+                        "extraction-progress",
+                        "extraction",
+                        &download_id,
+                        &label,
+                        f64::from(progress),
+                        &serde_json::json!({
+                            "downloadId": download_id,
+                            "status": "extracting",
+                            "progress": progress,
+                            "throughput": throughput
+                        }),
+                    );
+                },
+            )
+            .map_err(|e| e.to_string())
+            .and_then(|_| {
+                // แปลงชื่อไฟล์ภาษาอื่นเป็นตัวอักษรละตินสำหรับ engine เก่าที่อ่าน Unicode path ไม่ได้
+                if transliterate_filenames {
+                    transliterate::transliterate_tree(&staging)?;
+                }
+                staging::commit(&staging, Path::new(&output_dir))
+            })
+        })
+        .await
+        .map_err(|e| format!("Extraction task failed: {}", e))
+        .and_then(|result| result)
+    };
+    let skipped = monitor
+        .counters()
+        .skipped
+        .load(std::sync::atomic::Ordering::Relaxed);
+    // โฟลเดอร์พักของการแตกที่ล้มเหลวเก็บไว้ให้ครั้งถัดไปใช้ต่อ ถ้าไม่มีใครกลับมาแตกจะถูกลบเมื่อเก่าเกินกำหนด
+    drop(monitor);
+    if skipped > 0 {
+        println!(
+            "Kept {} unchanged files from the previous extraction of {}",
+            skipped, label
+        );
+    }
 
     match result {
         Ok(_) => {
@@ -119,6 +346,34 @@ async fn unarchive_file(
                 }),
             )
             .map_err(|e| format!("Failed to emit extraction complete: {}", e))?;
+            milestones::announce_completion(&app, "extraction", &download_id, &label);
+            soundcues::play(&app, soundcues::EXTRACTION_COMPLETE);
+            jobs::update(
+                &app,
+                &download_id,
+                &label,
+                jobs::Stage::Extract,
+                jobs::StageStatus::Done,
+                100.0,
+            );
+            jobs::detect_executables(&app, &download_id, &label, Path::new(&output_dir)).await;
+            // ลบ mark of the web ครั้งเดียวหลังแตกไฟล์ และเฉพาะไฟล์ที่ผ่านการตรวจ hash ทุก chunk แล้ว
+            let verified = app
+                .state::<RwLock<ActiveDownloads>>()
+                .read()
+                .is_ok_and(|downloads| {
+                    downloads
+                        .downloads
+                        .get(&download_id)
+                        .is_some_and(|d| d.chunks_verified)
+                });
+            if verified && motw::strip_enabled(&app) {
+                match motw::strip_tree(Path::new(&output_dir)) {
+                    Ok(0) => {}
+                    Ok(count) => println!("Removed mark of the web from {} files", count),
+                    Err(e) => println!("{}", e),
+                }
+            }
 
             {
                 let active_downloads = app.state::<RwLock<ActiveDownloads>>();
@@ -133,13 +388,25 @@ async fn unarchive_file(
                 }
                 save_active_downloads_to_file(&app, &downloads)?;
             }
+            telemetry::record(&app, "feature", "extraction_completed");
+            let kept = format!("{} unchanged files kept", skipped);
+            timeline::record(
+                &app,
+                &download_id,
+                "extraction",
+                "completed",
+                "extraction",
+                (skipped > 0).then_some(kept.as_str()),
+            );
 
-            app.notification()
-                .builder()
-                .title("Extraction Complete")
-                .body(format!("File extracted to {}", output_dir))
-                .show()
-                .map_err(|e| format!("Failed to show notification: {}", e))?;
+            if notify {
+                app.notification()
+                    .builder()
+                    .title("Extraction Complete")
+                    .body(format!("File extracted to {}", output_dir))
+                    .show()
+                    .map_err(|e| format!("Failed to show notification: {}", e))?;
+            }
 
             Ok(())
         }
@@ -151,10 +418,12 @@ async fn unarchive_file(
                     "downloadId": download_id,
                     "status": "failed",
                     "progress": 0.0,
-                    "error": e.to_string()
+                    "error": e
                 }),
             )
             .map_err(|e| format!("Failed to emit extraction error: {}", e))?;
+            soundcues::play(&app, soundcues::EXTRACTION_FAILED);
+            jobs::fail(&app, &download_id, &label, jobs::Stage::Extract, &e);
 
             {
                 let active_downloads = app.state::<RwLock<ActiveDownloads>>();
@@ -167,9 +436,65 @@ async fn unarchive_file(
                 }
                 save_active_downloads_to_file(&app, &downloads)?;
             }
+            telemetry::record(&app, "error", "extraction_failed");
+            timeline::record(
+                &app,
+                &download_id,
+                "extraction",
+                "failed",
+                "extraction",
+                Some(&e),
+            );
 
-            Err(e.to_string())
+            Err(e)
+        }
+    }
+}
+
+// ทำตามกฎการแตกไฟล์ตามชนิดไฟล์หลังดาวน์โหลดเสร็จ (auto / ask / never)
+fn apply_extraction_rule(app: &AppHandle, download_id: &str, path: &str, filename: &str) {
+    let action = match app.state::<Mutex<AppState>>().lock() {
+        Ok(app_state) => app_state.extraction_settings.action_for(path).to_string(),
+        Err(e) => {
+            println!("Failed to lock state: {}", e);
+            return;
+        }
+    };
+    println!("Extraction rule for {}: {}", path, action);
+
+    match action.as_str() {
+        "auto" if archiver::is_supported_archive(path) => {
+            let app = app.clone();
+            let file_path = path.to_string();
+            let output_dir = format!("{}_extracted", path);
+            let download_id = download_id.to_string();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) =
+                    extract_download(file_path, output_dir, download_id, app, true).await
+                {
+                    println!("Automatic extraction failed: {}", e);
+                }
+            });
+        }
+        "ask" if archiver::is_supported_archive(path) => {
+            jobs::update(
+                app,
+                download_id,
+                filename,
+                jobs::Stage::Extract,
+                jobs::StageStatus::Waiting,
+                0.0,
+            );
+            let _ = app.emit(
+                "extraction-prompt",
+                &serde_json::json!({
+                    "id": download_id,
+                    "filename": filename,
+                    "path": path
+                }),
+            );
         }
+        _ => jobs::skip_extraction(app, download_id, filename),
     }
 }
 
@@ -178,47 +503,385 @@ async fn check_path_exists(path: String) -> Result<bool, String> {
     Ok(std::path::Path::new(&path).exists())
 }
 
+// Windows games run through Wine elsewhere, so .exe stays in every list
+#[cfg(target_os = "windows")]
+const EXECUTABLE_EXTENSIONS: &[&str] = &["exe", "bat", "cmd", "py"];
+
+#[cfg(target_os = "macos")]
+const EXECUTABLE_EXTENSIONS: &[&str] = &["app", "command", "sh", "py", "exe"];
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const EXECUTABLE_EXTENSIONS: &[&str] = &["sh", "x86_64", "x86", "AppImage", "py", "exe"];
+
+// โฟลเดอร์ที่ใช้เลือกไฟล์ครั้งก่อนของเกมนี้ ถ้าไม่มีใช้โฟลเดอร์ที่แตกไฟล์ไว้
+fn picker_start_dir(app: &AppHandle, game_id: &str) -> Option<PathBuf> {
+    let remembered_or_extracted = {
+        let state = app.state::<Mutex<AppState>>();
+        let app_state = state.lock().ok()?;
+        let game = app_state.games.iter().flatten().find(|g| g.id == game_id);
+        [
+            app_state.picker_dirs.get(game_id).cloned(),
+            game.and_then(|g| g.extracted_path.clone()),
+        ]
+    };
+    let download_extracted = app
+        .state::<RwLock<ActiveDownloads>>()
+        .read()
+        .ok()
+        .and_then(|downloads| downloads.downloads.get(game_id)?.extracted_path.clone());
+    remembered_or_extracted
+        .into_iter()
+        .chain([download_extracted])
+        .flatten()
+        .map(PathBuf::from)
+        .find(|dir| dir.is_dir())
+}
+
 #[tauri::command]
-async fn select_game_executable(app: AppHandle, _game_id: String) -> Result<String, String> {
-    let dialog = app
+async fn select_game_executable(app: AppHandle, game_id: String) -> Result<String, String> {
+    let mut dialog = app
         .dialog()
         .file()
-        .add_filter("Executable Files", &["exe", "py", "sh"]);
+        .add_filter("Executable Files", EXECUTABLE_EXTENSIONS)
+        // Linux binaries usually have no extension at all
+        .add_filter("All Files", &["*"]);
+    if let Some(dir) = picker_start_dir(&app, &game_id) {
+        dialog = dialog.set_directory(dir);
+    }
     let result = dialog.blocking_pick_file();
 
     match result {
         Some(file_path) => {
             // Convert the FilePath to a String
             let path_str = file_path.to_string();
+            if let Some(dir) = Path::new(&path_str).parent() {
+                let state = app.state::<Mutex<AppState>>();
+                let mut app_state = state
+                    .lock()
+                    .map_err(|e| format!("Failed to lock state: {}", e))?;
+                app_state
+                    .picker_dirs
+                    .insert(game_id, dir.to_string_lossy().to_string());
+                save_state_to_file(&app, &app_state)?;
+            }
             Ok(path_str)
         }
         None => Err("No file selected".to_string()),
     }
 }
 
+// ฟอร์แมตที่แตกได้ในเครื่องนี้ และโปรแกรมภายนอกที่ใช้แตก
 #[tauri::command]
-async fn launch_game(
-    _app: AppHandle,
-    game_id: String,
-    launch_config: Option<LaunchConfig>, // เปลี่ยนเป็น Option
+fn get_archive_capabilities(app: AppHandle) -> archivetools::ArchiveCapabilities {
+    archivetools::capabilities(&app)
+}
+
+#[tauri::command]
+async fn install_archive_tools(app: AppHandle) -> Result<archivetools::ArchiveTool, String> {
+    archivetools::install_seven_zip(&app).await
+}
+
+// วัดความเร็วการเขียนไฟล์เล็กในคลังเกม และเตือนถ้า Defender น่าจะเป็นตัวถ่วง (PowerShell ช้า จึงไม่รันบน main thread)
+#[tauri::command]
+async fn get_defender_status(app: AppHandle) -> Result<defender::DefenderStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || defender::status(&app))
+        .await
+        .map_err(|e| format!("Failed to check Windows Defender: {}", e))?
+}
+
+#[tauri::command]
+async fn add_defender_exclusion(
+    confirm_token: Option<String>,
+    app: AppHandle,
     state: State<'_, Mutex<AppState>>,
-) -> Result<(), String> {
+) -> Result<confirm::Guarded<String>, String> {
+    let library_dir = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .download_dir
+        .clone()
+        .ok_or("No library folder is set")?;
+    // ต้องยืนยันก่อน: สรุปคำสั่งที่จะรัน และคำสั่งสำหรับย้อนกลับ
+    let fingerprint = confirm::fingerprint(&serde_json::json!(library_dir));
+    let Some(token) = confirm_token else {
+        let (add, remove) = defender::exclusion_commands(&library_dir);
+        return Ok(confirm::Guarded::ConfirmationRequired(confirm::issue(
+            &app,
+            "add_defender_exclusion",
+            fingerprint,
+            serde_json::json!({
+                "path": library_dir,
+                "command": add,
+                "revertCommand": remove,
+                "requiresAdmin": true
+            }),
+        )?));
+    };
+    confirm::consume(&app, &token, "add_defender_exclusion", &fingerprint)?;
+    let result = tauri::async_runtime::spawn_blocking(move || defender::add_exclusion(&app))
+        .await
+        .map_err(|e| format!("Failed to add Windows Defender exclusion: {}", e))??;
+    Ok(confirm::Guarded::Done { result })
+}
+
+#[tauri::command]
+async fn remove_defender_exclusion(app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || defender::remove_exclusion(&app))
+        .await
+        .map_err(|e| format!("Failed to remove Windows Defender exclusion: {}", e))?
+}
+
+// หา game_id จาก slug ของบทความ (ลิงก์และการแจ้งเตือนรู้แค่ slug) ถ้ามีหลายเวอร์ชันเลือกตัวที่ตั้งค่าการเปิดไว้และโหลดล่าสุด
+fn game_id_for_slug(app_state: &AppState, slug: &str) -> Result<String, String> {
+    let slug = slug.trim();
+    app_state
+        .games
+        .iter()
+        .flatten()
+        .filter(|game| {
+            game.article
+                .as_ref()
+                .is_some_and(|a| a.slug.eq_ignore_ascii_case(slug))
+        })
+        .max_by_key(|game| (game.launch_config.is_some(), game.downloaded_at.clone()))
+        .map(|game| game.id.clone())
+        .ok_or_else(|| format!("No installed game found for {}", slug))
+}
+
+#[tauri::command]
+async fn launch_game_by_slug(
+    app: AppHandle,
+    slug: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<runninggames::SlugLaunchResult, String> {
+    let game_id = {
+        let app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        game_id_for_slug(&app_state, &slug)?
+    };
+    println!("Launching {} as game {}", slug, game_id);
+    let result = launch_game(app, game_id.clone(), None, None, state).await?;
+    Ok(runninggames::SlugLaunchResult { game_id, result })
+}
+
+// เคล็ดลับการรันเกมจากผู้ใช้คนอื่น (wine-ge-8, ต้องใช้ locale ญี่ปุ่น ฯลฯ)
+#[tauri::command]
+async fn get_compat_notes(
+    slug: String,
+    token: Option<String>,
+    app: AppHandle,
+) -> Result<Vec<compatnotes::CompatNote>, String> {
+    compatnotes::fetch_notes(&app, &slug, token).await
+}
+
+// ใช้ค่าการเปิดเกมที่ note แนะนำกับเกมที่ติดตั้งไว้ ถ้าไม่ระบุ game_id จะหาเกมจาก slug
+// รับ note ตัวเดียวกับที่ผู้ใช้เห็น ไม่ดึงจาก server ใหม่ เพราะเนื้อหาอาจเปลี่ยนไปแล้ว
+#[tauri::command]
+async fn apply_compat_note(
+    slug: String,
+    note: compatnotes::CompatNote,
+    game_id: Option<String>,
+    executable_path: Option<String>,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<LaunchConfig, String> {
+    let (game_id, launch_config, previous) = {
+        let app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        let game_id = match game_id {
+            Some(game_id) => game_id,
+            None => game_id_for_slug(&app_state, &slug)?,
+        };
+        let (launch_config, previous) =
+            compatnotes::preview(&app_state, &game_id, &note, executable_path)?;
+        (game_id, launch_config, previous)
+    };
+    // คำสั่งที่มาจาก server ต้องยืนยันผ่านหน้าต่างของระบบก่อนบันทึก เหมือน save_launch_config
+    pathscope::ensure_commands_allowed(&app, &launch_config, previous.as_ref()).await?;
+
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    launchtemplates::store(&mut app_state, &game_id, launch_config.clone())?;
+    save_state_to_file(&app, &app_state)?;
+    println!(
+        "Applied compatibility note {} to game_id: {}",
+        note.id, game_id
+    );
+    Ok(launch_config)
+}
+
+#[tauri::command]
+fn get_launch_templates(
+    state: State<'_, Mutex<AppState>>,
+) -> Result<Vec<launchtemplates::LaunchTemplate>, String> {
     let app_state = state
         .lock()
         .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(app_state.launch_templates.clone())
+}
+
+// สร้างใหม่ (id ว่าง) หรือแก้ไข template ที่มีอยู่
+#[tauri::command]
+async fn save_launch_template(
+    template: launchtemplates::LaunchTemplate,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<launchtemplates::LaunchTemplate, String> {
+    // คำสั่งใน template ที่เพิ่มหรือเปลี่ยนใหม่ ต้องยืนยันก่อนบันทึก เหมือน save_launch_config
+    let previous = {
+        let app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        launchtemplates::find(&app_state, &template.id)
+            .ok()
+            .map(|t| t.commands())
+    };
+    pathscope::ensure_commands_allowed(&app, &template.commands(), previous.as_ref()).await?;
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    let template = launchtemplates::save(&mut app_state, template)?;
+    save_state_to_file(&app, &app_state)?;
+    Ok(template)
+}
+
+#[tauri::command]
+fn delete_launch_template(
+    template_id: String,
+    confirm_token: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<confirm::Guarded<()>, String> {
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    let fingerprint = confirm::fingerprint(&serde_json::json!(template_id));
+    let Some(token) = confirm_token else {
+        let template = launchtemplates::find(&app_state, &template_id)?;
+        return Ok(confirm::Guarded::ConfirmationRequired(confirm::issue(
+            &app,
+            "delete_launch_template",
+            fingerprint,
+            serde_json::json!({ "template": template }),
+        )?));
+    };
+    confirm::consume(&app, &token, "delete_launch_template", &fingerprint)?;
+    launchtemplates::delete(&mut app_state, &template_id)?;
+    save_state_to_file(&app, &app_state)?;
+    Ok(confirm::Guarded::Done { result: () })
+}
+
+// ใช้ template กับหลายเกมในครั้งเดียว แต่ละเกมใช้ไฟล์ exe ของตัวเอง
+#[tauri::command]
+async fn apply_launch_template(
+    template_id: String,
+    game_ids: Vec<String>,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<Vec<bulk::BulkItemResult>, String> {
+    let (template, previews) = {
+        let app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        let template = launchtemplates::find(&app_state, &template_id)?;
+        let previews: Vec<_> = game_ids
+            .into_iter()
+            .map(|game_id| {
+                let preview = launchtemplates::preview(&app_state, &game_id, &template, None);
+                (game_id, preview)
+            })
+            .collect();
+        (template, previews)
+    };
+
+    // คำสั่งหลังแทนค่าตัวแปรของแต่ละเกมต้องยืนยันก่อนบันทึก เกมที่ผู้ใช้ไม่อนุญาตจะไม่ถูกเปลี่ยน
+    let mut confirmed = Vec::new();
+    for (game_id, preview) in previews {
+        let result = match preview {
+            Ok((launch_config, current)) => {
+                pathscope::ensure_commands_allowed(&app, &launch_config, current.as_ref())
+                    .await
+                    .map(|()| launch_config)
+            }
+            Err(e) => Err(e),
+        };
+        confirmed.push((game_id, result));
+    }
+
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    let results: Vec<bulk::BulkItemResult> = confirmed
+        .into_iter()
+        .map(|(game_id, result)| {
+            let result = result.and_then(|launch_config| {
+                launchtemplates::store(&mut app_state, &game_id, launch_config)
+            });
+            bulk::BulkItemResult {
+                id: game_id,
+                success: result.is_ok(),
+                error: result.err(),
+            }
+        })
+        .collect();
+    save_state_to_file(&app, &app_state)?;
+    println!(
+        "Applied launch template {} to {} of {} games",
+        template.name,
+        results.iter().filter(|r| r.success).count(),
+        results.len()
+    );
+    Ok(results)
+}
 
+#[tauri::command]
+async fn launch_game(
+    app: AppHandle,
+    game_id: String,
+    launch_config: Option<LaunchConfig>, // เปลี่ยนเป็น Option
+    entry_name: Option<String>,          // ตัวเปิดอื่นของเกม ไม่ระบุคือตัวหลัก
+    state: State<'_, Mutex<AppState>>,
+) -> Result<runninggames::LaunchResult, String> {
     // ดึง launch_config จาก AppState หากมี
-    let stored_launch_config = app_state.games.as_ref().and_then(|games| {
-        games
-            .iter()
-            .find(|g| g.id == game_id)
-            .and_then(|game| game.launch_config.clone())
-    });
+    let (stored_launch_config, game_name, global_limit, guards, guest_mode) = {
+        let app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        let game = app_state
+            .games
+            .as_ref()
+            .and_then(|games| games.iter().find(|g| g.id == game_id));
+        (
+            match game {
+                Some(game) => launchentries::resolve(game, entry_name.as_deref())?,
+                None => None,
+            },
+            game.map(gamelinks::display_name)
+                .unwrap_or_else(|| game_id.clone()),
+            app_state.session_limit.clone(),
+            app_state.launch_guards.clone(),
+            app_state.guest_mode.active,
+        )
+    };
 
     // ใช้ launch_config จากพารามิเตอร์ถ้าไม่มีใน AppState
+    let from_caller = stored_launch_config.is_none();
+    // ในโหมดผู้เยี่ยมชม คนที่กดยืนยันหน้าต่างถามคือผู้เยี่ยมชมเอง จึงเปิดได้เฉพาะค่าที่บันทึกไว้
+    if from_caller && guest_mode {
+        return Err("Only saved launch configurations can be used in guest mode".to_string());
+    }
     let launch_config = stored_launch_config
         .or(launch_config)
         .ok_or("No launch configuration provided or found")?;
+    // ค่าที่ส่งมาจากหน้าเว็บตรงๆ ไม่ได้ผ่านการบันทึก ต้องให้ผู้ใช้ยืนยันถ้าอยู่นอกโฟลเดอร์ของแอป
+    if from_caller {
+        pathscope::ensure_allowed(&app, "launch", Path::new(&launch_config.executable_path))
+            .await?;
+        pathscope::ensure_commands_allowed(&app, &launch_config, None).await?;
+    }
 
     let executable_path = &launch_config.executable_path;
     let path_obj = Path::new(executable_path);
@@ -227,341 +890,1686 @@ async fn launch_game(
         return Err("Executable does not exist".to_string());
     }
 
+    // เปิดซ้ำขณะเกมยังรันอยู่ทำเซฟพังได้ ดึงหน้าต่างเดิมขึ้นมาแทน
+    let claim = match runninggames::claim(&app, &game_id, executable_path).await {
+        Ok(claim) => claim,
+        Err(result) => return Ok(result),
+    };
+
+    // คำเตือนแสดงผ่าน check_launch_requirements ก่อนกดเล่น ที่นี่หยุดเฉพาะกรณีที่ตั้งให้บล็อก
+    if let Some(reason) = launchguard::run_checks(executable_path, &guards).blocked_reason() {
+        return Err(reason);
+    }
+
+    // เขียนค่า GPU ซ้ำทุกครั้ง เผื่อเกมถูกย้ายที่ (registry อ้างด้วย path เต็มของ exe)
+    if let Some(preference) = launch_config.gpu_preference
+        && let Err(e) = gpupreference::set(executable_path, preference)
+    {
+        println!("Failed to apply GPU preference: {}", e);
+    }
+
     let launch_method = &launch_config.launch_method;
-    match launch_method.as_str() {
-        "direct" => {
-            #[cfg(target_os = "windows")]
-            {
-                StdCommand::new(executable_path)
-                    .spawn()
-                    .map_err(|e| format!("Failed to launch: {}", e))?;
-            }
-            #[cfg(not(target_os = "windows"))]
-            {
-                return Err("Direct launch only supported on Windows".to_string());
-            }
-        }
+    telemetry::record(&app, "feature", &format!("launch_{}", launch_method));
+
+    let log_path = launchhooks::game_log_path(&app, &game_id);
+    launchhooks::run_pre_launch(&launch_config, log_path.as_deref()).await?;
+
+    // DXVK/VKD3D ต้องอยู่ใน prefix และมี DLL override ก่อนเปิดเกม
+    if launch_method == "wine" {
+        winegraphics::prepare(&app, &launch_config, false).await?;
+    }
+
+    let mut command = match launch_method.as_str() {
+        "direct" => direct_command(executable_path)?,
         "python" => {
             let python_check = StdCommand::new("python3").arg("--version").output();
             if python_check.is_err() {
                 return Err("Python3 is not installed".to_string());
             }
-            StdCommand::new("python3")
-                .arg(executable_path)
-                .spawn()
-                .map_err(|e| format!("Failed to launch Python script: {}", e))?;
-        }
-        "wine" => {
-            #[cfg(not(target_os = "windows"))]
-            {
-                let wine_check = StdCommand::new("wine").arg("--version").output();
-                if wine_check.is_err() {
-                    return Err("Wine is not installed".to_string());
-                }
-                StdCommand::new("wine")
-                    .arg(executable_path)
-                    .spawn()
-                    .map_err(|e| format!("Failed to launch with Wine: {}", e))?;
-            }
-            #[cfg(target_os = "windows")]
-            {
-                return Err("Wine not needed on Windows".to_string());
-            }
+            let mut command = StdCommand::new("python3");
+            command.arg(executable_path);
+            command
         }
+        "wine" => wine_command(executable_path)?,
         "custom" => {
             if let Some(cmd) = &launch_config.custom_command {
-                StdCommand::new("sh")
-                    .arg("-c")
-                    .arg(cmd)
-                    .spawn()
-                    .map_err(|e| format!("Failed to launch custom command: {}", e))?;
+                launchhooks::std_shell_command(cmd)
             } else {
                 return Err("Custom command not provided".to_string());
             }
         }
         _ => return Err("Invalid launch method".to_string()),
+    };
+    if launch_method == "wine" {
+        winegraphics::apply(&app, &mut command, &launch_config)?;
     }
-
-    Ok(())
-}
-
-#[tauri::command]
-async fn extract_icon(app: AppHandle, executable_path: String) -> Result<String, String> {
-    let path_obj = Path::new(&executable_path);
-    if !path_obj.exists() {
-        return Err("Executable does not exist".to_string());
+    portablesaves::apply(&mut command, &launch_config)?;
+    if launch_config.elevate {
+        command = elevate::wrap(command)?;
     }
 
-    let icon_path = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data dir: {}", e))?
-        .join("icons")
-        .join(format!("{}.png", Uuid::new_v4()));
+    // ตัว remap จอยเปิดก่อนเกม และปิดตามเมื่อเกมปิด
+    let remapper = inputremap::start(&app, &launch_config, log_path.as_deref())?;
+    let spawned = match command.spawn() {
+        Ok(child) if launch_config.elevate => {
+            // รอผู้ใช้ตอบหน้าต่างขอสิทธิ์ผู้ดูแลระบบก่อน
+            tauri::async_runtime::spawn_blocking(move || {
+                let mut child = child;
+                elevate::wait_until_started(&mut child).map(|_| child)
+            })
+            .await
+            .map_err(|e| format!("Failed to wait for elevation: {}", e))
+            .and_then(|result| result)
+        }
+        Ok(child) => Ok(child),
+        Err(e) => Err(format!("Failed to launch ({}): {}", launch_method, e)),
+    };
+    let child = match spawned {
+        Ok(child) => child,
+        Err(e) => {
+            if let Some(handle) = remapper {
+                inputremap::stop(handle, log_path.as_deref());
+            }
+            launchhooks::append_log(log_path.as_deref(), &e);
+            return Err(e);
+        }
+    };
 
-    fs::create_dir_all(icon_path.parent().unwrap())
-        .map_err(|e| format!("Failed to create icons dir: {}", e))?;
+    launchhooks::append_log(
+        log_path.as_deref(),
+        &format!("launched {} ({})", executable_path, launch_method),
+    );
 
-    #[cfg(target_os = "windows")]
-    {
-        if executable_path.to_lowercase().ends_with(".exe") {
-            let file =
-                File::open(&executable_path).map_err(|e| format!("Failed to open file: {}", e))?;
-            let icon_dir_result = IconDir::read(file);
-            let icon_image = match icon_dir_result {
-                Ok(icon_dir) => {
-                    let entry = icon_dir
-                        .entries()
-                        .first()
-                        .ok_or("No icons found in executable")?;
-                    entry
-                        .decode()
-                        .map_err(|e| format!("Failed to decode icon: {}", e))?
-                }
-                Err(e) => {
-                    println!("Icon extraction failed: {}. Using default icon.", e);
-                    // Use a default icon
-                    let default_icon = app
-                        .path()
-                        .resource_dir()
-                        .map_err(|e| format!("Failed to get resource dir: {}", e))?
-                        .join("default_icon.png");
-                    if default_icon.exists() {
-                        fs::copy(&default_icon, &icon_path)
-                            .map_err(|e| format!("Failed to copy default icon: {}", e))?;
-                        return Ok(icon_path
-                            .to_str()
-                            .ok_or("Failed to convert path to string")?
-                            .to_string());
-                    } else {
-                        return Err("Default icon not found and icon extraction failed".to_string());
-                    }
-                }
-            };
+    // ตั้งเวลาเตือน/ปิดเกมตามที่ตั้งไว้ของเกมนั้น ถ้าไม่มีใช้ค่ารวม
+    let exited = CancellationToken::new();
+    let limit = launch_config.session_limit.clone().unwrap_or(global_limit);
+    session::start(
+        app.clone(),
+        game_id.clone(),
+        game_name.clone(),
+        child.id(),
+        limit,
+        exited.clone(),
+    );
+    autopause::game_started(app.clone(), game_id.clone(), exited.clone());
+    let pid = child.id();
+    let now_playing = nowplaying::session(
+        &app,
+        game_id.clone(),
+        game_name,
+        executable_path.clone(),
+        pid,
+    );
+    claim.commit(now_playing.clone(), exited.clone());
+    nowplaying::started(app.clone(), now_playing, exited.clone());
+    if let Some(handle) = remapper {
+        inputremap::stop_on_exit(handle, log_path.clone(), exited.clone());
+    }
+    if let Some(actions) = launch_config.window_actions.clone() {
+        gamewindow::apply_after_launch(app.clone(), pid, actions, log_path.clone(), exited.clone());
+    }
+    launchhooks::watch_for_exit(
+        app.clone(),
+        game_id.clone(),
+        child,
+        launch_config.clone(),
+        log_path,
+        exited,
+    );
+    Ok(runninggames::LaunchResult::launched(pid))
+}
+
+#[cfg(target_os = "windows")]
+fn direct_command(executable_path: &str) -> Result<StdCommand, String> {
+    Ok(StdCommand::new(executable_path))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn direct_command(_executable_path: &str) -> Result<StdCommand, String> {
+    Err("Direct launch only supported on Windows".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn wine_command(executable_path: &str) -> Result<StdCommand, String> {
+    let wine_check = StdCommand::new("wine").arg("--version").output();
+    if wine_check.is_err() {
+        return Err("Wine is not installed".to_string());
+    }
+    let mut command = StdCommand::new("wine");
+    command.arg(executable_path);
+    Ok(command)
+}
+
+#[cfg(target_os = "windows")]
+fn wine_command(_executable_path: &str) -> Result<StdCommand, String> {
+    Err("Wine not needed on Windows".to_string())
+}
+
+#[tauri::command]
+async fn extract_icon(
+    app: AppHandle,
+    executable_path: String,
+    game_id: Option<String>,
+) -> Result<String, String> {
+    let path_obj = Path::new(&executable_path);
+    if !path_obj.exists() {
+        return Err("Executable does not exist".to_string());
+    }
+
+    let icon_path = cache::icon_path_for(&app, &executable_path)?;
+    if icon_path.exists() {
+        println!("Reusing cached icon: {:?}", icon_path);
+        return Ok(icon_path
+            .to_str()
+            .ok_or("Failed to convert path to string")?
+            .to_string());
+    }
+
+    #[cfg(target_os = "windows")]
+    match extract_exe_icon(&executable_path, &icon_path) {
+        Ok(()) => {
+            return Ok(icon_path
+                .to_str()
+                .ok_or("Failed to convert path to string")?
+                .to_string());
+        }
+        Err(e) => println!("Icon extraction failed: {}. Trying cover art.", e),
+    }
+
+    // ใช้ภาพปกของบทความแทนไอคอนถ้าดึงจาก exe ไม่ได้
+    if let Some(cover_url) = game_id.and_then(|id| game_cover_url(&app, &id)) {
+        match cache::cover_icon(&app, &cover_url).await {
+            Ok(cover_icon) => {
+                return Ok(cover_icon
+                    .to_str()
+                    .ok_or("Failed to convert path to string")?
+                    .to_string());
+            }
+            Err(e) => println!("Cover icon failed: {}. Using default icon.", e),
+        }
+    }
+
+    let default_icon = app
+        .path()
+        .resource_dir()
+        .map_err(|e| format!("Failed to get resource dir: {}", e))?
+        .join("default_icon.png");
+    if !default_icon.exists() {
+        return Err("Default icon not found and icon extraction failed".to_string());
+    }
+    let shared_icon = cache::default_icon_path(&app)?;
+    fs::copy(&default_icon, &shared_icon)
+        .map_err(|e| format!("Failed to copy default icon: {}", e))?;
+    Ok(shared_icon
+        .to_str()
+        .ok_or("Failed to convert path to string")?
+        .to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn extract_exe_icon(executable_path: &str, icon_path: &Path) -> Result<(), String> {
+    if !executable_path.to_lowercase().ends_with(".exe") {
+        return Err("Only .exe files supported for icon extraction on Windows".to_string());
+    }
+    let file = File::open(executable_path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let icon_dir = IconDir::read(file).map_err(|e| format!("Failed to read icons: {}", e))?;
+    let entry = icon_dir
+        .entries()
+        .first()
+        .ok_or("No icons found in executable")?;
+    let icon_image = entry
+        .decode()
+        .map_err(|e| format!("Failed to decode icon: {}", e))?;
+
+    let rgba = icon_image.rgba_data();
+    let img = image::RgbaImage::from_raw(icon_image.width(), icon_image.height(), rgba.to_vec())
+        .ok_or("Failed to create RGBA image")?;
+    DynamicImage::ImageRgba8(img)
+        .save_with_format(icon_path, image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to save icon: {}", e))
+}
+
+// ภาพปกจากบทความของเกม (ดูจากคลังเกมก่อน แล้วค่อยดูรายการดาวน์โหลด)
+fn game_cover_url(app: &AppHandle, game_id: &str) -> Option<String> {
+    let from_library = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .ok()
+        .and_then(|app_state| {
+            app_state
+                .games
+                .iter()
+                .flatten()
+                .find(|g| g.id == game_id)
+                .and_then(|g| g.article.as_ref()?.cover_image.clone())
+        });
+    from_library.or_else(|| {
+        let downloads = app.state::<RwLock<ActiveDownloads>>();
+        let downloads = downloads.read().ok()?;
+        downloads
+            .downloads
+            .get(game_id)?
+            .article
+            .as_ref()?
+            .cover_image
+            .clone()
+    })
+}
+
+#[tauri::command]
+fn generate_ico(image_path: String) -> Result<String, String> {
+    let ico_path = cache::ico_from_png(&image_path)?;
+    Ok(ico_path
+        .to_str()
+        .ok_or("Failed to convert path to string")?
+        .to_string())
+}
+
+#[tauri::command]
+async fn save_launch_config(
+    game_id: String,
+    launch_config: LaunchConfig,
+    icon_path: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    if let Some(remapper) = &launch_config.input_remapper {
+        remapper.validate()?;
+    }
+    // คำสั่ง custom / ก่อนเปิด / หลังปิด ที่เพิ่มหรือเปลี่ยนใหม่ ต้องยืนยันผ่านหน้าต่างของระบบก่อนบันทึก
+    let previous = {
+        let app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        app_state
+            .games
+            .as_ref()
+            .and_then(|games| games.iter().find(|g| g.id == game_id))
+            .and_then(|game| game.launch_config.clone())
+    };
+    pathscope::ensure_commands_allowed(&app, &launch_config, previous.as_ref()).await?;
+    // launch_game เชื่อค่าที่บันทึกไว้ จึงตรวจ path ของ exe ตอนบันทึกแทน
+    if previous
+        .as_ref()
+        .is_none_or(|previous| previous.executable_path != launch_config.executable_path)
+    {
+        pathscope::ensure_allowed(&app, "launch", Path::new(&launch_config.executable_path))
+            .await?;
+    }
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    if app_state.games.is_none() {
+        app_state.games = Some(Vec::new());
+        println!("Initialized empty games list");
+    }
+    if let Some(games) = app_state.games.as_mut() {
+        if let Some(game) = games.iter_mut().find(|g| g.id == game_id) {
+            game.launch_config = Some(launch_config.clone());
+            game.icon_path = icon_path.clone();
+            println!("Updated launch config for game_id: {}", game_id);
+        } else {
+            println!("Game with id {} not found", game_id);
+            return Err(format!("Game with id {} not found", game_id));
+        }
+    }
+    save_state_to_file(&app, &app_state)?;
+    println!("Launch config saved to file for game_id: {}", game_id);
+    if let Err(e) = cache::gc_icons(&app, &app_state) {
+        println!("Failed to clean up unused icons: {}", e);
+    }
+    if let Some(preference) = launch_config.gpu_preference
+        && let Err(e) = gpupreference::set(&launch_config.executable_path, preference)
+    {
+        println!("Failed to apply GPU preference: {}", e);
+    }
+    Ok(())
+}
+
+// ตัวเปิดอื่นของเกม นอกจาก launch_config หลัก
+#[tauri::command]
+fn get_launch_entries(
+    game_id: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<Vec<launchentries::LaunchEntry>, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    app_state
+        .games
+        .iter()
+        .flatten()
+        .find(|g| g.id == game_id)
+        .map(|g| g.launch_entries.clone())
+        .ok_or_else(|| format!("Game with id {} not found", game_id))
+}
+
+#[tauri::command]
+async fn save_launch_entry(
+    game_id: String,
+    name: String,
+    launch_config: LaunchConfig,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<launchentries::LaunchEntry, String> {
+    // launch_game เชื่อค่าที่บันทึกไว้ จึงตรวจคำสั่งและ path ของ exe ตอนบันทึก เหมือน save_launch_config
+    let previous = {
+        let app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        launchentries::existing(&app_state, &game_id, &name)
+    };
+    pathscope::ensure_commands_allowed(&app, &launch_config, previous.as_ref()).await?;
+    if previous
+        .as_ref()
+        .is_none_or(|previous| previous.executable_path != launch_config.executable_path)
+    {
+        pathscope::ensure_allowed(&app, "launch", Path::new(&launch_config.executable_path))
+            .await?;
+    }
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    let entry = launchentries::save(&mut app_state, &game_id, &name, launch_config)?;
+    save_state_to_file(&app, &app_state)?;
+    println!("Saved launch entry {} for game_id: {}", entry.name, game_id);
+    Ok(entry)
+}
+
+#[tauri::command]
+fn delete_launch_entry(
+    game_id: String,
+    name: String,
+    confirm_token: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<confirm::Guarded<()>, String> {
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    let fingerprint = confirm::fingerprint(&serde_json::json!([game_id, name]));
+    let Some(token) = confirm_token else {
+        let launch_config = launchentries::existing(&app_state, &game_id, &name)
+            .ok_or_else(|| format!("Launch entry {} not found", name))?;
+        return Ok(confirm::Guarded::ConfirmationRequired(confirm::issue(
+            &app,
+            "delete_launch_entry",
+            fingerprint,
+            serde_json::json!({ "gameId": game_id, "name": name, "launchConfig": launch_config }),
+        )?));
+    };
+    confirm::consume(&app, &token, "delete_launch_entry", &fingerprint)?;
+    launchentries::delete(&mut app_state, &game_id, &name)?;
+    save_state_to_file(&app, &app_state)?;
+    println!("Deleted launch entry {} for game_id: {}", name, game_id);
+    Ok(confirm::Guarded::Done { result: () })
+}
+
+// ค่า GPU ที่ Windows ใช้กับเกมนี้อยู่จริง (อาจถูกตั้งจากหน้า Settings ของ Windows เอง)
+#[tauri::command]
+fn get_gpu_preference(
+    game_id: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<gpupreference::GpuPreference, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    let launch_config = stored_launch_config(&app_state, &game_id)?;
+    Ok(gpupreference::current(&launch_config.executable_path))
+}
+
+fn stored_launch_config(app_state: &AppState, game_id: &str) -> Result<LaunchConfig, String> {
+    app_state
+        .games
+        .iter()
+        .flatten()
+        .find(|g| g.id == game_id)
+        .and_then(|g| g.launch_config.clone())
+        .ok_or_else(|| format!("No launch configuration for game {}", game_id))
+}
+
+// เวอร์ชัน DXVK/VKD3D ใน prefix ของเกม และ DLL/override ที่ยังไม่ครบ
+#[tauri::command]
+fn get_wine_graphics_status(
+    game_id: String,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<winegraphics::WineGraphicsStatus, String> {
+    let launch_config = {
+        let app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        stored_launch_config(&app_state, &game_id)?
+    };
+    winegraphics::status(&app, &launch_config)
+}
+
+// เช็กเวอร์ชันใหม่ทันที (ไม่รอรอบ 24 ชม.) แล้วติดตั้ง/ซ่อม prefix
+#[tauri::command]
+async fn update_wine_graphics(
+    game_id: String,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<winegraphics::WineGraphicsStatus, String> {
+    let launch_config = {
+        let app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        stored_launch_config(&app_state, &game_id)?
+    };
+    winegraphics::prepare(&app, &launch_config, true).await?;
+    winegraphics::status(&app, &launch_config)
+}
+
+#[tauri::command]
+fn echo_test(message: String) -> String {
+    println!("Echo test received: {}", message);
+    format!("Echo reply: {}", message)
+}
+
+#[tauri::command]
+fn verify_config_exists(app: AppHandle) -> Result<String, String> {
+    state::verify_config_file(&app).map(|_| "Config file verified successfully".to_string())
+}
+
+#[tauri::command]
+fn show_download_notification(
+    app: AppHandle,
+    title: String,
+    message: String,
+) -> Result<(), String> {
+    app.notification()
+        .builder()
+        .title(title)
+        .body(message)
+        .show()
+        .map_err(|e| format!("Failed to show notification: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_token(
+    token: String,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    app_state.token = Some(token);
+    save_state_to_file(&app, &app_state)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn get_token(state: State<'_, Mutex<AppState>>) -> Result<Option<String>, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(app_state.token.clone())
+}
+
+#[tauri::command]
+fn set_cloudinary_config(
+    cloud_name: String,
+    api_key: String,
+    api_secret: String,
+    upload_preset: Option<String>, // ใช้ preset แบบ unsigned ได้โดยไม่ต้องเก็บ api_secret
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    let config = CloudinaryConfig {
+        cloud_name,
+        api_key,
+        api_secret,
+        upload_preset: upload_preset.filter(|p| !p.trim().is_empty()),
+    };
+    app_state.cloudinary = Some(config);
+    save_state_to_file(&app, &app_state)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn get_cloudinary_config(
+    state: State<'_, Mutex<AppState>>,
+) -> Result<Option<CloudinaryConfig>, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(app_state.cloudinary.clone())
+}
+
+#[tauri::command]
+fn save_all_settings(
+    token: String,
+    cloudinary_config: state::CloudinaryConfig,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    app_state.token = Some(token);
+    app_state.cloudinary = Some(CloudinaryConfig {
+        cloud_name: cloudinary_config.cloud_name,
+        api_key: cloudinary_config.api_key,
+        api_secret: cloudinary_config.api_secret,
+        upload_preset: cloudinary_config.upload_preset,
+    });
+    save_state_to_file(&app, &app_state)?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn export_settings(
+    path: String,
+    include_secrets: bool,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    pathscope::ensure_allowed(&app, "export settings to", Path::new(&path)).await?;
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    settingsio::export_settings(&app_state, &path, include_secrets)
+}
+
+#[tauri::command]
+async fn import_settings(
+    path: String,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<settingsio::ImportSummary, String> {
+    pathscope::ensure_allowed(&app, "import settings from", Path::new(&path)).await?;
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    let summary = settingsio::import_settings(&mut app_state, &path)?;
+    save_state_to_file(&app, &app_state)?;
+    if summary.applied.iter().any(|key| key == "ui_prefs") {
+        let _ = app.emit("ui-prefs-changed", &app_state.ui_prefs);
+    }
+    Ok(summary)
+}
+
+#[tauri::command]
+fn get_ui_prefs(state: State<'_, Mutex<AppState>>) -> Result<UiPrefs, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(app_state.ui_prefs.clone())
+}
+
+// บันทึกแล้วแจ้งทุกหน้าต่างให้ใช้ค่าใหม่
+#[tauri::command]
+fn set_ui_prefs(
+    prefs: UiPrefs,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    app_state.ui_prefs = prefs;
+    save_state_to_file(&app, &app_state)?;
+    let _ = app.emit("ui-prefs-changed", &app_state.ui_prefs);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_startup_settings(state: State<'_, Mutex<AppState>>) -> Result<StartupSettings, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    let mut settings = app_state.startup_settings.clone();
+    // The entry can be removed from the OS side (e.g. Task Manager), so report what is really there
+    settings.launch_at_login = autostart::is_enabled();
+    Ok(settings)
+}
+
+#[tauri::command]
+fn set_startup_settings(
+    settings: StartupSettings,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    autostart::set_enabled(settings.launch_at_login)?;
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    app_state.startup_settings = settings;
+    save_state_to_file(&app, &app_state)
+}
+
+// โหลดต่อดาวน์โหลดที่ค้างจากรอบก่อน ด้วย id เดิม
+async fn resume_interrupted_downloads(app: AppHandle, ids: Vec<String>) {
+    let pending: Vec<DownloadInfo> = match app.state::<RwLock<ActiveDownloads>>().read() {
+        Ok(downloads) => ids
+            .iter()
+            .filter_map(|id| downloads.downloads.get(id))
+            .filter(|d| matches!(d.provider.as_deref(), Some("webview2" | "native")))
+            .filter(|d| !d.url.is_empty())
+            .cloned()
+            .collect(),
+        Err(e) => {
+            println!("Failed to lock active downloads: {}", e);
+            return;
+        }
+    };
+
+    for download in pending {
+        println!("Resuming interrupted download: {}", download.id);
+        if let Err(e) = downloader::resume(&app, &download.id).await {
+            println!("Failed to resume download {}: {}", download.id, e);
+        }
+    }
+}
+
+// ไฟล์ .chanomhub ที่เปิดตอนเริ่มโปรแกรม ก่อนหน้าเว็บพร้อมรับ event
+#[tauri::command]
+fn take_opened_manifest(
+    pending: State<'_, Mutex<shellopen::PendingManifest>>,
+) -> Result<Option<shellopen::ChanomhubManifest>, String> {
+    let mut pending = pending
+        .lock()
+        .map_err(|e| format!("Failed to lock pending manifest: {}", e))?;
+    Ok(pending.0.take())
+}
+
+#[tauri::command]
+fn get_extract_context_menu() -> Result<bool, String> {
+    Ok(shellopen::is_context_menu_enabled())
+}
+
+#[tauri::command]
+fn set_extract_context_menu(enabled: bool) -> Result<(), String> {
+    shellopen::set_context_menu(enabled)
+}
+
+#[tauri::command]
+fn get_telemetry_settings(state: State<'_, Mutex<AppState>>) -> Result<TelemetrySettings, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(app_state.telemetry.clone())
+}
+
+// ปิด telemetry แล้วลบคิวที่ค้างทิ้งทันที
+#[tauri::command]
+fn set_telemetry_settings(
+    settings: TelemetrySettings,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    let enabled = settings.enabled;
+    {
+        let mut app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        app_state.telemetry = settings;
+        save_state_to_file(&app, &app_state)?;
+    }
+    if !enabled {
+        telemetry::clear(&app)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn get_pending_telemetry(app: AppHandle) -> Result<telemetry::TelemetryPayload, String> {
+    telemetry::pending(&app)
+}
+
+#[tauri::command]
+fn record_telemetry(kind: String, name: String, app: AppHandle) -> Result<(), String> {
+    if kind != "feature" && kind != "error" {
+        return Err(format!("Unknown telemetry kind: {}", kind));
+    }
+    if !telemetry::is_valid_name(&name) {
+        return Err(format!("Invalid telemetry name: {}", name));
+    }
+    telemetry::record(&app, &kind, &name);
+    Ok(())
+}
+
+#[tauri::command]
+async fn flush_telemetry(app: AppHandle) -> Result<usize, String> {
+    telemetry::flush(&app).await
+}
+
+#[tauri::command]
+fn get_session_limit(state: State<'_, Mutex<AppState>>) -> Result<session::SessionLimit, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(app_state.session_limit.clone())
+}
+
+#[tauri::command]
+fn set_session_limit(
+    limit: session::SessionLimit,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    app_state.session_limit = limit;
+    save_state_to_file(&app, &app_state)
+}
+
+#[tauri::command]
+fn get_launch_guards(
+    state: State<'_, Mutex<AppState>>,
+) -> Result<launchguard::LaunchGuards, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(app_state.launch_guards.clone())
+}
+
+#[tauri::command]
+fn set_launch_guards(
+    guards: launchguard::LaunchGuards,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    app_state.launch_guards = guards;
+    save_state_to_file(&app, &app_state)
+}
+
+#[tauri::command]
+fn get_request_settings(
+    state: State<'_, Mutex<AppState>>,
+) -> Result<httpconfig::RequestSettings, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(app_state.request_settings.clone())
+}
+
+#[tauri::command]
+fn set_request_settings(
+    settings: httpconfig::RequestSettings,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    settings.validate()?;
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    app_state.request_settings = settings;
+    save_state_to_file(&app, &app_state)
+}
+
+#[tauri::command]
+fn get_default_user_agent() -> String {
+    httpconfig::default_user_agent()
+}
+
+// ตัวอย่างความเร็ว (ทุก 1 วินาที ย้อนหลังสูงสุด 10 นาที) สำหรับวาดกราฟ
+#[tauri::command]
+fn get_download_speed_history(
+    id: String,
+    app: AppHandle,
+) -> Result<Vec<speedhistory::SpeedSample>, String> {
+    speedhistory::samples(&app, &id)
+}
+
+#[tauri::command]
+async fn check_helper_update(app: AppHandle) -> Result<helperupdate::HelperUpdateStatus, String> {
+    helperupdate::check(&app).await
+}
+
+#[tauri::command]
+async fn install_helper_update(app: AppHandle) -> Result<String, String> {
+    helperupdate::install(&app).await
+}
+
+// ผลตรวจไฟล์ที่มากับตัวติดตั้ง (sidecar, ไอคอน) ครั้งล่าสุด ตรวจใหม่ถ้ายังไม่เคยตรวจ
+#[tauri::command]
+async fn check_installation(
+    app: AppHandle,
+    refresh: Option<bool>,
+) -> Result<installcheck::InstallationReport, String> {
+    if !refresh.unwrap_or(false)
+        && let Some(report) = installcheck::last_report()
+    {
+        return Ok(report);
+    }
+    tauri::async_runtime::spawn_blocking(move || installcheck::verify(&app))
+        .await
+        .map_err(|e| format!("Installation check panicked: {}", e))?
+}
+
+// ดาวน์โหลดไฟล์ที่หายหรือถูกแก้ไขกลับมาใหม่
+#[tauri::command]
+async fn repair_installation(app: AppHandle) -> Result<installcheck::InstallationReport, String> {
+    installcheck::repair(&app).await
+}
+
+#[tauri::command]
+fn get_cookie_providers(app: AppHandle) -> Result<Vec<cookiejar::CookieProvider>, String> {
+    cookiejar::providers(&app)
+}
+
+#[tauri::command]
+fn clear_cookies(
+    host: Option<String>,
+    confirm_token: Option<String>,
+    app: AppHandle,
+) -> Result<confirm::Guarded<()>, String> {
+    let fingerprint = confirm::fingerprint(&serde_json::json!(host));
+    let Some(token) = confirm_token else {
+        // ไม่ระบุ host คือล้างทั้งหมด สรุปจึงแสดงทุก provider
+        let key = host.as_deref().map(|h| {
+            let h = h.trim().to_ascii_lowercase();
+            h.trim_start_matches("www.").to_string()
+        });
+        let providers: Vec<cookiejar::CookieProvider> = cookiejar::providers(&app)?
+            .into_iter()
+            .filter(|p| key.as_ref().is_none_or(|k| &p.host == k))
+            .collect();
+        return Ok(confirm::Guarded::ConfirmationRequired(confirm::issue(
+            &app,
+            "clear_cookies",
+            fingerprint,
+            serde_json::json!({ "providers": providers }),
+        )?));
+    };
+    confirm::consume(&app, &token, "clear_cookies", &fingerprint)?;
+    cookiejar::clear(&app, host)?;
+    Ok(confirm::Guarded::Done { result: () })
+}
+
+// Same lookup as launch_game, so the UI can show warnings before the game starts
+#[tauri::command]
+fn check_launch_requirements(
+    game_id: String,
+    launch_config: Option<LaunchConfig>,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<launchguard::LaunchCheckReport, String> {
+    let (stored_launch_config, guards) = {
+        let app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        let stored = app_state
+            .games
+            .as_ref()
+            .and_then(|games| games.iter().find(|g| g.id == game_id))
+            .and_then(|game| game.launch_config.clone());
+        (stored, app_state.launch_guards.clone())
+    };
+    let launch_config = stored_launch_config
+        .or(launch_config)
+        .ok_or("No launch configuration provided or found")?;
+    Ok(launchguard::run_checks(
+        &launch_config.executable_path,
+        &guards,
+    ))
+}
+
+// ตรวจค่าการเปิดเกมก่อนบันทึก (คำสั่ง custom/hook แยกคำได้ไหม โปรแกรมมีอยู่จริงไหม มีคำสั่งอันตรายไหม)
+#[tauri::command]
+fn validate_launch_config(launch_config: LaunchConfig) -> launchguard::LaunchCheckReport {
+    commandcheck::validate(&launch_config)
+}
+
+#[tauri::command]
+fn get_guest_mode(state: State<'_, Mutex<AppState>>) -> Result<guest::GuestModeStatus, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(guest::status(&app_state))
+}
+
+#[tauri::command]
+fn set_guest_pin(
+    current_pin: Option<String>,
+    new_pin: String,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    guest::set_pin(&app, &mut app_state, current_pin, new_pin)
+}
+
+#[tauri::command]
+fn enter_guest_mode(state: State<'_, Mutex<AppState>>, app: AppHandle) -> Result<(), String> {
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    guest::enter(&app, &mut app_state)
+}
+
+#[tauri::command]
+fn exit_guest_mode(
+    pin: String,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    guest::exit(&app, &mut app_state, &pin)
+}
+
+// ไฟล์ config ที่ตรวจลายเซ็นไม่ผ่านตอนเปิดโปรแกรม และกู้จาก backup ได้หรือไม่
+#[tauri::command]
+fn get_integrity_report() -> Vec<integrity::IntegrityFailure> {
+    integrity::failures()
+}
+
+#[tauri::command]
+fn open_downloads_window(app: AppHandle) -> Result<(), String> {
+    windows::open_downloads_window(&app)
+}
+
+#[tauri::command]
+fn set_progress_overlay(
+    enabled: bool,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    windows::set_progress_overlay(&app, enabled)?;
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    app_state.ui_prefs.progress_overlay = enabled;
+    save_state_to_file(&app, &app_state)?;
+    let _ = app.emit("ui-prefs-changed", &app_state.ui_prefs);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_download_settings(state: State<'_, Mutex<AppState>>) -> Result<DownloadSettings, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(app_state.download_settings.clone())
+}
+
+#[tauri::command]
+fn set_download_settings(
+    settings: DownloadSettings,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    app_state.download_settings = settings;
+    save_state_to_file(&app, &app_state)?;
+    // จำนวน slot อาจเพิ่มขึ้น เริ่มดาวน์โหลดที่รอคิวอยู่
+    scheduler::spawn_fill(&app);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_extraction_settings(
+    state: State<'_, Mutex<AppState>>,
+) -> Result<ExtractionSettings, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(app_state.extraction_settings.clone())
+}
+
+#[tauri::command]
+async fn set_extraction_settings(
+    settings: ExtractionSettings,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    // แอปล้างโฟลเดอร์ staging ที่ค้างอยู่ในโฟลเดอร์นี้เอง จึงต้องยืนยันเมื่อเปลี่ยน
+    let current_staging_dir = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .extraction_settings
+        .staging_dir
+        .clone();
+    if let Some(dir) = settings
+        .staging_dir
+        .as_deref()
+        .filter(|dir| !dir.trim().is_empty())
+        && current_staging_dir.as_deref() != Some(dir)
+    {
+        pathscope::confirm_root(&app, "staging folder", Path::new(dir)).await?;
+    }
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    app_state.extraction_settings = settings;
+    save_state_to_file(&app, &app_state)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn get_library_settings(state: State<'_, Mutex<AppState>>) -> Result<LibrarySettings, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(app_state.library_settings.clone())
+}
+
+#[tauri::command]
+async fn set_library_settings(
+    settings: LibrarySettings,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    let current_links_dir = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .library_settings
+        .games_links_dir
+        .clone();
+    if let Some(dir) = &settings.games_links_dir
+        && current_links_dir.as_ref() != Some(dir)
+    {
+        pathscope::confirm_root(&app, "games folder", Path::new(dir)).await?;
+    }
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    app_state.library_settings = settings;
+    save_state_to_file(&app, &app_state)?;
+    gamelinks::sync(&app_state)?;
+    Ok(())
+}
+
+// สร้าง/อัปเดตโฟลเดอร์ Games/<ชื่อเกม> ที่ลิงก์ไปยังโฟลเดอร์ที่แตกไฟล์แล้ว
+#[tauri::command]
+fn sync_games_links(state: State<'_, Mutex<AppState>>) -> Result<usize, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    gamelinks::sync(&app_state)
+}
+
+// คืนชื่อไฟล์เดิมของไฟล์ที่ถูกแปลงชื่อตอนแตกไฟล์ (ถ้าไม่ได้ถูกแปลงจะคืน path เดิม)
+#[tauri::command]
+fn get_original_filename(extracted_path: String, path: String) -> Result<String, String> {
+    let original = transliterate::original_path(
+        std::path::Path::new(&extracted_path),
+        std::path::Path::new(&path),
+    );
+    Ok(original
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or(path))
+}
+
+#[tauri::command]
+async fn upload_to_cloudinary(
+    file_path: String,
+    public_id: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<String, String> {
+    let cloudinary_config = {
+        let app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        app_state
+            .cloudinary
+            .as_ref()
+            .ok_or("Cloudinary config not set")?
+            .clone()
+    };
+    cloudinary::upload_to_cloudinary(file_path, public_id, &cloudinary_config).await
+}
+
+fn cloudinary_config(state: &State<'_, Mutex<AppState>>) -> Result<CloudinaryConfig, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    app_state
+        .cloudinary
+        .clone()
+        .ok_or_else(|| "Cloudinary config not set".to_string())
+}
+
+// อัปโหลดวิดีโอ (เช่น trailer) แบบแบ่งชิ้น แจ้ง "video-ready" เมื่อ Cloudinary แปลงไฟล์เสร็จ
+#[tauri::command]
+async fn upload_video(
+    file_path: String,
+    public_id: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<cloudinary::VideoUpload, String> {
+    let cloudinary_config = cloudinary_config(&state)?;
+    cloudinary::upload_video(&app, file_path, public_id, &cloudinary_config).await
+}
+
+// ไฟล์ที่อัปโหลดไว้บน Cloudinary ทีละหน้า page คือ nextCursor ของหน้าก่อน
+#[tauri::command]
+async fn list_uploads(
+    prefix: Option<String>,
+    page: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<cloudinary::UploadPage, String> {
+    let cloudinary_config = cloudinary_config(&state)?;
+    let client = httpconfig::client(&app)?;
+    cloudinary::list_uploads(prefix, page, &client, &cloudinary_config).await
+}
+
+// ลบไฟล์บน Cloudinary ย้อนกลับไม่ได้ เรียกครั้งแรกได้ token ต้องเรียกซ้ำพร้อม token ถึงจะลบจริง
+#[tauri::command]
+async fn delete_upload(
+    public_id: String,
+    confirm_token: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<confirm::Guarded<()>, String> {
+    let cloudinary_config = cloudinary_config(&state)?;
+    let fingerprint = confirm::fingerprint(&serde_json::json!(public_id));
+    let Some(token) = confirm_token else {
+        return Ok(confirm::Guarded::ConfirmationRequired(confirm::issue(
+            &app,
+            "delete_upload",
+            fingerprint,
+            serde_json::json!({ "publicId": public_id }),
+        )?));
+    };
+    confirm::consume(&app, &token, "delete_upload", &fingerprint)?;
+    let client = httpconfig::client(&app)?;
+    cloudinary::delete_upload(public_id, &client, &cloudinary_config).await?;
+    Ok(confirm::Guarded::Done { result: () })
+}
+
+// อัปโหลดภาพหน้าจอใหม่ของเกมขึ้น Cloudinary ในโฟลเดอร์ชื่อ slug ของบทความ คืน URL ทั้งหมดไว้แปะในบทความ
+// watch = true จะคอยดูโฟลเดอร์ต่อจนกว่าจะเรียก stop_screenshot_sync
+#[tauri::command]
+async fn sync_game_screenshots(
+    game_id: String,
+    folder: Option<String>,
+    watch: Option<bool>,
+    app: AppHandle,
+) -> Result<screenshotsync::ScreenshotGallery, String> {
+    screenshotsync::sync(&app, &game_id, folder, watch.unwrap_or(false)).await
+}
+
+#[tauri::command]
+fn stop_screenshot_sync(game_id: String, app: AppHandle) -> Result<bool, String> {
+    screenshotsync::stop(&app, &game_id)
+}
+
+#[tauri::command]
+fn get_screenshot_gallery(
+    game_id: String,
+    app: AppHandle,
+) -> Result<Option<screenshotsync::ScreenshotGallery>, String> {
+    Ok(screenshotsync::load_gallery(&app, &game_id))
+}
+
+#[tauri::command]
+fn open_directory(path: String, _app: AppHandle) -> Result<(), String> {
+    let path_obj = std::path::Path::new(&path);
+    if !path_obj.exists() {
+        return Err("Directory does not exist".to_string());
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        StdCommand::new("explorer")
+            .arg(path)
+            .spawn()
+            .map_err(|e| format!("Failed to open directory: {}", e))?;
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        StdCommand::new("open")
+            .arg(path)
+            .spawn()
+            .map_err(|e| format!("Failed to open directory: {}", e))?;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        StdCommand::new("xdg-open")
+            .arg(path)
+            .spawn()
+            .map_err(|e| format!("Failed to open directory: {}", e))?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn fetch_article_by_slug(
+    slug: String,
+    token: Option<String>,
+    app: AppHandle,
+) -> Result<ArticleResponse, String> {
+    let body =
+        articlecache::fetch_article_body(&app, &httpconfig::client(&app)?, &slug, token).await?;
+    // This is the article page opening, so it becomes the baseline for the changelog
+    articlehistory::record_view(&app, &slug, &body);
+    serde_json::from_value(body).map_err(|e| format!("Failed to parse response: {}", e))
+}
+
+#[tauri::command]
+fn get_subscription_settings(
+    state: State<'_, Mutex<AppState>>,
+) -> Result<subscriptions::SubscriptionSettings, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(app_state.subscription_settings.clone())
+}
+
+#[tauri::command]
+fn set_subscription_settings(
+    settings: subscriptions::SubscriptionSettings,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    settings.validate()?;
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    app_state.subscription_settings = settings;
+    save_state_to_file(&app, &app_state)
+}
+
+#[tauri::command]
+fn get_feed_settings(state: State<'_, Mutex<AppState>>) -> Result<feeds::FeedSettings, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(app_state.feed_settings.clone())
+}
+
+#[tauri::command]
+fn set_feed_settings(
+    settings: feeds::FeedSettings,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    settings.validate()?;
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    app_state.feed_settings = settings;
+    save_state_to_file(&app, &app_state)
+}
+
+// เกมที่กำลังเล่นอยู่ (เปิดจากแอป) สำหรับหน้าสถานะหรือ overlay
+#[tauri::command]
+fn get_now_playing(app: AppHandle) -> Vec<nowplaying::GameSession> {
+    nowplaying::sessions(&app)
+}
+
+#[tauri::command]
+fn get_now_playing_settings(
+    state: State<'_, Mutex<AppState>>,
+) -> Result<nowplaying::NowPlayingSettings, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(app_state.now_playing_settings.clone())
+}
+
+// ไฟล์สถานะให้สคริปต์ OBS/overlay อ่าน ไม่ระบุ path ใช้ now-playing.json ในโฟลเดอร์ข้อมูลของแอป
+#[tauri::command]
+async fn set_now_playing_settings(
+    settings: nowplaying::NowPlayingSettings,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    if let Some(path) = settings
+        .status_file_path
+        .as_deref()
+        .filter(|p| !p.trim().is_empty())
+    {
+        if !Path::new(path).parent().is_some_and(|dir| dir.is_dir()) {
+            return Err(format!(
+                "Folder for the status file does not exist: {}",
+                path
+            ));
+        }
+        // ไฟล์นี้ถูกเขียนทับทุกครั้งที่เกมเปิด/ปิด ถ้าอยู่นอกโฟลเดอร์ที่แอปดูแลต้องให้ผู้ใช้ยืนยันก่อน
+        pathscope::ensure_allowed(&app, "write", Path::new(path)).await?;
+    }
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    app_state.now_playing_settings = settings;
+    save_state_to_file(&app, &app_state)
+}
+
+#[tauri::command]
+fn get_network_settings(
+    state: State<'_, Mutex<AppState>>,
+) -> Result<offline::NetworkSettings, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(app_state.network_settings.clone())
+}
+
+// โหมดออฟไลน์และจำนวน request ไปยัง API พร้อมกัน ปิดโหมดออฟไลน์แล้วจะเริ่มดาวน์โหลดที่ค้างไว้ให้เอง
+#[tauri::command]
+fn set_network_settings(
+    settings: offline::NetworkSettings,
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<(), String> {
+    settings.validate()?;
+    let back_online = {
+        let mut app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        let back_online = app_state.network_settings.offline_mode && !settings.offline_mode;
+        app_state.network_settings = settings;
+        save_state_to_file(&app, &app_state)?;
+        back_online
+    };
+    if back_online {
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = offline::flush(&app).await {
+                println!("Failed to start queued downloads: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
 
-            let rgba = icon_image.rgba_data();
-            let img =
-                image::RgbaImage::from_raw(icon_image.width(), icon_image.height(), rgba.to_vec())
-                    .ok_or("Failed to create RGBA image")?;
-            let dynamic_img = DynamicImage::ImageRgba8(img);
+// ดาวน์โหลดที่สั่งไว้ระหว่างโหมดออฟไลน์ รอเริ่มเมื่อกลับมาออนไลน์
+#[tauri::command]
+fn get_offline_queue(app: AppHandle) -> Vec<offline::QueuedDownload> {
+    offline::load_queue(&app)
+}
+
+#[tauri::command]
+fn remove_offline_download(download_id: String, app: AppHandle) -> Result<bool, String> {
+    offline::remove_queued(&app, &download_id)
+}
+
+// ตั้งเวลาเริ่มดาวน์โหลด (เช่นตีสองที่เน็ตไม่โดนบีบ) ถึงเวลาแล้วจะเริ่มเหมือนกด start_download
+#[tauri::command]
+fn schedule_download(
+    download: downloadschedule::ScheduledDownload,
+    chunk_manifest: Option<chunkhash::ChunkManifest>,
+    app: AppHandle,
+) -> Result<(), String> {
+    if let Some(manifest) = &chunk_manifest {
+        chunkhash::save(&app, &download.download_id, manifest)?;
+    }
+    downloadschedule::schedule(&app, download)
+}
+
+// ความคืบหน้าแบบรวมของแต่ละเกม (ดาวน์โหลด → ตรวจไฟล์ → แตกไฟล์ → หา exe → พร้อมเล่น) ใช้ id เดียวกับดาวน์โหลด
+#[tauri::command]
+fn get_jobs(app: AppHandle) -> Vec<jobs::Job> {
+    jobs::list(&app)
+}
+
+// ล้างงานที่พร้อมเล่น ล้มเหลว หรือยกเลิกไปแล้วออกจากรายการ
+#[tauri::command]
+fn clear_finished_jobs(
+    confirm_token: Option<String>,
+    app: AppHandle,
+) -> Result<confirm::Guarded<usize>, String> {
+    let fingerprint = confirm::fingerprint(&serde_json::Value::Null);
+    let Some(token) = confirm_token else {
+        return Ok(confirm::Guarded::ConfirmationRequired(confirm::issue(
+            &app,
+            "clear_finished_jobs",
+            fingerprint,
+            serde_json::json!({ "jobs": jobs::finished(&app) }),
+        )?));
+    };
+    confirm::consume(&app, &token, "clear_finished_jobs", &fingerprint)?;
+    Ok(confirm::Guarded::Done {
+        result: jobs::clear_finished(&app),
+    })
+}
 
-            dynamic_img
-                .save_with_format(&icon_path, image::ImageFormat::Png)
-                .map_err(|e| format!("Failed to save icon: {}", e))?;
-        } else {
-            return Err("Only .exe files supported for icon extraction on Windows".to_string());
-        }
-    }
+#[tauri::command]
+fn get_scheduled_downloads(app: AppHandle) -> Vec<downloadschedule::ScheduledDownload> {
+    downloadschedule::load(&app)
+}
 
-    #[cfg(not(target_os = "windows"))]
-    {
-        let default_icon = app
-            .path()
-            .resource_dir()
-            .map_err(|e| format!("Failed to get resource dir: {}", e))?
-            .join("default_icon.png");
-        if default_icon.exists() {
-            fs::copy(&default_icon, &icon_path)
-                .map_err(|e| format!("Failed to copy default icon: {}", e))?;
-        } else {
-            return Err("Default icon not found".to_string());
-        }
+#[tauri::command]
+fn unschedule_download(download_id: String, app: AppHandle) -> Result<bool, String> {
+    // ลบ manifest ของ chunk เฉพาะเมื่อยกเลิกตารางได้จริง ถ้าดาวน์โหลดเริ่มไปแล้วยังต้องใช้อยู่
+    let removed = downloadschedule::unschedule(&app, &download_id)?;
+    if removed {
+        chunkhash::forget(&app, &download_id);
     }
+    Ok(removed)
+}
 
-    Ok(icon_path
-        .to_str()
-        .ok_or("Failed to convert path to string")?
-        .to_string())
+#[tauri::command]
+fn get_sound_settings(
+    state: State<'_, Mutex<AppState>>,
+) -> Result<soundcues::SoundSettings, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(app_state.sound_settings.clone())
 }
 
 #[tauri::command]
-async fn save_launch_config(
-    game_id: String,
-    launch_config: LaunchConfig,
-    icon_path: Option<String>,
+fn set_sound_settings(
+    settings: soundcues::SoundSettings,
     state: State<'_, Mutex<AppState>>,
     app: AppHandle,
 ) -> Result<(), String> {
+    settings.validate()?;
     let mut app_state = state
         .lock()
         .map_err(|e| format!("Failed to lock state: {}", e))?;
-    if app_state.games.is_none() {
-        app_state.games = Some(Vec::new());
-        println!("Initialized empty games list");
-    }
-    if let Some(games) = app_state.games.as_mut() {
-        if let Some(game) = games.iter_mut().find(|g| g.id == game_id) {
-            game.launch_config = Some(launch_config.clone());
-            game.icon_path = icon_path.clone();
-            println!("Updated launch config for game_id: {}", game_id);
-        } else {
-            println!("Game with id {} not found", game_id);
-            return Err(format!("Game with id {} not found", game_id));
-        }
-    }
-    save_state_to_file(&app, &app_state)?;
-    println!("Launch config saved to file for game_id: {}", game_id);
-    Ok(())
-}
-
-#[tauri::command]
-fn echo_test(message: String) -> String {
-    println!("Echo test received: {}", message);
-    format!("Echo reply: {}", message)
+    app_state.sound_settings = settings;
+    save_state_to_file(&app, &app_state)
 }
 
+// ลองฟังเสียง (ชื่อเสียงในตัวหรือ path ของไฟล์) ก่อนเลือก ไม่ระบุ volume ใช้ค่าที่ตั้งไว้
 #[tauri::command]
-fn verify_config_exists(app: AppHandle) -> Result<String, String> {
-    state::verify_config_file(&app).map(|_| "Config file verified successfully".to_string())
+async fn preview_sound(sound: String, volume: Option<u8>, app: AppHandle) -> Result<(), String> {
+    soundcues::preview(&app, &sound, volume).await
 }
 
 #[tauri::command]
-fn show_download_notification(
-    app: AppHandle,
-    title: String,
-    message: String,
-) -> Result<(), String> {
-    app.notification()
-        .builder()
-        .title(title)
-        .body(message)
-        .show()
-        .map_err(|e| format!("Failed to show notification: {}", e))?;
-    Ok(())
+fn get_retention_settings(
+    state: State<'_, Mutex<AppState>>,
+) -> Result<retention::RetentionSettings, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(app_state.retention_settings.clone())
 }
 
 #[tauri::command]
-fn set_token(
-    token: String,
+fn set_retention_settings(
+    settings: retention::RetentionSettings,
     state: State<'_, Mutex<AppState>>,
     app: AppHandle,
 ) -> Result<(), String> {
     let mut app_state = state
         .lock()
         .map_err(|e| format!("Failed to lock state: {}", e))?;
-    app_state.token = Some(token);
-    save_state_to_file(&app, &app_state)?;
-    Ok(())
+    app_state.retention_settings = settings;
+    save_state_to_file(&app, &app_state)
 }
 
+// ดูก่อนว่าการล้างรายการดาวน์โหลดเก่าจะลบอะไรบ้าง (ไม่ลบจริง)
 #[tauri::command]
-fn get_token(state: State<'_, Mutex<AppState>>) -> Result<Option<String>, String> {
+fn preview_retention_cleanup(app: AppHandle) -> Result<retention::RetentionPlan, String> {
+    retention::plan(&app)
+}
+
+// ล้างทันทีโดยไม่ต้องรอรอบของ scheduler ย้อนกลับได้ด้วย undo
+#[tauri::command]
+fn run_retention_cleanup(app: AppHandle) -> Result<retention::RetentionPlan, String> {
+    retention::run(&app)
+}
+
+// แปลงเนื้อหาบทความเป็น HTML ที่ปลอดภัย รูปภาพฝังจาก cache หน้าเว็บไม่ต้องโหลดอะไรจากภายนอก
+#[tauri::command]
+async fn render_article(
+    slug: String,
+    app: AppHandle,
+) -> Result<articlerender::RenderedArticle, String> {
+    articlerender::render(&app, &slug).await
+}
+
+#[tauri::command]
+fn get_article_render_settings(
+    state: State<'_, Mutex<AppState>>,
+) -> Result<articlerender::ArticleRenderSettings, String> {
     let app_state = state
         .lock()
         .map_err(|e| format!("Failed to lock state: {}", e))?;
-    Ok(app_state.token.clone())
+    Ok(app_state.article_render_settings.clone())
 }
 
 #[tauri::command]
-fn set_cloudinary_config(
-    cloud_name: String,
-    api_key: String,
-    api_secret: String,
+fn set_article_render_settings(
+    settings: articlerender::ArticleRenderSettings,
     state: State<'_, Mutex<AppState>>,
     app: AppHandle,
 ) -> Result<(), String> {
     let mut app_state = state
         .lock()
         .map_err(|e| format!("Failed to lock state: {}", e))?;
-    let config = CloudinaryConfig {
-        cloud_name,
-        api_key,
-        api_secret,
-    };
-    app_state.cloudinary = Some(config);
-    save_state_to_file(&app, &app_state)?;
-    Ok(())
+    app_state.article_render_settings = settings;
+    save_state_to_file(&app, &app_state)
 }
 
 #[tauri::command]
-fn get_cloudinary_config(
+fn get_bandwidth_usage(app: AppHandle) -> Result<bandwidth::BandwidthUsage, String> {
+    bandwidth::usage(&app)
+}
+
+#[tauri::command]
+fn get_bandwidth_settings(
     state: State<'_, Mutex<AppState>>,
-) -> Result<Option<CloudinaryConfig>, String> {
+) -> Result<bandwidth::BandwidthSettings, String> {
     let app_state = state
         .lock()
         .map_err(|e| format!("Failed to lock state: {}", e))?;
-    Ok(app_state.cloudinary.clone())
+    Ok(app_state.bandwidth_settings.clone())
 }
 
 #[tauri::command]
-fn save_all_settings(
-    token: String,
-    cloudinary_config: state::CloudinaryConfig,
+fn set_bandwidth_settings(
+    settings: bandwidth::BandwidthSettings,
     state: State<'_, Mutex<AppState>>,
     app: AppHandle,
 ) -> Result<(), String> {
+    settings.validate()?;
     let mut app_state = state
         .lock()
         .map_err(|e| format!("Failed to lock state: {}", e))?;
-    app_state.token = Some(token);
-    app_state.cloudinary = Some(CloudinaryConfig {
-        cloud_name: cloudinary_config.cloud_name,
-        api_key: cloudinary_config.api_key,
-        api_secret: cloudinary_config.api_secret,
-    });
-    save_state_to_file(&app, &app_state)?;
-    Ok(())
+    app_state.bandwidth_settings = settings;
+    save_state_to_file(&app, &app_state)
 }
 
+// ประวัติดาวน์โหลด (ไฟล์, ขนาด, ระยะเวลา, ความเร็วเฉลี่ย, ผู้ให้บริการ) เป็น CSV หรือ JSON
 #[tauri::command]
-async fn upload_to_cloudinary(
-    file_path: String,
-    public_id: Option<String>,
-    state: State<'_, Mutex<AppState>>,
-) -> Result<String, String> {
-    let cloudinary_config = {
-        let app_state = state
-            .lock()
-            .map_err(|e| format!("Failed to lock state: {}", e))?;
-        app_state
-            .cloudinary
-            .as_ref()
-            .ok_or("Cloudinary config not set")?
-            .clone()
-    };
-    cloudinary::upload_to_cloudinary(file_path, public_id, &cloudinary_config).await
+async fn export_history(
+    format: String,
+    date_range: Option<downloadhistory::DateRange>,
+    path: String,
+    app: AppHandle,
+) -> Result<usize, String> {
+    pathscope::ensure_allowed(&app, "export history to", Path::new(&path)).await?;
+    downloadhistory::export(&app, &format, &date_range.unwrap_or_default(), &path)
 }
 
+// ตรวจบทความใหม่ของแท็ก/ผู้เขียนที่ติดตามทันที ไม่ต้องรอรอบถัดไป
 #[tauri::command]
-fn open_directory(path: String, _app: AppHandle) -> Result<(), String> {
-    let path_obj = std::path::Path::new(&path);
-    if !path_obj.exists() {
-        return Err("Directory does not exist".to_string());
-    }
+async fn check_subscriptions(app: AppHandle) -> Result<Vec<subscriptions::NewArticle>, String> {
+    subscriptions::check(&app).await
+}
 
-    #[cfg(target_os = "windows")]
-    {
-        StdCommand::new("explorer")
-            .arg(path)
-            .spawn()
-            .map_err(|e| format!("Failed to open directory: {}", e))?;
-    }
+// ตรวจ RSS/Atom ที่ติดตามไว้ทันที ไม่ต้องรอรอบถัดไป
+#[tauri::command]
+async fn check_feeds(app: AppHandle) -> Result<Vec<feeds::FeedItem>, String> {
+    feeds::check(&app).await
+}
 
-    #[cfg(target_os = "macos")]
-    {
-        StdCommand::new("open")
-            .arg(path)
-            .spawn()
-            .map_err(|e| format!("Failed to open directory: {}", e))?;
+// โหลด feed มาดูก่อนกดติดตาม (ไม่บันทึกว่าเห็นแล้ว) ไม่ระบุ url คือ feed ของเว็บ
+#[tauri::command]
+async fn preview_feed(url: Option<String>, app: AppHandle) -> Result<Vec<feeds::FeedItem>, String> {
+    let feed = feeds::Feed {
+        url: url.unwrap_or_else(|| feeds::HUB_FEED_URL.to_string()),
+        name: String::new(),
+    };
+    feeds::FeedSettings {
+        feeds: vec![feed.clone()],
+        ..Default::default()
     }
+    .validate()?;
+    feeds::fetch(&app, &feed).await
+}
 
-    #[cfg(target_os = "linux")]
-    {
-        StdCommand::new("xdg-open")
-            .arg(path)
-            .spawn()
-            .map_err(|e| format!("Failed to open directory: {}", e))?;
+#[tauri::command]
+async fn get_article_changelog(
+    slug: String,
+    token: Option<String>,
+    app: AppHandle,
+) -> Result<articlehistory::ArticleChangelog, String> {
+    articlehistory::changelog(&app, &httpconfig::client(&app)?, &slug, token).await
+}
+
+#[tauri::command]
+async fn fetch_articles_by_slugs(
+    slugs: Vec<String>,
+    token: Option<String>,
+    app: AppHandle,
+) -> Result<articlecache::ArticleBatch, String> {
+    Ok(articlecache::fetch_articles(&app, slugs, token).await)
+}
+
+#[tauri::command]
+async fn refresh_library_metadata(
+    app: AppHandle,
+) -> Result<Vec<library::LibraryMetadataUpdate>, String> {
+    library::refresh_library_metadata(&app).await
+}
+
+#[tauri::command]
+fn suggest_download_locations() -> Result<Vec<setup::DownloadLocation>, String> {
+    Ok(setup::suggest_download_locations())
+}
+
+#[tauri::command]
+async fn check_api_connectivity(app: AppHandle) -> Result<setup::SetupCheck, String> {
+    if offline::is_offline(&app) {
+        return Ok(setup::SetupCheck::failed(
+            "api",
+            offline::OFFLINE_ERROR.to_string(),
+        ));
     }
+    Ok(setup::check_api_connectivity(&httpconfig::settings(&app)).await)
+}
 
-    Ok(())
+#[tauri::command]
+async fn get_current_user(token: String, app: AppHandle) -> Result<setup::CurrentUser, String> {
+    offline::ensure_online(&app)?;
+    setup::get_current_user(&httpconfig::settings(&app), &token).await
 }
 
 #[tauri::command]
-async fn fetch_article_by_slug(
-    slug: String,
-    token: Option<String>,
-) -> Result<ArticleResponse, String> {
-    state::fetch_article_by_slug(slug, token).await
+async fn validate_token(token: String, app: AppHandle) -> Result<setup::SetupCheck, String> {
+    Ok(setup::validate_token(&httpconfig::settings(&app), &token).await)
+}
+
+#[tauri::command]
+fn check_compat_layers() -> Result<Vec<setup::SetupCheck>, String> {
+    Ok(setup::check_compat_layers())
 }
 
 #[tauri::command]
@@ -576,12 +2584,90 @@ fn get_download_dir(app: AppHandle) -> Result<String, String> {
         .ok_or_else(|| "Download directory not set".to_string())
 }
 
+#[derive(Serialize)]
+struct LegacyDownloadDir {
+    #[serde(rename = "legacyPath")]
+    legacy_path: String,
+    #[serde(rename = "suggestedPath")]
+    suggested_path: Option<String>,
+    #[serde(rename = "entryCount")]
+    entry_count: usize,
+}
+
+// แจ้ง frontend ถ้ายังใช้โฟลเดอร์ดาวน์โหลดเก่าใน resource dir (จะถูกลบตอนอัปเดตแอป)
+#[tauri::command]
+fn get_legacy_download_dir(app: AppHandle) -> Result<Option<LegacyDownloadDir>, String> {
+    let download_dir = match get_download_dir(app.clone()) {
+        Ok(dir) => dir,
+        Err(_) => return Ok(None),
+    };
+    if !state::is_legacy_download_dir(&app, &download_dir) {
+        return Ok(None);
+    }
+
+    let entry_count = fs::read_dir(&download_dir)
+        .map(|entries| entries.count())
+        .unwrap_or(0);
+    Ok(Some(LegacyDownloadDir {
+        legacy_path: download_dir,
+        suggested_path: state::get_default_download_dir(&app),
+        entry_count,
+    }))
+}
+
+// ย้ายไฟล์จากโฟลเดอร์เก่าไปยังโฟลเดอร์ใหม่ แล้วอัปเดต path ของ downloads และ games ทั้งหมด
+#[tauri::command]
+async fn migrate_legacy_download_dir(
+    target: Option<String>,
+    app: AppHandle,
+) -> Result<String, String> {
+    let legacy = get_download_dir(app.clone())?;
+    if !state::is_legacy_download_dir(&app, &legacy) {
+        return Err("Download directory is not the legacy location".to_string());
+    }
+    let target = match target.filter(|t| !t.is_empty()) {
+        // โฟลเดอร์ที่หน้าเว็บเลือกจะกลายเป็นโฟลเดอร์ดาวน์โหลด ต้องยืนยันเหมือน set_download_dir
+        Some(target) => {
+            pathscope::confirm_root(&app, "download folder", Path::new(&target)).await?;
+            target
+        }
+        None => state::get_default_download_dir(&app)
+            .ok_or("Could not determine a new download directory")?,
+    };
+
+    let moved = diskio::move_dir_contents(Path::new(&legacy), Path::new(&target))?;
+    println!("Moved {} entries from {} to {}", moved, legacy, target);
+
+    {
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        let mut downloads = active_downloads
+            .write()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        state::rebase_download_paths(&mut downloads, &legacy, &target);
+        save_active_downloads_to_file(&app, &downloads)?;
+    }
+
+    let state = app.state::<Mutex<AppState>>();
+    let mut app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    state::rebase_game_paths(&mut app_state, &legacy, &target);
+    app_state.download_dir = Some(target.clone());
+    save_state_to_file(&app, &app_state)?;
+    if let Err(e) = gamelinks::sync(&app_state) {
+        println!("Failed to update games folder links: {}", e);
+    }
+    Ok(target)
+}
+
 #[tauri::command]
-fn set_download_dir(
+async fn set_download_dir(
     dir: String,
     state: State<'_, Mutex<AppState>>,
     app: AppHandle,
 ) -> Result<(), String> {
+    // โฟลเดอร์นี้กลายเป็นที่ที่แอปเปิด/ลบไฟล์ได้ ต้องยืนยันผ่านหน้าต่างของระบบก่อน
+    pathscope::confirm_root(&app, "download folder", Path::new(&dir)).await?;
     let mut app_state = state
         .lock()
         .map_err(|e| format!("Failed to lock state: {}", e))?;
@@ -640,7 +2726,8 @@ fn ensure_webview2_runtime(app: &tauri::AppHandle) -> Result<(), String> {
     }
 }
 
-#[tauri::command]
+// สถานะจาก WebView2 helper และ native engine ไม่เปิดเป็น invoke command
+// เพราะหน้าเว็บอาจส่ง success ปลอม ทำให้ path ใดๆ กลายเป็นไฟล์ที่แอปเปิด/ลบได้โดยไม่ถาม
 async fn webview2_response(
     response: serde_json::Value,
     app: AppHandle,
@@ -648,6 +2735,25 @@ async fn webview2_response(
 ) -> Result<(), String> {
     println!("Received WebView2 response: {:?}", response);
 
+    // helper เขียนไฟล์เป็น .part ก่อน ย้ายไปชื่อจริงเมื่อดาวน์โหลดเสร็จเท่านั้น
+    let mut response = response;
+    let finished_path = match response.get("status").and_then(|s| s.as_str()) {
+        Some("success") => response
+            .get("path")
+            .and_then(|p| p.as_str())
+            .map(|p| p.to_string()),
+        _ => None,
+    };
+    if let Some(path) = finished_path {
+        match diskio::finalize_part_file(&path) {
+            Ok(final_path) => response["path"] = serde_json::json!(final_path),
+            Err(e) => {
+                response["status"] = serde_json::json!("error");
+                response["message"] = serde_json::json!(e);
+            }
+        }
+    }
+
     let download_id = match response.get("downloadId").and_then(|id| id.as_str()) {
         Some(id) => id,
         None => {
@@ -666,9 +2772,24 @@ async fn webview2_response(
         .and_then(|s| s.as_str())
         .unwrap_or("unknown");
 
+    // ข้อความที่ไม่ได้มาจาก helper ระบุที่มาเอง (native engine, ผู้ใช้ยกเลิก ฯลฯ) ใช้ใน timeline
+    let source = response
+        .get("source")
+        .and_then(|s| s.as_str())
+        .unwrap_or("sidecar")
+        .to_string();
+    let message = response
+        .get("message")
+        .and_then(|m| m.as_str())
+        .map(|m| m.to_string());
+
     let mut downloads = active_downloads
         .write()
         .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+    let previous_status = downloads
+        .downloads
+        .get(download_id)
+        .map(|d| d.status.clone());
 
     let download_started = response
         .get("downloadStarted")
@@ -701,6 +2822,8 @@ async fn webview2_response(
             extracted_path: None,
             extraction_status: Some("idle".to_string()), // Default to "idle"
             extraction_progress: Some(0.0),              // Default to 0.0
+            started_at: Some(chrono::Utc::now().to_rfc3339()),
+            ..Default::default()
         };
 
         downloads
@@ -717,6 +2840,45 @@ async fn webview2_response(
         );
     }
 
+    if download_started && let Some(download) = downloads.downloads.get_mut(download_id) {
+        let field = |name: &str| {
+            response
+                .get(name)
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string())
+        };
+        download.source_url = field("sourceUrl").or(download.source_url.take());
+        download.part_path = field("partPath").or(download.part_path.take());
+    }
+
+    // ย้ายไป native engine แล้ว ข้อความที่ helper ส่งตามมา (เช่นยืนยันการยกเลิก) ไม่ต้องใช้
+    if downloads
+        .downloads
+        .get(download_id)
+        .is_some_and(|d| d.status == "transferring")
+    {
+        println!(
+            "Ignoring {} for {}, it is being handed over to the native engine",
+            status, download_id
+        );
+        if status != "progress" {
+            timeline::record(
+                &app,
+                download_id,
+                "event",
+                &format!("ignored {}", status),
+                &source,
+                message.as_deref(),
+            );
+        }
+        return Ok(());
+    }
+
+    // ลิงก์นี้ล้มเหลวแต่ยังมีมิเรอร์สำรอง ลองลิงก์ถัดไปแทนการแจ้งว่าดาวน์โหลดล้มเหลว
+    if status == "error" && downloader::try_next_mirror(&app, download_id, message.as_deref()) {
+        return Ok(());
+    }
+
     if let Some(download) = downloads.downloads.get_mut(download_id) {
         match status {
             "success" => {
@@ -725,18 +2887,29 @@ async fn webview2_response(
                     download.progress = 100.0;
                     download.path = Some(path.to_string());
                     download.downloaded_at = Some(chrono::Utc::now().to_rfc3339());
+                    download.uncompressed_size = archiver::estimate_uncompressed_size(path).ok();
+                    download.chunks_verified = response
+                        .get("chunksVerified")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false);
 
                     if let Some(filename) = response.get("filename").and_then(|f| f.as_str()) {
                         download.filename = filename.to_string();
                     }
 
+                    downloader::forget_mirrors(&app, download_id);
+                    chunkhash::forget(&app, download_id);
+                    scheduler::forget(&app, download_id);
+                    scheduler::spawn_fill(&app);
                     println!("Download completed: id={}, path={}", download_id, path);
                     let _ = app.emit(
                         "download-complete",
                         &serde_json::json!({
                             "id": download_id,
                             "filename": download.filename,
-                            "path": path
+                            "path": path,
+                            "uncompressedSize": download.uncompressed_size,
+                            "article": download.article
                         }),
                     );
                     let _ = show_download_notification(
@@ -744,6 +2917,24 @@ async fn webview2_response(
                         "Download Complete".to_string(),
                         format!("Downloaded: {}", download.filename),
                     );
+                    soundcues::play(&app, soundcues::DOWNLOAD_COMPLETE);
+                    milestones::announce_completion(
+                        &app,
+                        "download",
+                        download_id,
+                        &download.filename,
+                    );
+                    telemetry::record(&app, "feature", "download_completed");
+                    downloadhistory::record(&app, download);
+                    bandwidth::record_completed(
+                        &app,
+                        download_id,
+                        fs::metadata(path).ok().map(|m| m.len()),
+                    );
+                    // part ของไฟล์แยกที่อยู่ในคิว จะแตกไฟล์ทีเดียวหลังโหลดครบทุก part
+                    if !splitqueue::on_finished(&app, download_id, None) {
+                        apply_extraction_rule(&app, download_id, path, &download.filename);
+                    }
                 } else {
                     download.status = "downloading".to_string();
 
@@ -762,11 +2953,22 @@ async fn webview2_response(
                 }
             }
             "error" => {
+                telemetry::record(&app, "error", "download_failed");
+                speedhistory::interrupt(&app, download_id);
                 download.status = "failed".to_string();
                 download.error = response
                     .get("message")
                     .and_then(|m| m.as_str())
                     .map(|s| s.to_string());
+                downloadhistory::record(&app, download);
+                bandwidth::forget(&app, download_id);
+                scheduler::forget(&app, download_id);
+                scheduler::spawn_fill(&app);
+                splitqueue::on_finished(
+                    &app,
+                    download_id,
+                    Some(download.error.as_deref().unwrap_or("Download failed")),
+                );
                 println!(
                     "Download error: id={}, error={:?}",
                     download_id, download.error
@@ -783,8 +2985,67 @@ async fn webview2_response(
                     "Download Failed".to_string(),
                     format!("Failed to download: {}", download.filename),
                 );
+                soundcues::play(&app, soundcues::DOWNLOAD_FAILED);
+            }
+            "cancelled" => {
+                download.status = "cancelled".to_string();
+                download.progress = 0.0;
+                download.error = Some("Download cancelled by user".to_string());
+                speedhistory::forget(&app, download_id);
+                bandwidth::forget(&app, download_id);
+                downloader::forget_mirrors(&app, download_id);
+                chunkhash::forget(&app, download_id);
+                scheduler::forget(&app, download_id);
+                scheduler::spawn_fill(&app);
+                splitqueue::on_finished(&app, download_id, Some("Download cancelled by user"));
+                println!("Download cancelled: id={}", download_id);
+                let _ = app.emit(
+                    "download-cancelled",
+                    &serde_json::json!({
+                        "id": download_id
+                    }),
+                );
+            }
+            "paused" => {
+                download.status = "paused".to_string();
+                speedhistory::interrupt(&app, download_id);
+                println!("Download paused: id={}", download_id);
+                let _ = app.emit(
+                    "download-paused",
+                    &serde_json::json!({
+                        "id": download_id
+                    }),
+                );
             }
             "progress" => {
+                // ความเร็วคำนวณจากจำนวนไบต์ที่ helper ส่งมา (helper รุ่นเก่าไม่ส่ง)
+                let bytes_received = response.get("bytesReceived").and_then(|b| b.as_u64());
+                if let Some(bytes) = bytes_received {
+                    bandwidth::record_progress(&app, download_id, bytes);
+                    download.bytes_downloaded = Some(bytes);
+                }
+                if let Some(total) = response.get("totalBytes").and_then(|b| b.as_u64()) {
+                    // รู้ขนาดไฟล์ครั้งแรก เตือนทันทีถ้าพื้นที่ไม่พอ แทนที่จะไปล้มเหลวกลางทาง
+                    if download.total_bytes.is_none()
+                        && let Some(target) = download
+                            .part_path
+                            .clone()
+                            .or_else(|| get_download_dir(app.clone()).ok())
+                    {
+                        let remaining =
+                            total.saturating_sub(download.bytes_downloaded.unwrap_or(0));
+                        let _ = diskio::preflight(
+                            &app,
+                            download_id,
+                            "download",
+                            Path::new(&target),
+                            remaining,
+                        );
+                    }
+                    download.total_bytes = Some(total);
+                }
+                let bytes_per_sec =
+                    bytes_received.and_then(|bytes| speedhistory::record(&app, download_id, bytes));
                 if let Some(progress) = response.get("progress").and_then(|p| p.as_f64()) {
                     if progress as f32 > download.progress || download_started {
                         download.progress = progress as f32;
@@ -793,11 +3054,17 @@ async fn webview2_response(
                             "Download progress: id={}, progress={}",
                             download_id, progress
                         );
-                        let _ = app.emit(
+                        milestones::emit_progress(
+                            &app,
                             "download-progress",
+                            "download",
+                            download_id,
+                            &download.filename,
+                            progress,
                             &serde_json::json!({
                                 "id": download_id,
-                                "progress": progress
+                                "progress": progress,
+                                "bytesPerSec": bytes_per_sec
                             }),
                         );
                     }
@@ -871,6 +3138,11 @@ async fn webview2_response(
                 extracted_path: None,
                 extraction_status: Some("idle".to_string()), // Default to "idle"
                 extraction_progress: Some(0.0),              // Default to 0.0
+                uncompressed_size: response
+                    .get("path")
+                    .and_then(|p| p.as_str())
+                    .and_then(|p| archiver::estimate_uncompressed_size(p).ok()),
+                ..Default::default()
             };
 
             downloads
@@ -880,8 +3152,13 @@ async fn webview2_response(
 
             match status {
                 "progress" => {
-                    let _ = app.emit(
+                    milestones::emit_progress(
+                        &app,
                         "download-progress",
+                        "download",
+                        download_id,
+                        filename,
+                        f64::from(progress),
                         &serde_json::json!({
                             "id": download_id,
                             "progress": progress
@@ -905,14 +3182,121 @@ async fn webview2_response(
         }
     }
 
-    if matches!(status, "success" | "error") {
+    if matches!(status, "success" | "error" | "cancelled") {
         downloads.tokens.remove(download_id);
     }
 
+    if let Some(download) = downloads.downloads.get(download_id)
+        && previous_status.as_deref() != Some(download.status.as_str())
+    {
+        timeline::record(
+            &app,
+            download_id,
+            "download",
+            &download.status,
+            &source,
+            message.as_deref(),
+        );
+    }
+    if let Some(download) = downloads.downloads.get(download_id) {
+        jobs::track_download(&app, download);
+    }
+
     save_active_downloads_to_file(&app, &downloads)?;
     Ok(())
 }
 
+#[tauri::command]
+async fn get_sidecar_info(app: AppHandle) -> Result<sidecar::SidecarInfo, String> {
+    sidecar::ensure_running(&app).await
+}
+
+#[tauri::command]
+async fn extract_downloads(
+    ids: Vec<String>,
+    app: AppHandle,
+) -> Result<Vec<bulk::BulkItemResult>, String> {
+    Ok(bulk::extract(&app, ids).await)
+}
+
+#[tauri::command]
+async fn delete_downloads(
+    ids: Vec<String>,
+    delete_files: bool,
+    confirm_token: Option<String>,
+    app: AppHandle,
+) -> Result<confirm::Guarded<Vec<bulk::BulkItemResult>>, String> {
+    // เรียกครั้งแรกได้สรุป + token ต้องเรียกซ้ำพร้อม token เดิมถึงจะลบจริง
+    let fingerprint = confirm::fingerprint(&serde_json::json!([ids, delete_files]));
+    let Some(token) = confirm_token else {
+        let summary = bulk::delete_summary(&app, &ids, delete_files)?;
+        return Ok(confirm::Guarded::ConfirmationRequired(confirm::issue(
+            &app,
+            "delete_downloads",
+            fingerprint,
+            summary,
+        )?));
+    };
+    confirm::consume(&app, &token, "delete_downloads", &fingerprint)?;
+    Ok(confirm::Guarded::Done {
+        result: bulk::delete(&app, ids, delete_files).await,
+    })
+}
+
+#[tauri::command]
+fn undo_last_operation(app: AppHandle) -> Result<undo::UndoResult, String> {
+    undo::undo_last(&app)
+}
+
+#[tauri::command]
+async fn verify_downloads(
+    ids: Vec<String>,
+    app: AppHandle,
+) -> Result<Vec<bulk::BulkItemResult>, String> {
+    Ok(bulk::verify(&app, ids).await)
+}
+
+#[tauri::command]
+fn get_cache_stats(app: AppHandle) -> Result<Vec<cache::CacheCategory>, String> {
+    Ok(cache::get_cache_stats(&app))
+}
+
+#[tauri::command]
+fn collect_unused_icons(
+    state: State<'_, Mutex<AppState>>,
+    app: AppHandle,
+) -> Result<usize, String> {
+    let app_state = state
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    cache::gc_icons(&app, &app_state)
+}
+
+#[tauri::command]
+fn clear_cache(
+    kinds: Vec<String>,
+    confirm_token: Option<String>,
+    app: AppHandle,
+) -> Result<confirm::Guarded<u64>, String> {
+    let fingerprint = confirm::fingerprint(&serde_json::json!(kinds));
+    let Some(token) = confirm_token else {
+        let stats: Vec<cache::CacheCategory> = cache::get_cache_stats(&app)
+            .into_iter()
+            .filter(|c| kinds.contains(&c.kind))
+            .collect();
+        return Ok(confirm::Guarded::ConfirmationRequired(confirm::issue(
+            &app,
+            "clear_cache",
+            fingerprint,
+            serde_json::json!({ "caches": stats }),
+        )?));
+    };
+    confirm::consume(&app, &token, "clear_cache", &fingerprint)?;
+    Ok(confirm::Guarded::Done {
+        result: cache::clear_cache(&app, &kinds)?,
+    })
+}
+
 #[tauri::command]
 fn get_active_downloads(
     active_downloads: State<'_, RwLock<ActiveDownloads>>,
@@ -924,11 +3308,12 @@ fn get_active_downloads(
 }
 
 #[tauri::command]
-fn open_file(path: String, _app: AppHandle) -> Result<(), String> {
+async fn open_file(path: String, app: AppHandle) -> Result<(), String> {
     let path_obj = std::path::Path::new(&path);
     if !path_obj.exists() {
         return Err("File does not exist".to_string());
     }
+    pathscope::ensure_allowed(&app, "open", path_obj).await?;
 
     #[cfg(target_os = "windows")]
     {
@@ -960,65 +3345,123 @@ fn open_file(path: String, _app: AppHandle) -> Result<(), String> {
 #[tauri::command]
 async fn cancel_active_download(download_id: String, app: AppHandle) -> Result<(), String> {
     println!("Cancellation requested for download: {}", download_id);
-    let active_downloads = app.state::<RwLock<ActiveDownloads>>();
-    let mut downloads = active_downloads
-        .write()
-        .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+    let provider = {
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        let mut downloads = active_downloads
+            .write()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
 
-    if let Some(token) = downloads.tokens.remove(&download_id) {
+        let token = downloads
+            .tokens
+            .remove(&download_id)
+            .ok_or_else(|| format!("No active download found for id: {}", download_id))?;
         token.cancel();
-        if let Some(download) = downloads.downloads.get_mut(&download_id) {
-            download.status = "cancelled".to_string();
-            download.progress = 0.0;
-            download.error = Some("Download cancelled by user".to_string());
-        }
-
-        let binary_path = app
-            .path()
-            .resource_dir()
-            .map_err(|e| format!("Failed to get resource dir: {}", e))?
-            .join("binaries")
-            .join("Release")
-            .join("ConsoleApp2.exe-x86_64-pc-windows-msvc.exe");
 
-        if !binary_path.exists() {
-            return Err("WebView2 binary not found".to_string());
+        let provider = downloads
+            .downloads
+            .get_mut(&download_id)
+            .map(|download| {
+                download.status = "cancelling".to_string();
+                download.provider.clone()
+            })
+            .unwrap_or_default();
+        save_active_downloads_to_file(&app, &downloads)?;
+        provider
+    };
+    timeline::record(&app, &download_id, "download", "cancelling", "user", None);
+
+    // ยกเลิกผ่าน helper ตัวเดียวกับที่ดาวน์โหลดอยู่ และรอการยืนยันก่อนเปลี่ยนสถานะ
+    // helper ไม่ตอบหรือส่งคำสั่งไม่ได้: token ถูกยกเลิกไปแล้ว จึงบันทึกว่ายกเลิกเองแทนการค้างสถานะ cancelling
+    let acknowledged = if provider.as_deref() == Some("webview2") {
+        match sidecar::cancel_download(&app, &download_id).await {
+            Ok(acknowledged) => acknowledged,
+            Err(e) => {
+                println!("Helper did not confirm cancelling {}: {}", download_id, e);
+                false
+            }
         }
+    } else {
+        false
+    };
 
-        let message = serde_json::json!({
-            "action": "cancelDownload",
-            "downloadId": download_id
+    if !acknowledged {
+        let cancelled_json = serde_json::json!({
+            "status": "cancelled",
+            "message": "Download cancelled by user",
+            "downloadId": download_id,
+            "source": "user"
         });
-        let message_str = message.to_string();
+        webview2_response(
+            cancelled_json,
+            app.clone(),
+            app.state::<RwLock<ActiveDownloads>>(),
+        )
+        .await?;
+    }
 
-        app.shell()
-            .command(binary_path.to_str().ok_or("Invalid binary path")?)
-            .arg(&message_str)
-            .spawn()
-            .map_err(|e| format!("Failed to send cancel command: {}", e))?;
+    app.emit(
+        "cancel-download",
+        &serde_json::json!({ "download_id": download_id }),
+    )
+    .map_err(|e| format!("Failed to emit cancel-download event: {}", e))?;
 
-        app.emit(
-            "cancel-download",
-            &serde_json::json!({ "download_id": download_id }),
-        )
-        .map_err(|e| format!("Failed to emit cancel-download event: {}", e))?;
+    show_download_notification(
+        app.clone(),
+        "Download Cancelled".to_string(),
+        format!("Download {} was cancelled", download_id),
+    )?;
 
-        show_download_notification(
-            app.clone(),
-            "Download Cancelled".to_string(),
-            format!("Download {} was cancelled", download_id),
-        )?;
+    println!("Download {} cancelled successfully", download_id);
+    Ok(())
+}
 
-        save_active_downloads_to_file(&app, &downloads)?;
-        println!("Download {} cancelled successfully", download_id);
-        Ok(())
-    } else {
-        Err(format!("No active download found for id: {}", download_id))
-    }
+// ประวัติการเปลี่ยนสถานะของดาวน์โหลด/การแตกไฟล์ พร้อมเวลาและที่มา ใช้ไล่ปัญหาดาวน์โหลดค้าง
+#[tauri::command]
+fn get_download_timeline(
+    id: String,
+    app: AppHandle,
+) -> Result<Vec<timeline::TimelineEntry>, String> {
+    timeline::read(&app, &id)
+}
+
+// ดาวน์โหลดหลายไฟล์ของบทความเดียวกัน (หลาย part/หลาย mirror) รวมเป็นกลุ่มเดียว พร้อมความคืบหน้ารวม
+#[tauri::command]
+fn get_download_groups(
+    active_downloads: State<'_, RwLock<ActiveDownloads>>,
+) -> Result<Vec<downloadgroups::DownloadGroup>, String> {
+    let downloads = active_downloads
+        .read()
+        .map_err(|e| format!("Failed to read active downloads: {}", e))?;
+    Ok(downloadgroups::list(&downloads))
+}
+
+// แตกไฟล์ทั้งกลุ่ม ถ้าเป็นไฟล์แยก part จะต่อไฟล์/เปิดจาก part แรกแล้วแตกครั้งเดียว
+#[tauri::command]
+async fn extract_download_group(slug: String, app: AppHandle) -> Result<(), String> {
+    downloadgroups::extract(&app, &slug).await
+}
+
+// ได้ลิงก์ part เดียวของไฟล์แยก (.part1.rar, .7z.001) หา part ที่เหลือจาก mirror ของบทความแล้วโหลดทีละ part ตามลำดับ
+#[tauri::command]
+async fn download_split_release(
+    url: String,
+    filename: String,
+    article: ArticleMeta,
+    referer: Option<String>,
+    app: AppHandle,
+) -> Result<splitqueue::SplitRelease, String> {
+    splitqueue::queue(&app, url, filename, article, referer).await
 }
 
+// ดาวน์โหลดผ่าน WebView2 ที่ช้า/ค้าง ย้ายไปโหลดต่อด้วย HTTP client ของแอปเอง ใช้ไฟล์ .part เดิมถ้าโฮสต์รองรับ Range
 #[tauri::command]
-async fn remove_file(path: String) -> Result<(), String> {
+async fn transfer_to_native(download_id: String, app: AppHandle) -> Result<(), String> {
+    nativedownload::transfer(&app, &download_id).await
+}
+
+#[tauri::command]
+async fn remove_file(path: String, app: AppHandle) -> Result<(), String> {
+    pathscope::ensure_allowed(&app, "delete", Path::new(&path)).await?;
     fs::remove_file(&path).map_err(|e| format!("Failed to remove file: {}", e))?;
     Ok(())
 }
@@ -1032,6 +3475,7 @@ fn register_manual_download(
     app: AppHandle,
 ) -> Result<(), String> {
     println!("Manually registered download: {} at {}", download_id, path);
+    diskio::ensure_not_partial(&path)?;
     let mut downloads = active_downloads
         .write()
         .map_err(|e| format!("Failed to write active downloads: {}", e))?;
@@ -1064,6 +3508,8 @@ fn register_manual_download(
                 "idle".to_string()
             }), // Reflect extraction status
             extraction_progress: Some(if extracted { 100.0 } else { 0.0 }), // Reflect extraction progress
+            uncompressed_size: archiver::estimate_uncompressed_size(&path).ok(),
+            ..Default::default()
         },
     );
 
@@ -1072,11 +3518,38 @@ fn register_manual_download(
 }
 
 #[tauri::command]
-fn save_games(
+async fn save_games(
     games: Vec<DownloadInfo>,
     state: State<'_, Mutex<AppState>>,
     app: AppHandle,
 ) -> Result<(), String> {
+    // path ของเกมกลายเป็นที่ที่แอปเปิด/ลบไฟล์ได้ ที่เพิ่มหรือเปลี่ยนใหม่ต้องยืนยันก่อน
+    let changed_paths: Vec<String> = {
+        let app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        let existing_games = app_state.games.as_deref().unwrap_or_default();
+        games
+            .iter()
+            .flat_map(|game| {
+                let existing = existing_games.iter().find(|g| g.id == game.id);
+                let path = game
+                    .path
+                    .clone()
+                    .filter(|path| existing.is_none_or(|g| g.path != *path));
+                let extracted_path = game.extracted_path.clone().filter(|path| {
+                    existing.is_none_or(|g| g.extracted_path.as_ref() != Some(path))
+                });
+                [path, extracted_path]
+            })
+            .flatten()
+            .filter(|path| !path.is_empty())
+            .collect()
+    };
+    for path in changed_paths {
+        pathscope::confirm_root(&app, "game folder", Path::new(&path)).await?;
+    }
+
     let mut app_state = state
         .lock()
         .map_err(|e| format!("Failed to lock state: {}", e))?;
@@ -1100,13 +3573,30 @@ fn save_games(
                 // รักษา launch_config และ icon_path เดิมถ้ามี
                 launch_config: existing_game.and_then(|g| g.launch_config.clone()),
                 icon_path: existing_game.and_then(|g| g.icon_path.clone()),
+                launch_entries: existing_game
+                    .map(|g| g.launch_entries.clone())
+                    .unwrap_or_default(),
+                article: game
+                    .article
+                    .or_else(|| existing_game.and_then(|g| g.article.clone())),
             }
         })
         .collect();
 
+    // เกมที่หายไปจากรายการถูกลบจาก UI เก็บไว้ให้ undo ได้
+    let mut journal = undo::new_entry("remove_games");
+    journal.games = existing_games
+        .into_iter()
+        .filter(|g| !converted_games.iter().any(|c| c.id == g.id))
+        .collect();
+
     app_state.games = Some(converted_games);
     save_state_to_file(&app, &app_state)?;
+    undo::record(&app, journal);
     println!("Games saved successfully to config");
+    if let Err(e) = gamelinks::sync(&app_state) {
+        println!("Failed to update games folder links: {}", e);
+    }
     Ok(())
 }
 
@@ -1153,11 +3643,45 @@ fn get_saved_games(
     Ok(valid_games)
 }
 
+async fn fill_article_meta(app: AppHandle, download_id: String, mut meta: ArticleMeta) {
+    let token = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .ok()
+        .and_then(|app_state| app_state.token.clone());
+    let Ok(client) = httpconfig::client(&app) else {
+        return;
+    };
+    let article = match articlecache::fetch_article(&app, &client, &meta.slug, token).await {
+        Ok(article) => article,
+        Err(e) => {
+            println!("Failed to fetch article {} for download: {}", meta.slug, e);
+            return;
+        }
+    };
+    meta.title = meta.title.or(Some(article.title));
+    meta.cover_image = meta.cover_image.or(article.main_image);
+    meta.version = meta.version.or(article.version);
+
+    let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+    let Ok(mut downloads) = active_downloads.write() else {
+        return;
+    };
+    if let Some(download) = downloads.downloads.get_mut(&download_id) {
+        download.article = Some(meta);
+        if let Err(e) = save_active_downloads_to_file(&app, &downloads) {
+            println!("Failed to save article info for {}: {}", download_id, e);
+        }
+    }
+}
+
 #[tauri::command]
 async fn start_webview2_download(
     url: String,
     filename: String,
     download_id: String,
+    article: Option<ArticleMeta>,
+    referer: Option<String>,
     app: AppHandle,
     active_downloads: State<'_, RwLock<ActiveDownloads>>,
 ) -> Result<(), String> {
@@ -1165,6 +3689,30 @@ async fn start_webview2_download(
         "Starting WebView2 download: id={}, url={}, filename={}",
         download_id, url, filename
     );
+    // โหมดออฟไลน์: เก็บเข้าคิวเหมือน start_download แล้วเริ่มผ่าน downloader เมื่อกลับมาออนไลน์
+    if offline::is_offline(&app) {
+        return offline::queue_download(
+            &app,
+            offline::QueuedDownload {
+                download_id,
+                url,
+                filename,
+                article,
+                referer,
+                queued_at: chrono::Utc::now().to_rfc3339(),
+                mirrors: Vec::new(),
+                options: downloader::TransferOptions::default(),
+            },
+        );
+    }
+    bandwidth::ensure_under_cap(&app)?;
+    let article = article.filter(|a| !a.slug.is_empty());
+    // บางโฮสต์ปฏิเสธคำขอที่ไม่มีหน้าบทความเป็น Referer
+    let referer = referer.filter(|r| !r.trim().is_empty()).or_else(|| {
+        article
+            .as_ref()
+            .map(|a| cookiejar::article_referer(&a.slug))
+    });
 
     // ตรวจสอบ WebView2 runtime ก่อน
     #[cfg(target_os = "windows")]
@@ -1183,6 +3731,7 @@ async fn start_webview2_download(
 
     let save_folder = get_download_dir(app.clone())?;
     println!("Save folder: {}", save_folder);
+    let request_settings = httpconfig::settings(&app);
 
     if !std::path::Path::new(&save_folder).exists() {
         if let Err(e) = std::fs::create_dir_all(&save_folder) {
@@ -1212,152 +3761,60 @@ async fn start_webview2_download(
                 extracted_path: None,
                 extraction_status: Some("idle".to_string()), // Default to "idle"
                 extraction_progress: Some(0.0),              // Default to 0.0
+                article: article.clone(),
+                referer: referer.clone(),
+                started_at: Some(chrono::Utc::now().to_rfc3339()),
+                ..Default::default()
             },
         );
         downloads.tokens.insert(download_id.clone(), token.clone());
 
         save_active_downloads_to_file(&app, &downloads)?;
     }
+    timeline::record(
+        &app,
+        &download_id,
+        "download",
+        "starting",
+        "user",
+        Some(&url),
+    );
+
+    // มีแค่ slug -> ดึงชื่อและภาพปกจาก API โดยไม่บล็อกการดาวน์โหลด
+    if let Some(meta) = article.filter(|a| a.title.is_none() || a.cover_image.is_none()) {
+        tauri::async_runtime::spawn(fill_article_meta(app.clone(), download_id.clone(), meta));
+    }
 
     let message = serde_json::json!({
         "action": "setDownload",
         "url": url,
         "saveFolder": save_folder,
         "downloadId": download_id,
-        "filename": filename
+        "filename": filename,
+        // The helper keeps the browser's own User-Agent unless one was set explicitly
+        "userAgent": Some(request_settings.user_agent.trim()).filter(|ua| !ua.is_empty()),
+        "providerHeaders": request_settings.provider_headers,
+        "referer": referer,
+        "cookies": cookiejar::cookies_for(&app, &url)
     });
-    let message_str = message.to_string();
-    println!("Sending message to WebView2: {}", message_str);
-
-    let mut binary_path = app
-        .path()
-        .resource_dir()
-        .map_err(|e| format!("Failed to get resource dir: {}", e))?
-        .join("binaries")
-        .join("Release")
-        .join("WebView2-x86_64-pc-windows-msvc.exe");
-
-    if !binary_path.exists() {
-        println!("Binary not found at: {:?}", binary_path);
-
-        let paths_to_check = vec![std::path::PathBuf::from(
-            "WebView2-x86_64-pc-windows-msvc.exe",
-        )];
-
-        let mut found = false;
-        for path in paths_to_check {
-            if path.exists() {
-                println!("Found binary at alternate location: {:?}", path);
-                binary_path = path;
-                found = true;
-                break;
-            }
-        }
-
-        if !found {
-            return Err(format!(
-                "WebView2 binary not found in any expected location"
-            ));
+    if let Err(e) = sidecar::send(&app, &message).await {
+        let _ = show_download_notification(
+            app.clone(),
+            "WebView2 Helper Unavailable".to_string(),
+            e.clone(),
+        );
+        let mut downloads = active_downloads
+            .write()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        if let Some(download) = downloads.downloads.get_mut(&download_id) {
+            download.status = "failed".to_string();
+            download.error = Some(e.clone());
         }
+        downloads.tokens.remove(&download_id);
+        save_active_downloads_to_file(&app, &downloads)?;
+        return Err(e);
     }
 
-    println!("Starting WebView2 binary at: {:?}", binary_path);
-
-    let (mut rx, _child) = app
-        .shell()
-        .command(binary_path.to_str().ok_or("Invalid binary path")?)
-        .arg(&message_str)
-        .spawn()
-        .map_err(|e| format!("Failed to spawn WebView2 process: {}", e))?;
-
-    let app_clone = app.clone();
-    let download_id_clone = download_id.clone();
-
-    tauri::async_runtime::spawn(async move {
-        while let Some(event) = rx.recv().await {
-            match event {
-                CommandEvent::Stdout(line) => {
-                    let output = String::from_utf8_lossy(&line).to_string();
-                    println!("WebView2 stdout: {}", output);
-                    if let Ok(json) = serde_json::from_str::<serde_json::Value>(&output) {
-                        println!("Parsed WebView2 response: {:?}", json);
-                        let active_downloads = app_clone.state::<RwLock<ActiveDownloads>>();
-                        if let Err(e) =
-                            webview2_response(json, app_clone.clone(), active_downloads).await
-                        {
-                            println!("Error processing WebView2 response: {}", e);
-                        }
-                    }
-                }
-                CommandEvent::Stderr(line) => {
-                    let output = String::from_utf8_lossy(&line).to_string();
-                    println!("WebView2 stderr: {}", output);
-                    if let Ok(json) = serde_json::from_str::<serde_json::Value>(&output) {
-                        println!("Parsed WebView2 stderr response: {:?}", json);
-                        let active_downloads = app_clone.state::<RwLock<ActiveDownloads>>();
-                        if let Err(e) =
-                            webview2_response(json, app_clone.clone(), active_downloads).await
-                        {
-                            println!("Error processing WebView2 stderr response: {}", e);
-                        }
-                    }
-                }
-                CommandEvent::Error(e) => {
-                    println!("WebView2 process error: {}", e);
-                    let active_downloads = app_clone.state::<RwLock<ActiveDownloads>>();
-                    let error_json = serde_json::json!({
-                        "status": "error",
-                        "message": format!("WebView2 process error: {}", e),
-                        "downloadId": download_id_clone
-                    });
-                    let _ =
-                        webview2_response(error_json, app_clone.clone(), active_downloads).await;
-                }
-                CommandEvent::Terminated(code) => {
-                    println!("WebView2 process terminated with code: {:?}", code);
-                    if code.code != Some(0) {
-                        let should_report_error = {
-                            let active_downloads = app_clone.state::<RwLock<ActiveDownloads>>();
-                            match active_downloads.read() {
-                                Ok(downloads) => {
-                                    if let Some(download) =
-                                        downloads.downloads.get(&download_id_clone)
-                                    {
-                                        download.status != "completed"
-                                            && download.status != "failed"
-                                    } else {
-                                        false
-                                    }
-                                }
-                                Err(e) => {
-                                    println!("Failed to lock active downloads: {}", e);
-                                    false
-                                }
-                            }
-                        };
-
-                        if should_report_error {
-                            let error_json = serde_json::json!({
-                                "status": "error",
-                                "message": format!("WebView2 process terminated unexpectedly with code: {:?}", code),
-                                "downloadId": download_id_clone
-                            });
-
-                            let active_downloads = app_clone.state::<RwLock<ActiveDownloads>>();
-                            if let Err(e) =
-                                webview2_response(error_json, app_clone.clone(), active_downloads)
-                                    .await
-                            {
-                                println!("Error reporting WebView2 termination: {}", e);
-                            }
-                        }
-                    }
-                }
-                _ => {}
-            }
-        }
-    });
-
     app.emit(
         "start-webview2-download",
         &serde_json::json!({
@@ -1372,8 +3829,150 @@ async fn start_webview2_download(
     Ok(())
 }
 
+// โหลดต่อจากไฟล์ .part ที่ค้างไว้ (หลังปิดแอปหรือเน็ตหลุด) ด้วย Range ถ้าโฮสต์ไม่รองรับจะเริ่มใหม่ตั้งแต่ต้น
+#[tauri::command]
+async fn resume_download(download_id: String, app: AppHandle) -> Result<String, String> {
+    downloader::resume(&app, &download_id).await
+}
+
+// หยุดพักดาวน์โหลด เก็บไฟล์ .part และรายการไว้ในสถานะ paused (ยังอยู่หลังปิดเปิดแอปใหม่)
+#[tauri::command]
+async fn pause_download(download_id: String, app: AppHandle) -> Result<(), String> {
+    downloader::pause_by_user(&app, &download_id).await
+}
+
+// โหลดต่อจากที่หยุดพักไว้ ถ้า slot เต็มจะรอคิวจนมีที่ว่าง คืนชื่อ engine หรือ "queued"
+#[tauri::command]
+async fn resume_paused_download(download_id: String, app: AppHandle) -> Result<String, String> {
+    downloader::resume_paused(&app, &download_id).await
+}
+
+// ดาวน์โหลดด้วย HTTP client ของแอปเอง ส่งให้ WebView2 เฉพาะโฮสต์ที่ต้องใช้เบราว์เซอร์ คืนชื่อ provider ที่รับไป
+// options: ลิงก์สำรอง (ลองตามลำดับเมื่อลิงก์ก่อนหน้าล้มเหลว) และลำดับความสำคัญ
+#[tauri::command]
+async fn start_download(
+    url: String,
+    filename: String,
+    download_id: String,
+    article: Option<ArticleMeta>,
+    referer: Option<String>,
+    options: Option<downloader::StartOptions>,
+    app: AppHandle,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    if let Some(manifest) = &options.chunk_manifest {
+        chunkhash::save(&app, &download_id, manifest)?;
+    }
+    let urls = std::iter::once(url).chain(options.mirrors).collect();
+    downloader::start_mirrored(
+        &app,
+        urls,
+        filename,
+        download_id,
+        article,
+        referer,
+        options.transfer,
+    )
+    .await
+}
+
+// เปลี่ยนลำดับความสำคัญของดาวน์โหลด มีผลกับลำดับในคิวและส่วนแบ่งความเร็ว
+#[tauri::command]
+fn set_download_priority(
+    download_id: String,
+    priority: scheduler::Priority,
+    app: AppHandle,
+) -> Result<(), String> {
+    scheduler::set_priority(&app, &download_id, priority)
+}
+
+// ดันขึ้นเป็น high ทันที ถ้ายังรอคิวอยู่จะพักดาวน์โหลดที่สำคัญน้อยที่สุดเพื่อให้เริ่มได้เลย
+// คืน id ของดาวน์โหลดที่ถูกพักไว้ (ถ้ามี)
+#[tauri::command]
+async fn bump_priority(id: String, app: AppHandle) -> Result<Option<String>, String> {
+    scheduler::bump(&app, &id).await
+}
+
+// ทางสำรองเมื่อดาวน์โหลดผ่าน helper ไม่ได้: เปิดลิงก์ในเบราว์เซอร์ แล้วเฝ้าโฟลเดอร์ Downloads
+// จนไฟล์ที่ชื่อหรือขนาดตรงกันปรากฏ จากนั้นลงทะเบียนให้อัตโนมัติ
+#[tauri::command]
+async fn start_browser_download(
+    url: String,
+    filename: Option<String>,
+    expected_size: Option<u64>,
+    download_id: String,
+    article: Option<ArticleMeta>,
+    app: AppHandle,
+    active_downloads: State<'_, RwLock<ActiveDownloads>>,
+) -> Result<String, String> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err("Only http(s) links can be opened in the browser".to_string());
+    }
+    let filename = filename.filter(|f| !f.trim().is_empty());
+    if filename.is_none() && expected_size.is_none() {
+        return Err("A file name or size is needed to recognize the browser download".to_string());
+    }
+    bandwidth::ensure_under_cap(&app)?;
+    let watch_dir = browserfallback::watch_dir()?;
+    // เปิดเบราว์เซอร์ก่อน ถ้าเปิดไม่ได้จะไม่มีรายการค้างอยู่ในหน้าดาวน์โหลด
+    app.opener()
+        .open_url(&url, None::<&str>)
+        .map_err(|e| format!("Failed to open the browser: {}", e))?;
+
+    let token = CancellationToken::new();
+    {
+        let mut downloads = active_downloads
+            .write()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        downloads.downloads.insert(
+            download_id.clone(),
+            DownloadInfo {
+                id: download_id.clone(),
+                filename: filename
+                    .clone()
+                    .unwrap_or_else(|| "Browser download".to_string()),
+                url: url.clone(),
+                progress: 0.0,
+                status: "downloading".to_string(),
+                provider: Some("browser".to_string()),
+                extraction_status: Some("idle".to_string()),
+                extraction_progress: Some(0.0),
+                article: article.filter(|a| !a.slug.is_empty()),
+                started_at: Some(chrono::Utc::now().to_rfc3339()),
+                ..Default::default()
+            },
+        );
+        downloads.tokens.insert(download_id.clone(), token.clone());
+        save_active_downloads_to_file(&app, &downloads)?;
+    }
+
+    browserfallback::watch(
+        app.clone(),
+        download_id.clone(),
+        watch_dir.clone(),
+        filename,
+        expected_size,
+        token,
+    );
+
+    let watch_dir = watch_dir.to_string_lossy().to_string();
+    let _ = app.emit(
+        "browser-download-started",
+        &serde_json::json!({
+            "id": download_id,
+            "watchDir": watch_dir
+        }),
+    );
+    Ok(watch_dir)
+}
+
 fn main() {
     tauri::Builder::default()
+        // ต้องลงทะเบียนก่อน plugin อื่น ไฟล์ที่เปิดจาก OS ตอนแอปเปิดอยู่แล้วจะถูกส่งมาที่ตัวเดิมแทนการเปิดตัวที่สอง
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            windows::show_main_window(app);
+            shellopen::handle_args(app, args);
+        }))
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_notification::init())
@@ -1416,10 +4015,97 @@ fn main() {
                     ActiveDownloads::default()
                 }
             };
-            cleanup_active_downloads(&mut initial_downloads);
+            let interrupted = cleanup_active_downloads(&mut initial_downloads);
+            for download_id in &interrupted {
+                let status = initial_downloads
+                    .downloads
+                    .get(download_id)
+                    .map(|d| d.status.clone())
+                    .unwrap_or_default();
+                timeline::record(
+                    &app_handle,
+                    download_id,
+                    "download",
+                    &status,
+                    "startup",
+                    Some("Interrupted by application restart"),
+                );
+            }
 
             app.manage(Mutex::new(initial_state));
             app.manage(RwLock::new(initial_downloads));
+            app.manage(Mutex::new(sidecar::SidecarState::default()));
+            app.manage(Mutex::new(shellopen::PendingManifest::default()));
+            app.manage(Mutex::new(telemetry::load_queue(&app_handle)));
+            app.manage(Mutex::new(milestones::MilestoneTracker::default()));
+            app.manage(Mutex::new(autopause::AutoPause::default()));
+            app.manage(Mutex::new(runninggames::RunningGames::default()));
+            app.manage(Mutex::new(crashloop::CrashHistory::default()));
+            app.manage(Mutex::new(splitqueue::load_queues(&app_handle)));
+            app.manage(Mutex::new(pathscope::ScopeApprovals::default()));
+            app.manage(Mutex::new(screenshotsync::ScreenshotSync::default()));
+            app.manage(Mutex::new(idledownloads::IdleDownloads::default()));
+            app.manage(Mutex::new(sleepguard::SleepGuard::default()));
+            app.manage(Mutex::new(powerpause::PowerPause::default()));
+            app.manage(Mutex::new(offline::ApiLimiter::default()));
+            app.manage(Mutex::new(downloader::MirrorQueue::default()));
+            app.manage(Mutex::new(scheduler::Scheduler::default()));
+            app.manage(Mutex::new(confirm::Confirmations::default()));
+            app.manage(Mutex::new(jobs::Jobs::default()));
+            app.manage(Mutex::new(undo::load_journal(&app_handle)));
+            app.manage(Mutex::new(cookiejar::load_jar(&app_handle)));
+            app.manage(Mutex::new(speedhistory::SpeedHistory::default()));
+            app.manage(Mutex::new(subscriptions::load_seen(&app_handle)));
+            app.manage(Mutex::new(feeds::load_seen(&app_handle)));
+            app.manage(Mutex::new(downloadhistory::load_history(&app_handle)));
+            app.manage(Mutex::new(bandwidth::load_tracker(&app_handle)));
+            undo::purge_expired(&app_handle);
+            let cloak_app = app_handle.clone();
+            tauri::async_runtime::spawn_blocking(move || inputremap::restore_cloak(&cloak_app));
+            undo::start(app_handle.clone());
+            watchdog::start(app_handle.clone());
+            telemetry::start(app_handle.clone());
+            poller::start(app_handle.clone());
+            retention::start(app_handle.clone());
+            installcheck::start(app_handle.clone());
+            idledownloads::start(app_handle.clone());
+            sleepguard::start(app_handle.clone());
+            powerpause::start(app_handle.clone());
+            downloadschedule::start(app_handle.clone());
+            scheduler::spawn_fill(&app_handle);
+
+            if let Ok(app_state) = app.state::<Mutex<AppState>>().lock() {
+                if let Err(e) = cache::gc_icons(&app_handle, &app_state) {
+                    println!("Failed to clean up unused icons: {}", e);
+                }
+                if let Some(main_window) = app.get_webview_window(windows::MAIN_WINDOW) {
+                    windowstate::restore(&main_window.as_ref().window(), &app_state);
+                    if app_state.startup_settings.start_minimized
+                        && autostart::launched_by_autostart()
+                    {
+                        match windows::create_tray(&app_handle) {
+                            Ok(()) => {
+                                let _ = main_window.hide();
+                            }
+                            Err(e) => println!("Failed to start minimized: {}", e),
+                        }
+                    }
+                }
+                let resuming = app_state.startup_settings.resume_interrupted;
+                // ส่วนของไฟล์แยกที่ค้างจากครั้งก่อนโหลดต่อ ถ้าส่วนที่กำลังโหลดอยู่จะถูกโหลดต่อเองก็รอส่วนนั้น
+                splitqueue::resume(&app_handle, if resuming { &interrupted } else { &[] });
+                if resuming && !interrupted.is_empty() {
+                    tauri::async_runtime::spawn(resume_interrupted_downloads(
+                        app_handle.clone(),
+                        interrupted,
+                    ));
+                }
+                if app_state.ui_prefs.progress_overlay
+                    && let Err(e) = windows::set_progress_overlay(&app_handle, true)
+                {
+                    println!("Failed to restore progress overlay: {}", e);
+                }
+            }
 
             if let Ok(mut app_state) = app.state::<Mutex<AppState>>().lock() {
                 if app_state.download_dir.is_none() {
@@ -1427,27 +4113,104 @@ fn main() {
                     if let Err(e) = state::save_state_to_file(&app_handle, &app_state) {
                         println!("Failed to save default download directory: {}", e);
                     }
+                } else if let Some(dir) = app_state.download_dir.as_deref()
+                    && state::is_legacy_download_dir(&app_handle, dir)
+                {
+                    println!("Download directory is in the legacy resource location: {}", dir);
+                    let _ = app
+                        .notification()
+                        .builder()
+                        .title("Move your downloads")
+                        .body("Downloads are stored inside the app folder and will be lost on update. Open settings to move them.")
+                        .show();
                 }
             }
 
+            let integrity_failures = integrity::failures();
+            if !integrity_failures.is_empty() {
+                let files: Vec<&str> = integrity_failures.iter().map(|f| f.file.as_str()).collect();
+                let _ = app
+                    .notification()
+                    .builder()
+                    .title("Settings file was damaged")
+                    .body(format!(
+                        "{} failed validation and was restored from a backup where possible",
+                        files.join(", ")
+                    ))
+                    .show();
+            }
+
+            shellopen::handle_args(&app_handle, std::env::args().collect());
+
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
+        .invoke_handler(guest::guard(tauri::generate_handler![
             echo_test,
             set_token,
             get_token,
             set_cloudinary_config,
             get_cloudinary_config,
             save_all_settings,
+            export_settings,
+            import_settings,
+            get_ui_prefs,
+            set_ui_prefs,
+            get_startup_settings,
+            set_startup_settings,
+            take_opened_manifest,
+            get_extract_context_menu,
+            set_extract_context_menu,
+            get_telemetry_settings,
+            set_telemetry_settings,
+            get_pending_telemetry,
+            record_telemetry,
+            flush_telemetry,
+            get_session_limit,
+            set_session_limit,
+            get_launch_guards,
+            set_launch_guards,
+            check_launch_requirements,
+            open_downloads_window,
+            set_progress_overlay,
+            get_download_settings,
+            set_download_settings,
+            get_extraction_settings,
+            set_extraction_settings,
+            get_original_filename,
+            get_library_settings,
+            set_library_settings,
+            sync_games_links,
             upload_to_cloudinary,
+            upload_video,
+            list_uploads,
+            delete_upload,
+            sync_game_screenshots,
+            stop_screenshot_sync,
+            get_screenshot_gallery,
             fetch_article_by_slug,
+            fetch_articles_by_slugs,
+            refresh_library_metadata,
             get_download_dir,
             set_download_dir,
+            get_legacy_download_dir,
+            migrate_legacy_download_dir,
+            suggest_download_locations,
+            check_api_connectivity,
+            get_current_user,
+            validate_token,
+            check_compat_layers,
             verify_config_exists,
             show_download_notification,
             open_directory,
             cancel_active_download,
             get_active_downloads,
+            extract_downloads,
+            delete_downloads,
+            undo_last_operation,
+            verify_downloads,
+            get_cache_stats,
+            clear_cache,
+            collect_unused_icons,
             open_file,
             remove_file,
             unarchive_file,
@@ -1456,17 +4219,99 @@ fn main() {
             get_saved_games,
             register_manual_download,
             start_webview2_download,
-            webview2_response,
+            start_download,
+            set_download_priority,
+            bump_priority,
+            resume_download,
+            pause_download,
+            resume_paused_download,
+            get_sidecar_info,
             is_directory,
             select_game_executable,
             launch_game,
             extract_icon,
-            save_launch_config
-        ])
+            generate_ico,
+            save_launch_config,
+            get_guest_mode,
+            get_integrity_report,
+            get_request_settings,
+            set_request_settings,
+            get_default_user_agent,
+            get_cookie_providers,
+            clear_cookies,
+            get_download_speed_history,
+            check_helper_update,
+            install_helper_update,
+            check_installation,
+            repair_installation,
+            start_browser_download,
+            get_article_changelog,
+            get_subscription_settings,
+            set_subscription_settings,
+            check_subscriptions,
+            get_feed_settings,
+            set_feed_settings,
+            get_now_playing,
+            get_now_playing_settings,
+            set_now_playing_settings,
+            get_network_settings,
+            set_network_settings,
+            get_offline_queue,
+            remove_offline_download,
+            schedule_download,
+            get_scheduled_downloads,
+            unschedule_download,
+            get_jobs,
+            clear_finished_jobs,
+            get_sound_settings,
+            set_sound_settings,
+            preview_sound,
+            check_feeds,
+            preview_feed,
+            get_retention_settings,
+            set_retention_settings,
+            preview_retention_cleanup,
+            run_retention_cleanup,
+            render_article,
+            get_article_render_settings,
+            set_article_render_settings,
+            export_history,
+            get_bandwidth_usage,
+            get_bandwidth_settings,
+            set_bandwidth_settings,
+            launch_game_by_slug,
+            get_defender_status,
+            add_defender_exclusion,
+            remove_defender_exclusion,
+            get_archive_capabilities,
+            install_archive_tools,
+            get_compat_notes,
+            apply_compat_note,
+            get_launch_templates,
+            save_launch_template,
+            delete_launch_template,
+            apply_launch_template,
+            get_gpu_preference,
+            get_wine_graphics_status,
+            update_wine_graphics,
+            validate_launch_config,
+            get_launch_entries,
+            save_launch_entry,
+            delete_launch_entry,
+            transfer_to_native,
+            get_download_timeline,
+            get_download_groups,
+            extract_download_group,
+            download_split_release,
+            set_guest_pin,
+            enter_guest_mode,
+            exit_guest_mode
+        ]))
         .on_window_event(|app, event| {
             if let tauri::WindowEvent::CloseRequested { .. } = event {
                 let app_handle = app.app_handle().clone();
-                if let Ok(app_state) = app.state::<Mutex<AppState>>().lock() {
+                if let Ok(mut app_state) = app.state::<Mutex<AppState>>().lock() {
+                    windowstate::capture(app, &mut app_state);
                     if let Err(e) = state::save_state_to_file(&app_handle, &app_state) {
                         println!("Failed to save state on close: {}", e);
                     } else {
@@ -1487,7 +4332,20 @@ fn main() {
                     println!("Failed to lock active downloads on close");
                 }
             }
+            if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+                tauri::async_runtime::spawn(dropimport::import_dropped(
+                    app.app_handle().clone(),
+                    paths.clone(),
+                ));
+            }
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|_app, _event| {
+            // macOS ส่งไฟล์ที่เปิดจาก Finder มาเป็น event ไม่ใช่ argument
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Opened { urls } = _event {
+                shellopen::handle_urls(_app, urls);
+            }
+        });
 }
@@ -0,0 +1,83 @@
+use crate::state::AppState;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+const MILESTONES: [u8; 4] = [25, 50, 75, 100];
+
+// Last milestone announced per "<kind>:<id>"
+#[derive(Default)]
+pub struct MilestoneTracker(HashMap<String, u8>);
+
+fn reduced_mode(app: &AppHandle) -> bool {
+    app.state::<Mutex<AppState>>()
+        .lock()
+        .is_ok_and(|app_state| app_state.ui_prefs.reduced_progress_events)
+}
+
+fn summary(kind: &str, label: &str, milestone: u8) -> String {
+    match (kind, milestone) {
+        ("extraction", 100) => format!("Finished extracting {}", label),
+        ("extraction", m) => format!("Extracting {}: {} percent done", label, m),
+        (_, 100) => format!("Download of {} finished", label),
+        (_, m) => format!("Download of {}: {} percent done", label, m),
+    }
+}
+
+fn announce(app: &AppHandle, kind: &str, id: &str, label: &str, progress: f64) {
+    let key = format!("{}:{}", kind, id);
+    let tracker = app.state::<Mutex<MilestoneTracker>>();
+    let Ok(mut tracker) = tracker.lock() else {
+        return;
+    };
+    let Some(reached) = MILESTONES
+        .iter()
+        .copied()
+        .filter(|m| progress >= f64::from(*m))
+        .max()
+    else {
+        // Back under the first milestone means the transfer restarted
+        tracker.0.remove(&key);
+        return;
+    };
+    if tracker.0.get(&key).is_some_and(|last| *last >= reached) {
+        return;
+    }
+    tracker.0.insert(key, reached);
+    drop(tracker);
+
+    let _ = app.emit(
+        "progress-milestone",
+        &serde_json::json!({
+            "id": id,
+            "kind": kind,
+            "milestone": reached,
+            "summary": summary(kind, label, reached)
+        }),
+    );
+}
+
+// Emits the fine-grained progress event, or in reduced mode only a "progress-milestone"
+// event with a readable summary each time 25/50/75/100% is crossed
+pub fn emit_progress(
+    app: &AppHandle,
+    event: &str,
+    kind: &str,
+    id: &str,
+    label: &str,
+    progress: f64,
+    payload: &serde_json::Value,
+) {
+    if reduced_mode(app) {
+        announce(app, kind, id, label, progress);
+    } else {
+        let _ = app.emit(event, payload);
+    }
+}
+
+// Completion has its own event, so this only makes sure the 100% summary is announced
+pub fn announce_completion(app: &AppHandle, kind: &str, id: &str, label: &str) {
+    if reduced_mode(app) {
+        announce(app, kind, id, label, 100.0);
+    }
+}
@@ -0,0 +1,71 @@
+use crate::state::AppState;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+// Windows keeps the mark of the web in this NTFS stream, macOS in the quarantine xattr.
+// Both make the first launch of an extracted game fail or prompt in confusing ways.
+#[cfg(target_os = "windows")]
+const ZONE_STREAM: &str = ":Zone.Identifier";
+
+#[cfg(target_os = "macos")]
+const QUARANTINE_ATTR: &str = "com.apple.quarantine";
+
+// Returns true when a mark was removed
+#[cfg(target_os = "windows")]
+fn strip(path: &Path) -> Result<bool, String> {
+    let mut stream = path.as_os_str().to_owned();
+    stream.push(ZONE_STREAM);
+    match std::fs::remove_file(&stream) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(format!(
+            "Failed to remove Zone.Identifier from {}: {}",
+            path.display(),
+            e
+        )),
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn strip(_path: &Path) -> Result<bool, String> {
+    Ok(false)
+}
+
+// Clears every file under an extracted game; returns how many marks were removed
+#[cfg(target_os = "macos")]
+pub fn strip_tree(dir: &Path) -> Result<usize, String> {
+    let status = std::process::Command::new("xattr")
+        .args(["-dr", QUARANTINE_ATTR])
+        .arg(dir)
+        .status()
+        .map_err(|e| format!("Failed to run xattr: {}", e))?;
+    Ok(usize::from(status.success()))
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn strip_tree(dir: &Path) -> Result<usize, String> {
+    let mut stripped = 0;
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            stripped += strip_tree(&path)?;
+        } else if file_type.is_file() && strip(&path)? {
+            stripped += 1;
+        }
+    }
+    Ok(stripped)
+}
+
+// "strip" removes the marks from what a hash-verified download extracts to; "preserve" leaves
+// them for the OS
+pub fn strip_enabled(app: &AppHandle) -> bool {
+    app.state::<Mutex<AppState>>()
+        .lock()
+        .is_ok_and(|app_state| app_state.download_settings.mark_of_the_web == "strip")
+}
@@ -0,0 +1,490 @@
+use crate::chunkhash::{self, ChunkVerifier, CorruptChunk};
+use crate::diskio::{self, DownloadWriter, WritePolicy};
+use crate::state::{AppState, save_active_downloads_to_file};
+use crate::{ActiveDownloads, cookiejar, httpconfig, scheduler, segmented, sidecar, timeline};
+use futures_util::StreamExt;
+use reqwest::StatusCode;
+use reqwest::header::{ACCEPT_RANGES, CONTENT_RANGE, CONTENT_TYPE, COOKIE, RANGE, REFERER};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+
+pub(crate) const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+const PAUSE_WAIT: Duration = Duration::from_secs(10);
+
+// Times a download re-fetches chunks that failed verification before it gives up
+const CHUNK_RETRIES: u32 = 3;
+
+// run() fails with this when the host answers with a page, which only a browser can get past
+pub(crate) const NEEDS_BROWSER: &str = "The host answered with a web page instead of the file";
+
+pub(crate) struct Transfer {
+    pub(crate) url: String,
+    pub(crate) part_path: PathBuf,
+    pub(crate) filename: String,
+    pub(crate) referer: Option<String>,
+    pub(crate) status: String,
+}
+
+fn snapshot(app: &AppHandle, download_id: &str) -> Result<Transfer, String> {
+    let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+    let downloads = active_downloads
+        .read()
+        .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+    let download = downloads
+        .downloads
+        .get(download_id)
+        .ok_or_else(|| format!("No active download found for id: {}", download_id))?;
+    if download.provider.as_deref() != Some("webview2") {
+        return Err("Only WebView2 downloads can be moved to the native engine".to_string());
+    }
+    if !matches!(
+        download.status.as_str(),
+        "starting" | "downloading" | "paused"
+    ) {
+        return Err(format!("Download is {}, nothing to move", download.status));
+    }
+    // The page URL usually serves HTML; only the address the helper ended up downloading from is the file
+    let url = download
+        .source_url
+        .clone()
+        .ok_or("The helper has not reported the file's address yet")?;
+    let part_path = match &download.part_path {
+        Some(path) => PathBuf::from(path),
+        None => Path::new(&crate::get_download_dir(app.clone())?).join(format!(
+            "{}{}",
+            download.filename,
+            diskio::PART_SUFFIX
+        )),
+    };
+    Ok(Transfer {
+        url,
+        part_path,
+        filename: download.filename.clone(),
+        referer: download.referer.clone(),
+        status: download.status.clone(),
+    })
+}
+
+fn set_status(app: &AppHandle, download_id: &str, status: &str) -> Result<(), String> {
+    let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+    let mut downloads = active_downloads
+        .write()
+        .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+    if let Some(download) = downloads.downloads.get_mut(download_id) {
+        download.status = status.to_string();
+    }
+    save_active_downloads_to_file(app, &downloads)
+}
+
+fn current_status(app: &AppHandle, download_id: &str) -> Option<String> {
+    app.state::<RwLock<ActiveDownloads>>()
+        .read()
+        .ok()
+        .and_then(|downloads| {
+            downloads
+                .downloads
+                .get(download_id)
+                .map(|d| d.status.clone())
+        })
+}
+
+// Bytes the helper last reported as received
+fn received(app: &AppHandle, download_id: &str) -> Option<u64> {
+    app.state::<RwLock<ActiveDownloads>>()
+        .read()
+        .ok()
+        .and_then(|downloads| downloads.downloads.get(download_id)?.bytes_downloaded)
+}
+
+// Stops the helper from writing so the partial file can be taken over; false when it cannot pause
+async fn pause_helper(app: &AppHandle, download_id: &str, status: &str) -> bool {
+    if status == "paused" {
+        return true;
+    }
+    if let Err(e) = sidecar::pause_download(app, download_id).await {
+        println!("Could not pause {} before moving it: {}", download_id, e);
+        return false;
+    }
+    let started = Instant::now();
+    while started.elapsed() < PAUSE_WAIT {
+        if current_status(app, download_id).as_deref() == Some("paused") {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    println!("Helper did not pause {} in time", download_id);
+    false
+}
+
+// Cancelling in the helper deletes its file, so the bytes are moved aside first
+fn set_aside(part_path: &Path) -> Option<PathBuf> {
+    if !part_path.is_file() {
+        return None;
+    }
+    let aside = part_path.with_extension("handoff");
+    let moved = std::fs::rename(part_path, &aside)
+        .or_else(|_| std::fs::copy(part_path, &aside).map(|_| ()));
+    match moved {
+        Ok(()) => Some(aside),
+        Err(e) => {
+            println!("Failed to keep partial file {}: {}", part_path.display(), e);
+            None
+        }
+    }
+}
+
+// Cancels the WebView2 transfer and continues it with the app's own HTTP client
+pub async fn transfer(app: &AppHandle, download_id: &str) -> Result<(), String> {
+    let transfer = snapshot(app, download_id)?;
+    let paused = pause_helper(app, download_id, &transfer.status).await;
+
+    // From here on webview2_response ignores what the helper sends for this download
+    set_status(app, download_id, "transferring")?;
+    timeline::record(
+        app,
+        download_id,
+        "download",
+        "transferring",
+        "user",
+        Some(&transfer.url),
+    );
+    let aside = if paused {
+        set_aside(&transfer.part_path)
+    } else {
+        None
+    };
+    if let Err(e) = sidecar::cancel_download(app, download_id).await {
+        println!("Failed to cancel {} in the helper: {}", download_id, e);
+    }
+    if let Some(aside) = aside
+        && let Err(e) = std::fs::rename(&aside, &transfer.part_path)
+    {
+        println!("Failed to restore partial file: {}", e);
+        let _ = std::fs::remove_file(&aside);
+    }
+    // fetch() resumes from the file's length, which is only right if the helper wrote exactly the
+    // bytes it reported. A preallocated or unreported file is started over instead.
+    let on_disk = std::fs::metadata(&transfer.part_path).map(|m| m.len()).ok();
+    if let Some(len) = on_disk.filter(|len| *len > 0)
+        && received(app, download_id) != Some(len)
+    {
+        println!(
+            "Partial file of {} holds {} bytes, not what the helper received; starting over",
+            download_id, len
+        );
+        let _ = std::fs::remove_file(&transfer.part_path);
+    }
+
+    let token = CancellationToken::new();
+    {
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        let mut downloads = active_downloads
+            .write()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        if let Some(download) = downloads.downloads.get_mut(download_id) {
+            download.provider = Some("native".to_string());
+            download.status = "downloading".to_string();
+            download.error = None;
+            download.part_path = Some(transfer.part_path.to_string_lossy().to_string());
+        }
+        downloads
+            .tokens
+            .insert(download_id.to_string(), token.clone());
+        save_active_downloads_to_file(app, &downloads)?;
+    }
+    println!(
+        "Moved download {} to the native engine ({})",
+        download_id, transfer.url
+    );
+
+    let app = app.clone();
+    let download_id = download_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let status = match run(&app, &download_id, &transfer, &token).await {
+            Ok(()) => serde_json::json!({
+                "status": "success",
+                "downloadId": download_id,
+                "path": transfer.part_path.to_string_lossy(),
+                "filename": transfer.filename
+            }),
+            // cancel_active_download reports the cancellation itself
+            Err(_) if token.is_cancelled() => return,
+            Err(e) => serde_json::json!({
+                "status": "error",
+                "message": e,
+                "downloadId": download_id
+            }),
+        };
+        report(&app, status).await;
+    });
+    Ok(())
+}
+
+// Same path as the helper's messages, so completion, history and extraction rules apply unchanged
+pub(crate) async fn report(app: &AppHandle, mut response: serde_json::Value) {
+    response["source"] = serde_json::json!("native");
+    let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+    if let Err(e) = crate::webview2_response(response, app.clone(), active_downloads).await {
+        println!("Failed to report native download status: {}", e);
+    }
+}
+
+// GET for the transfer's URL with the provider headers, Referer and cookies the host expects
+pub(crate) fn request(
+    app: &AppHandle,
+    transfer: &Transfer,
+) -> Result<reqwest::RequestBuilder, String> {
+    let settings = httpconfig::settings(app);
+    let client = httpconfig::client(app)?;
+    let mut request = httpconfig::apply(&settings, client.get(&transfer.url), &transfer.url);
+    if let Some(referer) = &transfer.referer {
+        request = request.header(REFERER, referer);
+    }
+    let cookies: Vec<String> = cookiejar::cookies_for(app, &transfer.url)
+        .into_iter()
+        .map(|c| format!("{}={}", c.name, c.value))
+        .collect();
+    if !cookies.is_empty() {
+        request = request.header(COOKIE, cookies.join("; "));
+    }
+    Ok(request)
+}
+
+// How a single request ended when it did not fail outright
+enum Outcome {
+    Done,
+    // The file was cut back to the start of this chunk, the next request continues from there
+    Corrupt(CorruptChunk),
+}
+
+pub(crate) async fn run(
+    app: &AppHandle,
+    download_id: &str,
+    transfer: &Transfer,
+    token: &CancellationToken,
+) -> Result<(), String> {
+    let mut attempt = 0;
+    loop {
+        match fetch(app, download_id, transfer, token).await? {
+            Outcome::Done => return Ok(()),
+            Outcome::Corrupt(chunk) if attempt < CHUNK_RETRIES => {
+                attempt += 1;
+                chunkhash::report_corrupt(app, download_id, &chunk);
+            }
+            Outcome::Corrupt(chunk) => return Err(chunk.message()),
+        }
+    }
+}
+
+async fn fetch(
+    app: &AppHandle,
+    download_id: &str,
+    transfer: &Transfer,
+    token: &CancellationToken,
+) -> Result<Outcome, String> {
+    // A split download continues segment by segment; its .part file is already full size
+    if segmented::has_state(&transfer.part_path) {
+        return segmented::run(app, download_id, transfer, token, None)
+            .await
+            .map(|_| Outcome::Done);
+    }
+    let offset = std::fs::metadata(&transfer.part_path)
+        .map(|m| m.len())
+        .unwrap_or(0);
+    let mut request = request(app, transfer)?;
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={}-", offset));
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to request {}: {}", transfer.url, e))?;
+    let resumed_from = match response.status() {
+        StatusCode::PARTIAL_CONTENT => {
+            let start = response
+                .headers()
+                .get(CONTENT_RANGE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("bytes "))
+                .and_then(|v| v.split('-').next())
+                .and_then(|v| v.parse::<u64>().ok());
+            if start != Some(offset) {
+                return Err(format!(
+                    "Host resumed from {:?} instead of byte {}",
+                    start, offset
+                ));
+            }
+            offset
+        }
+        // Everything is already on disk, if the host's size ("bytes */<total>") agrees
+        StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+            let total = response
+                .headers()
+                .get(CONTENT_RANGE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("bytes */"))
+                .and_then(|v| v.trim().parse::<u64>().ok());
+            if total == Some(offset) {
+                return Ok(Outcome::Done);
+            }
+            // The partial file cannot be trusted, so the next attempt starts over
+            let _ = std::fs::remove_file(&transfer.part_path);
+            return Err(format!(
+                "Host refused to resume at byte {} (file size {:?}); the partial file was discarded",
+                offset, total
+            ));
+        }
+        status if status.is_success() => {
+            if offset > 0 {
+                println!(
+                    "Host does not support ranges, downloading {} from the start",
+                    download_id
+                );
+            }
+            0
+        }
+        status => return Err(format!("Host answered {}", status)),
+    };
+    let html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/html"));
+    if html {
+        return Err(NEEDS_BROWSER.to_string());
+    }
+    let total = response.content_length().map(|len| len + resumed_from);
+    let ranges = response
+        .headers()
+        .get(ACCEPT_RANGES)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("bytes"));
+    if resumed_from == 0
+        && ranges
+        && let Some(total) = total
+        && segmented::worth_splitting(app, download_id, total)
+    {
+        // This connection is dropped; every segment asks for its own range
+        drop(response);
+        return segmented::run(app, download_id, transfer, token, Some(total))
+            .await
+            .map(|_| Outcome::Done);
+    }
+
+    let download_settings = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .map(|app_state| app_state.download_settings.clone())
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    if let Some(total) = total {
+        diskio::preflight(
+            app,
+            download_id,
+            "download",
+            &transfer.part_path,
+            total.saturating_sub(resumed_from),
+        )?;
+    }
+    let policy = WritePolicy::for_target(&download_settings, &transfer.part_path);
+    let mut writer = if resumed_from > 0 {
+        DownloadWriter::append(&transfer.part_path, policy)?
+    } else {
+        DownloadWriter::create(&transfer.part_path, policy, total)?
+    };
+
+    let mut verifier = match chunkhash::for_download(app, download_id) {
+        Some(manifest) => Some(ChunkVerifier::resume(
+            manifest,
+            &transfer.part_path,
+            0,
+            resumed_from,
+        )?),
+        None => None,
+    };
+
+    let mut stream = response.bytes_stream();
+    let mut last_report = Instant::now();
+    let mut throttle = scheduler::Throttle::new(app, download_id);
+    loop {
+        let chunk = tokio::select! {
+            _ = token.cancelled() => return Err("Download cancelled".to_string()),
+            chunk = stream.next() => chunk,
+        };
+        let Some(chunk) = chunk else {
+            break;
+        };
+        let chunk = chunk.map_err(|e| format!("Download interrupted: {}", e))?;
+        writer
+            .write_chunk(&chunk)
+            .map_err(|e| format!("Failed to write {}: {}", transfer.part_path.display(), e))?;
+        if let Some(verifier) = &mut verifier
+            && let Err(corrupt) = verifier.update(&chunk)
+        {
+            return rewind(transfer, writer, corrupt);
+        }
+        throttle.wait(app, download_id, chunk.len()).await;
+
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            last_report = Instant::now();
+            let received = resumed_from + writer.written();
+            let progress = total
+                .filter(|total| *total > 0)
+                .map(|total| received as f64 / total as f64 * 100.0);
+            report(
+                app,
+                serde_json::json!({
+                    "status": "progress",
+                    "downloadId": download_id,
+                    "progress": progress.unwrap_or(0.0),
+                    "bytesReceived": received,
+                    "totalBytes": total
+                }),
+            )
+            .await;
+        }
+    }
+    let written = writer
+        .finish()
+        .map_err(|e| format!("Failed to write {}: {}", transfer.part_path.display(), e))?;
+    if let Some(total) = total
+        && resumed_from + written < total
+    {
+        return Err(format!(
+            "Connection closed after {} of {} bytes",
+            resumed_from + written,
+            total
+        ));
+    }
+    if let Some(verifier) = &mut verifier
+        && let Err(corrupt) = verifier.finish()
+    {
+        truncate(&transfer.part_path, corrupt.start)?;
+        return Ok(Outcome::Corrupt(corrupt));
+    }
+    Ok(Outcome::Done)
+}
+
+fn truncate(part_path: &Path, len: u64) -> Result<(), String> {
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(part_path)
+        .and_then(|file| file.set_len(len))
+        .map_err(|e| format!("Failed to truncate {}: {}", part_path.display(), e))
+}
+
+// Drops the bad chunk and everything after it, so resuming fetches it again
+fn rewind(
+    transfer: &Transfer,
+    writer: DownloadWriter,
+    corrupt: CorruptChunk,
+) -> Result<Outcome, String> {
+    writer
+        .finish()
+        .map_err(|e| format!("Failed to write {}: {}", transfer.part_path.display(), e))?;
+    truncate(&transfer.part_path, corrupt.start)?;
+    Ok(Outcome::Corrupt(corrupt))
+}
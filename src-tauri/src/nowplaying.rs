@@ -0,0 +1,131 @@
+use crate::runninggames;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+
+const STATUS_FILE: &str = "now-playing.json";
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct NowPlayingSettings {
+    // Keep a JSON file with the running games for OBS scripts and overlay tools to poll
+    #[serde(rename = "writeStatusFile")]
+    pub write_status_file: bool,
+    // None writes now-playing.json into the app data folder
+    #[serde(rename = "statusFilePath")]
+    pub status_file_path: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GameSession {
+    pub game_id: String,
+    pub title: String,
+    pub article_slug: Option<String>,
+    pub cover_image: Option<String>,
+    pub executable: String,
+    pub pid: u32,
+    pub started_at: String,
+}
+
+// Games running right now, in launch order
+pub fn sessions(app: &AppHandle) -> Vec<GameSession> {
+    runninggames::sessions(app)
+}
+
+pub fn status_file(app: &AppHandle, settings: &NowPlayingSettings) -> Option<PathBuf> {
+    match settings
+        .status_file_path
+        .as_deref()
+        .filter(|p| !p.trim().is_empty())
+    {
+        Some(path) => Some(PathBuf::from(path)),
+        None => app
+            .path()
+            .app_data_dir()
+            .ok()
+            .map(|dir| dir.join(STATUS_FILE)),
+    }
+}
+
+// Written to a temporary file and renamed, so a tool polling it never reads half a file
+fn write_status(app: &AppHandle, last_session: Option<&serde_json::Value>) {
+    let settings = match app.state::<Mutex<AppState>>().lock() {
+        Ok(app_state) => app_state.now_playing_settings.clone(),
+        Err(_) => return,
+    };
+    if !settings.write_status_file {
+        return;
+    }
+    let Some(path) = status_file(app, &settings) else {
+        return;
+    };
+    let sessions = sessions(app);
+    let status = serde_json::json!({
+        "playing": !sessions.is_empty(),
+        // The most recently started game, what most overlays show
+        "current": sessions.last(),
+        "sessions": sessions,
+        "lastSession": last_session,
+        "updatedAt": chrono::Utc::now().to_rfc3339()
+    });
+    let incoming = path.with_extension("tmp");
+    let result = serde_json::to_string_pretty(&status)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(&incoming, json).map_err(|e| e.to_string()))
+        .and_then(|_| std::fs::rename(&incoming, &path).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        println!("Failed to write {}: {}", path.display(), e);
+    }
+}
+
+pub fn session(
+    app: &AppHandle,
+    game_id: String,
+    title: String,
+    executable: String,
+    pid: u32,
+) -> GameSession {
+    let article = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .ok()
+        .and_then(|app_state| {
+            app_state
+                .games
+                .as_ref()
+                .and_then(|games| games.iter().find(|g| g.id == game_id))
+                .and_then(|game| game.article.clone())
+        });
+    GameSession {
+        game_id,
+        title,
+        article_slug: article.as_ref().map(|a| a.slug.clone()),
+        cover_image: article.and_then(|a| a.cover_image),
+        executable,
+        pid,
+        started_at: chrono::Utc::now().to_rfc3339(),
+    }
+}
+
+// Emits "game-session-started" now and "game-session-ended" once `exited` fires.
+// The session must already be committed to runninggames, which is what the status file lists.
+pub fn started(app: AppHandle, session: GameSession, exited: CancellationToken) {
+    let _ = app.emit("game-session-started", &session);
+    write_status(&app, None);
+
+    tauri::async_runtime::spawn(async move {
+        let started = std::time::Instant::now();
+        exited.cancelled().await;
+        // Released here as well, so the status written below no longer lists the game
+        runninggames::release(&app, &session.game_id, session.pid);
+        let mut ended = serde_json::to_value(&session).unwrap_or_default();
+        ended["endedAt"] = serde_json::json!(chrono::Utc::now().to_rfc3339());
+        ended["durationSecs"] = serde_json::json!(started.elapsed().as_secs());
+        let _ = app.emit("game-session-ended", &ended);
+        write_status(&app, Some(&ended));
+    });
+}
@@ -0,0 +1,213 @@
+use crate::downloader::TransferOptions;
+use crate::state::{self, AppState, ArticleMeta};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+pub const OFFLINE_ERROR: &str = "Offline mode is on";
+
+// Returned by downloader::start instead of a provider when the download was queued
+pub const QUEUED_PROVIDER: &str = "queued";
+
+const QUEUE_FILE: &str = "offline_queue.json";
+
+const MAX_API_REQUESTS: usize = 32;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct NetworkSettings {
+    // API calls answer from the cache (or fail) and new downloads wait until it is turned off
+    #[serde(rename = "offlineMode")]
+    pub offline_mode: bool,
+    // API requests in flight at once across the whole app; the rest wait for a free slot
+    #[serde(rename = "maxApiRequests")]
+    pub max_api_requests: usize,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            offline_mode: false,
+            max_api_requests: 4,
+        }
+    }
+}
+
+impl NetworkSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_api_requests == 0 || self.max_api_requests > MAX_API_REQUESTS {
+            return Err(format!(
+                "API request limit must be between 1 and {}",
+                MAX_API_REQUESTS
+            ));
+        }
+        Ok(())
+    }
+}
+
+// Download requested while offline, started in order once the app is back online
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedDownload {
+    pub download_id: String,
+    pub url: String,
+    pub filename: String,
+    pub article: Option<ArticleMeta>,
+    pub referer: Option<String>,
+    pub queued_at: String,
+    // Fallback links tried after `url`, see downloader::start_mirrored
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+    #[serde(flatten)]
+    pub options: TransferOptions,
+}
+
+// The semaphore is swapped when the limit changes; requests already holding a permit finish normally
+pub struct ApiLimiter {
+    limit: usize,
+    semaphore: Arc<Semaphore>,
+}
+
+impl Default for ApiLimiter {
+    fn default() -> Self {
+        let limit = NetworkSettings::default().max_api_requests;
+        Self {
+            limit,
+            semaphore: Arc::new(Semaphore::new(limit)),
+        }
+    }
+}
+
+pub fn settings(app: &AppHandle) -> NetworkSettings {
+    app.state::<Mutex<AppState>>()
+        .lock()
+        .map(|app_state| app_state.network_settings.clone())
+        .unwrap_or_default()
+}
+
+pub fn is_offline(app: &AppHandle) -> bool {
+    settings(app).offline_mode
+}
+
+pub fn ensure_online(app: &AppHandle) -> Result<(), String> {
+    if is_offline(app) {
+        return Err(OFFLINE_ERROR.to_string());
+    }
+    Ok(())
+}
+
+// Every call to the Chanomhub API holds one of these for the length of the request
+pub async fn api_permit(app: &AppHandle) -> Result<OwnedSemaphorePermit, String> {
+    let settings = settings(app);
+    if settings.offline_mode {
+        return Err(OFFLINE_ERROR.to_string());
+    }
+    let semaphore = {
+        let limiter = app.state::<Mutex<ApiLimiter>>();
+        let mut limiter = limiter
+            .lock()
+            .map_err(|e| format!("Failed to lock API limiter: {}", e))?;
+        if limiter.limit != settings.max_api_requests {
+            limiter.limit = settings.max_api_requests;
+            limiter.semaphore = Arc::new(Semaphore::new(settings.max_api_requests));
+        }
+        limiter.semaphore.clone()
+    };
+    semaphore
+        .acquire_owned()
+        .await
+        .map_err(|e| format!("Failed to wait for an API slot: {}", e))
+}
+
+fn queue_path(app: &AppHandle) -> Option<PathBuf> {
+    state::get_config_dir(app).map(|dir| dir.join(QUEUE_FILE))
+}
+
+pub fn load_queue(app: &AppHandle) -> Vec<QueuedDownload> {
+    queue_path(app)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_queue(app: &AppHandle, queue: &[QueuedDownload]) -> Result<(), String> {
+    let path = queue_path(app).ok_or("Failed to get config dir")?;
+    let json = serde_json::to_string_pretty(queue)
+        .map_err(|e| format!("Failed to serialize offline queue: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to save offline queue: {}", e))
+}
+
+// A download queued twice under the same id keeps only the latest request
+pub fn queue_download(app: &AppHandle, download: QueuedDownload) -> Result<(), String> {
+    println!(
+        "Offline: queued download id={}, url={}",
+        download.download_id, download.url
+    );
+    let mut queue = load_queue(app);
+    queue.retain(|d| d.download_id != download.download_id);
+    let _ = app.emit("download-queued-offline", &download);
+    queue.push(download);
+    save_queue(app, &queue)
+}
+
+pub fn remove_queued(app: &AppHandle, download_id: &str) -> Result<bool, String> {
+    let mut queue = load_queue(app);
+    let before = queue.len();
+    queue.retain(|d| d.download_id != download_id);
+    if queue.len() == before {
+        return Ok(false);
+    }
+    save_queue(app, &queue)?;
+    Ok(true)
+}
+
+// Starts everything queued while offline. Downloads that fail to start stay queued for next time.
+pub async fn flush(app: &AppHandle) -> Result<Vec<String>, String> {
+    ensure_online(app)?;
+    let queue = load_queue(app);
+    if queue.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut started = Vec::new();
+    for download in queue {
+        let urls = std::iter::once(download.url.clone())
+            .chain(download.mirrors.iter().cloned())
+            .collect();
+        let result = crate::downloader::start_mirrored(
+            app,
+            urls,
+            download.filename.clone(),
+            download.download_id.clone(),
+            download.article.clone(),
+            download.referer.clone(),
+            download.options,
+        )
+        .await;
+        match result {
+            // Offline mode came back on mid-way; the download was queued again
+            Ok(provider) if provider == QUEUED_PROVIDER && is_offline(app) => break,
+            Ok(_) => {
+                remove_queued(app, &download.download_id)?;
+                started.push(download.download_id);
+            }
+            Err(e) => println!(
+                "Failed to start queued download {}: {}",
+                download.download_id, e
+            ),
+        }
+    }
+    let remaining = load_queue(app).len();
+    println!(
+        "Started {} queued downloads, {} left in the queue",
+        started.len(),
+        remaining
+    );
+    let _ = app.emit(
+        "offline-queue-flushed",
+        &serde_json::json!({ "started": started, "remaining": remaining }),
+    );
+    Ok(started)
+}
@@ -0,0 +1,201 @@
+use crate::ActiveDownloads;
+use crate::state::{AppState, LaunchConfig};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+// Operations the user allowed through the native prompt, kept until the app restarts so the same
+// game or file does not ask again
+#[derive(Default)]
+pub struct ScopeApprovals(HashSet<String>);
+
+// Folders the app manages: downloads, extracted games, library links and its own data.
+// Download and extraction paths are set by the backend itself; the download folder, library
+// folder and game paths come from the webview but only change after confirm_root. Drive roots
+// and the home folder never count, whatever was confirmed.
+fn allowed_roots(app: &AppHandle) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = Vec::new();
+    let paths = app.path();
+    roots.extend(paths.app_data_dir().ok());
+    roots.extend(paths.app_local_data_dir().ok());
+    roots.extend(paths.app_cache_dir().ok());
+    roots.extend(paths.app_log_dir().ok());
+    roots.extend(paths.app_config_dir().ok());
+
+    if let Ok(downloads) = app.state::<RwLock<ActiveDownloads>>().read() {
+        for download in downloads.downloads.values() {
+            roots.extend(download.path.as_deref().map(PathBuf::from));
+            roots.extend(download.extracted_path.as_deref().map(PathBuf::from));
+        }
+    }
+    if let Ok(app_state) = app.state::<Mutex<AppState>>().lock() {
+        roots.extend(app_state.download_dir.as_deref().map(PathBuf::from));
+        roots.extend(
+            app_state
+                .library_settings
+                .games_links_dir
+                .as_deref()
+                .map(PathBuf::from),
+        );
+        for game in app_state.games.iter().flatten() {
+            roots.push(PathBuf::from(&game.path));
+            roots.extend(game.extracted_path.as_deref().map(PathBuf::from));
+        }
+    }
+    roots
+        .into_iter()
+        .filter(|root| !root.as_os_str().is_empty())
+        .filter_map(|root| root.canonicalize().ok())
+        .filter(|root| !too_wide(app, root))
+        .collect()
+}
+
+fn too_wide(app: &AppHandle, root: &Path) -> bool {
+    root.parent().is_none()
+        || app
+            .path()
+            .home_dir()
+            .is_ok_and(|home| home.canonicalize().is_ok_and(|home| home == root))
+}
+
+// `..` and links are resolved first; a path that does not exist yet is judged by its parent
+fn resolve(path: &Path) -> Option<PathBuf> {
+    path.canonicalize().ok().or_else(|| {
+        let parent = path.parent()?.canonicalize().ok()?;
+        Some(parent.join(path.file_name()?))
+    })
+}
+
+pub fn is_allowed(app: &AppHandle, path: &Path) -> bool {
+    let Some(path) = resolve(path) else {
+        return false;
+    };
+    allowed_roots(app).iter().any(|root| path.starts_with(root))
+}
+
+fn approved(app: &AppHandle, key: &str) -> bool {
+    app.state::<Mutex<ScopeApprovals>>()
+        .lock()
+        .is_ok_and(|approvals| approvals.0.contains(key))
+}
+
+// Asked through a native dialog rather than the webview, so a compromised page cannot answer it
+async fn ask(app: &AppHandle, key: String, message: String) -> Result<(), String> {
+    if approved(app, &key) {
+        return Ok(());
+    }
+    let dialog_app = app.clone();
+    let allowed = tauri::async_runtime::spawn_blocking(move || {
+        dialog_app
+            .dialog()
+            .message(message)
+            .title("Chanomhub")
+            .kind(MessageDialogKind::Warning)
+            .buttons(MessageDialogButtons::OkCancelCustom(
+                "Allow".to_string(),
+                "Cancel".to_string(),
+            ))
+            .blocking_show()
+    })
+    .await
+    .map_err(|e| format!("Failed to ask for permission: {}", e))?;
+    if !allowed {
+        return Err("Not allowed by the user".to_string());
+    }
+    if let Ok(mut approvals) = app.state::<Mutex<ScopeApprovals>>().lock() {
+        approvals.0.insert(key);
+    }
+    Ok(())
+}
+
+// `action` reads as a verb in the prompt: "delete", "open", "launch"
+pub async fn ensure_allowed(app: &AppHandle, action: &str, path: &Path) -> Result<(), String> {
+    if is_allowed(app, path) {
+        return Ok(());
+    }
+    println!(
+        "{} requested outside the managed folders: {}",
+        action,
+        path.display()
+    );
+    let resolved = resolve(path).unwrap_or_else(|| path.to_path_buf());
+    ask(
+        app,
+        format!("{}:{}", action, resolved.display()),
+        format!(
+            "The app was asked to {} a file outside the download and library folders:\n\n{}\n\nAllow this?",
+            action,
+            resolved.display()
+        ),
+    )
+    .await
+    .map_err(|e| format!("{} {}: {}", action, path.display(), e))
+}
+
+// For paths that become managed folders: the download folder, the library folder and game paths.
+// `what` names the folder in the prompt, e.g. "download folder".
+pub async fn confirm_root(app: &AppHandle, what: &str, path: &Path) -> Result<(), String> {
+    if is_allowed(app, path) {
+        return Ok(());
+    }
+    let resolved = resolve(path).unwrap_or_else(|| path.to_path_buf());
+    if too_wide(app, &resolved) {
+        return Err(format!(
+            "{} cannot be used as the {}, choose a folder inside it",
+            resolved.display(),
+            what
+        ));
+    }
+    ask(
+        app,
+        format!("root:{}", resolved.display()),
+        format!(
+            "The app was asked to use this as its {}:\n\n{}\n\nThe app can then open and delete files in it. Allow this?",
+            what,
+            resolved.display()
+        ),
+    )
+    .await
+    .map_err(|e| format!("Failed to change the {}: {}", what, e))
+}
+
+// Shell commands run as-is, so every new or changed one is confirmed once
+pub async fn ensure_commands_allowed(
+    app: &AppHandle,
+    launch_config: &LaunchConfig,
+    previous: Option<&LaunchConfig>,
+) -> Result<(), String> {
+    let commands = |config: Option<&LaunchConfig>| -> Vec<String> {
+        let Some(config) = config else {
+            return Vec::new();
+        };
+        let custom = (config.launch_method == "custom")
+            .then(|| config.custom_command.clone())
+            .flatten();
+        [custom, config.pre_launch.clone(), config.post_exit.clone()]
+            .into_iter()
+            .flatten()
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty())
+            .collect()
+    };
+    let known = commands(previous);
+    for command in commands(Some(launch_config)) {
+        if known.contains(&command) {
+            continue;
+        }
+        ask(
+            app,
+            format!("command:{}", command),
+            format!(
+                "The app was asked to run this command when launching a game:\n\n{}\n\nAllow this?",
+                command
+            ),
+        )
+        .await
+        .map_err(|e| format!("Command not saved: {}", e))?;
+    }
+    Ok(())
+}
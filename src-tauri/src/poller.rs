@@ -0,0 +1,78 @@
+use crate::{feeds, subscriptions};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+// Gives the app time to finish starting before the first request
+const STARTUP_DELAY: Duration = Duration::from_secs(30);
+
+// How often the intervals are looked at, so a changed setting applies within a minute
+const TICK: Duration = Duration::from_secs(60);
+
+// Background checks that share this task; they run one after another, so they never hit the
+// network at the same moment
+#[derive(Clone, Copy)]
+enum Source {
+    Subscriptions,
+    Feeds,
+}
+
+const SOURCES: [Source; 2] = [Source::Subscriptions, Source::Feeds];
+
+impl Source {
+    // Minutes between checks, 0 while there is nothing to poll
+    fn interval(self, app: &AppHandle) -> u64 {
+        match self {
+            Source::Subscriptions => subscriptions::poll_interval(app),
+            Source::Feeds => feeds::poll_interval(app),
+        }
+    }
+
+    async fn check(self, app: &AppHandle) -> Result<(), String> {
+        match self {
+            Source::Subscriptions => subscriptions::check(app).await.map(|_| ()),
+            Source::Feeds => feeds::check(app).await.map(|_| ()),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Source::Subscriptions => "Subscription",
+            Source::Feeds => "Feed",
+        }
+    }
+}
+
+// Settings check: 0 turns background polling off, anything else must be at least `min` minutes
+pub fn validate_interval(minutes: u64, min: u64) -> Result<(), String> {
+    if minutes != 0 && minutes < min {
+        return Err(format!(
+            "Poll interval must be 0 (off) or at least {} minutes",
+            min
+        ));
+    }
+    Ok(())
+}
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+        let mut last_run: [Option<Instant>; SOURCES.len()] = [None; SOURCES.len()];
+        loop {
+            for (source, last) in SOURCES.iter().zip(last_run.iter_mut()) {
+                let minutes = source.interval(&app);
+                if minutes == 0 {
+                    continue;
+                }
+                let due = last.is_none_or(|at| at.elapsed() >= Duration::from_secs(minutes * 60));
+                if !due {
+                    continue;
+                }
+                *last = Some(Instant::now());
+                if let Err(e) = source.check(&app).await {
+                    println!("{} check failed: {}", source.name(), e);
+                }
+            }
+            tokio::time::sleep(TICK).await;
+        }
+    });
+}
@@ -0,0 +1,143 @@
+use crate::state::LaunchConfig;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command as StdCommand;
+
+const SAVES_FOLDER_NAME: &str = "ChanomhubSaves";
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct PortableSaves {
+    pub enabled: bool,
+
+    // Windows only: save folders the game hardcodes (e.g. "%USERPROFILE%\Documents\My Games\Foo"),
+    // moved into the saves folder and replaced by a junction before launch
+    #[serde(rename = "savePaths")]
+    pub save_paths: Vec<String>,
+}
+
+// Kept next to the executable so saves move, and get backed up, together with the game
+pub fn saves_dir(launch_config: &LaunchConfig) -> Option<PathBuf> {
+    Path::new(&launch_config.executable_path)
+        .parent()
+        .map(|dir| dir.join(SAVES_FOLDER_NAME))
+}
+
+// Per-game prefix used by wine launches with portable saves on
+pub fn wine_prefix(launch_config: &LaunchConfig) -> Option<PathBuf> {
+    let enabled = launch_config
+        .portable_saves
+        .as_ref()
+        .is_some_and(|p| p.enabled);
+    if !enabled || launch_config.launch_method != "wine" || cfg!(target_os = "windows") {
+        return None;
+    }
+    saves_dir(launch_config).map(|root| root.join("wineprefix"))
+}
+
+fn create_dir(path: &Path) -> Result<(), String> {
+    fs::create_dir_all(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))
+}
+
+// Points the game's per-user folders into the saves folder; a no-op unless enabled for this game
+pub fn apply(command: &mut StdCommand, launch_config: &LaunchConfig) -> Result<(), String> {
+    let Some(portable) = launch_config.portable_saves.as_ref().filter(|p| p.enabled) else {
+        return Ok(());
+    };
+    let root = saves_dir(launch_config).ok_or("Executable has no parent folder")?;
+
+    #[cfg(target_os = "windows")]
+    {
+        let roaming = root.join("AppData").join("Roaming");
+        let local = root.join("AppData").join("Local");
+        create_dir(&roaming)?;
+        create_dir(&local)?;
+        // Junctions first: their paths are resolved against the real, not the redirected, folders
+        link_save_paths(&root, &portable.save_paths)?;
+        command.env("APPDATA", &roaming).env("LOCALAPPDATA", &local);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        if !portable.save_paths.is_empty() {
+            println!("Save path links are only used on Windows, ignoring them");
+        }
+        if let Some(prefix) = wine_prefix(launch_config) {
+            // A prefix per game holds its whole C:\users tree, Documents and AppData included
+            create_dir(&prefix)?;
+            command.env("WINEPREFIX", &prefix);
+        } else {
+            let data = root.join("data");
+            let config = root.join("config");
+            create_dir(&data)?;
+            create_dir(&config)?;
+            command
+                .env("XDG_DATA_HOME", &data)
+                .env("XDG_CONFIG_HOME", &config);
+        }
+    }
+
+    println!(
+        "Redirecting saves for {} to {}",
+        launch_config.executable_path,
+        root.display()
+    );
+    Ok(())
+}
+
+// Expands %VAR% using the real environment of the app, not the redirected one
+#[cfg(target_os = "windows")]
+fn expand_env(path: &str) -> Result<PathBuf, String> {
+    let mut expanded = String::new();
+    let mut parts = path.split('%');
+    expanded.push_str(parts.next().unwrap_or_default());
+    let mut is_var = true;
+    for part in parts {
+        if is_var {
+            let value = std::env::var(part)
+                .map_err(|_| format!("Unknown variable %{}% in save path {}", part, path))?;
+            expanded.push_str(&value);
+        } else {
+            expanded.push_str(part);
+        }
+        is_var = !is_var;
+    }
+    Ok(PathBuf::from(expanded))
+}
+
+#[cfg(target_os = "windows")]
+fn link_save_paths(root: &Path, save_paths: &[String]) -> Result<(), String> {
+    for save_path in save_paths.iter().filter(|p| !p.trim().is_empty()) {
+        let real = expand_env(save_path.trim())?;
+        let name: String = save_path
+            .trim()
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        let target = root.join("links").join(name);
+        create_dir(&target)?;
+
+        if crate::gamelinks::is_link(&real) {
+            if crate::gamelinks::links_to(&real, &target) {
+                continue;
+            }
+            return Err(format!(
+                "{} is already a link to another folder",
+                real.display()
+            ));
+        }
+
+        // Existing saves are moved in, so the first portable launch starts where the player left off
+        if real.exists() {
+            crate::diskio::move_dir_contents(&real, &target)?;
+            fs::remove_dir(&real)
+                .map_err(|e| format!("Failed to remove {}: {}", real.display(), e))?;
+        } else if let Some(parent) = real.parent() {
+            create_dir(parent)?;
+        }
+        crate::gamelinks::create_link(&target, &real)?;
+        println!("Linked {} to {}", real.display(), target.display());
+    }
+    Ok(())
+}
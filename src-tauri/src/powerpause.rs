@@ -0,0 +1,112 @@
+use crate::downloader;
+use crate::launchguard::{PowerStatus, power_status};
+use crate::state::AppState;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+// Read by the extraction threads between progress steps
+static EXTRACTIONS_HELD: AtomicBool = AtomicBool::new(false);
+
+// Downloads paused because of the battery; only these are resumed on AC power
+#[derive(Default)]
+pub struct PowerPause {
+    paused: Vec<String>,
+    holding: bool,
+}
+
+// Called from an extraction's progress callback; blocks that thread while on low battery.
+// Extractions run by an external tool only report progress at the end, so they finish anyway.
+pub fn wait_while_held() {
+    while EXTRACTIONS_HELD.load(Ordering::Relaxed) {
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+// Held extractions are waiting for the charger, not working, so they should not keep the system awake
+pub fn extractions_held() -> bool {
+    EXTRACTIONS_HELD.load(Ordering::Relaxed)
+}
+
+fn should_hold(app: &AppHandle, status: &PowerStatus) -> bool {
+    let (enabled, threshold) = match app.state::<Mutex<AppState>>().lock() {
+        Ok(app_state) => (
+            app_state.download_settings.pause_on_battery,
+            app_state.download_settings.battery_threshold,
+        ),
+        Err(_) => return false,
+    };
+    // Without a percentage, being on battery at all counts as low
+    enabled && status.on_battery && status.percent.is_none_or(|p| p <= threshold)
+}
+
+async fn hold(app: &AppHandle, status: &PowerStatus) {
+    let first = !EXTRACTIONS_HELD.swap(true, Ordering::Relaxed);
+    // Downloads started while on battery are caught on the next check
+    let paused = downloader::pause_running(app, "battery").await;
+    if let Ok(mut power) = app.state::<Mutex<PowerPause>>().lock() {
+        power.holding = true;
+        power.paused.extend(paused.iter().cloned());
+    }
+    if !first && paused.is_empty() {
+        return;
+    }
+    println!(
+        "On battery ({:?}%), paused {} downloads and held extractions",
+        status.percent,
+        paused.len()
+    );
+    let _ = app.emit(
+        "power-paused",
+        &serde_json::json!({
+            "reason": "battery",
+            "status": status,
+            "ids": paused
+        }),
+    );
+}
+
+async fn release(app: &AppHandle, status: &PowerStatus) {
+    EXTRACTIONS_HELD.store(false, Ordering::Relaxed);
+    let ids = match app.state::<Mutex<PowerPause>>().lock() {
+        Ok(mut power) if power.holding => {
+            power.holding = false;
+            std::mem::take(&mut power.paused)
+        }
+        _ => return,
+    };
+    let resumed = downloader::unpause_all(app, ids, "battery").await;
+    println!(
+        "Power restored, resumed {} downloads and extractions",
+        resumed.len()
+    );
+    let _ = app.emit(
+        "power-resumed",
+        &serde_json::json!({
+            "status": status,
+            "ids": resumed
+        }),
+    );
+}
+
+// Follows the power source in the background; the setting is re-read every round
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let status = tauri::async_runtime::spawn_blocking(power_status)
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or_default();
+            if should_hold(&app, &status) {
+                hold(&app, &status).await;
+            } else {
+                release(&app, &status).await;
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,170 @@
+use crate::state::{AppState, DownloadedGameInfo, save_active_downloads_to_file};
+use crate::{ActiveDownloads, DownloadInfo, undo};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+// Give startup and the first downloads a moment before anything is cleaned up
+const STARTUP_DELAY: Duration = Duration::from_secs(5 * 60);
+const RUN_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct RetentionSettings {
+    // Completed downloads older than this are removed from the list; 0 keeps them forever
+    #[serde(rename = "maxAgeDays")]
+    pub max_age_days: u64,
+    // Also move their archives to the trash (recoverable through undo until it is purged)
+    #[serde(rename = "deleteArchives")]
+    pub delete_archives: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionItem {
+    pub id: String,
+    pub filename: String,
+    pub downloaded_at: String,
+    pub age_days: i64,
+    // Set when the archive would be moved to the trash
+    pub archive_path: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPlan {
+    pub max_age_days: u64,
+    pub delete_archives: bool,
+    pub items: Vec<RetentionItem>,
+    // Old enough but kept because a library game still uses them
+    pub kept_installed: usize,
+}
+
+pub fn settings(app: &AppHandle) -> RetentionSettings {
+    app.state::<Mutex<AppState>>()
+        .lock()
+        .map(|app_state| app_state.retention_settings.clone())
+        .unwrap_or_default()
+}
+
+fn is_installed(download: &DownloadInfo, games: &[DownloadedGameInfo]) -> bool {
+    games.iter().any(|game| {
+        game.id == download.id
+            || download.path.as_deref() == Some(game.path.as_str())
+            || (download.extracted_path.is_some() && download.extracted_path == game.extracted_path)
+    })
+}
+
+// Same selection for the preview and the real run, so the preview is exactly what would happen
+pub fn plan(app: &AppHandle) -> Result<RetentionPlan, String> {
+    let settings = settings(app);
+    let mut plan = RetentionPlan {
+        max_age_days: settings.max_age_days,
+        delete_archives: settings.delete_archives,
+        items: Vec::new(),
+        kept_installed: 0,
+    };
+    if settings.max_age_days == 0 {
+        return Ok(plan);
+    }
+
+    let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+    let downloads = active_downloads
+        .read()
+        .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+    let games = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .games
+        .clone()
+        .unwrap_or_default();
+
+    let now = chrono::Utc::now();
+    for download in downloads
+        .downloads
+        .values()
+        .filter(|d| d.status == "completed")
+    {
+        let Some(downloaded_at) = download
+            .downloaded_at
+            .as_deref()
+            .and_then(|at| chrono::DateTime::parse_from_rfc3339(at).ok())
+        else {
+            continue;
+        };
+        let age_days = (now - downloaded_at.with_timezone(&chrono::Utc)).num_days();
+        if age_days < settings.max_age_days as i64 {
+            continue;
+        }
+        if is_installed(download, &games) {
+            plan.kept_installed += 1;
+            continue;
+        }
+        plan.items.push(RetentionItem {
+            id: download.id.clone(),
+            filename: download.filename.clone(),
+            downloaded_at: downloaded_at.to_rfc3339(),
+            age_days,
+            archive_path: download
+                .path
+                .clone()
+                .filter(|p| settings.delete_archives && !p.is_empty() && Path::new(p).is_file()),
+        });
+    }
+    plan.items
+        .sort_by_key(|item| std::cmp::Reverse(item.age_days));
+    Ok(plan)
+}
+
+pub fn run(app: &AppHandle) -> Result<RetentionPlan, String> {
+    let plan = plan(app)?;
+    if plan.items.is_empty() {
+        return Ok(plan);
+    }
+
+    let mut journal = undo::new_entry("retention_cleanup");
+    {
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        let mut downloads = active_downloads
+            .write()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        for item in &plan.items {
+            journal
+                .downloads
+                .extend(downloads.downloads.remove(&item.id));
+        }
+        save_active_downloads_to_file(app, &downloads)?;
+    }
+    for path in plan.items.iter().filter_map(|i| i.archive_path.as_deref()) {
+        if let Err(e) = undo::trash(&mut journal, Path::new(path)) {
+            println!("Failed to remove old archive: {}", e);
+        }
+    }
+    undo::record(app, journal);
+
+    println!(
+        "Retention cleanup removed {} completed downloads older than {} days",
+        plan.items.len(),
+        plan.max_age_days
+    );
+    let _ = app.emit("retention-cleanup", &plan);
+    Ok(plan)
+}
+
+// Runs in the background; the setting is re-read every round so changes apply without a restart
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+        loop {
+            if settings(&app).max_age_days > 0
+                && let Err(e) = run(&app)
+            {
+                println!("Retention cleanup failed: {}", e);
+            }
+            tokio::time::sleep(RUN_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,257 @@
+use crate::nowplaying::GameSession;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::process::Command as StdCommand;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+// Games launched from the app, by game id. `pid` is None while hooks run and the game is starting.
+#[derive(Default)]
+pub struct RunningGames {
+    games: HashMap<String, Option<u32>>,
+    // What the now-playing status shows for the spawned games, in launch order
+    sessions: Vec<GameSession>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchResult {
+    // "launched" or "alreadyRunning"
+    pub status: String,
+    pub pid: Option<u32>,
+    // Whether the running instance's window was brought to the front
+    pub focused: bool,
+}
+
+// launch_game_by_slug's answer: which installed game the slug matched, and what happened to it
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SlugLaunchResult {
+    pub game_id: String,
+    #[serde(flatten)]
+    pub result: LaunchResult,
+}
+
+impl LaunchResult {
+    pub fn launched(pid: u32) -> Self {
+        LaunchResult {
+            status: "launched".to_string(),
+            pid: Some(pid),
+            focused: false,
+        }
+    }
+}
+
+async fn already_running(pid: Option<u32>) -> LaunchResult {
+    let focused = match pid {
+        Some(pid) => tauri::async_runtime::spawn_blocking(move || focus(pid))
+            .await
+            .unwrap_or(false),
+        None => false,
+    };
+    LaunchResult {
+        status: "alreadyRunning".to_string(),
+        pid,
+        focused,
+    }
+}
+
+// Holds the game's slot from the check until the game is spawned; dropping it frees the slot
+pub struct Claim {
+    app: AppHandle,
+    game_id: String,
+    committed: bool,
+}
+
+impl Claim {
+    // Keeps the slot until `exited` fires
+    pub fn commit(mut self, session: GameSession, exited: CancellationToken) {
+        self.committed = true;
+        let pid = session.pid;
+        if let Ok(mut running) = self.app.state::<Mutex<RunningGames>>().lock() {
+            running.games.insert(self.game_id.clone(), Some(pid));
+            running.sessions.push(session);
+        }
+        let app = self.app.clone();
+        let game_id = self.game_id.clone();
+        tauri::async_runtime::spawn(async move {
+            exited.cancelled().await;
+            release(&app, &game_id, pid);
+        });
+    }
+}
+
+// Frees the slot of a game that exited; a newer launch of the same game keeps its own
+pub fn release(app: &AppHandle, game_id: &str, pid: u32) {
+    if let Ok(mut running) = app.state::<Mutex<RunningGames>>().lock() {
+        if running.games.get(game_id) == Some(&Some(pid)) {
+            running.games.remove(game_id);
+        }
+        running
+            .sessions
+            .retain(|s| !(s.game_id == game_id && s.pid == pid));
+    }
+}
+
+pub fn sessions(app: &AppHandle) -> Vec<GameSession> {
+    app.state::<Mutex<RunningGames>>()
+        .lock()
+        .map(|running| running.sessions.clone())
+        .unwrap_or_default()
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+        if let Ok(mut running) = self.app.state::<Mutex<RunningGames>>().lock() {
+            running.games.remove(&self.game_id);
+        }
+    }
+}
+
+fn tracked(app: &AppHandle, game_id: &str) -> Option<Option<u32>> {
+    app.state::<Mutex<RunningGames>>()
+        .lock()
+        .ok()
+        .and_then(|running| running.games.get(game_id).copied())
+}
+
+// Err carries the result to hand back instead of launching a second copy
+pub async fn claim(
+    app: &AppHandle,
+    game_id: &str,
+    executable_path: &str,
+) -> Result<Claim, LaunchResult> {
+    if let Some(pid) = tracked(app, game_id) {
+        println!("{} is already running (pid {:?})", game_id, pid);
+        return Err(already_running(pid).await);
+    }
+
+    // Started outside the app, or before it was restarted
+    let path = executable_path.to_string();
+    if let Ok(Some(pid)) = tauri::async_runtime::spawn_blocking(move || find_process(&path)).await {
+        println!("{} is already running as pid {}", executable_path, pid);
+        return Err(already_running(Some(pid)).await);
+    }
+
+    // Checked again under the lock, a second click may have claimed it during the scan
+    let existing = match app.state::<Mutex<RunningGames>>().lock() {
+        Ok(mut running) => match running.games.get(game_id).copied() {
+            Some(pid) => Some(pid),
+            None => {
+                running.games.insert(game_id.to_string(), None);
+                None
+            }
+        },
+        Err(e) => {
+            // Tracking is best effort, a broken lock should not stop the game
+            println!("Failed to lock running games: {}", e);
+            None
+        }
+    };
+    match existing {
+        Some(pid) => Err(already_running(pid).await),
+        None => Ok(Claim {
+            app: app.clone(),
+            game_id: game_id.to_string(),
+            committed: false,
+        }),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn powershell(script: &str) -> Option<String> {
+    use std::os::windows::process::CommandExt;
+
+    let output = StdCommand::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn find_process(executable_path: &str) -> Option<u32> {
+    let script = format!(
+        "Get-Process | Where-Object {{ $_.Path -eq '{}' }} | Select-Object -First 1 -ExpandProperty Id",
+        executable_path.replace('\'', "''")
+    );
+    powershell(&script)?.parse().ok()
+}
+
+// Native programs match on /proc/<pid>/exe; Wine and interpreters carry the game as an argument
+#[cfg(not(target_os = "windows"))]
+fn find_process(executable_path: &str) -> Option<u32> {
+    use std::path::PathBuf;
+
+    let target =
+        std::fs::canonicalize(executable_path).unwrap_or_else(|_| PathBuf::from(executable_path));
+    let target_str = target.to_string_lossy().to_string();
+    let matches_arg = |arg: &[u8]| {
+        let arg = String::from_utf8_lossy(arg).replace('\\', "/");
+        let arg = match arg.get(..2) {
+            Some(drive) if drive.eq_ignore_ascii_case("z:") => &arg[2..],
+            _ => arg.as_str(),
+        };
+        arg == target_str
+    };
+    let own_pid = std::process::id();
+    for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if pid == own_pid {
+            continue;
+        }
+        if std::fs::read_link(entry.path().join("exe")).is_ok_and(|exe| exe == target) {
+            return Some(pid);
+        }
+        if let Ok(cmdline) = std::fs::read(entry.path().join("cmdline"))
+            && cmdline.split(|b| *b == 0).skip(1).any(matches_arg)
+        {
+            return Some(pid);
+        }
+    }
+    None
+}
+
+#[cfg(target_os = "windows")]
+fn focus(pid: u32) -> bool {
+    powershell(&format!(
+        "(New-Object -ComObject WScript.Shell).AppActivate({})",
+        pid
+    ))
+    .is_some_and(|out| out.eq_ignore_ascii_case("true"))
+}
+
+// Needs xdotool; Wayland compositors generally do not allow it
+#[cfg(not(target_os = "windows"))]
+fn focus(pid: u32) -> bool {
+    if crate::archivetools::find_in_path(&["xdotool"]).is_none() {
+        return false;
+    }
+    StdCommand::new("xdotool")
+        .args([
+            "search",
+            "--onlyvisible",
+            "--pid",
+            &pid.to_string(),
+            "windowactivate",
+        ])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
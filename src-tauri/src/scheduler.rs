@@ -0,0 +1,398 @@
+use crate::downloader::{self, TransferOptions};
+use crate::state::{AppState, ArticleMeta, save_active_downloads_to_file};
+use crate::{ActiveDownloads, DownloadInfo, offline, timeline};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+
+// Status of a download waiting for a free slot, and what downloader::start returns for it
+pub const QUEUED: &str = "queued";
+
+// How long a bandwidth share is kept before it is worked out again from the running downloads
+const SHARE_WINDOW: Duration = Duration::from_secs(1);
+
+static FILLING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl Priority {
+    // Relative share of the speed limit
+    fn weight(self) -> u64 {
+        match self {
+            Priority::Low => 1,
+            Priority::Normal => 2,
+            Priority::High => 4,
+        }
+    }
+}
+
+// Transfers paused to make room for a bumped download; they continue instead of starting over
+#[derive(Default)]
+pub struct Scheduler {
+    preempted: HashSet<String>,
+}
+
+fn is_moving(download: &DownloadInfo) -> bool {
+    matches!(
+        download.status.as_str(),
+        "starting" | "downloading" | "transferring"
+    )
+}
+
+fn limits(app: &AppHandle) -> (usize, u64) {
+    app.state::<Mutex<AppState>>()
+        .lock()
+        .map(|app_state| {
+            let settings = &app_state.download_settings;
+            (settings.max_active_downloads, settings.speed_limit_kbps)
+        })
+        .unwrap_or_default()
+}
+
+fn free_slots(app: &AppHandle, downloads: &ActiveDownloads, download_id: &str) -> usize {
+    let (max_active, _) = limits(app);
+    if max_active == 0 {
+        return usize::MAX;
+    }
+    let moving = downloads
+        .downloads
+        .values()
+        .filter(|d| d.id != download_id && is_moving(d))
+        .count();
+    max_active.saturating_sub(moving)
+}
+
+// Checked by downloader::start; a download without a free slot is registered as queued instead.
+// Returns true when it was queued.
+pub fn queue_if_full(
+    app: &AppHandle,
+    url: &str,
+    filename: &str,
+    download_id: &str,
+    article: &Option<ArticleMeta>,
+    referer: &Option<String>,
+    options: TransferOptions,
+) -> Result<bool, String> {
+    let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+    let mut downloads = active_downloads
+        .write()
+        .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+    if free_slots(app, &downloads, download_id) > 0 {
+        return Ok(false);
+    }
+    downloads.downloads.insert(
+        download_id.to_string(),
+        DownloadInfo {
+            id: download_id.to_string(),
+            filename: filename.to_string(),
+            url: url.to_string(),
+            status: QUEUED.to_string(),
+            article: article.clone(),
+            referer: referer.clone(),
+            priority: options.priority,
+            segments: options.segments,
+            queued_at: Some(chrono::Utc::now().to_rfc3339()),
+            ..Default::default()
+        },
+    );
+    // Lets cancel_active_download take it off the queue like any running download
+    downloads
+        .tokens
+        .insert(download_id.to_string(), CancellationToken::new());
+    save_active_downloads_to_file(app, &downloads)?;
+    drop(downloads);
+    println!("Queued download {} until a slot is free", download_id);
+    timeline::record(app, download_id, "download", QUEUED, "scheduler", Some(url));
+    let _ = app.emit(
+        "download-queued",
+        &serde_json::json!({ "id": download_id, "priority": options.priority }),
+    );
+    Ok(true)
+}
+
+enum Next {
+    Start(Box<DownloadInfo>),
+    Continue(String),
+}
+
+// Highest priority first, then whoever has waited longest
+fn next_waiting(app: &AppHandle) -> Option<Next> {
+    // Offline, downloader::start would only move them to the offline queue
+    if offline::is_offline(app) {
+        return None;
+    }
+    let preempted = app
+        .state::<Mutex<Scheduler>>()
+        .lock()
+        .map(|scheduler| scheduler.preempted.clone())
+        .unwrap_or_default();
+    let downloads = app.state::<RwLock<ActiveDownloads>>();
+    let downloads = downloads.read().ok()?;
+    if free_slots(app, &downloads, "") == 0 {
+        return None;
+    }
+    let download = downloads
+        .downloads
+        .values()
+        .filter(|d| d.status == QUEUED || (d.status == "paused" && preempted.contains(&d.id)))
+        .max_by(|a, b| {
+            a.priority
+                .cmp(&b.priority)
+                .then_with(|| b.queued_at.cmp(&a.queued_at))
+        })?;
+    Some(if download.status == QUEUED {
+        Next::Start(Box::new(download.clone()))
+    } else {
+        Next::Continue(download.id.clone())
+    })
+}
+
+// Starts queued downloads while slots are free. Runs after a download finishes, when the limit or
+// a priority changes, and at startup.
+pub async fn fill_slots(app: AppHandle) {
+    if FILLING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    loop {
+        fill(&app).await;
+        FILLING.store(false, Ordering::SeqCst);
+        // A slot freed after the last look found FILLING still set and left the work to this call
+        if next_waiting(&app).is_none() || FILLING.swap(true, Ordering::SeqCst) {
+            break;
+        }
+    }
+}
+
+async fn fill(app: &AppHandle) {
+    while let Some(next) = next_waiting(app) {
+        let (download_id, result) = match next {
+            Next::Start(download) => {
+                let options = downloader::options_of(&download);
+                let result = downloader::start(
+                    app,
+                    download.url,
+                    download.filename,
+                    download.id.clone(),
+                    download.article,
+                    download.referer,
+                    options,
+                )
+                .await
+                .map(|_| ());
+                (download.id, result)
+            }
+            Next::Continue(download_id) => {
+                if let Ok(mut scheduler) = app.state::<Mutex<Scheduler>>().lock() {
+                    scheduler.preempted.remove(&download_id);
+                }
+                let result = downloader::unpause(app, &download_id).await;
+                (download_id, result)
+            }
+        };
+        if let Err(e) = result {
+            println!("Failed to start queued download {}: {}", download_id, e);
+            mark_failed(app, &download_id, e);
+        }
+    }
+}
+
+// A queued download that cannot start must leave the queue, or the scheduler would pick it forever
+fn mark_failed(app: &AppHandle, download_id: &str, error: String) {
+    let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+    let Ok(mut downloads) = active_downloads.write() else {
+        return;
+    };
+    if let Some(download) = downloads.downloads.get_mut(download_id)
+        && matches!(download.status.as_str(), QUEUED | "paused")
+    {
+        download.status = "failed".to_string();
+        download.error = Some(error.clone());
+        let _ = save_active_downloads_to_file(app, &downloads);
+        let _ = app.emit(
+            "download-error",
+            &serde_json::json!({ "id": download_id, "error": error }),
+        );
+    }
+}
+
+// A paused transfer resumed while every slot is taken continues once one frees up.
+// Returns true when it has to wait.
+pub fn wait_for_slot(app: &AppHandle, download_id: &str) -> Result<bool, String> {
+    let full = {
+        let downloads = app.state::<RwLock<ActiveDownloads>>();
+        let downloads = downloads
+            .read()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        free_slots(app, &downloads, download_id) == 0
+    };
+    if full {
+        app.state::<Mutex<Scheduler>>()
+            .lock()
+            .map_err(|e| format!("Failed to lock scheduler: {}", e))?
+            .preempted
+            .insert(download_id.to_string());
+        println!("Download {} continues once a slot is free", download_id);
+    }
+    Ok(full)
+}
+
+pub fn spawn_fill(app: &AppHandle) {
+    tauri::async_runtime::spawn(fill_slots(app.clone()));
+}
+
+pub fn forget(app: &AppHandle, download_id: &str) {
+    if let Ok(mut scheduler) = app.state::<Mutex<Scheduler>>().lock() {
+        scheduler.preempted.remove(download_id);
+    }
+}
+
+pub fn set_priority(app: &AppHandle, download_id: &str, priority: Priority) -> Result<(), String> {
+    {
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        let mut downloads = active_downloads
+            .write()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        downloads
+            .downloads
+            .get_mut(download_id)
+            .ok_or_else(|| format!("No download found for id: {}", download_id))?
+            .priority = priority;
+        save_active_downloads_to_file(app, &downloads)?;
+    }
+    let _ = app.emit(
+        "download-priority-changed",
+        &serde_json::json!({ "id": download_id, "priority": priority }),
+    );
+    spawn_fill(app);
+    Ok(())
+}
+
+// Running transfer with the lowest priority below `priority`, the most recently started one on ties
+fn lowest_running(app: &AppHandle, priority: Priority) -> Option<String> {
+    let downloads = app.state::<RwLock<ActiveDownloads>>();
+    let downloads = downloads.read().ok()?;
+    downloads
+        .downloads
+        .values()
+        .filter(|d| d.status == "downloading" && d.priority < priority)
+        .filter(|d| matches!(d.provider.as_deref(), Some("webview2" | "native")))
+        .min_by(|a, b| {
+            a.priority
+                .cmp(&b.priority)
+                .then_with(|| b.started_at.cmp(&a.started_at))
+        })
+        .map(|d| d.id.clone())
+}
+
+// Raises a download to high priority. A queued download with no free slot takes one from the
+// lowest-priority running transfer, which is paused and continues once a slot frees up again.
+// Returns the id of the transfer that was paused, if any.
+pub async fn bump(app: &AppHandle, download_id: &str) -> Result<Option<String>, String> {
+    set_priority(app, download_id, Priority::High)?;
+    let waiting = app
+        .state::<RwLock<ActiveDownloads>>()
+        .read()
+        .map_err(|e| format!("Failed to lock active downloads: {}", e))?
+        .downloads
+        .get(download_id)
+        .is_some_and(|d| d.status == QUEUED);
+    let full = {
+        let downloads = app.state::<RwLock<ActiveDownloads>>();
+        let downloads = downloads
+            .read()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        free_slots(app, &downloads, download_id) == 0
+    };
+    if !waiting || !full {
+        return Ok(None);
+    }
+    let Some(victim) = lowest_running(app, Priority::High) else {
+        return Ok(None);
+    };
+    downloader::pause(app, &victim, "priority").await?;
+    {
+        // The helper confirms its pause later, so the slot is taken back here already
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        let mut downloads = active_downloads
+            .write()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        if let Some(download) = downloads.downloads.get_mut(&victim) {
+            download.status = "paused".to_string();
+        }
+    }
+    app.state::<Mutex<Scheduler>>()
+        .lock()
+        .map_err(|e| format!("Failed to lock scheduler: {}", e))?
+        .preempted
+        .insert(victim.clone());
+    println!(
+        "Paused download {} to make room for {}",
+        victim, download_id
+    );
+    let _ = app.emit(
+        "download-preempted",
+        &serde_json::json!({ "id": victim, "by": download_id }),
+    );
+    fill_slots(app.clone()).await;
+    Ok(Some(victim))
+}
+
+// Per-transfer speed cap for native downloads: the global limit split by priority weight
+pub struct Throttle {
+    window_start: Instant,
+    window_bytes: u64,
+    bytes_per_sec: Option<u64>,
+}
+
+impl Throttle {
+    pub fn new(app: &AppHandle, download_id: &str) -> Self {
+        Self {
+            window_start: Instant::now(),
+            window_bytes: 0,
+            bytes_per_sec: share(app, download_id),
+        }
+    }
+
+    pub async fn wait(&mut self, app: &AppHandle, download_id: &str, bytes: usize) {
+        self.window_bytes += bytes as u64;
+        if let Some(rate) = self.bytes_per_sec.filter(|r| *r > 0) {
+            let due = Duration::from_secs_f64(self.window_bytes as f64 / rate as f64);
+            let elapsed = self.window_start.elapsed();
+            if due > elapsed {
+                tokio::time::sleep(due - elapsed).await;
+            }
+        }
+        if self.window_start.elapsed() >= SHARE_WINDOW {
+            *self = Self::new(app, download_id);
+        }
+    }
+}
+
+fn share(app: &AppHandle, download_id: &str) -> Option<u64> {
+    let (_, limit_kbps) = limits(app);
+    if limit_kbps == 0 {
+        return None;
+    }
+    let downloads = app.state::<RwLock<ActiveDownloads>>();
+    let downloads = downloads.read().ok()?;
+    let own = downloads.downloads.get(download_id)?.priority.weight();
+    // Only native transfers can be slowed down, so the limit is split between them alone
+    let total: u64 = downloads
+        .downloads
+        .values()
+        .filter(|d| is_moving(d) && d.provider.as_deref() == Some("native"))
+        .map(|d| d.priority.weight())
+        .sum();
+    Some(limit_kbps * 1024 * own / total.max(own))
+}
@@ -0,0 +1,319 @@
+use crate::state::{self, AppState};
+use crate::{cloudinary, pathscope};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
+
+const RECORD_FILE: &str = "screenshot_sync.json";
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(10);
+
+// A screenshot younger than this may still be being written by the game or overlay
+const SETTLE_TIME: Duration = Duration::from_secs(3);
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "gif", "bmp"];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadedScreenshot {
+    pub file: String,
+    // Modification time when uploaded; a file saved again under the same name is uploaded again
+    pub modified: u64,
+    pub url: String,
+    // Smaller variant for galleries and article previews, generated by Cloudinary on first request
+    #[serde(default)]
+    pub thumbnail_url: String,
+    pub uploaded_at: String,
+}
+
+// Per game: where its screenshots are and what was already uploaded, kept across restarts
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenshotGallery {
+    pub folder: String,
+    pub slug: String,
+    pub uploads: Vec<UploadedScreenshot>,
+}
+
+// Games watched in the background and games with an upload round in progress
+#[derive(Default)]
+pub struct ScreenshotSync {
+    watching: HashSet<String>,
+    syncing: HashSet<String>,
+}
+
+fn record_path(app: &AppHandle) -> Option<PathBuf> {
+    state::get_config_dir(app).map(|dir| dir.join(RECORD_FILE))
+}
+
+fn load_galleries(app: &AppHandle) -> HashMap<String, ScreenshotGallery> {
+    record_path(app)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_galleries(app: &AppHandle, galleries: &HashMap<String, ScreenshotGallery>) {
+    let Some(path) = record_path(app) else {
+        return;
+    };
+    // Written beside the record and renamed over it, so a crash mid-write keeps the old one
+    let temp = path.with_extension("json.tmp");
+    let result = serde_json::to_string_pretty(galleries)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(&temp, json).map_err(|e| e.to_string()))
+        .and_then(|()| fs::rename(&temp, &path).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        println!("Failed to save screenshot uploads: {}", e);
+    }
+}
+
+pub fn load_gallery(app: &AppHandle, game_id: &str) -> Option<ScreenshotGallery> {
+    let mut gallery = load_galleries(app).remove(game_id)?;
+    // Records from before thumbnails were kept get theirs from the upload URL
+    for upload in gallery
+        .uploads
+        .iter_mut()
+        .filter(|u| u.thumbnail_url.is_empty())
+    {
+        upload.thumbnail_url =
+            cloudinary::thumbnail_url(&upload.url).unwrap_or_else(|| upload.url.clone());
+    }
+    Some(gallery)
+}
+
+// Cloudinary public ids become folder paths, so only a safe subset of characters is kept
+fn safe_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+// "shot.png" and "shot.jpg", or names that only differ in characters safe_name replaces, would
+// otherwise overwrite each other in the same Cloudinary folder
+fn public_id(slug: &str, path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|s| safe_name(&s.to_string_lossy()))
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| safe_name(&e.to_string_lossy().to_lowercase()))
+        .unwrap_or_default();
+    let hash = hex::encode(Sha256::digest(path.to_string_lossy().as_bytes()));
+    format!("{}/{}_{}_{}", safe_name(slug), stem, extension, &hash[..8])
+}
+
+fn modified_secs(path: &Path) -> Option<(u64, Duration)> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let secs = modified
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    Some((secs, modified.elapsed().unwrap_or_default()))
+}
+
+// Screenshots not uploaded yet (or changed since), oldest first so the gallery keeps their order
+fn pending(gallery: &ScreenshotGallery) -> Result<Vec<(PathBuf, u64)>, String> {
+    let entries = fs::read_dir(&gallery.folder)
+        .map_err(|e| format!("Failed to read {}: {}", gallery.folder, e))?;
+    let mut files: Vec<(PathBuf, u64)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        })
+        .filter_map(|path| {
+            let (modified, age) = modified_secs(&path)?;
+            (age >= SETTLE_TIME).then_some((path, modified))
+        })
+        .filter(|(path, modified)| {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string());
+            !gallery
+                .uploads
+                .iter()
+                .any(|u| Some(&u.file) == name.as_ref() && u.modified == *modified)
+        })
+        .collect();
+    files.sort_by_key(|(_, modified)| *modified);
+    Ok(files)
+}
+
+async fn upload_pending(app: &AppHandle, game_id: &str) -> Result<ScreenshotGallery, String> {
+    let mut gallery = load_gallery(app, game_id)
+        .ok_or_else(|| format!("No screenshot folder set for {}", game_id))?;
+    let files = pending(&gallery)?;
+    if files.is_empty() {
+        return Ok(gallery);
+    }
+    let config = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .cloudinary
+        .clone()
+        .ok_or("Cloudinary config not set")?;
+
+    for (path, modified) in files {
+        let file = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let url = match cloudinary::upload_to_cloudinary(
+            path.to_string_lossy().to_string(),
+            Some(public_id(&gallery.slug, &path)),
+            &config,
+        )
+        .await
+        {
+            Ok(url) => url,
+            // One bad file should not hold back the rest; it is tried again next round
+            Err(e) => {
+                println!("Failed to upload screenshot {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        println!("Uploaded screenshot {} to {}", file, url);
+        let thumbnail_url = cloudinary::thumbnail_url(&url).unwrap_or_else(|| url.clone());
+        let _ = app.emit(
+            "screenshot-uploaded",
+            &serde_json::json!({
+                "gameId": game_id,
+                "file": file,
+                "url": url,
+                "thumbnailUrl": thumbnail_url
+            }),
+        );
+        gallery.uploads.retain(|u| u.file != file);
+        gallery.uploads.push(UploadedScreenshot {
+            file,
+            modified,
+            url,
+            thumbnail_url,
+            uploaded_at: chrono::Utc::now().to_rfc3339(),
+        });
+        // Saved after every upload so a crash mid-way does not upload the same files again
+        let mut galleries = load_galleries(app);
+        galleries.insert(game_id.to_string(), gallery.clone());
+        save_galleries(app, &galleries);
+    }
+    Ok(gallery)
+}
+
+// Only one upload round per game at a time, whether started by hand or by the watcher
+async fn sync_once(app: &AppHandle, game_id: &str) -> Result<ScreenshotGallery, String> {
+    {
+        let sync = app.state::<Mutex<ScreenshotSync>>();
+        let mut sync = sync
+            .lock()
+            .map_err(|e| format!("Failed to lock screenshot sync: {}", e))?;
+        if !sync.syncing.insert(game_id.to_string()) {
+            return Err("Screenshots of this game are already being uploaded".to_string());
+        }
+    }
+    let result = upload_pending(app, game_id).await;
+    if let Ok(mut sync) = app.state::<Mutex<ScreenshotSync>>().lock() {
+        sync.syncing.remove(game_id);
+    }
+    result
+}
+
+fn watch(app: AppHandle, game_id: String) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(WATCH_INTERVAL).await;
+            let watching = app
+                .state::<Mutex<ScreenshotSync>>()
+                .lock()
+                .is_ok_and(|sync| sync.watching.contains(&game_id));
+            if !watching {
+                break;
+            }
+            if let Err(e) = sync_once(&app, &game_id).await {
+                println!("Screenshot sync for {} failed: {}", game_id, e);
+            }
+        }
+        println!("Stopped watching screenshots of {}", game_id);
+    });
+}
+
+// Uploads new screenshots of a game to a Cloudinary folder named after its article slug.
+// The folder is remembered, so later calls can leave it out. With `keep_watching` the folder
+// is checked again every few seconds until stop() is called or the app exits.
+pub async fn sync(
+    app: &AppHandle,
+    game_id: &str,
+    folder: Option<String>,
+    keep_watching: bool,
+) -> Result<ScreenshotGallery, String> {
+    let slug = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .games
+        .as_ref()
+        .and_then(|games| games.iter().find(|g| g.id == game_id))
+        .ok_or_else(|| format!("Game with id {} not found", game_id))?
+        .article
+        .as_ref()
+        .map(|a| a.slug.clone())
+        .filter(|slug| !slug.is_empty())
+        .ok_or("This game is not linked to an article")?;
+
+    let folder = folder.filter(|f| !f.trim().is_empty());
+    if let Some(folder) = &folder {
+        if !Path::new(folder).is_dir() {
+            return Err(format!("Screenshot folder does not exist: {}", folder));
+        }
+        // Everything in the folder is uploaded, so one outside the managed folders is confirmed first
+        pathscope::ensure_allowed(app, "upload", Path::new(folder)).await?;
+    }
+
+    let mut galleries = load_galleries(app);
+    let gallery = galleries.entry(game_id.to_string()).or_default();
+    if let Some(folder) = folder {
+        gallery.folder = folder;
+    }
+    if gallery.folder.is_empty() {
+        return Err("No screenshot folder given".to_string());
+    }
+    gallery.slug = slug;
+    save_galleries(app, &galleries);
+
+    let gallery = sync_once(app, game_id).await?;
+    if keep_watching {
+        let started = app
+            .state::<Mutex<ScreenshotSync>>()
+            .lock()
+            .map_err(|e| format!("Failed to lock screenshot sync: {}", e))?
+            .watching
+            .insert(game_id.to_string());
+        if started {
+            println!("Watching {} for screenshots of {}", gallery.folder, game_id);
+            watch(app.clone(), game_id.to_string());
+        }
+    }
+    Ok(gallery)
+}
+
+pub fn stop(app: &AppHandle, game_id: &str) -> Result<bool, String> {
+    Ok(app
+        .state::<Mutex<ScreenshotSync>>()
+        .lock()
+        .map_err(|e| format!("Failed to lock screenshot sync: {}", e))?
+        .watching
+        .remove(game_id))
+}
@@ -0,0 +1,442 @@
+use crate::chunkhash::{self, ChunkManifest, ChunkVerifier, CorruptChunk};
+use crate::nativedownload::{self, PROGRESS_INTERVAL, Transfer};
+use crate::state::AppState;
+use crate::{ActiveDownloads, diskio, scheduler};
+use futures_util::StreamExt;
+use reqwest::StatusCode;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+
+// Kept next to the .part file while segments are outstanding
+const STATE_SUFFIX: &str = ".segments";
+
+pub const MAX_SEGMENTS: u8 = 16;
+
+// Smaller pieces cost more in requests than they gain in speed
+const MIN_SEGMENT_SIZE: u64 = 16 * 1024 * 1024;
+
+// A segment flushes and commits its position this often
+const COMMIT_BYTES: u64 = 4 * 1024 * 1024;
+
+// Committed positions reach the state file this often, so a crash resumes close to where it was
+const STATE_SAVE_INTERVAL: Duration = Duration::from_secs(2);
+
+const WRITE_BUFFER: usize = 1024 * 1024;
+
+const SEGMENT_RETRIES: u32 = 3;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Segment {
+    start: u64,
+    // Inclusive, as in the Range header
+    end: u64,
+    // Bytes of this segment already on disk
+    done: u64,
+}
+
+impl Segment {
+    fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SegmentState {
+    total: u64,
+    segments: Vec<Segment>,
+}
+
+fn state_path(part_path: &Path) -> PathBuf {
+    let mut path = part_path.as_os_str().to_owned();
+    path.push(STATE_SUFFIX);
+    PathBuf::from(path)
+}
+
+fn load_state(part_path: &Path) -> Option<SegmentState> {
+    let content = fs::read_to_string(state_path(part_path)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+// Written beside the record and renamed over it, so a crash mid-write keeps the previous one
+fn save_state(part_path: &Path, state: &SegmentState) {
+    let path = state_path(part_path);
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    let result = serde_json::to_string(state)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(&temp, json).map_err(|e| e.to_string()))
+        .and_then(|()| fs::rename(&temp, &path).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        println!(
+            "Failed to save segment state for {}: {}",
+            part_path.display(),
+            e
+        );
+    }
+}
+
+// True when a split download of this file can be continued. A record whose .part file is gone
+// (cancelled, or deleted by hand) is dropped so the file starts over.
+pub fn has_state(part_path: &Path) -> bool {
+    let path = state_path(part_path);
+    if !path.is_file() {
+        return false;
+    }
+    if part_path.is_file() && load_state(part_path).is_some() {
+        return true;
+    }
+    let _ = fs::remove_file(path);
+    false
+}
+
+// Bytes a split download has on disk; None when the file is not being downloaded in segments
+pub fn downloaded(part_path: &Path) -> Option<u64> {
+    load_state(part_path).map(|state| state.segments.iter().map(|s| s.done).sum())
+}
+
+fn segment_count(app: &AppHandle, download_id: &str) -> u8 {
+    let chosen = app
+        .state::<RwLock<ActiveDownloads>>()
+        .read()
+        .ok()
+        .and_then(|downloads| downloads.downloads.get(download_id)?.segments);
+    let count = chosen.unwrap_or_else(|| {
+        app.state::<Mutex<AppState>>()
+            .lock()
+            .map(|app_state| app_state.download_settings.segments)
+            .unwrap_or(1)
+    });
+    count.clamp(1, MAX_SEGMENTS)
+}
+
+pub fn worth_splitting(app: &AppHandle, download_id: &str, total: u64) -> bool {
+    segment_count(app, download_id) > 1 && total >= MIN_SEGMENT_SIZE * 2
+}
+
+fn plan(total: u64, count: u8, manifest: Option<&ChunkManifest>) -> Vec<Segment> {
+    let count = (total / MIN_SEGMENT_SIZE).clamp(1, u64::from(count));
+    let size = chunkhash::align(manifest, total.div_ceil(count));
+    (0..count)
+        .map(|i| i * size)
+        .filter(|start| *start < total)
+        .map(|start| Segment {
+            start,
+            end: (start + size).min(total) - 1,
+            done: 0,
+        })
+        .collect()
+}
+
+// Counters shared between a segment and the progress loop
+struct Progress {
+    // Received so far, including what is still in the write buffer
+    received: AtomicU64,
+    // Flushed to the file; this is what the state file records
+    committed: AtomicU64,
+}
+
+// What every segment of one download shares
+struct Job<'a> {
+    app: &'a AppHandle,
+    download_id: &'a str,
+    transfer: &'a Transfer,
+    token: &'a CancellationToken,
+    throttle: tokio::sync::Mutex<scheduler::Throttle>,
+    manifest: Option<Arc<ChunkManifest>>,
+}
+
+async fn fetch_range(job: &Job<'_>, segment: &Segment, progress: &Progress) -> Result<(), String> {
+    let transfer = job.transfer;
+    let committed = progress.committed.load(Ordering::SeqCst);
+    // Whatever did not reach the file last time is fetched again
+    progress.received.store(committed, Ordering::SeqCst);
+    if committed >= segment.len() {
+        return Ok(());
+    }
+    let from = segment.start + committed;
+    let mut verifier = match &job.manifest {
+        Some(manifest) => Some(ChunkVerifier::resume(
+            manifest.clone(),
+            &transfer.part_path,
+            segment.start,
+            from,
+        )?),
+        None => None,
+    };
+    let response = nativedownload::request(job.app, transfer)?
+        .header(RANGE, format!("bytes={}-{}", from, segment.end))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to request {}: {}", transfer.url, e))?;
+    if response.status() != StatusCode::PARTIAL_CONTENT {
+        return Err(format!(
+            "Host answered {} to a range request",
+            response.status()
+        ));
+    }
+    let start = response
+        .headers()
+        .get(CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("bytes "))
+        .and_then(|v| v.split('-').next())
+        .and_then(|v| v.parse::<u64>().ok());
+    if start != Some(from) {
+        return Err(format!(
+            "Host sent from byte {:?} instead of {}",
+            start, from
+        ));
+    }
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(&transfer.part_path)
+        .map_err(|e| format!("Failed to open {}: {}", transfer.part_path.display(), e))?;
+    file.seek(SeekFrom::Start(from))
+        .map_err(|e| format!("Failed to seek in {}: {}", transfer.part_path.display(), e))?;
+    let mut writer = BufWriter::with_capacity(WRITE_BUFFER, file);
+    let write_error =
+        |e: std::io::Error| format!("Failed to write {}: {}", transfer.part_path.display(), e);
+    // A bad chunk is fetched again from its first byte on the next attempt
+    let rewind = |writer: &mut BufWriter<File>, chunk: CorruptChunk| -> Result<(), String> {
+        chunkhash::report_corrupt(job.app, job.download_id, &chunk);
+        writer.flush().map_err(write_error)?;
+        let offset = chunk.start - segment.start;
+        progress.committed.store(offset, Ordering::SeqCst);
+        progress.received.store(offset, Ordering::SeqCst);
+        Err(chunk.message())
+    };
+
+    let mut stream = response.bytes_stream();
+    let mut unflushed = 0u64;
+    loop {
+        let chunk = tokio::select! {
+            _ = job.token.cancelled() => return Err("Download cancelled".to_string()),
+            chunk = stream.next() => chunk,
+        };
+        let Some(chunk) = chunk else {
+            break;
+        };
+        let chunk = chunk.map_err(|e| format!("Download interrupted: {}", e))?;
+        // Hosts that ignore the end of the range would overwrite the next segment
+        let room = segment.len() - progress.received.load(Ordering::SeqCst);
+        let chunk = &chunk[..chunk.len().min(room as usize)];
+        writer.write_all(chunk).map_err(write_error)?;
+        progress
+            .received
+            .fetch_add(chunk.len() as u64, Ordering::SeqCst);
+        if let Some(verifier) = &mut verifier
+            && let Err(corrupt) = verifier.update(chunk)
+        {
+            return rewind(&mut writer, corrupt);
+        }
+        unflushed += chunk.len() as u64;
+        job.throttle
+            .lock()
+            .await
+            .wait(job.app, job.download_id, chunk.len())
+            .await;
+        if unflushed >= COMMIT_BYTES {
+            writer.flush().map_err(write_error)?;
+            progress
+                .committed
+                .store(progress.received.load(Ordering::SeqCst), Ordering::SeqCst);
+            unflushed = 0;
+        }
+        if progress.received.load(Ordering::SeqCst) >= segment.len() {
+            break;
+        }
+    }
+    if let Some(verifier) = &mut verifier
+        && progress.received.load(Ordering::SeqCst) >= segment.len()
+        && let Err(corrupt) = verifier.finish()
+    {
+        return rewind(&mut writer, corrupt);
+    }
+    writer.flush().map_err(write_error)?;
+    writer.get_ref().sync_data().map_err(write_error)?;
+    let received = progress.received.load(Ordering::SeqCst);
+    progress.committed.store(received, Ordering::SeqCst);
+    if received < segment.len() {
+        return Err(format!(
+            "Connection closed after {} of {} bytes",
+            received,
+            segment.len()
+        ));
+    }
+    Ok(())
+}
+
+// One connection per segment; a dropped connection is retried from where that segment got to
+async fn fetch_segment(
+    job: &Job<'_>,
+    (number, segment): (usize, &Segment),
+    progress: &Progress,
+) -> Result<(), String> {
+    let mut attempt = 0;
+    loop {
+        match fetch_range(job, segment, progress).await {
+            Ok(()) => return Ok(()),
+            Err(e) if job.token.is_cancelled() => return Err(e),
+            Err(e) if attempt < SEGMENT_RETRIES => {
+                attempt += 1;
+                println!(
+                    "Segment {} of {} failed ({}), retrying",
+                    number + 1,
+                    job.download_id,
+                    e
+                );
+                tokio::time::sleep(Duration::from_secs(2 * u64::from(attempt))).await;
+            }
+            Err(e) => return Err(format!("Segment {}: {}", number + 1, e)),
+        }
+    }
+}
+
+// Copies each segment's committed position into the record; true when any of them moved
+fn record_committed(state: &mut SegmentState, progress: &[Progress]) -> bool {
+    let mut changed = false;
+    for (segment, progress) in state.segments.iter_mut().zip(progress) {
+        let committed = progress.committed.load(Ordering::SeqCst);
+        changed |= segment.done != committed;
+        segment.done = committed;
+    }
+    changed
+}
+
+fn start_fresh(
+    part_path: &Path,
+    total: u64,
+    count: u8,
+    manifest: Option<&ChunkManifest>,
+) -> Result<SegmentState, String> {
+    let file = File::create(part_path)
+        .map_err(|e| format!("Failed to create {}: {}", part_path.display(), e))?;
+    // Full size up front so every segment can write at its own offset
+    file.set_len(total)
+        .map_err(|e| format!("Failed to size {}: {}", part_path.display(), e))?;
+    let state = SegmentState {
+        total,
+        segments: plan(total, count, manifest),
+    };
+    save_state(part_path, &state);
+    Ok(state)
+}
+
+// Downloads the file over several connections, each writing its byte range straight into the
+// .part file, so there is nothing to merge once the last segment finishes. Progress is reported
+// for the whole file. `total` is None when continuing from the state file.
+pub async fn run(
+    app: &AppHandle,
+    download_id: &str,
+    transfer: &Transfer,
+    token: &CancellationToken,
+    total: Option<u64>,
+) -> Result<(), String> {
+    let manifest = chunkhash::for_download(app, download_id);
+    let mut state = match (load_state(&transfer.part_path), total) {
+        (Some(state), _) => state,
+        (None, Some(total)) => {
+            diskio::preflight(app, download_id, "download", &transfer.part_path, total)?;
+            start_fresh(
+                &transfer.part_path,
+                total,
+                segment_count(app, download_id),
+                manifest.as_deref(),
+            )?
+        }
+        (None, None) => return Err("No segment state to continue from".to_string()),
+    };
+    println!(
+        "Downloading {} in {} segments ({} bytes)",
+        download_id,
+        state.segments.len(),
+        state.total
+    );
+
+    let progress: Vec<Progress> = state
+        .segments
+        .iter()
+        .map(|s| Progress {
+            received: AtomicU64::new(s.done),
+            committed: AtomicU64::new(s.done),
+        })
+        .collect();
+    let job = Job {
+        app,
+        download_id,
+        transfer,
+        token,
+        throttle: tokio::sync::Mutex::new(scheduler::Throttle::new(app, download_id)),
+        manifest,
+    };
+    // The segment futures borrow a copy; `state` takes the committed positions once they stop
+    let segments = state.segments.clone();
+    let work = futures_util::future::try_join_all(
+        segments
+            .iter()
+            .enumerate()
+            .zip(&progress)
+            .filter(|((_, segment), _)| segment.done < segment.len())
+            .map(|(segment, progress)| fetch_segment(&job, segment, progress)),
+    );
+    tokio::pin!(work);
+
+    let total = state.total;
+    let mut ticker = tokio::time::interval(PROGRESS_INTERVAL);
+    let mut last_save = Instant::now();
+    let result = loop {
+        tokio::select! {
+            result = &mut work => break result.map(|_| ()),
+            _ = ticker.tick() => {
+                let received: u64 = progress
+                    .iter()
+                    .map(|p| p.received.load(Ordering::SeqCst))
+                    .sum();
+                let active = segments
+                    .iter()
+                    .zip(&progress)
+                    .filter(|(s, p)| p.received.load(Ordering::SeqCst) < s.len())
+                    .count();
+                nativedownload::report(
+                    app,
+                    serde_json::json!({
+                        "status": "progress",
+                        "downloadId": download_id,
+                        "progress": received as f64 / total as f64 * 100.0,
+                        "bytesReceived": received,
+                        "totalBytes": total,
+                        "segments": segments.len(),
+                        "activeSegments": active
+                    }),
+                )
+                .await;
+                if last_save.elapsed() >= STATE_SAVE_INTERVAL && record_committed(&mut state, &progress) {
+                    save_state(&transfer.part_path, &state);
+                    last_save = Instant::now();
+                }
+            }
+        }
+    };
+
+    record_committed(&mut state, &progress);
+    match result {
+        Ok(()) => {
+            let _ = fs::remove_file(state_path(&transfer.part_path));
+            Ok(())
+        }
+        Err(e) => {
+            save_state(&transfer.part_path, &state);
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use std::process::Command as StdCommand;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SessionLimit {
+    // Notification after this many minutes of play, None disables it
+    #[serde(rename = "reminderMinutes")]
+    pub reminder_minutes: Option<u64>,
+
+    // Game is closed after this many minutes, None disables it
+    #[serde(rename = "hardLimitMinutes")]
+    pub hard_limit_minutes: Option<u64>,
+}
+
+impl SessionLimit {
+    pub fn is_active(&self) -> bool {
+        self.reminder_minutes.is_some_and(|m| m > 0)
+            || self.hard_limit_minutes.is_some_and(|m| m > 0)
+    }
+}
+
+// Closes the game and whatever it started (launchers, wine, sh -c wrappers)
+fn terminate(pid: u32) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    let status = StdCommand::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T", "/F"])
+        .status();
+    #[cfg(not(target_os = "windows"))]
+    let status = {
+        let _ = StdCommand::new("pkill")
+            .args(["-TERM", "-P", &pid.to_string()])
+            .status();
+        StdCommand::new("kill")
+            .args(["-TERM", &pid.to_string()])
+            .status()
+    };
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("Failed to close game: {}", status)),
+        Err(e) => Err(format!("Failed to close game: {}", e)),
+    }
+}
+
+fn notify(app: &AppHandle, title: &str, body: &str) {
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        println!("Failed to show notification: {}", e);
+    }
+}
+
+// Runs the reminder / hard limit timers until they fire or `exited` is cancelled
+pub fn start(
+    app: AppHandle,
+    game_id: String,
+    game_name: String,
+    pid: u32,
+    limit: SessionLimit,
+    exited: CancellationToken,
+) {
+    if !limit.is_active() {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        let mut events: Vec<(u64, bool)> = Vec::new();
+        if let Some(minutes) = limit.reminder_minutes.filter(|m| *m > 0) {
+            events.push((minutes, false));
+        }
+        if let Some(minutes) = limit.hard_limit_minutes.filter(|m| *m > 0) {
+            events.push((minutes, true));
+        }
+        events.sort();
+
+        let mut elapsed = 0;
+        for (minutes, hard) in events {
+            let wait = Duration::from_secs(minutes.saturating_sub(elapsed) * 60);
+            tokio::select! {
+                _ = exited.cancelled() => return,
+                _ = tokio::time::sleep(wait) => {}
+            }
+            elapsed = minutes;
+
+            let _ = app.emit(
+                "session-limit",
+                &serde_json::json!({
+                    "gameId": game_id,
+                    "minutes": minutes,
+                    "hardLimit": hard
+                }),
+            );
+            if hard {
+                println!("Session limit reached for {}, closing the game", game_id);
+                notify(
+                    &app,
+                    "Session limit reached",
+                    &format!("{} was closed after {} minutes", game_name, minutes),
+                );
+                if let Err(e) = terminate(pid) {
+                    println!("{}", e);
+                }
+                return;
+            }
+            notify(
+                &app,
+                "Time for a break?",
+                &format!(
+                    "You have been playing {} for {} minutes",
+                    game_name, minutes
+                ),
+            );
+        }
+    });
+}
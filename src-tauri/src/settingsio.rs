@@ -0,0 +1,187 @@
+use crate::state::AppState;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fs;
+
+const EXPORT_FORMAT: &str = "chanomhub-settings";
+const EXPORT_VERSION: u32 = 1;
+
+// Placeholder written in place of secrets; importing it keeps whatever value is already set
+pub const REDACTED: &str = "<redacted>";
+
+// The game library is tied to paths on this machine, so it is never part of a settings file.
+// Guest mode is left out so an imported file cannot switch it off or replace the PIN.
+const EXCLUDED_KEYS: &[&str] = &["games", "guest_mode"];
+
+#[derive(Serialize, Debug, Default)]
+pub struct ImportSummary {
+    pub applied: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+fn redact(settings: &mut Map<String, Value>) {
+    if let Some(token) = settings.get_mut("token")
+        && !token.is_null()
+    {
+        *token = Value::String(REDACTED.to_string());
+    }
+    if let Some(Value::Object(cloudinary)) = settings.get_mut("cloudinary") {
+        for key in ["api_key", "api_secret"] {
+            if let Some(value) = cloudinary.get_mut(key) {
+                *value = Value::String(REDACTED.to_string());
+            }
+        }
+    }
+    // Provider headers usually carry cookies or API tokens
+    for provider in provider_headers(settings) {
+        if let Some(Value::Object(headers)) = provider.get_mut("headers") {
+            for value in headers.values_mut() {
+                *value = Value::String(REDACTED.to_string());
+            }
+        }
+    }
+}
+
+fn provider_headers(settings: &mut Map<String, Value>) -> impl Iterator<Item = &mut Value> {
+    settings
+        .get_mut("request_settings")
+        .and_then(|r| r.get_mut("providerHeaders"))
+        .and_then(|p| p.as_array_mut())
+        .into_iter()
+        .flatten()
+}
+
+// Lists are replaced whole on import, so a redacted header keeps the value this machine has for the
+// same host, or is dropped when there is none
+fn restore_provider_headers(previous: &Value, settings: &mut Map<String, Value>) {
+    let host_of = |provider: &Value| {
+        provider
+            .get("host")
+            .and_then(|h| h.as_str())
+            .map(str::to_string)
+    };
+    for provider in provider_headers(settings) {
+        let host = host_of(provider);
+        let known = previous
+            .get("request_settings")
+            .and_then(|r| r.get("providerHeaders"))
+            .and_then(|p| p.as_array())
+            .and_then(|p| p.iter().find(|p| host.is_some() && host_of(p) == host))
+            .and_then(|p| p.get("headers"));
+        if let Some(Value::Object(headers)) = provider.get_mut("headers") {
+            headers.retain(|name, value| {
+                if value.as_str() != Some(REDACTED) {
+                    return true;
+                }
+                match known.and_then(|k| k.get(name)) {
+                    Some(original) => {
+                        *value = original.clone();
+                        true
+                    }
+                    None => false,
+                }
+            });
+        }
+    }
+}
+
+pub fn export_settings(
+    app_state: &AppState,
+    path: &str,
+    include_secrets: bool,
+) -> Result<(), String> {
+    let Value::Object(mut settings) = serde_json::to_value(app_state)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?
+    else {
+        return Err("Failed to serialize settings".to_string());
+    };
+    for key in EXCLUDED_KEYS {
+        settings.remove(*key);
+    }
+    if !include_secrets {
+        redact(&mut settings);
+    }
+
+    let export = serde_json::json!({
+        "format": EXPORT_FORMAT,
+        "version": EXPORT_VERSION,
+        "exportedAt": chrono::Utc::now().to_rfc3339(),
+        "appVersion": env!("CARGO_PKG_VERSION"),
+        "secretsIncluded": include_secrets,
+        "settings": settings
+    });
+    let json = serde_json::to_string_pretty(&export)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write settings file: {}", e))?;
+    println!(
+        "Exported settings to {} (secrets included: {})",
+        path, include_secrets
+    );
+    Ok(())
+}
+
+// Objects merge key by key so partial files only touch what they contain
+fn merge(target: &mut Value, incoming: &Value) {
+    match (target, incoming) {
+        (_, Value::String(s)) if s == REDACTED => {}
+        (target, Value::Object(incoming)) => {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            if let Value::Object(target) = target {
+                for (key, value) in incoming {
+                    merge(target.entry(key.clone()).or_insert(Value::Null), value);
+                }
+            }
+        }
+        (target, incoming) => *target = incoming.clone(),
+    }
+}
+
+// Merges a settings file into `app_state`; keys that would leave the state invalid are skipped
+pub fn import_settings(app_state: &mut AppState, path: &str) -> Result<ImportSummary, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read settings file: {}", e))?;
+    let file: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse settings file: {}", e))?;
+
+    // Plain config.json files are accepted too
+    let incoming = match file.get("format").and_then(|f| f.as_str()) {
+        Some(EXPORT_FORMAT) => file.get("settings").cloned().unwrap_or(Value::Null),
+        Some(other) => return Err(format!("Unsupported settings format: {}", other)),
+        None => file,
+    };
+    let Value::Object(incoming) = incoming else {
+        return Err("Settings file does not contain a settings object".to_string());
+    };
+
+    let mut current = serde_json::to_value(&*app_state)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    let mut summary = ImportSummary::default();
+    for (key, value) in incoming {
+        if EXCLUDED_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+
+        let mut candidate = current.clone();
+        if let Value::Object(map) = &mut candidate {
+            merge(map.entry(key.clone()).or_insert(Value::Null), &value);
+            restore_provider_headers(&current, map);
+        }
+        if serde_json::from_value::<AppState>(candidate.clone()).is_ok() {
+            current = candidate;
+            summary.applied.push(key);
+        } else {
+            println!("Skipping invalid or incomplete setting: {}", key);
+            summary.skipped.push(key);
+        }
+    }
+
+    *app_state = serde_json::from_value(current)
+        .map_err(|e| format!("Failed to apply imported settings: {}", e))?;
+    println!(
+        "Imported settings from {}: applied {:?}, skipped {:?}",
+        path, summary.applied, summary.skipped
+    );
+    Ok(summary)
+}
@@ -0,0 +1,261 @@
+use crate::httpconfig::{self, RequestSettings};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command as StdCommand;
+use std::time::{Duration, Instant};
+
+const API_BASE: &str = "https://api.chanomhub.online/api";
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+const DOWNLOAD_FOLDER_NAME: &str = "Chanomhub";
+
+// Result of one onboarding step, shaped the same for every check so the wizard can render them uniformly
+#[derive(Serialize, Clone, Debug)]
+pub struct SetupCheck {
+    pub step: String,
+    pub ok: bool,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+}
+
+impl SetupCheck {
+    fn passed(step: &str, message: String, details: Option<serde_json::Value>) -> Self {
+        Self {
+            step: step.to_string(),
+            ok: true,
+            message,
+            details,
+        }
+    }
+
+    pub fn failed(step: &str, message: String) -> Self {
+        Self {
+            step: step.to_string(),
+            ok: false,
+            message,
+            details: None,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DownloadLocation {
+    pub path: String,
+    #[serde(rename = "availableBytes")]
+    pub available_bytes: u64,
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: u64,
+    pub recommended: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CurrentUser {
+    pub username: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub bio: Option<String>,
+    #[serde(default)]
+    pub image: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UserResponse {
+    user: CurrentUser,
+}
+
+fn existing_ancestor(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|p| p.exists())
+}
+
+fn candidate_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    roots.extend(dirs::download_dir());
+    roots.extend(dirs::data_local_dir());
+
+    // Every fixed drive is a candidate on Windows, since the biggest one is rarely C:
+    #[cfg(target_os = "windows")]
+    for letter in b'C'..=b'Z' {
+        let drive = PathBuf::from(format!("{}:\\", letter as char));
+        if drive.exists() {
+            roots.push(drive);
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    roots.extend(dirs::home_dir());
+
+    roots
+}
+
+// Lists possible download folders with their free space; the one with the most room is recommended
+pub fn suggest_download_locations() -> Vec<DownloadLocation> {
+    let mut locations: Vec<DownloadLocation> = Vec::new();
+    for root in candidate_roots() {
+        let path = root.join(DOWNLOAD_FOLDER_NAME);
+        let Some(probe) = existing_ancestor(&path) else {
+            continue;
+        };
+        let (Ok(available), Ok(total)) = (fs2::available_space(probe), fs2::total_space(probe))
+        else {
+            continue;
+        };
+        let path = path.to_string_lossy().to_string();
+        if locations.iter().any(|l| l.path == path) {
+            continue;
+        }
+        locations.push(DownloadLocation {
+            path,
+            available_bytes: available,
+            total_bytes: total,
+            recommended: false,
+        });
+    }
+
+    locations.sort_by_key(|l| std::cmp::Reverse(l.available_bytes));
+    if let Some(best) = locations.first_mut() {
+        best.recommended = true;
+    }
+    locations
+}
+
+pub async fn check_api_connectivity(settings: &RequestSettings) -> SetupCheck {
+    let client = match httpconfig::client_builder(settings)
+        .timeout(CHECK_TIMEOUT)
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => return SetupCheck::failed("api", format!("Failed to create client: {}", e)),
+    };
+
+    let url = format!("{}/articles?limit=1", API_BASE);
+    let started = Instant::now();
+    match httpconfig::apply(settings, client.get(&url), &url)
+        .header("accept", "application/json")
+        .send()
+        .await
+    {
+        Ok(response) if response.status().is_success() => {
+            let latency_ms = started.elapsed().as_millis() as u64;
+            SetupCheck::passed(
+                "api",
+                format!("Connected to Chanomhub in {} ms", latency_ms),
+                Some(serde_json::json!({ "latencyMs": latency_ms })),
+            )
+        }
+        Ok(response) => {
+            SetupCheck::failed("api", format!("API responded with {}", response.status()))
+        }
+        Err(e) => SetupCheck::failed("api", format!("Failed to reach the API: {}", e)),
+    }
+}
+
+pub async fn get_current_user(
+    settings: &RequestSettings,
+    token: &str,
+) -> Result<CurrentUser, String> {
+    let client = httpconfig::client_builder(settings)
+        .timeout(CHECK_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+    let url = format!("{}/user", API_BASE);
+    let response = httpconfig::apply(settings, client.get(&url), &url)
+        .header("accept", "application/json")
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("API request failed: {}", response.status()));
+    }
+    let body: UserResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    Ok(body.user)
+}
+
+pub async fn validate_token(settings: &RequestSettings, token: &str) -> SetupCheck {
+    if token.trim().is_empty() {
+        return SetupCheck::failed("token", "Token is empty".to_string());
+    }
+    match get_current_user(settings, token.trim()).await {
+        Ok(user) => SetupCheck::passed(
+            "token",
+            format!("Signed in as {}", user.username),
+            serde_json::to_value(&user).ok(),
+        ),
+        Err(e) => SetupCheck::failed("token", format!("Token was rejected: {}", e)),
+    }
+}
+
+fn command_version(program: &str, arg: &str) -> Option<String> {
+    let output = StdCommand::new(program).arg(arg).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // python2-era builds print the version on stderr
+    let version = if stdout.trim().is_empty() {
+        stderr
+    } else {
+        stdout
+    };
+    version.lines().next().map(|l| l.trim().to_string())
+}
+
+fn check_tool(step: &str, program: &str, arg: &str, missing: &str) -> SetupCheck {
+    match command_version(program, arg) {
+        Some(version) => SetupCheck::passed(
+            step,
+            format!("{} found", version),
+            Some(serde_json::json!({ "version": version })),
+        ),
+        None => SetupCheck::failed(step, missing.to_string()),
+    }
+}
+
+// Reports which launch methods (see `launch_game`) can work on this machine
+pub fn check_compat_layers() -> Vec<SetupCheck> {
+    let mut checks = vec![check_tool(
+        "python",
+        "python3",
+        "--version",
+        "Python3 is not installed; Python-based games cannot be launched",
+    )];
+
+    #[cfg(not(target_os = "windows"))]
+    checks.push(check_tool(
+        "wine",
+        "wine",
+        "--version",
+        "Wine is not installed; Windows games cannot be launched",
+    ));
+
+    #[cfg(target_os = "windows")]
+    {
+        let installed = StdCommand::new("reg")
+            .args(["query", "HKLM\\SOFTWARE\\Microsoft\\EdgeUpdate\\Clients"])
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false);
+        checks.push(if installed {
+            SetupCheck::passed(
+                "webview2",
+                "WebView2 runtime is installed".to_string(),
+                None,
+            )
+        } else {
+            SetupCheck::failed(
+                "webview2",
+                "WebView2 runtime is not installed; it will be installed on first download"
+                    .to_string(),
+            )
+        });
+    }
+
+    checks
+}
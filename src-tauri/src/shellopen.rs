@@ -0,0 +1,220 @@
+use crate::{archiver, dropimport};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+// Passed by the "Extract with Chanomhub" context menu entry
+pub const EXTRACT_ARG: &str = "--extract";
+
+pub const MANIFEST_EXTENSION: &str = "chanomhub";
+
+#[cfg(not(target_os = "macos"))]
+const ARCHIVE_EXTENSIONS: &[&str] = &["zip", "7z", "rar"];
+
+// Small file shared next to a release, pointing at the article and where to get it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ChanomhubManifest {
+    pub slug: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+}
+
+// Manifest opened before the UI was ready to listen for it
+#[derive(Default)]
+pub struct PendingManifest(pub Option<ChanomhubManifest>);
+
+pub fn parse_manifest(path: &Path) -> Result<ChanomhubManifest, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read manifest: {}", e))?;
+    let mut manifest: ChanomhubManifest =
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse manifest: {}", e))?;
+    if manifest.slug.trim().is_empty() {
+        return Err("Manifest has no article slug".to_string());
+    }
+    manifest.slug = manifest.slug.trim().to_string();
+    // ไม่รับลิงก์ที่ไม่ใช่ http(s) จากไฟล์ที่ได้มาจากที่อื่น
+    manifest.mirrors.retain(|mirror| {
+        url::Url::parse(mirror).is_ok_and(|u| u.scheme() == "http" || u.scheme() == "https")
+    });
+    Ok(manifest)
+}
+
+fn open_manifest(app: &AppHandle, path: &Path) -> Result<(), String> {
+    let manifest = parse_manifest(path)?;
+    println!(
+        "Opened manifest for {} with {} mirrors",
+        manifest.slug,
+        manifest.mirrors.len()
+    );
+    if let Ok(mut pending) = app.state::<Mutex<PendingManifest>>().lock() {
+        pending.0 = Some(manifest.clone());
+    }
+    let _ = app.emit("manifest-opened", &manifest);
+    Ok(())
+}
+
+// Handles files handed over on the command line by the OS (file association or context menu)
+pub fn handle_args(app: &AppHandle, args: Vec<String>) {
+    let mut extract_next = false;
+    let stamp = chrono::Utc::now().timestamp_millis();
+    for (index, arg) in args.into_iter().skip(1).enumerate() {
+        if arg == EXTRACT_ARG {
+            extract_next = true;
+            continue;
+        }
+        let extract_now = std::mem::take(&mut extract_next);
+        let path = PathBuf::from(&arg);
+        if !path.is_file() {
+            continue;
+        }
+
+        let is_manifest = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(MANIFEST_EXTENSION));
+        if is_manifest {
+            if let Err(e) = open_manifest(app, &path) {
+                println!("Failed to open {}: {}", path.display(), e);
+            }
+        } else if archiver::is_supported_archive(&arg) {
+            let app = app.clone();
+            let download_id = format!("open_{}_{}", stamp, index);
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) =
+                    dropimport::import_archive(&app, download_id, &path, extract_now).await
+                {
+                    println!("Failed to open {}: {}", path.display(), e);
+                }
+            });
+        }
+    }
+}
+
+// Files opened from Finder; the empty first argument stands in for the program name
+#[cfg(target_os = "macos")]
+pub fn handle_urls(app: &AppHandle, urls: Vec<url::Url>) {
+    let paths = urls
+        .into_iter()
+        .filter_map(|url| url.to_file_path().ok())
+        .map(|path| path.to_string_lossy().to_string());
+    handle_args(app, std::iter::once(String::new()).chain(paths).collect());
+}
+
+#[cfg(not(target_os = "macos"))]
+fn exe_path() -> Result<PathBuf, String> {
+    std::env::current_exe().map_err(|e| format!("Failed to get executable path: {}", e))
+}
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+#[cfg(target_os = "windows")]
+fn context_menu_key(ext: &str) -> String {
+    format!(
+        "HKCU\\Software\\Classes\\SystemFileAssociations\\.{}\\shell\\ChanomhubExtract",
+        ext
+    )
+}
+
+#[cfg(target_os = "windows")]
+fn reg(args: &[&str]) -> Result<bool, String> {
+    use std::os::windows::process::CommandExt;
+
+    std::process::Command::new("reg")
+        .args(args)
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map(|o| o.status.success())
+        .map_err(|e| format!("Failed to run reg: {}", e))
+}
+
+// Per-user verb on zip/7z/rar, so it shows up next to the built-in "Extract All"
+#[cfg(target_os = "windows")]
+pub fn set_context_menu(enabled: bool) -> Result<(), String> {
+    let command = format!("\"{}\" {} \"%1\"", exe_path()?.display(), EXTRACT_ARG);
+    for ext in ARCHIVE_EXTENSIONS {
+        let key = context_menu_key(ext);
+        let ok = if enabled {
+            reg(&["add", &key, "/ve", "/d", "Extract with Chanomhub", "/f"])?
+                && reg(&[
+                    "add",
+                    &format!("{}\\command", key),
+                    "/ve",
+                    "/d",
+                    &command,
+                    "/f",
+                ])?
+        } else {
+            !is_context_menu_enabled() || reg(&["delete", &key, "/f"])?
+        };
+        if !ok {
+            return Err(format!("Failed to update context menu for .{}", ext));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+pub fn is_context_menu_enabled() -> bool {
+    reg(&["query", &context_menu_key(ARCHIVE_EXTENSIONS[0])]).unwrap_or(false)
+}
+
+// Desktop entry that file managers list under "Open With" for archives
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn desktop_entry_path() -> Result<PathBuf, String> {
+    let data = dirs::data_dir().ok_or("Failed to get data dir")?;
+    Ok(data.join("applications").join("chanomhub-extract.desktop"))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn set_context_menu(enabled: bool) -> Result<(), String> {
+    let path = desktop_entry_path()?;
+    if !enabled {
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| format!("Failed to remove desktop entry: {}", e))?;
+        }
+        return Ok(());
+    }
+
+    let exe = std::env::var_os("APPIMAGE")
+        .map(PathBuf::from)
+        .map_or_else(exe_path, Ok)?;
+    let mime_types: Vec<&str> = ARCHIVE_EXTENSIONS
+        .iter()
+        .map(|ext| match *ext {
+            "zip" => "application/zip",
+            "7z" => "application/x-7z-compressed",
+            _ => "application/vnd.rar",
+        })
+        .collect();
+    let entry = format!(
+        "[Desktop Entry]\nType=Application\nName=Extract with Chanomhub\nExec=\"{}\" {} %f\nMimeType={};\nNoDisplay=true\nTerminal=false\n",
+        exe.display(),
+        EXTRACT_ARG,
+        mime_types.join(";")
+    );
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create applications dir: {}", e))?;
+    }
+    fs::write(&path, entry).map_err(|e| format!("Failed to write desktop entry: {}", e))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn is_context_menu_enabled() -> bool {
+    desktop_entry_path().is_ok_and(|p| p.exists())
+}
+
+// Finder services need a bundled extension, which the app does not ship
+#[cfg(target_os = "macos")]
+pub fn set_context_menu(_enabled: bool) -> Result<(), String> {
+    Err("The extract context menu is not supported on macOS".to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub fn is_context_menu_enabled() -> bool {
+    false
+}
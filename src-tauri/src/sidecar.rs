@@ -0,0 +1,417 @@
+use crate::ActiveDownloads;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use tauri::async_runtime::Receiver;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_shell::ShellExt;
+use tauri_plugin_shell::process::{CommandChild, CommandEvent};
+use tokio::sync::oneshot;
+
+// Version of the JSON protocol spoken with the WebView2 helper.
+// Bump this whenever the message format changes incompatibly.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+// Actions the app relies on the helper to understand
+const REQUIRED_CAPABILITIES: &[&str] = &["setDownload"];
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+const CANCEL_ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Held while a helper is being started and handshaken, so concurrent callers wait for that one
+// instead of each spawning their own
+static STARTING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+pub const HELPER_BINARY_NAME: &str = "WebView2-x86_64-pc-windows-msvc.exe";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SidecarInfo {
+    #[serde(rename = "protocolVersion")]
+    pub protocol_version: u32,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    #[serde(rename = "helperVersion", default)]
+    pub helper_version: Option<String>,
+}
+
+impl SidecarInfo {
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+}
+
+// One helper process serves every WebView2 download; messages are routed by downloadId
+#[derive(Default)]
+pub struct SidecarState {
+    pub info: Option<SidecarInfo>,
+    child: Option<CommandChild>,
+    pending_hello: Option<oneshot::Sender<SidecarInfo>>,
+    pending_cancels: HashMap<String, oneshot::Sender<()>>,
+    // Bumped for every spawned helper; events from an older one must not touch the current one
+    generation: u64,
+}
+
+pub fn resolve_helper_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if let Some(updated) = crate::helperupdate::installed_helper_path(app) {
+        return Ok(updated);
+    }
+
+    let binary_path = app
+        .path()
+        .resource_dir()
+        .map_err(|e| format!("Failed to get resource dir: {}", e))?
+        .join("binaries")
+        .join("Release")
+        .join(HELPER_BINARY_NAME);
+
+    if binary_path.exists() {
+        return Ok(binary_path);
+    }
+    println!("Binary not found at: {:?}", binary_path);
+
+    let paths_to_check = vec![PathBuf::from(HELPER_BINARY_NAME)];
+    for path in paths_to_check {
+        if path.exists() {
+            println!("Found binary at alternate location: {:?}", path);
+            return Ok(path);
+        }
+    }
+
+    Err("WebView2 binary not found in any expected location".to_string())
+}
+
+fn parse_hello(line: &[u8]) -> Option<SidecarInfo> {
+    let output = String::from_utf8_lossy(line);
+    let json = serde_json::from_str::<serde_json::Value>(output.trim()).ok()?;
+    if json.get("action").and_then(|a| a.as_str()) != Some("hello") {
+        return None;
+    }
+    serde_json::from_value(json).ok()
+}
+
+pub fn check_compatibility(info: &SidecarInfo) -> Result<(), String> {
+    if info.protocol_version != PROTOCOL_VERSION {
+        return Err(format!(
+            "WebView2 helper speaks protocol v{} but this app requires v{}. Please reinstall or update the app so the helper matches.",
+            info.protocol_version, PROTOCOL_VERSION
+        ));
+    }
+
+    let missing: Vec<&str> = REQUIRED_CAPABILITIES
+        .iter()
+        .filter(|c| !info.supports(c))
+        .copied()
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "WebView2 helper is missing required capabilities: {}",
+            missing.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+fn lock_state(app: &AppHandle) -> Result<std::sync::MutexGuard<'_, SidecarState>, String> {
+    app.state::<Mutex<SidecarState>>()
+        .inner()
+        .lock()
+        .map_err(|e| format!("Failed to lock sidecar state: {}", e))
+}
+
+fn write_message(sidecar: &mut SidecarState, message: &serde_json::Value) -> Result<(), String> {
+    let child = sidecar
+        .child
+        .as_mut()
+        .ok_or("WebView2 helper is not running")?;
+    child
+        .write(format!("{}\n", message).as_bytes())
+        .map_err(|e| format!("Failed to write to WebView2 helper: {}", e))
+}
+
+// Starts the long-lived helper if needed and returns the negotiated protocol info
+pub async fn ensure_running(app: &AppHandle) -> Result<SidecarInfo, String> {
+    if let Some(info) = running_info(app)? {
+        return Ok(info);
+    }
+    let _starting = STARTING.lock().await;
+    // Another caller may have finished starting it while this one waited
+    if let Some(info) = running_info(app)? {
+        return Ok(info);
+    }
+
+    let hello_rx = {
+        let mut sidecar = lock_state(app)?;
+
+        let binary_path = resolve_helper_path(app)?;
+        println!("Starting persistent WebView2 helper at: {:?}", binary_path);
+        let (rx, child) = app
+            .shell()
+            .command(binary_path.to_str().ok_or("Invalid binary path")?)
+            .arg("--stdio")
+            .spawn()
+            .map_err(|e| format!("Failed to spawn WebView2 process: {}", e))?;
+
+        let (hello_tx, hello_rx) = oneshot::channel();
+        sidecar.generation += 1;
+        sidecar.child = Some(child);
+        sidecar.info = None;
+        sidecar.pending_hello = Some(hello_tx);
+        tauri::async_runtime::spawn(pump_events(app.clone(), rx, sidecar.generation));
+
+        let hello = serde_json::json!({
+            "action": "hello",
+            "protocolVersion": PROTOCOL_VERSION
+        });
+        println!("Sending handshake to WebView2 helper: {}", hello);
+        write_message(&mut sidecar, &hello)?;
+        hello_rx
+    };
+
+    let info = match tokio::time::timeout(HANDSHAKE_TIMEOUT, hello_rx).await {
+        Ok(Ok(info)) => info,
+        Ok(Err(_)) => {
+            shutdown(app);
+            return Err("WebView2 helper exited without answering the handshake. It is older than protocol v1; please reinstall or update the app.".to_string());
+        }
+        Err(_) => {
+            shutdown(app);
+            return Err("WebView2 helper did not answer the handshake in time".to_string());
+        }
+    };
+    println!("WebView2 helper handshake: {:?}", info);
+
+    if let Err(e) = check_compatibility(&info) {
+        shutdown(app);
+        return Err(e);
+    }
+
+    let mut sidecar = lock_state(app)?;
+    sidecar.info = Some(info.clone());
+    Ok(info)
+}
+
+fn running_info(app: &AppHandle) -> Result<Option<SidecarInfo>, String> {
+    let sidecar = lock_state(app)?;
+    Ok(sidecar.child.as_ref().and(sidecar.info.clone()))
+}
+
+// Sends one message to the running helper, starting it first if necessary
+pub async fn send(app: &AppHandle, message: &serde_json::Value) -> Result<(), String> {
+    ensure_running(app).await?;
+    let mut sidecar = lock_state(app)?;
+    println!("Sending message to WebView2: {}", message);
+    write_message(&mut sidecar, message)
+}
+
+// Asks the helper that owns the download to cancel it and waits for the acknowledgment.
+// Returns false when the helper had to be killed because it never confirmed.
+pub async fn cancel_download(app: &AppHandle, download_id: &str) -> Result<bool, String> {
+    let info = ensure_running(app).await?;
+    if !info.supports("cancelDownload") {
+        println!("WebView2 helper cannot cancel single downloads, terminating it");
+        shutdown(app);
+        return Ok(false);
+    }
+
+    let ack_rx = {
+        let mut sidecar = lock_state(app)?;
+        let (ack_tx, ack_rx) = oneshot::channel();
+        sidecar
+            .pending_cancels
+            .insert(download_id.to_string(), ack_tx);
+        let message = serde_json::json!({
+            "action": "cancelDownload",
+            "downloadId": download_id
+        });
+        println!("Sending message to WebView2: {}", message);
+        if let Err(e) = write_message(&mut sidecar, &message) {
+            sidecar.pending_cancels.remove(download_id);
+            return Err(e);
+        }
+        ack_rx
+    };
+
+    match tokio::time::timeout(CANCEL_ACK_TIMEOUT, ack_rx).await {
+        Ok(Ok(())) => Ok(true),
+        _ => {
+            println!(
+                "WebView2 helper did not confirm cancellation of {}, terminating it",
+                download_id
+            );
+            if let Ok(mut sidecar) = lock_state(app) {
+                sidecar.pending_cancels.remove(download_id);
+            }
+            shutdown(app);
+            Ok(false)
+        }
+    }
+}
+
+// Sends a per-download action the helper advertised in its hello
+async fn send_download_action(
+    app: &AppHandle,
+    action: &str,
+    download_id: &str,
+) -> Result<(), String> {
+    let info = ensure_running(app).await?;
+    if !info.supports(action) {
+        return Err(format!("WebView2 helper does not support {}", action));
+    }
+    let message = serde_json::json!({
+        "action": action,
+        "downloadId": download_id
+    });
+    let mut sidecar = lock_state(app)?;
+    println!("Sending message to WebView2: {}", message);
+    write_message(&mut sidecar, &message)
+}
+
+// Asks the helper to pause and resume a transfer, which continues from the bytes already on disk
+pub async fn restart_download(app: &AppHandle, download_id: &str) -> Result<(), String> {
+    send_download_action(app, "restartDownload", download_id).await
+}
+
+// The helper answers with a "paused" status once the transfer has stopped
+pub async fn pause_download(app: &AppHandle, download_id: &str) -> Result<(), String> {
+    send_download_action(app, "pauseDownload", download_id).await
+}
+
+pub async fn resume_download(app: &AppHandle, download_id: &str) -> Result<(), String> {
+    send_download_action(app, "resumeDownload", download_id).await
+}
+
+pub fn shutdown(app: &AppHandle) {
+    if let Ok(mut sidecar) = lock_state(app) {
+        sidecar.info = None;
+        sidecar.pending_hello = None;
+        sidecar.pending_cancels.clear();
+        if let Some(child) = sidecar.child.take()
+            && let Err(e) = child.kill()
+        {
+            println!("Failed to kill WebView2 helper: {}", e);
+        }
+    }
+}
+
+async fn handle_line(app: &AppHandle, line: &[u8], generation: u64) {
+    if let Some(info) = parse_hello(line) {
+        if let Ok(mut sidecar) = lock_state(app)
+            && sidecar.generation == generation
+            && let Some(tx) = sidecar.pending_hello.take()
+        {
+            let _ = tx.send(info);
+        }
+        return;
+    }
+
+    let output = String::from_utf8_lossy(line).to_string();
+    let json = serde_json::from_str::<serde_json::Value>(output.trim()).ok();
+    // Cookie values are credentials, so that message is kept out of the log
+    if let Some(json) = json
+        .as_ref()
+        .filter(|j| j.get("status").and_then(|s| s.as_str()) == Some("cookies"))
+    {
+        crate::cookiejar::record_from_helper(app, json);
+        return;
+    }
+    println!("WebView2 output: {}", output);
+    if let Some(json) = json {
+        if json.get("downloadId").is_none() {
+            println!("Ignoring WebView2 message without downloadId: {:?}", json);
+            return;
+        }
+        let cancelled_id = match json.get("status").and_then(|s| s.as_str()) {
+            Some("cancelled") => json
+                .get("downloadId")
+                .and_then(|id| id.as_str())
+                .map(|id| id.to_string()),
+            _ => None,
+        };
+
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        if let Err(e) = crate::webview2_response(json, app.clone(), active_downloads).await {
+            println!("Error processing WebView2 response: {}", e);
+        }
+
+        // Resolve the waiting cancel request only after the state has been updated
+        if let Some(download_id) = cancelled_id
+            && let Ok(mut sidecar) = lock_state(app)
+            && let Some(tx) = sidecar.pending_cancels.remove(&download_id)
+        {
+            let _ = tx.send(());
+        }
+    }
+}
+
+// Fails every download still owned by the helper after it exits unexpectedly
+async fn fail_orphaned_downloads(app: &AppHandle, reason: String) {
+    let orphaned: Vec<String> = {
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        match active_downloads.read() {
+            Ok(downloads) => downloads
+                .downloads
+                .values()
+                .filter(|d| d.provider.as_deref() == Some("webview2"))
+                .filter(|d| matches!(d.status.as_str(), "starting" | "downloading" | "paused"))
+                .map(|d| d.id.clone())
+                .collect(),
+            Err(e) => {
+                println!("Failed to lock active downloads: {}", e);
+                Vec::new()
+            }
+        }
+    };
+
+    for download_id in orphaned {
+        let error_json = serde_json::json!({
+            "status": "error",
+            "message": reason,
+            "downloadId": download_id,
+            "source": "sidecar-exit"
+        });
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        if let Err(e) = crate::webview2_response(error_json, app.clone(), active_downloads).await {
+            println!("Error reporting WebView2 termination: {}", e);
+        }
+    }
+}
+
+async fn pump_events(app: AppHandle, mut rx: Receiver<CommandEvent>, generation: u64) {
+    while let Some(event) = rx.recv().await {
+        match event {
+            CommandEvent::Stdout(line) | CommandEvent::Stderr(line) => {
+                handle_line(&app, &line, generation).await;
+            }
+            CommandEvent::Error(e) => {
+                println!("WebView2 process error: {}", e);
+            }
+            CommandEvent::Terminated(payload) => {
+                println!("WebView2 helper terminated with code: {:?}", payload.code);
+                // A late exit of a helper that was already replaced must not clear the new one
+                let current = lock_state(&app).is_ok_and(|s| s.generation == generation);
+                if !current {
+                    continue;
+                }
+                if let Ok(mut sidecar) = lock_state(&app) {
+                    sidecar.child = None;
+                    sidecar.info = None;
+                    sidecar.pending_hello = None;
+                    sidecar.pending_cancels.clear();
+                }
+                fail_orphaned_downloads(
+                    &app,
+                    format!(
+                        "WebView2 process terminated unexpectedly with code: {:?}",
+                        payload.code
+                    ),
+                )
+                .await;
+            }
+            _ => {}
+        }
+    }
+}
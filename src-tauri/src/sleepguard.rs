@@ -0,0 +1,154 @@
+use crate::state::AppState;
+use crate::{ActiveDownloads, powerpause};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+// Held while a download or extraction is running; dropping it lets the system sleep again.
+// Only system sleep is blocked, the display may still turn off.
+#[derive(Default)]
+pub struct SleepGuard(Option<Inhibitor>);
+
+#[cfg(target_os = "windows")]
+const ES_CONTINUOUS: u32 = 0x8000_0000;
+
+#[cfg(target_os = "windows")]
+const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+
+#[cfg(target_os = "windows")]
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn SetThreadExecutionState(flags: u32) -> u32;
+}
+
+// The execution state belongs to the thread that set it, so a thread of its own holds it
+#[cfg(target_os = "windows")]
+struct Inhibitor(std::sync::mpsc::Sender<()>);
+
+#[cfg(target_os = "windows")]
+fn inhibit() -> Result<Inhibitor, String> {
+    let (release, released) = std::sync::mpsc::channel::<()>();
+    let (started, result) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let previous = unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) };
+        let _ = started.send(previous != 0);
+        if previous == 0 {
+            return;
+        }
+        // Returns once the sender is dropped
+        let _ = released.recv();
+        unsafe {
+            SetThreadExecutionState(ES_CONTINUOUS);
+        }
+    });
+    match result.recv() {
+        Ok(true) => Ok(Inhibitor(release)),
+        _ => Err(format!(
+            "SetThreadExecutionState failed: {}",
+            std::io::Error::last_os_error()
+        )),
+    }
+}
+
+// The lock lasts as long as the child process runs. The child also ends with the app, so a
+// crash does not leave the system awake.
+#[cfg(not(target_os = "windows"))]
+struct Inhibitor(std::process::Child);
+
+#[cfg(not(target_os = "windows"))]
+impl Drop for Inhibitor {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn inhibit() -> Result<Inhibitor, String> {
+    std::process::Command::new("systemd-inhibit")
+        .args([
+            "--what=sleep",
+            "--who=Chanomhub",
+            "--why=Downloading or extracting files",
+            "--mode=block",
+            "tail",
+            "-f",
+            "/dev/null",
+        ])
+        .arg(format!("--pid={}", std::process::id()))
+        .spawn()
+        .map(Inhibitor)
+        .map_err(|e| format!("Failed to run systemd-inhibit: {}", e))
+}
+
+// -i holds an IOPMAssertion against idle sleep
+#[cfg(target_os = "macos")]
+fn inhibit() -> Result<Inhibitor, String> {
+    std::process::Command::new("caffeinate")
+        .args(["-i", "-w"])
+        .arg(std::process::id().to_string())
+        .spawn()
+        .map(Inhibitor)
+        .map_err(|e| format!("Failed to run caffeinate: {}", e))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+fn inhibit() -> Result<Inhibitor, String> {
+    Err("Preventing sleep is not supported on this system".to_string())
+}
+
+fn transfers_running(app: &AppHandle) -> bool {
+    app.state::<RwLock<ActiveDownloads>>()
+        .read()
+        .is_ok_and(|downloads| {
+            downloads.downloads.values().any(|d| {
+                matches!(
+                    d.status.as_str(),
+                    "starting" | "downloading" | "transferring"
+                ) || (d.extraction_status.as_deref() == Some("extracting")
+                    && !powerpause::extractions_held())
+            })
+        })
+}
+
+fn set_held(app: &AppHandle, hold: bool) {
+    let guard = app.state::<Mutex<SleepGuard>>();
+    let Ok(mut guard) = guard.lock() else {
+        return;
+    };
+    if guard.0.is_some() == hold {
+        return;
+    }
+    if hold {
+        match inhibit() {
+            Ok(inhibitor) => guard.0 = Some(inhibitor),
+            Err(e) => {
+                println!("Failed to prevent sleep: {}", e);
+                return;
+            }
+        }
+    } else {
+        guard.0 = None;
+    }
+    println!(
+        "{} system sleep",
+        if hold { "Preventing" } else { "Allowing" }
+    );
+    let _ = app.emit("sleep-prevented", &serde_json::json!({ "active": hold }));
+}
+
+// Follows the transfers in the background; the setting is re-read every round
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let enabled = app
+                .state::<Mutex<AppState>>()
+                .lock()
+                .is_ok_and(|app_state| app_state.download_settings.prevent_sleep);
+            set_held(&app, enabled && transfers_running(&app));
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,241 @@
+use crate::cache;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+pub const DOWNLOAD_COMPLETE: &str = "downloadComplete";
+pub const DOWNLOAD_FAILED: &str = "downloadFailed";
+pub const EXTRACTION_COMPLETE: &str = "extractionComplete";
+pub const EXTRACTION_FAILED: &str = "extractionFailed";
+
+const SAMPLE_RATE: u32 = 44100;
+
+// Built-in cues as (frequency in Hz, seconds) notes; 0 Hz is a pause
+const BUILTIN_SOUNDS: &[(&str, &[(f32, f32)])] = &[
+    ("chime", &[(880.0, 0.12), (1318.5, 0.25)]),
+    ("alert", &[(440.0, 0.15), (0.0, 0.05), (330.0, 0.3)]),
+    ("ping", &[(1046.5, 0.2)]),
+];
+
+const SOUND_EXTENSIONS: &[&str] = &["wav", "mp3", "ogg"];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SoundSettings {
+    pub enabled: bool,
+    // 0-100
+    pub volume: u8,
+    // Per event: "" for silence, a built-in name ("chime", "alert", "ping") or a .wav/.mp3/.ogg file
+    #[serde(rename = "downloadComplete")]
+    pub download_complete: String,
+    #[serde(rename = "downloadFailed")]
+    pub download_failed: String,
+    #[serde(rename = "extractionComplete")]
+    pub extraction_complete: String,
+    #[serde(rename = "extractionFailed")]
+    pub extraction_failed: String,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            volume: 80,
+            download_complete: "chime".to_string(),
+            download_failed: "alert".to_string(),
+            extraction_complete: "ping".to_string(),
+            extraction_failed: "alert".to_string(),
+        }
+    }
+}
+
+impl SoundSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.volume > 100 {
+            return Err("Volume must be between 0 and 100".to_string());
+        }
+        for sound in [
+            &self.download_complete,
+            &self.download_failed,
+            &self.extraction_complete,
+            &self.extraction_failed,
+        ] {
+            validate_sound(sound)?;
+        }
+        Ok(())
+    }
+
+    fn sound_for(&self, event: &str) -> &str {
+        match event {
+            DOWNLOAD_COMPLETE => &self.download_complete,
+            DOWNLOAD_FAILED => &self.download_failed,
+            EXTRACTION_COMPLETE => &self.extraction_complete,
+            EXTRACTION_FAILED => &self.extraction_failed,
+            _ => "",
+        }
+    }
+}
+
+fn is_builtin(sound: &str) -> bool {
+    BUILTIN_SOUNDS.iter().any(|(name, _)| *name == sound)
+}
+
+pub fn validate_sound(sound: &str) -> Result<(), String> {
+    if sound.is_empty() || is_builtin(sound) {
+        return Ok(());
+    }
+    let path = Path::new(sound);
+    let supported = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| SOUND_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+    if !supported {
+        return Err(format!(
+            "Sound must be a .wav, .mp3 or .ogg file: {}",
+            sound
+        ));
+    }
+    if !path.is_file() {
+        return Err(format!("Sound file does not exist: {}", sound));
+    }
+    Ok(())
+}
+
+// 16-bit mono PCM; each note fades out so it does not click when it stops
+fn tone_wav(notes: &[(f32, f32)]) -> Vec<u8> {
+    let samples: Vec<i16> = notes
+        .iter()
+        .flat_map(|&(frequency, seconds)| {
+            let count = (seconds * SAMPLE_RATE as f32) as u32;
+            (0..count).map(move |i| {
+                if frequency == 0.0 {
+                    return 0;
+                }
+                let t = i as f32 / SAMPLE_RATE as f32;
+                let fade = 1.0 - i as f32 / count as f32;
+                let wave = (t * frequency * std::f32::consts::TAU).sin();
+                (wave * fade * 0.6 * i16::MAX as f32) as i16
+            })
+        })
+        .collect();
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+// Built-in cues are written to the cache once, so they play the same way as a chosen file
+fn sound_path(app: &AppHandle, sound: &str) -> Result<PathBuf, String> {
+    let Some((name, notes)) = BUILTIN_SOUNDS.iter().find(|(name, _)| *name == sound) else {
+        return Ok(PathBuf::from(sound));
+    };
+    let dir = cache::cache_dir(app, "sounds").ok_or("Failed to get cache dir")?;
+    let path = dir.join(format!("{}.wav", name));
+    if !path.is_file() {
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create cache dir: {}", e))?;
+        fs::write(&path, tone_wav(notes))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    Ok(path)
+}
+
+// The desktop's own player, so the app does not need the ALSA development libraries to build
+#[cfg(target_os = "linux")]
+fn play_file(path: &Path, volume: f32) -> Result<(), String> {
+    let players: [(&str, Vec<String>); 3] = [
+        (
+            "paplay",
+            vec![format!("--volume={}", (volume * 65536.0) as u32)],
+        ),
+        ("pw-play", vec!["--volume".to_string(), volume.to_string()]),
+        ("aplay", vec!["-q".to_string()]),
+    ];
+    for (player, args) in players {
+        match std::process::Command::new(player)
+            .args(&args)
+            .arg(path)
+            .status()
+        {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => println!("{} could not play {}: {}", player, path.display(), status),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => println!("Failed to run {}: {}", player, e),
+        }
+    }
+    Err("No audio player found (paplay, pw-play or aplay)".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn play_file(path: &Path, volume: f32) -> Result<(), String> {
+    let file =
+        fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let (_stream, handle) = rodio::OutputStream::try_default()
+        .map_err(|e| format!("Failed to open audio output: {}", e))?;
+    let sink =
+        rodio::Sink::try_new(&handle).map_err(|e| format!("Failed to start playback: {}", e))?;
+    let source = rodio::Decoder::new(std::io::BufReader::new(file))
+        .map_err(|e| format!("Failed to decode {}: {}", path.display(), e))?;
+    sink.set_volume(volume);
+    sink.append(source);
+    sink.sleep_until_end();
+    Ok(())
+}
+
+fn settings(app: &AppHandle) -> SoundSettings {
+    app.state::<Mutex<AppState>>()
+        .lock()
+        .map(|app_state| app_state.sound_settings.clone())
+        .unwrap_or_default()
+}
+
+pub async fn preview(app: &AppHandle, sound: &str, volume: Option<u8>) -> Result<(), String> {
+    validate_sound(sound)?;
+    if sound.is_empty() {
+        return Ok(());
+    }
+    let path = sound_path(app, sound)?;
+    let volume = f32::from(volume.unwrap_or_else(|| settings(app).volume).min(100)) / 100.0;
+    tauri::async_runtime::spawn_blocking(move || play_file(&path, volume))
+        .await
+        .map_err(|e| format!("Failed to play sound: {}", e))?
+}
+
+// Plays the cue chosen for `event` in the background; does nothing while sounds are off
+pub fn play(app: &AppHandle, event: &str) {
+    let settings = settings(app);
+    let sound = settings.sound_for(event);
+    if !settings.enabled || sound.is_empty() {
+        return;
+    }
+    let path = match sound_path(app, sound) {
+        Ok(path) => path,
+        Err(e) => {
+            println!("Failed to prepare {} sound: {}", event, e);
+            return;
+        }
+    };
+    let volume = f32::from(settings.volume.min(100)) / 100.0;
+    let event = event.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = play_file(&path, volume) {
+            println!("Failed to play {} sound: {}", event, e);
+        }
+    });
+}
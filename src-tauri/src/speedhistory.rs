@@ -0,0 +1,94 @@
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+// The helper reports on every chunk; one sample per second keeps the graph smooth and the buffer small
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+// Ten minutes of history per download
+const MAX_SAMPLES: usize = 600;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SpeedSample {
+    // Unix milliseconds
+    pub timestamp: i64,
+    #[serde(rename = "bytesPerSec")]
+    pub bytes_per_sec: u64,
+    #[serde(rename = "bytesReceived")]
+    pub bytes_received: u64,
+}
+
+#[derive(Default)]
+struct DownloadSpeed {
+    // Bytes at the last sample; None after a pause so the paused time is not averaged in
+    baseline: Option<(Instant, u64)>,
+    samples: VecDeque<SpeedSample>,
+}
+
+#[derive(Default)]
+pub struct SpeedHistory(HashMap<String, DownloadSpeed>);
+
+pub fn record(app: &AppHandle, download_id: &str, bytes_received: u64) -> Option<u64> {
+    let history = app.state::<Mutex<SpeedHistory>>();
+    let mut history = history.lock().ok()?;
+    let entry = history.0.entry(download_id.to_string()).or_default();
+    let now = Instant::now();
+
+    let Some((since, bytes_then)) = entry.baseline else {
+        entry.baseline = Some((now, bytes_received));
+        return None;
+    };
+    // Fewer bytes than before means the transfer restarted from zero
+    if bytes_received < bytes_then {
+        entry.samples.clear();
+        entry.baseline = Some((now, bytes_received));
+        return None;
+    }
+    let elapsed = now.duration_since(since);
+    if elapsed < SAMPLE_INTERVAL {
+        return None;
+    }
+
+    let bytes_per_sec = ((bytes_received - bytes_then) as f64 / elapsed.as_secs_f64()) as u64;
+    if entry.samples.len() >= MAX_SAMPLES {
+        entry.samples.pop_front();
+    }
+    entry.samples.push_back(SpeedSample {
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        bytes_per_sec,
+        bytes_received,
+    });
+    entry.baseline = Some((now, bytes_received));
+    Some(bytes_per_sec)
+}
+
+// Called when a transfer pauses or stops, so the next sample starts a fresh measurement
+pub fn interrupt(app: &AppHandle, download_id: &str) {
+    let history = app.state::<Mutex<SpeedHistory>>();
+    if let Ok(mut history) = history.lock()
+        && let Some(entry) = history.0.get_mut(download_id)
+    {
+        entry.baseline = None;
+    }
+}
+
+pub fn forget(app: &AppHandle, download_id: &str) {
+    let history = app.state::<Mutex<SpeedHistory>>();
+    if let Ok(mut history) = history.lock() {
+        history.0.remove(download_id);
+    }
+}
+
+pub fn samples(app: &AppHandle, download_id: &str) -> Result<Vec<SpeedSample>, String> {
+    let history = app.state::<Mutex<SpeedHistory>>();
+    let history = history
+        .lock()
+        .map_err(|e| format!("Failed to lock speed history: {}", e))?;
+    Ok(history
+        .0
+        .get(download_id)
+        .map(|entry| entry.samples.iter().cloned().collect())
+        .unwrap_or_default())
+}
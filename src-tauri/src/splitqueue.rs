@@ -0,0 +1,412 @@
+use crate::downloader::{self, TransferOptions};
+use crate::downloadgroups::{self, SplitKind, split_part};
+use crate::state::{self, AppState, ArticleMeta};
+use crate::{ActiveDownloads, DownloadInfo, articlecache, chunkhash, httpconfig, offline};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use tauri::{AppHandle, Emitter, Manager};
+
+// Kept next to active_downloads.json so the remaining parts still follow after a restart
+const QUEUE_FILE: &str = "split_queues.json";
+
+// Split releases being downloaded part by part, by article slug
+#[derive(Default)]
+pub struct SplitQueues {
+    queues: HashMap<String, SplitQueue>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct SplitQueue {
+    article: ArticleMeta,
+    referer: Option<String>,
+    parts: Vec<QueuedPart>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedPart {
+    pub number: u32,
+    pub download_id: String,
+    pub url: String,
+    pub filename: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SplitRelease {
+    pub slug: String,
+    pub base: String,
+    pub kind: SplitKind,
+    pub parts: Vec<QueuedPart>,
+}
+
+// One entry of GET /api/downloads/article/{id}
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ArticleLink {
+    id: i64,
+    name: String,
+    url: String,
+    #[serde(default)]
+    is_active: bool,
+    #[serde(default)]
+    status: String,
+}
+
+fn queue_path(app: &AppHandle) -> Option<PathBuf> {
+    state::get_config_dir(app).map(|dir| dir.join(QUEUE_FILE))
+}
+
+pub fn load_queues(app: &AppHandle) -> SplitQueues {
+    let queues = queue_path(app)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    SplitQueues { queues }
+}
+
+fn save_queues(app: &AppHandle, queues: &SplitQueues) {
+    let result = queue_path(app)
+        .ok_or_else(|| "Failed to get config dir".to_string())
+        .and_then(|path| {
+            let json = serde_json::to_string_pretty(&queues.queues).map_err(|e| e.to_string())?;
+            fs::write(path, json).map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        println!("Failed to save split queues: {}", e);
+    }
+}
+
+fn remove_queue(app: &AppHandle, slug: &str) {
+    if let Ok(mut queues) = app.state::<Mutex<SplitQueues>>().lock()
+        && queues.queues.remove(slug).is_some()
+    {
+        save_queues(app, &queues);
+    }
+}
+
+// Picks the queues left from the last session back up. Queues whose current part is among
+// `resuming` wait for it, since finishing it advances them anyway.
+pub fn resume(app: &AppHandle, resuming: &[String]) {
+    let slugs: Vec<String> = match app.state::<Mutex<SplitQueues>>().lock() {
+        Ok(queues) => queues
+            .queues
+            .iter()
+            .filter(|(_, q)| !q.parts.iter().any(|p| resuming.contains(&p.download_id)))
+            .map(|(slug, _)| slug.clone())
+            .collect(),
+        Err(_) => return,
+    };
+    for slug in slugs {
+        println!("Resuming split release {}", slug);
+        tauri::async_runtime::spawn(advance(app.clone(), slug));
+    }
+}
+
+fn url_file_name(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url).ok()?;
+    parsed
+        .path_segments()?
+        .next_back()
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+}
+
+async fn article_links(app: &AppHandle, slug: &str) -> Result<Vec<ArticleLink>, String> {
+    let token = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .token
+        .clone();
+    let client = httpconfig::client(app)?;
+    // Download links are listed by the article's numeric id, not its slug
+    let body = articlecache::fetch_article_body(app, &client, slug, token).await?;
+    let article_id = body
+        .get("id")
+        .and_then(|id| id.as_i64())
+        .ok_or_else(|| format!("Article {} has no id", slug))?;
+
+    let url = format!(
+        "https://api.chanomhub.online/api/downloads/article/{}",
+        article_id
+    );
+    let _permit = offline::api_permit(app).await?;
+    let response = httpconfig::apply(&httpconfig::settings(app), client.get(&url), &url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("API request failed: {}", response.status()));
+    }
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    Ok(body
+        .get("links")
+        .and_then(|links| links.as_array())
+        .map(|links| {
+            links
+                .iter()
+                .filter_map(|link| serde_json::from_value(link.clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+fn emit_progress(app: &AppHandle, slug: &str, status: &str, error: Option<&str>) {
+    let parts = app
+        .state::<Mutex<SplitQueues>>()
+        .lock()
+        .ok()
+        .and_then(|queues| queues.queues.get(slug).map(|q| q.parts.len()));
+    let _ = app.emit(
+        "split-release-progress",
+        &serde_json::json!({
+            "slug": slug,
+            "parts": parts,
+            "status": status,
+            "error": error
+        }),
+    );
+}
+
+// Finds the other parts of a split release on its article and downloads all of them in order;
+// the release is joined and extracted once the last part is in
+pub async fn queue(
+    app: &AppHandle,
+    url: String,
+    filename: String,
+    article: ArticleMeta,
+    referer: Option<String>,
+) -> Result<SplitRelease, String> {
+    let given = split_part(&filename)
+        .or_else(|| url_file_name(&url).and_then(|name| split_part(&name)))
+        .ok_or_else(|| format!("{} is not part of a split release", filename))?;
+    let same_release = |name: &str| {
+        split_part(name)
+            .filter(|p| p.kind == given.kind && p.base.eq_ignore_ascii_case(&given.base))
+            .map(|p| p.number)
+    };
+
+    let links = article_links(app, &article.slug).await?;
+    let mut parts: Vec<QueuedPart> = Vec::new();
+    for link in links
+        .into_iter()
+        .filter(|l| l.is_active && l.status == "APPROVED")
+    {
+        let number = same_release(&link.name)
+            .or_else(|| url_file_name(&link.url).and_then(|name| same_release(&name)));
+        // Several mirrors of the same part: the first one listed is used
+        if let Some(number) = number
+            && !parts.iter().any(|p| p.number == number)
+        {
+            parts.push(QueuedPart {
+                number,
+                // Same id the article page uses, so its buttons follow the queued downloads
+                download_id: format!("article_download_{}", link.id),
+                url: link.url,
+                filename: link.name,
+            });
+        }
+    }
+    if !parts.iter().any(|p| p.number == given.number) {
+        parts.push(QueuedPart {
+            number: given.number,
+            download_id: format!("split_{}_{}", article.slug, given.number),
+            url,
+            filename,
+        });
+    }
+    parts.sort_by_key(|p| p.number);
+    for (i, part) in parts.iter().enumerate() {
+        if part.number != i as u32 + 1 {
+            return Err(format!("Part {} is not listed on the article", i + 1));
+        }
+    }
+    if parts.len() < 2 {
+        return Err("The article lists no other parts of this release".to_string());
+    }
+
+    let slug = article.slug.clone();
+    {
+        let queues = app.state::<Mutex<SplitQueues>>();
+        let mut queues = queues
+            .lock()
+            .map_err(|e| format!("Failed to lock split queues: {}", e))?;
+        if queues.queues.contains_key(&slug) {
+            return Err(format!("{} is already being downloaded", given.base));
+        }
+        queues.queues.insert(
+            slug.clone(),
+            SplitQueue {
+                article,
+                referer,
+                parts: parts.clone(),
+            },
+        );
+        save_queues(app, &queues);
+    }
+    println!(
+        "Queued {} parts of {} for {}",
+        parts.len(),
+        given.base,
+        slug
+    );
+    tauri::async_runtime::spawn(advance(app.clone(), slug.clone()));
+    Ok(SplitRelease {
+        slug,
+        base: given.base,
+        kind: given.kind,
+        parts,
+    })
+}
+
+fn fail(app: &AppHandle, slug: &str, error: &str) {
+    println!("Split release {} stopped: {}", slug, error);
+    emit_progress(app, slug, "failed", Some(error));
+    remove_queue(app, slug);
+}
+
+// A completed part still has to be the whole file: the size the host announced and, when the
+// article came with a chunk manifest, every chunk checked
+fn verify_part(app: &AppHandle, part: &QueuedPart, download: &DownloadInfo) -> Result<(), String> {
+    let path = download
+        .path
+        .as_deref()
+        .ok_or_else(|| format!("Part {} has no file", part.number))?;
+    let size = fs::metadata(path)
+        .map(|m| m.len())
+        .map_err(|e| format!("Part {} is missing ({}): {}", part.number, path, e))?;
+    if let Some(total) = download.total_bytes
+        && total != size
+    {
+        return Err(format!(
+            "Part {} is {} bytes instead of {}",
+            part.number, size, total
+        ));
+    }
+    if chunkhash::for_download(app, &part.download_id).is_some() && !download.chunks_verified {
+        return Err(format!("Part {} did not pass its chunk check", part.number));
+    }
+    Ok(())
+}
+
+// Marked failed so the part is downloaded again the next time the release is queued
+fn reject_part(app: &AppHandle, download_id: &str, error: &str) {
+    let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+    let Ok(mut downloads) = active_downloads.write() else {
+        return;
+    };
+    if let Some(download) = downloads.downloads.get_mut(download_id) {
+        download.status = "failed".to_string();
+        download.error = Some(error.to_string());
+    }
+    if let Err(e) = state::save_active_downloads_to_file(app, &downloads) {
+        println!("{}", e);
+    }
+}
+
+// Starts the first part that is not downloaded yet, or extracts once every part is complete
+async fn advance(app: AppHandle, slug: String) {
+    let Some(queue) = app
+        .state::<Mutex<SplitQueues>>()
+        .lock()
+        .ok()
+        .and_then(|queues| queues.queues.get(&slug).cloned())
+    else {
+        return;
+    };
+    let downloads: Vec<Option<DownloadInfo>> = {
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        let Ok(downloads) = active_downloads.read() else {
+            return;
+        };
+        queue
+            .parts
+            .iter()
+            .map(|p| downloads.downloads.get(&p.download_id).cloned())
+            .collect()
+    };
+    let statuses: Vec<Option<String>> = downloads
+        .iter()
+        .map(|d| d.as_ref().map(|d| d.status.clone()))
+        .collect();
+
+    let pending = queue
+        .parts
+        .iter()
+        .zip(&statuses)
+        .find(|(_, status)| status.as_deref() != Some("completed"));
+    match pending {
+        // Already running, its completion calls back into on_finished
+        Some((_, Some(status)))
+            if !matches!(status.as_str(), "failed" | "cancelled" | "interrupted") => {}
+        Some((part, _)) => {
+            emit_progress(&app, &slug, "downloading", None);
+            let started = downloader::start(
+                &app,
+                part.url.clone(),
+                part.filename.clone(),
+                part.download_id.clone(),
+                Some(queue.article.clone()),
+                queue.referer.clone(),
+                TransferOptions::default(),
+            )
+            .await;
+            if let Err(e) = started {
+                fail(&app, &slug, &format!("Part {}: {}", part.number, e));
+            }
+        }
+        None => {
+            for (part, download) in queue.parts.iter().zip(&downloads) {
+                let verified = download
+                    .as_ref()
+                    .ok_or_else(|| format!("Part {} is not downloaded", part.number))
+                    .and_then(|download| verify_part(&app, part, download));
+                if let Err(e) = verified {
+                    reject_part(&app, &part.download_id, &e);
+                    fail(&app, &slug, &e);
+                    return;
+                }
+            }
+            emit_progress(&app, &slug, "extracting", None);
+            remove_queue(&app, &slug);
+            let ids: Vec<String> = queue.parts.iter().map(|p| p.download_id.clone()).collect();
+            match downloadgroups::extract_downloads(&app, &ids).await {
+                Ok(()) => emit_progress(&app, &slug, "completed", None),
+                Err(e) => emit_progress(&app, &slug, "failed", Some(&e)),
+            }
+        }
+    }
+}
+
+// Called by webview2_response when a download ends; true when it was a queued part, in which
+// case the queue decides what happens next instead of the extraction rules
+pub fn on_finished(app: &AppHandle, download_id: &str, error: Option<&str>) -> bool {
+    let slug = app
+        .state::<Mutex<SplitQueues>>()
+        .lock()
+        .ok()
+        .and_then(|queues| {
+            queues
+                .queues
+                .iter()
+                .find(|(_, q)| q.parts.iter().any(|p| p.download_id == download_id))
+                .map(|(slug, _)| slug.clone())
+        });
+    let Some(slug) = slug else {
+        return false;
+    };
+    match error {
+        Some(e) => fail(app, &slug, e),
+        None => {
+            tauri::async_runtime::spawn(advance(app.clone(), slug));
+        }
+    }
+    true
+}
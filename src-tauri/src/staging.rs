@@ -0,0 +1,190 @@
+use crate::diskio;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::AppHandle;
+
+const STAGING_PREFIX: &str = "chanomhub-staging-";
+
+// The new tree while files the archive does not replace are moved into it from the old output.
+// Deliberately not a staging name: after a crash at that point it holds the user's files.
+const MERGING_PREFIX: &str = "chanomhub-merging-";
+
+// The old output right after the swap; only files the archive replaced are left in it
+const REPLACED_PREFIX: &str = "chanomhub-replaced-";
+
+// Leftovers from a crash or forced quit; anything this old cannot belong to a running extraction
+const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+// Where an archive is extracted before it is moved into place. Without a configured folder the
+// staging folder sits next to the output, so the final move is a rename on the same drive. The
+// name depends only on the download, so a failed or interrupted extraction of it is picked up again.
+pub fn prepare(
+    staging_dir: Option<&str>,
+    output_dir: &Path,
+    download_id: &str,
+) -> Result<PathBuf, String> {
+    let name: String = download_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let parent = match staging_dir.filter(|dir| !dir.trim().is_empty()) {
+        Some(dir) => {
+            let dir = PathBuf::from(dir);
+            fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create staging folder {}: {}", dir.display(), e))?;
+            dir
+        }
+        None => output_dir
+            .parent()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| format!("{} has no parent folder", output_dir.display()))?,
+    };
+    remove_stale(&parent);
+    let staging = parent.join(format!(".{}{}", STAGING_PREFIX, name));
+    fs::create_dir_all(&staging).map_err(|e| {
+        format!(
+            "Failed to create staging folder {}: {}",
+            staging.display(),
+            e
+        )
+    })?;
+    Ok(staging)
+}
+
+// Space check before extracting `needed` bytes. A staging folder on another drive needs the room
+// twice: once to extract into, once for the copy into the output folder.
+pub fn preflight(
+    app: &AppHandle,
+    download_id: &str,
+    staging_dir: Option<&str>,
+    output_dir: &Path,
+    needed: u64,
+) -> Result<(), String> {
+    if let Some(dir) = staging_dir.filter(|dir| !dir.trim().is_empty()) {
+        diskio::preflight(app, download_id, "extraction", Path::new(dir), needed)?;
+    }
+    diskio::preflight(app, download_id, "extraction", output_dir, needed)
+}
+
+fn remove_stale(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let is_staging = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|n| n.starts_with(&format!(".{}", STAGING_PREFIX)));
+        let is_stale = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age >= STALE_AFTER);
+        if is_staging && is_stale {
+            println!(
+                "Removing leftover staging folder {}",
+                entry.path().display()
+            );
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+pub fn discard(staging: &Path) {
+    if let Err(e) = fs::remove_dir_all(staging)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        println!(
+            "Failed to remove staging folder {}: {}",
+            staging.display(),
+            e
+        );
+    }
+}
+
+fn is_empty_dir(path: &Path) -> bool {
+    fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none())
+}
+
+fn next_to(output_dir: &Path, prefix: &str) -> PathBuf {
+    output_dir.with_file_name(format!(
+        ".{}{}",
+        prefix,
+        output_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    ))
+}
+
+// Moves a finished extraction to `output_dir`. A new output folder appears in one rename. An
+// existing one keeps whatever the archive does not replace (saves, mods): those are moved into the
+// new tree, which is then swapped in, so the output is never a half-merged mix.
+pub fn commit(staging: &Path, output_dir: &Path) -> Result<(), String> {
+    if is_empty_dir(output_dir) {
+        let _ = fs::remove_dir(output_dir);
+    }
+    // Staging on another drive is copied next to the output first, so the last steps are renames
+    let incoming = next_to(output_dir, STAGING_PREFIX);
+    if incoming != staging {
+        discard(&incoming);
+        if fs::rename(staging, &incoming).is_err() {
+            if let Err(e) = diskio::move_dir_contents(staging, &incoming) {
+                discard(&incoming);
+                return Err(e);
+            }
+            discard(staging);
+        }
+    }
+    let move_error = |e: std::io::Error| {
+        format!(
+            "Failed to move extracted files to {}: {}",
+            output_dir.display(),
+            e
+        )
+    };
+    if !output_dir.exists() {
+        return fs::rename(&incoming, output_dir).map_err(move_error);
+    }
+
+    let merging = next_to(output_dir, MERGING_PREFIX);
+    fs::rename(&incoming, &merging).map_err(move_error)?;
+    keep_unreplaced(output_dir, &merging)?;
+    let replaced = next_to(output_dir, REPLACED_PREFIX);
+    discard(&replaced);
+    fs::rename(output_dir, &replaced).map_err(move_error)?;
+    if let Err(e) = fs::rename(&merging, output_dir) {
+        let _ = fs::rename(&replaced, output_dir);
+        return Err(move_error(e));
+    }
+    discard(&replaced);
+    Ok(())
+}
+
+// Moves every entry of `from` that `to` does not have into `to`; both are on the same drive
+fn keep_unreplaced(from: &Path, to: &Path) -> Result<(), String> {
+    let entries =
+        fs::read_dir(from).map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+    for entry in entries.flatten() {
+        let source = entry.path();
+        let target = to.join(entry.file_name());
+        match target.symlink_metadata() {
+            Err(_) => fs::rename(&source, &target).map_err(|e| {
+                format!(
+                    "Failed to keep {} in {}: {}",
+                    source.display(),
+                    to.display(),
+                    e
+                )
+            })?,
+            Ok(meta) if meta.is_dir() && entry.file_type().is_ok_and(|t| t.is_dir()) => {
+                keep_unreplaced(&source, &target)?
+            }
+            // Replaced by the archive
+            Ok(_) => {}
+        }
+    }
+    Ok(())
+}
@@ -1,26 +1,311 @@
-use crate::ActiveDownloads;
+use crate::{ActiveDownloads, DownloadInfo};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::fs::{self, File};
-use std::io::Write;
-use std::path::PathBuf;
-use tauri::{AppHandle, Manager};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CloudinaryConfig {
     pub cloud_name: String,
+    // Key and secret may stay empty when uploads go through an unsigned preset
+    #[serde(default)]
     pub api_key: String,
+    #[serde(default)]
     pub api_secret: String,
+    // Upload preset name; signed when a secret is set, unsigned otherwise
+    #[serde(default)]
+    pub upload_preset: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct AppState {
     pub token: Option<String>,
     pub cloudinary: Option<CloudinaryConfig>,
     pub download_dir: Option<String>,
     pub games: Option<Vec<DownloadedGameInfo>>,
+    #[serde(default)]
+    pub download_settings: DownloadSettings,
+    #[serde(default)]
+    pub extraction_settings: ExtractionSettings,
+    #[serde(default)]
+    pub library_settings: LibrarySettings,
+    #[serde(default)]
+    pub ui_prefs: UiPrefs,
+    #[serde(default)]
+    pub startup_settings: StartupSettings,
+    #[serde(default)]
+    pub telemetry: TelemetrySettings,
+    #[serde(default)]
+    pub session_limit: crate::session::SessionLimit,
+    #[serde(default)]
+    pub launch_guards: crate::launchguard::LaunchGuards,
+    #[serde(default)]
+    pub guest_mode: crate::guest::GuestModeSettings,
+    #[serde(default)]
+    pub request_settings: crate::httpconfig::RequestSettings,
+    #[serde(default)]
+    pub subscription_settings: crate::subscriptions::SubscriptionSettings,
+    #[serde(default)]
+    pub bandwidth_settings: crate::bandwidth::BandwidthSettings,
+    #[serde(default)]
+    pub retention_settings: crate::retention::RetentionSettings,
+    #[serde(default)]
+    pub article_render_settings: crate::articlerender::ArticleRenderSettings,
+    #[serde(default)]
+    pub feed_settings: crate::feeds::FeedSettings,
+    #[serde(default)]
+    pub sound_settings: crate::soundcues::SoundSettings,
+    #[serde(default)]
+    pub now_playing_settings: crate::nowplaying::NowPlayingSettings,
+    #[serde(default)]
+    pub network_settings: crate::offline::NetworkSettings,
+    // Library folder this app registered as a Windows Defender exclusion, so it can be reverted
+    #[serde(default)]
+    pub defender_exclusion: Option<String>,
+    #[serde(default)]
+    pub launch_templates: Vec<crate::launchtemplates::LaunchTemplate>,
+    // Window geometry keyed by "<label>|<monitor layout>"
+    #[serde(default)]
+    pub window_states: HashMap<String, crate::windowstate::WindowGeometry>,
+    // Folder the executable picker was last used in, per game id
+    #[serde(default)]
+    pub picker_dirs: HashMap<String, String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DownloadSettings {
+    // Minutes without progress before a download is reported as stalled (0 disables the watchdog)
+    #[serde(rename = "stallTimeoutMinutes")]
+    pub stall_timeout_minutes: u64,
+
+    #[serde(rename = "autoRestartStalled")]
+    pub auto_restart_stalled: bool,
+
+    // Write buffer for downloads in MB (clamped to 4-16)
+    #[serde(rename = "writeBufferMb")]
+    pub write_buffer_mb: u64,
+
+    // MB written between fsyncs, 0 syncs only when the file completes
+    #[serde(rename = "fsyncIntervalMb")]
+    pub fsync_interval_mb: u64,
+
+    // "auto" enables safe mode on removable drives, "always" / "never" force it
+    #[serde(rename = "safeWriteMode")]
+    pub safe_write_mode: String,
+
+    // Pause active downloads while a launched game is running and resume them when it exits
+    #[serde(rename = "pauseWhilePlaying")]
+    pub pause_while_playing: bool,
+
+    // Run downloads only after this many minutes without keyboard or mouse input, and pause them
+    // again when the user comes back
+    #[serde(rename = "idleOnly")]
+    pub idle_only: bool,
+
+    #[serde(rename = "idleMinutes")]
+    pub idle_minutes: u64,
+
+    // Keep the system awake while downloading or extracting
+    #[serde(rename = "preventSleep")]
+    pub prevent_sleep: bool,
+
+    // Pause downloads and extractions on battery at or below this charge, resume on AC power
+    #[serde(rename = "pauseOnBattery")]
+    pub pause_on_battery: bool,
+
+    #[serde(rename = "batteryThreshold")]
+    pub battery_threshold: u8,
+
+    // "preserve" keeps Zone.Identifier / quarantine on finished files, "strip" removes it once verified
+    #[serde(rename = "markOfTheWeb")]
+    pub mark_of_the_web: String,
+
+    // Hosts that serve a page, captcha or countdown instead of the file; these go through the
+    // WebView2 helper, everything else is downloaded with the app's own HTTP client
+    #[serde(rename = "browserHosts")]
+    pub browser_hosts: Vec<String>,
+
+    // Downloads running at once, the rest wait in the queue by priority (0 = no limit)
+    #[serde(rename = "maxActiveDownloads")]
+    pub max_active_downloads: usize,
+
+    // Combined speed of native downloads in KB/s, split between them by priority (0 = no limit)
+    #[serde(rename = "speedLimitKbps")]
+    pub speed_limit_kbps: u64,
+
+    // Connections per native download for hosts that accept range requests; a download can
+    // override it when started (1 = a single connection)
+    #[serde(rename = "segments")]
+    pub segments: u8,
+
+    // Hash chunks while they are written and re-fetch any that do not match the download's
+    // chunk manifest, when it came with one
+    #[serde(rename = "verifyChunks")]
+    pub verify_chunks: bool,
+}
+
+impl Default for DownloadSettings {
+    fn default() -> Self {
+        Self {
+            stall_timeout_minutes: 5,
+            auto_restart_stalled: false,
+            write_buffer_mb: 8,
+            fsync_interval_mb: 256,
+            safe_write_mode: "auto".to_string(),
+            pause_while_playing: false,
+            idle_only: false,
+            idle_minutes: 10,
+            prevent_sleep: true,
+            pause_on_battery: false,
+            battery_threshold: 50,
+            mark_of_the_web: "preserve".to_string(),
+            browser_hosts: [
+                "mega.nz",
+                "drive.google.com",
+                "mediafire.com",
+                "gofile.io",
+                "1fichier.com",
+                "pixeldrain.com",
+            ]
+            .map(String::from)
+            .to_vec(),
+            max_active_downloads: 0,
+            speed_limit_kbps: 0,
+            segments: 4,
+            verify_chunks: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ExtractionSettings {
+    // Romanize non-ASCII file names after extraction for engines that cannot open Unicode paths
+    #[serde(rename = "transliterateFilenames")]
+    pub transliterate_filenames: bool,
+
+    // What to do when a download finishes, keyed by lowercase extension:
+    // "auto" extracts right away, "ask" prompts the user, "never" leaves the file alone
+    #[serde(rename = "fileTypeRules")]
+    pub file_type_rules: HashMap<String, String>,
+
+    // Action for extensions without a rule
+    #[serde(rename = "defaultAction")]
+    pub default_action: String,
+
+    // Extract at idle IO priority (background mode on Windows) so games and the UI stay responsive
+    #[serde(rename = "lowPriorityIo")]
+    pub low_priority_io: bool,
+
+    // Folder archives are extracted into before being moved into place (e.g. on a faster drive);
+    // unset stages next to the output folder
+    #[serde(rename = "stagingDir")]
+    pub staging_dir: Option<String>,
+}
+
+impl Default for ExtractionSettings {
+    fn default() -> Self {
+        Self {
+            transliterate_filenames: false,
+            file_type_rules: ["zip", "7z", "rar"]
+                .iter()
+                .map(|ext| (ext.to_string(), "ask".to_string()))
+                .collect(),
+            default_action: "never".to_string(),
+            low_priority_io: false,
+            staging_dir: None,
+        }
+    }
+}
+
+impl ExtractionSettings {
+    pub fn action_for(&self, path: &str) -> &str {
+        Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| self.file_type_rules.get(&ext.to_lowercase()))
+            .unwrap_or(&self.default_action)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct LibrarySettings {
+    // Keep a `Games/<Title>` folder of links to the extracted games for browsing in the file manager
+    #[serde(rename = "gamesLinksEnabled")]
+    pub games_links_enabled: bool,
+
+    // Where the links are created (defaults to <download dir>/Games)
+    #[serde(rename = "gamesLinksDir")]
+    pub games_links_dir: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct UiPrefs {
+    // "system", "light" or "dark"
+    pub theme: String,
+
+    #[serde(rename = "accentColor")]
+    pub accent_color: Option<String>,
+
+    // "grid" or "list"
+    #[serde(rename = "libraryView")]
+    pub library_view: String,
+
+    // UI language code, None follows the OS
+    pub language: Option<String>,
+
+    // Always-on-top compact progress window, reopened on start
+    #[serde(rename = "progressOverlay")]
+    pub progress_overlay: bool,
+
+    // Milestone events with spoken-style summaries instead of per-chunk progress (screen readers)
+    #[serde(rename = "reducedProgressEvents")]
+    pub reduced_progress_events: bool,
+}
+
+impl Default for UiPrefs {
+    fn default() -> Self {
+        Self {
+            theme: "system".to_string(),
+            accent_color: None,
+            library_view: "grid".to_string(),
+            language: None,
+            progress_overlay: false,
+            reduced_progress_events: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct StartupSettings {
+    // Mirrors the OS login entry, which is the source of truth
+    #[serde(rename = "launchAtLogin")]
+    pub launch_at_login: bool,
+
+    // Only applies to launches from the login entry
+    #[serde(rename = "startMinimized")]
+    pub start_minimized: bool,
+
+    // Restart downloads that were cut off when the app last exited
+    #[serde(rename = "resumeInterrupted")]
+    pub resume_interrupted: bool,
+}
+
+// Off until the user opts in; nothing is sent without an endpoint
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct TelemetrySettings {
+    pub enabled: bool,
+    pub endpoint: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct LaunchConfig {
     #[serde(rename = "executablePath")]
     pub executable_path: String,
@@ -30,6 +315,50 @@ pub struct LaunchConfig {
 
     #[serde(rename = "customCommand")]
     pub custom_command: Option<String>,
+
+    // Shell commands run in the game folder before launch / after the game exits
+    #[serde(rename = "preLaunch", default)]
+    pub pre_launch: Option<String>,
+
+    #[serde(rename = "postExit", default)]
+    pub post_exit: Option<String>,
+
+    // Per-hook limit, 30s when unset
+    #[serde(rename = "hookTimeoutSecs", default)]
+    pub hook_timeout_secs: Option<u64>,
+
+    // Starts the pre-launch hook and launches the game without waiting for it, for helpers that
+    // keep running alongside the game (trainers, overlays, local servers)
+    #[serde(rename = "preLaunchDetached", default)]
+    pub pre_launch_detached: bool,
+
+    // Overrides the global session limit for this game
+    #[serde(rename = "sessionLimit", default)]
+    pub session_limit: Option<crate::session::SessionLimit>,
+
+    // Redirects Documents / AppData writes into a folder next to the game
+    #[serde(rename = "portableSaves", default)]
+    pub portable_saves: Option<crate::portablesaves::PortableSaves>,
+
+    // Windows Graphics Performance preference written for the executable, unset leaves it alone
+    #[serde(rename = "gpuPreference", default)]
+    pub gpu_preference: Option<crate::gpupreference::GpuPreference>,
+
+    // DXVK / VKD3D-Proton in the game's Wine prefix, only used by the wine launch method
+    #[serde(rename = "wineGraphics", default)]
+    pub wine_graphics: Option<crate::winegraphics::WineGraphics>,
+
+    // Controller remapper started with the game and closed when it exits
+    #[serde(rename = "inputRemapper", default)]
+    pub input_remapper: Option<crate::inputremap::InputRemapper>,
+
+    // Maximize / borderless / monitor / always-on-top once the game window shows up
+    #[serde(rename = "windowActions", default)]
+    pub window_actions: Option<crate::gamewindow::WindowActions>,
+
+    // Run as administrator: UAC on Windows, pkexec on Linux
+    #[serde(default)]
+    pub elevate: bool,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -37,22 +366,28 @@ pub struct DownloadedGameInfo {
     pub id: String,
     pub filename: String,
     pub path: String,
+    #[serde(default)]
     pub extracted: bool,
     pub extracted_path: Option<String>,
     pub downloaded_at: Option<String>,
     pub launch_config: Option<LaunchConfig>, // New field
     pub icon_path: Option<String>,           // New field
+    #[serde(default)]
+    pub article: Option<ArticleMeta>, // บทความที่เป็นต้นทางของไฟล์ (ใช้แสดงชื่อเกมจริง)
+    #[serde(default)]
+    pub launch_entries: Vec<crate::launchentries::LaunchEntry>, // ตัวเปิดอื่นของเกม (config tool, patcher)
 }
 
-impl Default for AppState {
-    fn default() -> Self {
-        Self {
-            token: None,
-            cloudinary: None,
-            download_dir: None,
-            games: None,
-        }
-    }
+// Article a download came from, so the library can show the real title instead of the mirror filename
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ArticleMeta {
+    pub slug: String,
+    pub title: Option<String>,
+    #[serde(rename = "coverImage")]
+    pub cover_image: Option<String>,
+    // Article version when the file was downloaded, compared against the live one to spot updates
+    pub version: Option<i64>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -60,35 +395,10 @@ pub struct ArticleResponse {
     pub slug: String,
     pub title: String,
     pub content: String,
-}
-
-pub async fn fetch_article_by_slug(
-    slug: String,
-    token: Option<String>,
-) -> Result<ArticleResponse, String> {
-    let api_url = format!("https://api.chanomhub.online/articles/{}", slug);
-
-    let client = reqwest::Client::new();
-    let mut request = client.get(&api_url);
-
-    if let Some(token) = token {
-        request = request.header("Authorization", format!("Bearer {}", token));
-    }
-
-    let response = request
-        .send()
-        .await
-        .map_err(|e| format!("Failed to send request: {}", e))?;
-
-    if response.status().is_success() {
-        let article: ArticleResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
-        Ok(article)
-    } else {
-        Err(format!("API request failed: {}", response.status()))
-    }
+    #[serde(rename = "mainImage", default)]
+    pub main_image: Option<String>,
+    #[serde(default)]
+    pub version: Option<i64>,
 }
 
 pub fn verify_config_file(app: &AppHandle) -> Result<(), String> {
@@ -110,8 +420,8 @@ pub fn verify_config_file(app: &AppHandle) -> Result<(), String> {
                         println!("✅ Successfully parsed JSON");
                         println!("State: {:?}", state);
 
-                        if state.token.is_some() {
-                            println!("✅ Token is set: {}", state.token.unwrap());
+                        if let Some(token) = &state.token {
+                            println!("✅ Token is set: {}", token);
                         } else {
                             println!("⚠️ Token is not set");
                         }
@@ -146,27 +456,165 @@ pub fn get_config_dir(app: &AppHandle) -> Option<PathBuf> {
     app.path().app_config_dir().ok()
 }
 
+// Older versions downloaded into the resource dir, which is read-only for installed apps and wiped on update
+pub fn get_legacy_download_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .resource_dir()
+        .ok()
+        .map(|dir| dir.join("downloads"))
+}
+
+pub fn is_legacy_download_dir(app: &AppHandle, dir: &str) -> bool {
+    get_legacy_download_dir(app).is_some_and(|legacy| Path::new(dir) == legacy)
+}
+
 pub fn get_default_download_dir(app: &AppHandle) -> Option<String> {
-    let resource_dir = app.path().resource_dir().ok()?;
-    let download_dir = resource_dir.join("downloads");
-    if !download_dir.exists() {
-        if let Err(e) = fs::create_dir_all(&download_dir) {
-            println!("Failed to create downloads directory: {}", e);
-            return None;
-        }
+    let download_dir = dirs::download_dir()
+        .map(|dir| dir.join("Chanomhub"))
+        .or_else(|| {
+            app.path()
+                .app_data_dir()
+                .ok()
+                .map(|dir| dir.join("downloads"))
+        })?;
+    if !download_dir.exists()
+        && let Err(e) = fs::create_dir_all(&download_dir)
+    {
+        println!("Failed to create downloads directory: {}", e);
+        return None;
     }
     download_dir.to_str().map(|s| s.to_string())
 }
 
+// Compared by path components, so moving C:\Games leaves C:\Games2 alone
+fn rebase_path(path: &mut String, from: &str, to: &str) {
+    if let Ok(rest) = Path::new(path.as_str()).strip_prefix(from) {
+        *path = if rest.as_os_str().is_empty() {
+            to.to_string()
+        } else {
+            Path::new(to).join(rest).to_string_lossy().to_string()
+        };
+    }
+}
+
+fn rebase_opt(path: &mut Option<String>, from: &str, to: &str) {
+    if let Some(path) = path.as_mut() {
+        rebase_path(path, from, to);
+    }
+}
+
+// Points stored download paths that lived under `from` at the same place under `to`
+pub fn rebase_download_paths(active_downloads: &mut ActiveDownloads, from: &str, to: &str) {
+    for download in active_downloads.downloads.values_mut() {
+        rebase_opt(&mut download.path, from, to);
+        rebase_opt(&mut download.part_path, from, to);
+        rebase_opt(&mut download.extracted_path, from, to);
+    }
+}
+
+pub fn rebase_game_paths(app_state: &mut AppState, from: &str, to: &str) {
+    for game in app_state.games.iter_mut().flatten() {
+        rebase_path(&mut game.path, from, to);
+        rebase_opt(&mut game.extracted_path, from, to);
+        rebase_opt(&mut game.icon_path, from, to);
+        if let Some(config) = game.launch_config.as_mut() {
+            rebase_path(&mut config.executable_path, from, to);
+        }
+        for entry in game.launch_entries.iter_mut() {
+            rebase_path(&mut entry.launch_config.executable_path, from, to);
+        }
+    }
+}
+
+// Keeps the original next to the file before anything unreadable in it is dropped on the next save
+fn keep_unreadable_copy(path: &Path, contents: &str) {
+    let copy = path.with_extension("unreadable.json");
+    match fs::write(&copy, contents) {
+        Ok(()) => println!("Kept the unreadable original as {}", copy.display()),
+        Err(e) => println!("Failed to keep a copy of {}: {}", path.display(), e),
+    }
+}
+
+// Entries written by an older or newer version in a shape this one cannot read are skipped one
+// at a time, instead of failing the whole list. Returns how many were skipped.
+fn retain_readable<T: DeserializeOwned>(entries: &mut serde_json::Value, label: &str) -> usize {
+    let readable = |entry: &serde_json::Value, key: &str| match T::deserialize(entry) {
+        Ok(_) => true,
+        Err(e) => {
+            println!("Skipping unreadable {} {}: {}", label, key, e);
+            false
+        }
+    };
+    match entries {
+        serde_json::Value::Array(list) => {
+            let before = list.len();
+            let mut index = 0;
+            list.retain(|entry| {
+                index += 1;
+                readable(entry, &format!("#{}", index))
+            });
+            before - list.len()
+        }
+        serde_json::Value::Object(map) => {
+            let before = map.len();
+            map.retain(|key, entry| readable(entry, key));
+            before - map.len()
+        }
+        _ => 0,
+    }
+}
+
+// Top-level fields that do not parse fall back to their defaults one by one, so a setting saved
+// in an unexpected shape does not reset everything else in the file
+fn parse_lenient<T: Serialize + DeserializeOwned + Default>(
+    value: serde_json::Value,
+    label: &str,
+) -> (T, usize) {
+    if let Ok(parsed) = T::deserialize(&value) {
+        return (parsed, 0);
+    }
+    let serde_json::Value::Object(fields) = value else {
+        println!("{} is not a JSON object, using defaults", label);
+        return (T::default(), 1);
+    };
+    let mut merged = match serde_json::to_value(T::default()) {
+        Ok(serde_json::Value::Object(defaults)) => defaults,
+        _ => serde_json::Map::new(),
+    };
+    let mut skipped = 0;
+    for (key, field) in fields {
+        let mut candidate = merged.clone();
+        candidate.insert(key.clone(), field);
+        match T::deserialize(&serde_json::Value::Object(candidate.clone())) {
+            Ok(_) => merged = candidate,
+            Err(e) => {
+                println!(
+                    "Using the default for unreadable {} field {}: {}",
+                    label, key, e
+                );
+                skipped += 1;
+            }
+        }
+    }
+    let parsed = T::deserialize(&serde_json::Value::Object(merged)).unwrap_or_default();
+    (parsed, skipped)
+}
+
 pub fn load_state_from_file(app: &AppHandle) -> Result<AppState, String> {
     let config_dir = get_config_dir(app).ok_or("Could not get config directory")?;
     let config_path = config_dir.join("config.json");
 
-    let mut state = if config_path.exists() {
-        let contents = fs::read_to_string(&config_path)
-            .map_err(|e| format!("Failed to read config file: {}", e))?;
-        let state: AppState = serde_json::from_str(&contents)
+    let mut state = if let Some(contents) = crate::integrity::read_verified(app, &config_path)? {
+        let mut value: serde_json::Value = serde_json::from_str(&contents)
             .map_err(|e| format!("Failed to parse config file: {}", e))?;
+        let mut skipped = value.get_mut("games").map_or(0, |games| {
+            retain_readable::<DownloadedGameInfo>(games, "game")
+        });
+        let (state, skipped_fields) = parse_lenient::<AppState>(value, "config");
+        skipped += skipped_fields;
+        if skipped > 0 {
+            keep_unreadable_copy(&config_path, &contents);
+        }
         println!("Loaded state from file: {:?}", state);
         state
     } else {
@@ -190,12 +638,9 @@ pub fn save_state_to_file(app: &AppHandle, state: &AppState) -> Result<(), Strin
     let config_path = config_dir.join("config.json");
     println!("Saving state to: {:?}", config_path);
 
-    let mut file =
-        File::create(&config_path).map_err(|e| format!("Failed to create config file: {}", e))?;
     let json = serde_json::to_string_pretty(state)
         .map_err(|e| format!("Failed to serialize state: {}", e))?;
-    file.write_all(json.as_bytes())
-        .map_err(|e| format!("Failed to write config file: {}", e))?;
+    crate::integrity::write_signed(app, &config_path, json.as_bytes())?;
 
     println!("State saved successfully");
     Ok(())
@@ -211,14 +656,14 @@ pub fn save_active_downloads_to_file(
     let downloads_path = config_dir.join("active_downloads.json");
     println!("Saving active downloads to: {:?}", downloads_path);
 
-    let mut file = File::create(&downloads_path)
-        .map_err(|e| format!("Failed to create active downloads file: {}", e))?;
     let json = serde_json::to_string_pretty(active_downloads)
         .map_err(|e| format!("Failed to serialize active downloads: {}", e))?;
-    file.write_all(json.as_bytes())
-        .map_err(|e| format!("Failed to write active downloads file: {}", e))?;
+    crate::integrity::write_signed(app, &downloads_path, json.as_bytes())?;
 
     println!("Active downloads saved successfully");
+    // Every window (main, downloads, progress overlay) refreshes from this
+    let downloads: Vec<_> = active_downloads.downloads.values().collect();
+    let _ = app.emit("active-downloads-changed", &downloads);
     Ok(())
 }
 
@@ -226,22 +671,78 @@ pub fn load_active_downloads_from_file(app: &AppHandle) -> Result<ActiveDownload
     let config_dir = get_config_dir(app).ok_or("Could not get config directory")?;
     let downloads_path = config_dir.join("active_downloads.json");
 
-    if downloads_path.exists() {
-        let contents = fs::read_to_string(&downloads_path)
-            .map_err(|e| format!("Failed to read active downloads file: {}", e))?;
-        let active_downloads: ActiveDownloads = serde_json::from_str(&contents)
+    if let Some(contents) = crate::integrity::read_verified(app, &downloads_path)? {
+        let mut value: serde_json::Value = serde_json::from_str(&contents)
             .map_err(|e| format!("Failed to parse active downloads file: {}", e))?;
+        let mut skipped = value.get_mut("downloads").map_or(0, |downloads| {
+            retain_readable::<DownloadInfo>(downloads, "download")
+        });
+        let (mut active_downloads, skipped_fields) =
+            parse_lenient::<ActiveDownloads>(value, "active downloads");
+        skipped += skipped_fields;
+        if skipped > 0 {
+            keep_unreadable_copy(&downloads_path, &contents);
+        }
+        // Very old files did not repeat the id inside each entry
+        for (id, download) in active_downloads.downloads.iter_mut() {
+            if download.id.is_empty() {
+                download.id = id.clone();
+            }
+        }
         Ok(active_downloads)
     } else {
         Ok(ActiveDownloads::default())
     }
 }
 
-pub fn cleanup_active_downloads(active_downloads: &mut ActiveDownloads) {
+// Returns the ids of the downloads that were cut off
+pub fn cleanup_active_downloads(active_downloads: &mut ActiveDownloads) -> Vec<String> {
+    let mut interrupted = Vec::new();
     for download in active_downloads.downloads.values_mut() {
-        if download.status == "starting" || download.status == "downloading" {
-            download.status = "failed".to_string();
+        // Paused by the user: stays paused, and continues natively from the .part file since the
+        // helper's transfer is gone. Downloads that never started have nothing to continue.
+        if download.status == "paused" && download.paused_at.is_some() {
+            if download.provider.is_some() {
+                download.provider = Some("native".to_string());
+            }
+            download.bytes_downloaded = download.part_path.as_ref().and_then(|path| {
+                crate::segmented::downloaded(Path::new(path))
+                    .or_else(|| fs::metadata(path).ok().map(|m| m.len()))
+            });
+            continue;
+        }
+        if matches!(
+            download.status.as_str(),
+            "starting" | "downloading" | "paused" | "transferring"
+        ) {
+            // What reached the disk stays, so resume_download can continue from there
+            let kept = download
+                .part_path
+                .as_ref()
+                .and_then(|path| fs::metadata(path).ok())
+                .map(|m| m.len())
+                .filter(|len| *len > 0);
+            download.status = if kept.is_some() {
+                "interrupted".to_string()
+            } else {
+                "failed".to_string()
+            };
+            download.bytes_downloaded = kept;
             download.error = Some("Download interrupted due to application restart".to_string());
+            interrupted.push(download.id.clone());
         }
     }
+    // Queued and paused downloads keep waiting; the token lets them be cancelled before they continue
+    let queued: Vec<String> = active_downloads
+        .downloads
+        .values()
+        .filter(|d| d.status == crate::scheduler::QUEUED || d.status == "paused")
+        .map(|d| d.id.clone())
+        .collect();
+    for download_id in queued {
+        active_downloads
+            .tokens
+            .insert(download_id, tokio_util::sync::CancellationToken::new());
+    }
+    interrupted
 }
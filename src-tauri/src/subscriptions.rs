@@ -0,0 +1,294 @@
+use crate::state::{self, AppState};
+use crate::{httpconfig, offline, poller};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+// Same index and public search-only key the article browser uses
+const SEARCH_URL: &str = "https://search.chanomhub.online/indexes/article/search";
+const SEARCH_KEY: &str = "d7fc12050f11a0b6a069ec2b40570e83fca767d76a72a05e5bb3c0378fab2355";
+
+const SEEN_FILE: &str = "subscription_seen.json";
+
+// Newest articles looked at per subscription on each poll
+const HITS_PER_SUBSCRIPTION: usize = 20;
+
+// Far more than HITS_PER_SUBSCRIPTION times any sensible number of subscriptions
+const MAX_SEEN_IDS: usize = 5000;
+
+const MIN_POLL_MINUTES: u64 = 5;
+
+static POLLING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum SubscriptionKind {
+    Tag,
+    Author,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Subscription {
+    pub kind: SubscriptionKind,
+    pub id: i64,
+    // Display name, kept so notifications can say why an article showed up
+    pub name: String,
+}
+
+impl Subscription {
+    fn key(&self) -> String {
+        match self.kind {
+            SubscriptionKind::Tag => format!("tag:{}", self.id),
+            SubscriptionKind::Author => format!("author:{}", self.id),
+        }
+    }
+
+    fn filter(&self) -> String {
+        match self.kind {
+            SubscriptionKind::Tag => format!("tags.id = \"{}\"", self.id),
+            SubscriptionKind::Author => format!("author.id = \"{}\"", self.id),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SubscriptionSettings {
+    pub subscriptions: Vec<Subscription>,
+    // 0 turns background polling off; manual checks still work
+    #[serde(rename = "pollIntervalMinutes")]
+    pub poll_interval_minutes: u64,
+    #[serde(rename = "showNotifications")]
+    pub show_notifications: bool,
+}
+
+impl Default for SubscriptionSettings {
+    fn default() -> Self {
+        Self {
+            subscriptions: Vec::new(),
+            poll_interval_minutes: 30,
+            show_notifications: true,
+        }
+    }
+}
+
+impl SubscriptionSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        poller::validate_interval(self.poll_interval_minutes, MIN_POLL_MINUTES)
+    }
+}
+
+// Articles already announced, kept on disk so a restart does not announce them again
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SeenArticles {
+    // Subscriptions whose current articles were recorded without a notification.
+    // A new subscription starts from what exists now instead of announcing its whole back catalogue.
+    seeded: HashSet<String>,
+    ids: VecDeque<i64>,
+}
+
+impl SeenArticles {
+    fn contains(&self, id: i64) -> bool {
+        self.ids.contains(&id)
+    }
+
+    fn insert(&mut self, id: i64) {
+        if self.contains(id) {
+            return;
+        }
+        if self.ids.len() >= MAX_SEEN_IDS {
+            self.ids.pop_front();
+        }
+        self.ids.push_back(id);
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct NewArticle {
+    pub id: i64,
+    pub slug: String,
+    pub title: String,
+    #[serde(rename = "mainImage")]
+    pub main_image: Option<String>,
+    // Every subscription the article matched, so one article is announced once
+    #[serde(rename = "matchedBy")]
+    pub matched_by: Vec<Subscription>,
+}
+
+fn seen_path(app: &AppHandle) -> Option<PathBuf> {
+    state::get_config_dir(app).map(|dir| dir.join(SEEN_FILE))
+}
+
+pub fn load_seen(app: &AppHandle) -> SeenArticles {
+    seen_path(app)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_seen(app: &AppHandle, seen: &SeenArticles) {
+    let Some(path) = seen_path(app) else {
+        return;
+    };
+    let result = serde_json::to_string(seen)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        println!("Failed to save seen articles: {}", e);
+    }
+}
+
+fn settings(app: &AppHandle) -> SubscriptionSettings {
+    app.state::<Mutex<AppState>>()
+        .lock()
+        .map(|app_state| app_state.subscription_settings.clone())
+        .unwrap_or_default()
+}
+
+async fn search(
+    client: &reqwest::Client,
+    subscription: &Subscription,
+) -> Result<Vec<serde_json::Value>, String> {
+    let payload = serde_json::json!({
+        "q": "*",
+        "filter": [subscription.filter()],
+        "sort": ["createdAt:desc"],
+        "limit": HITS_PER_SUBSCRIPTION
+    });
+    let response = client
+        .post(SEARCH_URL)
+        .bearer_auth(SEARCH_KEY)
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to search articles: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Article search failed: {}", response.status()));
+    }
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse search response: {}", e))?;
+    Ok(body
+        .get("hits")
+        .and_then(|hits| hits.as_array())
+        .cloned()
+        .unwrap_or_default())
+}
+
+fn parse_hit(hit: &serde_json::Value) -> Option<NewArticle> {
+    let text = |key: &str| hit.get(key).and_then(|v| v.as_str()).map(|v| v.to_string());
+    Some(NewArticle {
+        id: hit.get("id")?.as_i64()?,
+        slug: text("slug")?,
+        title: text("title").unwrap_or_default(),
+        main_image: text("mainImage"),
+        matched_by: Vec::new(),
+    })
+}
+
+fn announce(app: &AppHandle, articles: &[NewArticle], show_notifications: bool) {
+    for article in articles {
+        let _ = app.emit("new-article", article);
+    }
+    if !show_notifications || articles.is_empty() {
+        return;
+    }
+    let (title, body) = match articles {
+        [article] => {
+            let names: Vec<&str> = article.matched_by.iter().map(|s| s.name.as_str()).collect();
+            (
+                format!("New article: {}", article.title),
+                format!("From your subscriptions: {}", names.join(", ")),
+            )
+        }
+        _ => (
+            format!("{} new articles", articles.len()),
+            articles
+                .iter()
+                .map(|a| a.title.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+    };
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        println!("Failed to show notification: {}", e);
+    }
+}
+
+// Looks for articles that appeared since the last check and announces them
+pub async fn check(app: &AppHandle) -> Result<Vec<NewArticle>, String> {
+    if POLLING.swap(true, Ordering::SeqCst) {
+        return Err("A subscription check is already running".to_string());
+    }
+    let result = poll(app).await;
+    POLLING.store(false, Ordering::SeqCst);
+    result
+}
+
+async fn poll(app: &AppHandle) -> Result<Vec<NewArticle>, String> {
+    let settings = settings(app);
+    let client = httpconfig::client(app)?;
+
+    // The lock is not held across requests, so results are gathered first and compared afterwards
+    let mut results = Vec::new();
+    for subscription in &settings.subscriptions {
+        let _permit = offline::api_permit(app).await?;
+        match search(&client, subscription).await {
+            Ok(hits) => results.push((subscription, hits)),
+            Err(e) => println!("Subscription check for {} failed: {}", subscription.name, e),
+        }
+    }
+
+    let seen = app.state::<Mutex<SeenArticles>>();
+    let mut seen = seen
+        .lock()
+        .map_err(|e| format!("Failed to lock seen articles: {}", e))?;
+    let keys: HashSet<String> = settings.subscriptions.iter().map(|s| s.key()).collect();
+    seen.seeded.retain(|key| keys.contains(key));
+
+    let mut found: HashMap<i64, NewArticle> = HashMap::new();
+    let mut order = Vec::new();
+    for (subscription, hits) in results {
+        let articles = hits.iter().filter_map(parse_hit);
+        if seen.seeded.insert(subscription.key()) {
+            for article in articles {
+                seen.insert(article.id);
+            }
+            continue;
+        }
+        for article in articles.filter(|a| !seen.contains(a.id)) {
+            let entry = found.entry(article.id).or_insert_with(|| {
+                order.push(article.id);
+                article
+            });
+            entry.matched_by.push(subscription.clone());
+        }
+    }
+
+    let articles: Vec<NewArticle> = order
+        .into_iter()
+        .filter_map(|id| found.remove(&id))
+        .collect();
+    for article in &articles {
+        seen.insert(article.id);
+    }
+    save_seen(app, &seen);
+    drop(seen);
+
+    if !articles.is_empty() {
+        println!("Found {} new articles from subscriptions", articles.len());
+    }
+    announce(app, &articles, settings.show_notifications);
+    Ok(articles)
+}
+
+// Minutes between background checks for poller, 0 while polling is off
+pub fn poll_interval(app: &AppHandle) -> u64 {
+    settings(app).poll_interval_minutes
+}
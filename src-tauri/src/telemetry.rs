@@ -0,0 +1,238 @@
+use crate::httpconfig;
+use crate::offline;
+use crate::state::{self, AppState};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const QUEUE_FILE: &str = "telemetry_queue.json";
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Caps how much piles up when no endpoint is configured or it stays unreachable
+const MAX_DISTINCT_EVENTS: usize = 200;
+
+const MAX_NAME_LEN: usize = 64;
+
+// Set while a save is queued, so a burst of events writes the file once
+static SAVE_PENDING: AtomicBool = AtomicBool::new(false);
+
+// Held from snapshot to write, so an older snapshot can never land after a newer one
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
+
+// Counts only: no paths, filenames, URLs or error messages ever go into the queue
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TelemetryEvent {
+    // "feature" or "error"
+    pub kind: String,
+    pub name: String,
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TelemetryQueue {
+    pub events: Vec<TelemetryEvent>,
+    pub since: Option<String>,
+}
+
+// Exactly what a flush would POST
+#[derive(Serialize, Clone, Debug)]
+pub struct TelemetryPayload {
+    #[serde(rename = "appVersion")]
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub since: Option<String>,
+    pub events: Vec<TelemetryEvent>,
+}
+
+fn queue_path(app: &AppHandle) -> Option<PathBuf> {
+    state::get_config_dir(app).map(|dir| dir.join(QUEUE_FILE))
+}
+
+pub fn load_queue(app: &AppHandle) -> TelemetryQueue {
+    queue_path(app)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// Callers may hold other locks (record runs under the downloads lock), so the queue is written
+// on a blocking thread from a snapshot taken there, never while the queue lock is held
+fn save_queue(app: &AppHandle) {
+    if SAVE_PENDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let _guard = SAVE_LOCK.lock();
+        SAVE_PENDING.store(false, Ordering::SeqCst);
+        let Some(path) = queue_path(&app) else {
+            return;
+        };
+        let snapshot = app
+            .state::<Mutex<TelemetryQueue>>()
+            .lock()
+            .map_err(|e| e.to_string())
+            .and_then(|queue| serde_json::to_string_pretty(&*queue).map_err(|e| e.to_string()));
+        let result = snapshot.and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            println!("Failed to save telemetry queue: {}", e);
+        }
+    });
+}
+
+// Names come from code (and the UI), never from user data, so anything that looks free-form is rejected
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-' | '.'))
+}
+
+fn is_enabled(app: &AppHandle) -> bool {
+    app.state::<Mutex<AppState>>()
+        .lock()
+        .is_ok_and(|app_state| app_state.telemetry.enabled)
+}
+
+// Counts one occurrence; does nothing unless the user opted in
+pub fn record(app: &AppHandle, kind: &str, name: &str) {
+    if !is_valid_name(name) || !is_enabled(app) {
+        return;
+    }
+    {
+        let queue = app.state::<Mutex<TelemetryQueue>>();
+        let Ok(mut queue) = queue.lock() else {
+            return;
+        };
+        if !count(&mut queue, kind, name) {
+            return;
+        }
+    }
+    save_queue(app);
+}
+
+// False when the event is new and the queue is already full
+fn count(queue: &mut TelemetryQueue, kind: &str, name: &str) -> bool {
+    if queue.since.is_none() {
+        queue.since = Some(chrono::Utc::now().to_rfc3339());
+    }
+    let full = queue.events.len() >= MAX_DISTINCT_EVENTS;
+    match queue
+        .events
+        .iter_mut()
+        .find(|e| e.kind == kind && e.name == name)
+    {
+        Some(event) => event.count += 1,
+        None if full => return false,
+        None => queue.events.push(TelemetryEvent {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            count: 1,
+        }),
+    }
+    true
+}
+
+pub fn pending(app: &AppHandle) -> Result<TelemetryPayload, String> {
+    let queue = app.state::<Mutex<TelemetryQueue>>();
+    let queue = queue
+        .lock()
+        .map_err(|e| format!("Failed to lock telemetry queue: {}", e))?;
+    Ok(TelemetryPayload {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        since: queue.since.clone(),
+        events: queue.events.clone(),
+    })
+}
+
+pub fn clear(app: &AppHandle) -> Result<(), String> {
+    let queue = app.state::<Mutex<TelemetryQueue>>();
+    let mut queue = queue
+        .lock()
+        .map_err(|e| format!("Failed to lock telemetry queue: {}", e))?;
+    *queue = TelemetryQueue::default();
+    drop(queue);
+    save_queue(app);
+    Ok(())
+}
+
+// Sends the queue to the configured endpoint and clears it; returns the number of events sent
+pub async fn flush(app: &AppHandle) -> Result<usize, String> {
+    let endpoint = {
+        let state = app.state::<Mutex<AppState>>();
+        let app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        if !app_state.telemetry.enabled {
+            return Ok(0);
+        }
+        app_state.telemetry.endpoint.clone()
+    };
+    let Some(endpoint) = endpoint.filter(|e| !e.trim().is_empty()) else {
+        return Ok(0);
+    };
+    // Events stay queued until the app is back online
+    offline::ensure_online(app)?;
+
+    let payload = pending(app)?;
+    if payload.events.is_empty() {
+        return Ok(0);
+    }
+    let sent = payload.events.len();
+    let response = httpconfig::client(app)?
+        .post(&endpoint)
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send telemetry: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Telemetry endpoint responded with {}",
+            response.status()
+        ));
+    }
+
+    // Events recorded while the request was in flight stay queued for the next flush
+    let queue = app.state::<Mutex<TelemetryQueue>>();
+    let mut queue = queue
+        .lock()
+        .map_err(|e| format!("Failed to lock telemetry queue: {}", e))?;
+    for sent_event in &payload.events {
+        if let Some(event) = queue
+            .events
+            .iter_mut()
+            .find(|e| e.kind == sent_event.kind && e.name == sent_event.name)
+        {
+            event.count = event.count.saturating_sub(sent_event.count);
+        }
+    }
+    queue.events.retain(|e| e.count > 0);
+    if queue.events.is_empty() {
+        queue.since = None;
+    }
+    drop(queue);
+    save_queue(app);
+    println!("Sent {} telemetry events to {}", sent, endpoint);
+    Ok(sent)
+}
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = flush(&app).await {
+                println!("Telemetry flush failed: {}", e);
+            }
+        }
+    });
+}
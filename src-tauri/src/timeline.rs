@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+// Oldest entries are dropped from what get_download_timeline returns past this
+const MAX_ENTRIES: usize = 500;
+
+// One state change of a download or its extraction, and what caused it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TimelineEntry {
+    pub at: String,
+    // "download", "extraction" or "event" for things that do not change the state (stalls, requests)
+    pub kind: String,
+    pub state: String,
+    // sidecar, sidecar-exit, native, user, watchdog, autopause, bandwidth, startup, extraction, verify
+    pub source: String,
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+// Next to the per-game launch logs, so "clear logs" removes these too
+fn timeline_path(app: &AppHandle, download_id: &str) -> Option<PathBuf> {
+    let name: String = download_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let dir = app.path().app_log_dir().ok()?.join("downloads");
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join(format!("{}.jsonl", name)))
+}
+
+pub fn record(
+    app: &AppHandle,
+    download_id: &str,
+    kind: &str,
+    state: &str,
+    source: &str,
+    detail: Option<&str>,
+) {
+    let Some(path) = timeline_path(app, download_id) else {
+        return;
+    };
+    let entry = TimelineEntry {
+        at: chrono::Utc::now().to_rfc3339(),
+        kind: kind.to_string(),
+        state: state.to_string(),
+        source: source.to_string(),
+        detail: detail.map(|d| d.to_string()),
+    };
+    let Ok(line) = serde_json::to_string(&entry) else {
+        return;
+    };
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+    if let Err(e) = written {
+        println!(
+            "Failed to write download timeline {}: {}",
+            path.display(),
+            e
+        );
+    }
+}
+
+pub fn read(app: &AppHandle, download_id: &str) -> Result<Vec<TimelineEntry>, String> {
+    let Some(path) = timeline_path(app, download_id) else {
+        return Err("Log directory is not available".to_string());
+    };
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read download timeline: {}", e)),
+    };
+    // A line cut off by a crash is skipped rather than failing the whole timeline
+    let entries: Vec<TimelineEntry> = contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    Ok(entries[entries.len().saturating_sub(MAX_ENTRIES)..].to_vec())
+}
@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Kept next to the extracted files so the original Unicode names can always be looked up again
+pub const MAP_FILE_NAME: &str = ".chanomhub-filenames.json";
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct FilenameMap {
+    // romanized relative path -> original relative path (forward slashes)
+    pub entries: BTreeMap<String, String>,
+}
+
+// Romanizes a single path component, keeping it a valid filename on every platform
+pub fn romanize(name: &str) -> String {
+    let romanized = deunicode::deunicode_with_tofu(name, "_")
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        // deunicode separates CJK syllables with spaces, which would otherwise end up before the extension
+        .replace(" .", ".");
+    let trimmed = romanized.trim().trim_end_matches('.');
+    if trimmed.is_empty() {
+        "_".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+fn unique_name(dir: &Path, name: &str, taken: &HashSet<String>) -> String {
+    let candidate_free = |candidate: &str| {
+        !taken.contains(&candidate.to_lowercase()) && !dir.join(candidate).exists()
+    };
+    if candidate_free(name) {
+        return name.to_string();
+    }
+
+    let (stem, ext) = match name.rfind('.') {
+        Some(i) if i > 0 => (&name[..i], &name[i..]),
+        _ => (name, ""),
+    };
+    (1..)
+        .map(|n| format!("{}_{}{}", stem, n, ext))
+        .find(|candidate| candidate_free(candidate))
+        .unwrap()
+}
+
+fn join_rel(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+// Moves `src` onto `dst`, merging directories and replacing files left by an earlier extraction
+fn replace_entry(src: &Path, dst: &Path) -> Result<(), String> {
+    if src.is_dir() && dst.is_dir() {
+        let entries = fs::read_dir(src)
+            .map_err(|e| format!("Failed to read directory {}: {}", src.display(), e))?;
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            replace_entry(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+        return fs::remove_dir(src)
+            .map_err(|e| format!("Failed to remove directory {}: {}", src.display(), e));
+    }
+
+    if dst.is_dir() {
+        fs::remove_dir_all(dst)
+    } else if dst.exists() {
+        fs::remove_file(dst)
+    } else {
+        Ok(())
+    }
+    .map_err(|e| format!("Failed to replace {}: {}", dst.display(), e))?;
+    fs::rename(src, dst).map_err(|e| {
+        format!(
+            "Failed to rename {} to {}: {}",
+            src.display(),
+            dst.display(),
+            e
+        )
+    })
+}
+
+fn transliterate_dir(
+    root: &Path,
+    new_rel: &str,
+    orig_rel: &str,
+    previous: &FilenameMap,
+    map: &mut FilenameMap,
+) -> Result<(), String> {
+    let dir = if new_rel.is_empty() {
+        root.to_path_buf()
+    } else {
+        root.join(new_rel)
+    };
+    let entries = fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read directory {}: {}", dir.display(), e))?;
+
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    let mut taken: HashSet<String> = names
+        .iter()
+        .filter(|name| name.is_ascii())
+        .map(|name| name.to_lowercase())
+        .collect();
+
+    for name in names {
+        if new_rel.is_empty() && name == MAP_FILE_NAME {
+            continue;
+        }
+
+        let child_orig;
+        let new_name = if name.is_ascii() {
+            // Names romanized by an earlier run keep pointing at their original
+            child_orig = previous
+                .entries
+                .get(&join_rel(new_rel, &name))
+                .cloned()
+                .unwrap_or_else(|| join_rel(orig_rel, &name));
+            name
+        } else {
+            child_orig = join_rel(orig_rel, &name);
+            let romanized = romanize(&name);
+            let reuse = previous.entries.get(&join_rel(new_rel, &romanized)) == Some(&child_orig);
+            let new_name = if reuse {
+                romanized
+            } else {
+                unique_name(&dir, &romanized, &taken)
+            };
+            replace_entry(&dir.join(&name), &dir.join(&new_name))?;
+            taken.insert(new_name.to_lowercase());
+            new_name
+        };
+
+        let child_new = join_rel(new_rel, &new_name);
+        if child_new != child_orig {
+            map.entries.insert(child_new.clone(), child_orig.clone());
+        }
+        if dir.join(&new_name).is_dir() {
+            transliterate_dir(root, &child_new, &child_orig, previous, map)?;
+        }
+    }
+    Ok(())
+}
+
+// Renames every non-ASCII file and folder under `root` to a romanized name and writes the mapping file
+pub fn transliterate_tree(root: &Path) -> Result<FilenameMap, String> {
+    let previous = load_map(root).unwrap_or_default();
+    let mut map = FilenameMap::default();
+    transliterate_dir(root, "", "", &previous, &mut map)?;
+    if map.entries.is_empty() {
+        return Ok(map);
+    }
+
+    println!(
+        "Transliterated {} paths under {}",
+        map.entries.len(),
+        root.display()
+    );
+    let json = serde_json::to_string_pretty(&map)
+        .map_err(|e| format!("Failed to serialize filename map: {}", e))?;
+    fs::write(root.join(MAP_FILE_NAME), json)
+        .map_err(|e| format!("Failed to write filename map: {}", e))?;
+    Ok(map)
+}
+
+pub fn load_map(root: &Path) -> Option<FilenameMap> {
+    let content = fs::read_to_string(root.join(MAP_FILE_NAME)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+// Maps a path inside a transliterated folder back to the name it had in the archive
+pub fn original_path(root: &Path, path: &Path) -> Option<PathBuf> {
+    let rel = path.strip_prefix(root).ok()?;
+    let rel = rel.to_string_lossy().replace('\\', "/");
+    let map = load_map(root)?;
+    map.entries.get(&rel).map(|orig| root.join(orig))
+}
@@ -0,0 +1,278 @@
+use crate::state::{self, AppState, DownloadedGameInfo};
+use crate::{ActiveDownloads, DownloadInfo};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+const JOURNAL_FILE: &str = "undo_journal.json";
+
+// Operations older than this can no longer be undone and their trashed files are deleted
+const UNDO_WINDOW_MINUTES: i64 = 10;
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60);
+
+// Next to the deleted file, so moving into the trash is a rename on the same drive
+const TRASH_FOLDER_NAME: &str = ".chanomhub-trash";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TrashedPath {
+    pub original: PathBuf,
+    pub trashed: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JournalEntry {
+    pub id: String,
+    // "delete_downloads", "remove_games" or "retention_cleanup"
+    pub kind: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    pub downloads: Vec<DownloadInfo>,
+    pub games: Vec<DownloadedGameInfo>,
+    pub files: Vec<TrashedPath>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct UndoJournal {
+    pub entries: Vec<JournalEntry>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct UndoResult {
+    pub kind: String,
+    #[serde(rename = "restoredDownloads")]
+    pub restored_downloads: usize,
+    #[serde(rename = "restoredGames")]
+    pub restored_games: usize,
+    #[serde(rename = "restoredFiles")]
+    pub restored_files: usize,
+    // Things that could not be put back, e.g. a new file now sits at the original path
+    pub failed: Vec<String>,
+}
+
+fn journal_path(app: &AppHandle) -> Option<PathBuf> {
+    state::get_config_dir(app).map(|dir| dir.join(JOURNAL_FILE))
+}
+
+pub fn load_journal(app: &AppHandle) -> UndoJournal {
+    journal_path(app)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_journal(app: &AppHandle, journal: &UndoJournal) {
+    let Some(path) = journal_path(app) else {
+        return;
+    };
+    let result = serde_json::to_string_pretty(journal)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        println!("Failed to save undo journal: {}", e);
+    }
+}
+
+fn is_expired(entry: &JournalEntry, now: chrono::DateTime<chrono::Utc>) -> bool {
+    chrono::DateTime::parse_from_rfc3339(&entry.created_at)
+        .ok()
+        .map(|created| now - created.with_timezone(&chrono::Utc))
+        .is_none_or(|age| age > chrono::Duration::minutes(UNDO_WINDOW_MINUTES))
+}
+
+pub fn new_entry(kind: &str) -> JournalEntry {
+    let now = chrono::Utc::now();
+    JournalEntry {
+        id: format!("{}-{}", kind, now.timestamp_millis()),
+        kind: kind.to_string(),
+        created_at: now.to_rfc3339(),
+        downloads: Vec::new(),
+        games: Vec::new(),
+        files: Vec::new(),
+    }
+}
+
+// Moves a file or folder into the trash instead of deleting it
+pub fn trash(entry: &mut JournalEntry, path: &Path) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(format!("Cannot move {} to the trash", path.display()));
+    };
+    let trash_dir = parent.join(TRASH_FOLDER_NAME).join(&entry.id);
+    fs::create_dir_all(&trash_dir)
+        .map_err(|e| format!("Failed to create {}: {}", trash_dir.display(), e))?;
+    let trashed = trash_dir.join(name);
+    fs::rename(path, &trashed)
+        .map_err(|e| format!("Failed to move {} to the trash: {}", path.display(), e))?;
+    entry.files.push(TrashedPath {
+        original: path.to_path_buf(),
+        trashed,
+    });
+    Ok(())
+}
+
+pub fn record(app: &AppHandle, entry: JournalEntry) {
+    if entry.downloads.is_empty() && entry.games.is_empty() && entry.files.is_empty() {
+        return;
+    }
+    let journal = app.state::<Mutex<UndoJournal>>();
+    let Ok(mut journal) = journal.lock() else {
+        return;
+    };
+    journal.entries.push(entry);
+    save_journal(app, &journal);
+}
+
+fn remove_trashed(file: &TrashedPath) {
+    let result = if file.trashed.is_dir() {
+        fs::remove_dir_all(&file.trashed)
+    } else {
+        fs::remove_file(&file.trashed)
+    };
+    if let Err(e) = result
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        println!("Failed to empty trash {}: {}", file.trashed.display(), e);
+    }
+    // Drops the per-operation folder and the trash folder once they are empty
+    for dir in file.trashed.ancestors().skip(1).take(2) {
+        if fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
+
+// Deletes trashed files of operations that fell out of the undo window
+pub fn purge_expired(app: &AppHandle) {
+    let expired: Vec<JournalEntry> = {
+        let journal = app.state::<Mutex<UndoJournal>>();
+        let Ok(mut journal) = journal.lock() else {
+            return;
+        };
+        let now = chrono::Utc::now();
+        let (expired, kept): (Vec<_>, Vec<_>) = journal
+            .entries
+            .drain(..)
+            .partition(|entry| is_expired(entry, now));
+        journal.entries = kept;
+        if !expired.is_empty() {
+            save_journal(app, &journal);
+        }
+        expired
+    };
+    for entry in expired {
+        for file in &entry.files {
+            remove_trashed(file);
+        }
+    }
+}
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            purge_expired(&app);
+        }
+    });
+}
+
+pub fn undo_last(app: &AppHandle) -> Result<UndoResult, String> {
+    let entry = {
+        let journal = app.state::<Mutex<UndoJournal>>();
+        let mut journal = journal
+            .lock()
+            .map_err(|e| format!("Failed to lock undo journal: {}", e))?;
+        let now = chrono::Utc::now();
+        let index = journal
+            .entries
+            .iter()
+            .rposition(|entry| !is_expired(entry, now))
+            .ok_or("Nothing to undo")?;
+        let entry = journal.entries.remove(index);
+        save_journal(app, &journal);
+        entry
+    };
+
+    let mut result = UndoResult {
+        kind: entry.kind.clone(),
+        restored_downloads: 0,
+        restored_games: 0,
+        restored_files: 0,
+        failed: Vec::new(),
+    };
+
+    for file in &entry.files {
+        if file.original.exists() {
+            result.failed.push(format!(
+                "{} already exists, kept the deleted copy at {}",
+                file.original.display(),
+                file.trashed.display()
+            ));
+            continue;
+        }
+        match fs::rename(&file.trashed, &file.original) {
+            Ok(()) => {
+                result.restored_files += 1;
+                for dir in file.trashed.ancestors().skip(1).take(2) {
+                    if fs::remove_dir(dir).is_err() {
+                        break;
+                    }
+                }
+            }
+            Err(e) => result.failed.push(format!(
+                "Failed to restore {}: {}",
+                file.original.display(),
+                e
+            )),
+        }
+    }
+
+    if !entry.downloads.is_empty() {
+        let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+        let mut downloads = active_downloads
+            .write()
+            .map_err(|e| format!("Failed to lock active downloads: {}", e))?;
+        for download in &entry.downloads {
+            if downloads.downloads.contains_key(&download.id) {
+                continue;
+            }
+            downloads
+                .downloads
+                .insert(download.id.clone(), download.clone());
+            result.restored_downloads += 1;
+        }
+        state::save_active_downloads_to_file(app, &downloads)?;
+    }
+
+    if !entry.games.is_empty() {
+        let state = app.state::<Mutex<AppState>>();
+        let mut app_state = state
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?;
+        let games = app_state.games.get_or_insert_with(Vec::new);
+        for game in &entry.games {
+            if games.iter().any(|g| g.id == game.id) {
+                continue;
+            }
+            games.push(game.clone());
+            result.restored_games += 1;
+        }
+        state::save_state_to_file(app, &app_state)?;
+        if let Err(e) = crate::gamelinks::sync(&app_state) {
+            println!("Failed to update games folder links: {}", e);
+        }
+    }
+
+    println!(
+        "Undid {}: {} downloads, {} games, {} files restored",
+        entry.kind, result.restored_downloads, result.restored_games, result.restored_files
+    );
+    let _ = app.emit("undo-applied", &result);
+    Ok(result)
+}
@@ -0,0 +1,141 @@
+use crate::ActiveDownloads;
+use crate::sidecar;
+use crate::state::AppState;
+use crate::timeline;
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+struct ProgressMark {
+    progress: f32,
+    since: Instant,
+    stalled: bool,
+}
+
+struct ActiveSnapshot {
+    id: String,
+    filename: String,
+    progress: f32,
+    provider: Option<String>,
+}
+
+fn snapshot_active(app: &AppHandle) -> Vec<ActiveSnapshot> {
+    let active_downloads = app.state::<RwLock<ActiveDownloads>>();
+    let downloads = match active_downloads.read() {
+        Ok(downloads) => downloads,
+        Err(e) => {
+            println!("Watchdog failed to read active downloads: {}", e);
+            return Vec::new();
+        }
+    };
+    downloads
+        .downloads
+        .values()
+        .filter(|d| d.status == "starting" || d.status == "downloading")
+        .map(|d| ActiveSnapshot {
+            id: d.id.clone(),
+            filename: d.filename.clone(),
+            progress: d.progress,
+            provider: d.provider.clone(),
+        })
+        .collect()
+}
+
+// Periodically flags downloads whose progress has not moved for the configured time
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut marks: HashMap<String, ProgressMark> = HashMap::new();
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let settings = match app.state::<Mutex<AppState>>().lock() {
+                Ok(app_state) => app_state.download_settings.clone(),
+                Err(e) => {
+                    println!("Watchdog failed to lock state: {}", e);
+                    continue;
+                }
+            };
+
+            let active = snapshot_active(&app);
+            marks.retain(|id, _| active.iter().any(|d| &d.id == id));
+            if settings.stall_timeout_minutes == 0 {
+                continue;
+            }
+            let stall_timeout = Duration::from_secs(settings.stall_timeout_minutes * 60);
+
+            for download in active {
+                let now = Instant::now();
+                let mark = marks.entry(download.id.clone()).or_insert(ProgressMark {
+                    progress: download.progress,
+                    since: now,
+                    stalled: false,
+                });
+
+                if (download.progress - mark.progress).abs() > f32::EPSILON {
+                    mark.progress = download.progress;
+                    mark.since = now;
+                    mark.stalled = false;
+                    continue;
+                }
+
+                let idle_for = now.duration_since(mark.since);
+                if mark.stalled || idle_for < stall_timeout {
+                    continue;
+                }
+                mark.stalled = true;
+
+                println!(
+                    "Download stalled: id={}, progress={}, idle for {}s",
+                    download.id,
+                    download.progress,
+                    idle_for.as_secs()
+                );
+                timeline::record(
+                    &app,
+                    &download.id,
+                    "event",
+                    "stalled",
+                    "watchdog",
+                    Some(&format!("No progress for {}s", idle_for.as_secs())),
+                );
+                let _ = app.emit(
+                    "download-stalled",
+                    &serde_json::json!({
+                        "id": download.id,
+                        "filename": download.filename,
+                        "progress": download.progress,
+                        "stalledSeconds": idle_for.as_secs(),
+                        "autoRestart": settings.auto_restart_stalled
+                    }),
+                );
+
+                if settings.auto_restart_stalled && download.provider.as_deref() == Some("webview2")
+                {
+                    match sidecar::restart_download(&app, &download.id).await {
+                        Ok(()) => {
+                            timeline::record(
+                                &app,
+                                &download.id,
+                                "event",
+                                "restarted",
+                                "watchdog",
+                                None,
+                            );
+                            // Give the resumed transfer a fresh window before flagging it again
+                            mark.since = Instant::now();
+                            mark.stalled = false;
+                        }
+                        Err(e) => {
+                            println!("Failed to restart stalled download {}: {}", download.id, e)
+                        }
+                    }
+                }
+            }
+        }
+    });
+}
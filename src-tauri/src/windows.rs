@@ -0,0 +1,128 @@
+use crate::state::AppState;
+use crate::windowstate;
+use std::sync::Mutex;
+use tauri::menu::{Menu, MenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+pub const DOWNLOADS_WINDOW: &str = "downloads";
+pub const PROGRESS_OVERLAY_WINDOW: &str = "progress-overlay";
+pub const MAIN_WINDOW: &str = "main";
+
+const TRAY_ID: &str = "main-tray";
+
+fn focus_existing(app: &AppHandle, label: &str) -> bool {
+    let Some(window) = app.get_webview_window(label) else {
+        return false;
+    };
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+    true
+}
+
+fn restore_geometry(app: &AppHandle, window: &WebviewWindow) {
+    if let Ok(app_state) = app.state::<Mutex<AppState>>().lock() {
+        windowstate::restore(&window.as_ref().window(), &app_state);
+    }
+}
+
+// Separate window with the regular downloads page, for keeping an eye on transfers while browsing
+pub fn open_downloads_window(app: &AppHandle) -> Result<(), String> {
+    if focus_existing(app, DOWNLOADS_WINDOW) {
+        return Ok(());
+    }
+    let window =
+        WebviewWindowBuilder::new(app, DOWNLOADS_WINDOW, WebviewUrl::App("downloads".into()))
+            .title("ChanomHub - Downloads")
+            .inner_size(900.0, 600.0)
+            .min_inner_size(480.0, 320.0)
+            .build()
+            .map_err(|e| format!("Failed to open downloads window: {}", e))?;
+    restore_geometry(app, &window);
+    Ok(())
+}
+
+// Small always-on-top window that only shows progress bars
+pub fn set_progress_overlay(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    let existing = app.get_webview_window(PROGRESS_OVERLAY_WINDOW);
+    match (enabled, existing) {
+        (true, Some(_)) => {
+            focus_existing(app, PROGRESS_OVERLAY_WINDOW);
+        }
+        (true, None) => {
+            let window = WebviewWindowBuilder::new(
+                app,
+                PROGRESS_OVERLAY_WINDOW,
+                WebviewUrl::App("overlay".into()),
+            )
+            .title("ChanomHub - Progress")
+            .inner_size(340.0, 180.0)
+            .resizable(true)
+            .decorations(false)
+            .always_on_top(true)
+            .skip_taskbar(true)
+            .build()
+            .map_err(|e| format!("Failed to open progress overlay: {}", e))?;
+            restore_geometry(app, &window);
+        }
+        (false, Some(window)) => {
+            window
+                .close()
+                .map_err(|e| format!("Failed to close progress overlay: {}", e))?;
+        }
+        (false, None) => {}
+    }
+    Ok(())
+}
+
+pub fn show_main_window(app: &AppHandle) {
+    focus_existing(app, MAIN_WINDOW);
+}
+
+// Way back into the app when it was started hidden
+pub fn create_tray(app: &AppHandle) -> Result<(), String> {
+    if app.tray_by_id(TRAY_ID).is_some() {
+        return Ok(());
+    }
+    let show = MenuItem::with_id(app, "show", "Show ChanomHub", true, None::<&str>)
+        .map_err(|e| format!("Failed to create tray menu: {}", e))?;
+    let downloads = MenuItem::with_id(app, "downloads", "Downloads", true, None::<&str>)
+        .map_err(|e| format!("Failed to create tray menu: {}", e))?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)
+        .map_err(|e| format!("Failed to create tray menu: {}", e))?;
+    let menu = Menu::with_items(app, &[&show, &downloads, &quit])
+        .map_err(|e| format!("Failed to create tray menu: {}", e))?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("ChanomHub")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id().as_ref() {
+            "show" => show_main_window(app),
+            "downloads" => {
+                if let Err(e) = open_downloads_window(app) {
+                    println!("{}", e);
+                }
+            }
+            "quit" => app.exit(0),
+            _ => {}
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder
+        .build(app)
+        .map_err(|e| format!("Failed to create tray icon: {}", e))?;
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use tauri::{Monitor, PhysicalPosition, PhysicalSize, Runtime, Window};
+
+// Smallest part of the title bar that must be on a monitor for a saved position to be reused
+const VISIBLE_MARGIN: i32 = 48;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+    pub fullscreen: bool,
+}
+
+// Identifies the current monitor setup, so a laptop docked and undocked keeps two layouts
+fn monitor_layout_key<R: Runtime>(window: &Window<R>) -> String {
+    let mut monitors: Vec<String> = window
+        .available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(|m| {
+            format!(
+                "{}x{}@{},{}",
+                m.size().width,
+                m.size().height,
+                m.position().x,
+                m.position().y
+            )
+        })
+        .collect();
+    monitors.sort();
+    monitors.join(";")
+}
+
+fn state_key<R: Runtime>(window: &Window<R>) -> String {
+    format!("{}|{}", window.label(), monitor_layout_key(window))
+}
+
+fn is_on_monitor(geometry: &WindowGeometry, monitors: &[Monitor]) -> bool {
+    monitors.iter().any(|m| {
+        let left = m.position().x;
+        let top = m.position().y;
+        let right = left + m.size().width as i32;
+        let bottom = top + m.size().height as i32;
+        geometry.x + VISIBLE_MARGIN > left
+            && geometry.x < right - VISIBLE_MARGIN
+            && geometry.y >= top
+            && geometry.y < bottom - VISIBLE_MARGIN
+    })
+}
+
+pub fn capture<R: Runtime>(window: &Window<R>, app_state: &mut AppState) {
+    let (Ok(position), Ok(size)) = (window.outer_position(), window.outer_size()) else {
+        return;
+    };
+    let maximized = window.is_maximized().unwrap_or(false);
+    let fullscreen = window.is_fullscreen().unwrap_or(false);
+    if window.is_minimized().unwrap_or(false) {
+        return;
+    }
+
+    let key = state_key(window);
+    // Keep the last normal bounds while maximized so un-maximizing restores them
+    let (x, y, width, height) = match app_state.window_states.get(&key) {
+        Some(previous) if maximized || fullscreen => {
+            (previous.x, previous.y, previous.width, previous.height)
+        }
+        _ => (position.x, position.y, size.width, size.height),
+    };
+    app_state.window_states.insert(
+        key,
+        WindowGeometry {
+            x,
+            y,
+            width,
+            height,
+            maximized,
+            fullscreen,
+        },
+    );
+}
+
+pub fn restore<R: Runtime>(window: &Window<R>, app_state: &AppState) {
+    let key = state_key(window);
+    let label_prefix = format!("{}|", window.label());
+    // Fall back to the geometry from another monitor layout, keeping only its size
+    let (geometry, same_layout) = match app_state.window_states.get(&key) {
+        Some(geometry) => (geometry.clone(), true),
+        None => match app_state
+            .window_states
+            .iter()
+            .find(|(k, _)| k.starts_with(&label_prefix))
+        {
+            Some((_, geometry)) => (geometry.clone(), false),
+            None => return,
+        },
+    };
+
+    let monitors = window.available_monitors().unwrap_or_default();
+    let on_screen = same_layout && is_on_monitor(&geometry, &monitors);
+
+    let _ = window.set_fullscreen(false);
+    let target = if on_screen {
+        None
+    } else {
+        window
+            .primary_monitor()
+            .ok()
+            .flatten()
+            .or_else(|| monitors.first().cloned())
+    };
+    let (width, height) = match &target {
+        Some(monitor) => (
+            geometry.width.min(monitor.size().width),
+            geometry.height.min(monitor.size().height),
+        ),
+        None => (geometry.width, geometry.height),
+    };
+    let _ = window.set_size(PhysicalSize::new(width, height));
+
+    match target {
+        None => {
+            let _ = window.set_position(PhysicalPosition::new(geometry.x, geometry.y));
+        }
+        Some(_) => {
+            println!(
+                "Saved position for window {} is off-screen, centering it",
+                window.label()
+            );
+            let _ = window.center();
+        }
+    }
+
+    if geometry.fullscreen {
+        let _ = window.set_fullscreen(true);
+    } else if geometry.maximized {
+        let _ = window.maximize();
+    }
+}
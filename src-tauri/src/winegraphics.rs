@@ -0,0 +1,703 @@
+use crate::state::LaunchConfig;
+use crate::{archiver, archivetools, httpconfig, offline, pathscope, portablesaves};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command as StdCommand;
+use tauri::{AppHandle, Emitter, Manager};
+
+const CACHE_DIR: &str = "wine-graphics";
+
+// Games without a prefix of their own get one here, never the user's shared ~/.wine
+const PREFIXES_DIR: &str = "wine-prefixes";
+
+const CACHE_MARKER: &str = "latest.json";
+
+// Written into the prefix, so a copied or shared prefix knows what it already has
+const PREFIX_MARKER: &str = ".chanomhub-wine-graphics.json";
+
+const OVERRIDES_SECTION: &str = "[Software\\\\Wine\\\\DllOverrides]";
+
+// Auto-update asks GitHub at most this often
+const UPDATE_CHECK_INTERVAL_HOURS: i64 = 24;
+
+// Per-game switches for the wine launch method; the DLLs are installed into the game's prefix
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct WineGraphics {
+    // Direct3D 8-11 over Vulkan
+    pub dxvk: bool,
+    // Direct3D 12 over Vulkan
+    pub vkd3d: bool,
+    // Move to new releases when launching instead of keeping the installed version
+    #[serde(rename = "autoUpdate")]
+    pub auto_update: bool,
+    // A prefix to use instead of the app's per-game one, e.g. a Proton compatdata folder
+    pub prefix: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Component {
+    Dxvk,
+    Vkd3d,
+}
+
+impl Component {
+    const ALL: [Component; 2] = [Component::Dxvk, Component::Vkd3d];
+
+    fn name(self) -> &'static str {
+        match self {
+            Component::Dxvk => "DXVK",
+            Component::Vkd3d => "VKD3D-Proton",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Component::Dxvk => "dxvk",
+            Component::Vkd3d => "vkd3d",
+        }
+    }
+
+    fn releases_url(self) -> &'static str {
+        match self {
+            Component::Dxvk => "https://api.github.com/repos/doitsujin/dxvk/releases/latest",
+            Component::Vkd3d => {
+                "https://api.github.com/repos/HansKristian-Work/vkd3d-proton/releases/latest"
+            }
+        }
+    }
+
+    fn asset_prefix(self) -> &'static str {
+        match self {
+            Component::Dxvk => "dxvk-",
+            Component::Vkd3d => "vkd3d-proton-",
+        }
+    }
+
+    // Release folders holding the 64-bit and 32-bit DLLs
+    fn arch_dirs(self) -> (&'static str, &'static str) {
+        match self {
+            Component::Dxvk => ("x64", "x32"),
+            Component::Vkd3d => ("x64", "x86"),
+        }
+    }
+
+    fn enabled(self, settings: &WineGraphics) -> bool {
+        match self {
+            Component::Dxvk => settings.dxvk,
+            Component::Vkd3d => settings.vkd3d,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+struct CacheMarker {
+    version: String,
+    #[serde(rename = "checkedAt")]
+    checked_at: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+struct PrefixMarker {
+    dxvk: Option<String>,
+    vkd3d: Option<String>,
+}
+
+impl PrefixMarker {
+    fn version(&self, component: Component) -> Option<&String> {
+        match component {
+            Component::Dxvk => self.dxvk.as_ref(),
+            Component::Vkd3d => self.vkd3d.as_ref(),
+        }
+    }
+
+    fn set_version(&mut self, component: Component, version: Option<String>) {
+        match component {
+            Component::Dxvk => self.dxvk = version,
+            Component::Vkd3d => self.vkd3d = version,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+    // "sha256:<hex>", on releases GitHub has computed it for
+    #[serde(default)]
+    digest: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ComponentStatus {
+    pub component: Component,
+    pub enabled: bool,
+    #[serde(rename = "installedVersion")]
+    pub installed_version: Option<String>,
+    #[serde(rename = "cachedVersion")]
+    pub cached_version: Option<String>,
+    // DLLs of the installed version missing from the prefix or replaced since
+    #[serde(rename = "missingDlls")]
+    pub missing_dlls: Vec<String>,
+    // DLLs Wine would still load its own builtin for
+    #[serde(rename = "missingOverrides")]
+    pub missing_overrides: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct WineGraphicsStatus {
+    pub prefix: String,
+    #[serde(rename = "prefixReady")]
+    pub prefix_ready: bool,
+    pub components: Vec<ComponentStatus>,
+}
+
+// Same prefix the launch will use: the one chosen for the game, the per-game one with portable
+// saves, otherwise one the app creates for the game. Wine's shared default prefix is never used.
+pub fn prefix_for(app: &AppHandle, launch_config: &LaunchConfig) -> Result<PathBuf, String> {
+    if let Some(prefix) = launch_config
+        .wine_graphics
+        .as_ref()
+        .and_then(|settings| settings.prefix.as_deref())
+        .filter(|prefix| !prefix.trim().is_empty())
+    {
+        return Ok(proton_prefix(Path::new(prefix)));
+    }
+    if let Some(prefix) = portablesaves::wine_prefix(launch_config) {
+        return Ok(prefix);
+    }
+    let game_dir = Path::new(&launch_config.executable_path)
+        .parent()
+        .ok_or("Executable has no parent folder")?;
+    let key = hex::encode(Sha256::digest(game_dir.to_string_lossy().as_bytes()));
+    app.path()
+        .app_local_data_dir()
+        .map(|dir| dir.join(PREFIXES_DIR).join(&key[..16]))
+        .map_err(|e| format!("Failed to get app data dir: {}", e))
+}
+
+// Proton keeps the actual Wine prefix in "pfx" inside its compatdata folder
+fn proton_prefix(prefix: &Path) -> PathBuf {
+    let pfx = prefix.join("pfx");
+    if pfx.is_dir() && !prefix.join("drive_c").is_dir() {
+        return pfx;
+    }
+    prefix.to_path_buf()
+}
+
+// Runs the game in the prefix the DLLs were installed into; a no-op without DXVK/VKD3D settings
+pub fn apply(
+    app: &AppHandle,
+    command: &mut StdCommand,
+    launch_config: &LaunchConfig,
+) -> Result<(), String> {
+    if launch_config.wine_graphics.is_none() || cfg!(target_os = "windows") {
+        return Ok(());
+    }
+    command.env("WINEPREFIX", prefix_for(app, launch_config)?);
+    Ok(())
+}
+
+fn cache_root(app: &AppHandle, component: Component) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map(|dir| dir.join(CACHE_DIR).join(component.key()))
+        .map_err(|e| format!("Failed to get app data dir: {}", e))
+}
+
+fn read_cache_marker(root: &Path) -> Option<CacheMarker> {
+    let content = fs::read_to_string(root.join(CACHE_MARKER)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_cache_marker(root: &Path, version: &str) {
+    let marker = CacheMarker {
+        version: version.to_string(),
+        checked_at: chrono::Utc::now().to_rfc3339(),
+    };
+    if let Err(e) = fs::write(
+        root.join(CACHE_MARKER),
+        serde_json::to_string_pretty(&marker).unwrap_or_default(),
+    ) {
+        println!("Failed to record wine graphics cache: {}", e);
+    }
+}
+
+fn read_prefix_marker(prefix: &Path) -> PrefixMarker {
+    fs::read_to_string(prefix.join(PREFIX_MARKER))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_prefix_marker(prefix: &Path, marker: &PrefixMarker) -> Result<(), String> {
+    fs::write(
+        prefix.join(PREFIX_MARKER),
+        serde_json::to_string_pretty(marker).unwrap_or_default(),
+    )
+    .map_err(|e| format!("Failed to record installed DLLs in the prefix: {}", e))
+}
+
+fn valid_version(version: &str) -> bool {
+    !version.is_empty()
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+}
+
+async fn download_release(
+    app: &AppHandle,
+    component: Component,
+    root: &Path,
+) -> Result<String, String> {
+    offline::ensure_online(app)?;
+    let client = httpconfig::client(app)?;
+    let url = component.releases_url();
+    let response = httpconfig::apply(&httpconfig::settings(app), client.get(url), url)
+        .header("accept", "application/vnd.github+json")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to check {} releases: {}", component.name(), e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "{} release check failed: {}",
+            component.name(),
+            response.status()
+        ));
+    }
+    let release: GithubRelease = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse {} release: {}", component.name(), e))?;
+    let version = release.tag_name.trim_start_matches('v').to_string();
+    if !valid_version(&version) {
+        return Err(format!(
+            "Invalid {} version: {}",
+            component.name(),
+            release.tag_name
+        ));
+    }
+    if root.join(&version).is_dir() {
+        write_cache_marker(root, &version);
+        return Ok(version);
+    }
+
+    // Debug and Steam Runtime builds are published next to the plain one
+    let asset = release
+        .assets
+        .iter()
+        .find(|asset| {
+            asset.name.starts_with(component.asset_prefix())
+                && (asset.name.ends_with(".tar.gz") || asset.name.ends_with(".tar.zst"))
+                && !asset.name.contains("debug")
+                && !asset.name.contains("sniper")
+        })
+        .ok_or_else(|| format!("{} {} has no release archive", component.name(), version))?;
+    let _ = app.emit(
+        "wine-graphics-downloading",
+        &serde_json::json!({ "component": component, "version": version }),
+    );
+    let response = client
+        .get(&asset.browser_download_url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", component.name(), e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "{} download failed: {}",
+            component.name(),
+            response.status()
+        ));
+    }
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", component.name(), e))?;
+    if let Some(expected) = asset
+        .digest
+        .as_deref()
+        .and_then(|d| d.strip_prefix("sha256:"))
+        && !hex::encode(Sha256::digest(&bytes)).eq_ignore_ascii_case(expected)
+    {
+        return Err(format!(
+            "{} download does not match the published SHA-256 hash",
+            component.name()
+        ));
+    }
+
+    fs::create_dir_all(root).map_err(|e| format!("Failed to create {}: {}", root.display(), e))?;
+    let archive_path = root.join(&asset.name);
+    fs::write(&archive_path, &bytes)
+        .map_err(|e| format!("Failed to save {}: {}", component.name(), e))?;
+    let archive = archive_path.to_string_lossy().to_string();
+    let tools = archivetools::ensure_for(app, &archive).await;
+    let staging = root.join(format!(".staging-{}", version));
+    let _ = fs::remove_dir_all(&staging);
+    let staging_dir = staging.to_string_lossy().to_string();
+    let unpacked = tauri::async_runtime::spawn_blocking(move || {
+        archiver::unarchive_file_with_progress(
+            &archive,
+            &staging_dir,
+            &tools?,
+            &archiver::IoCounters::default(),
+            |_| {},
+        )
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Unpacking {} panicked: {}", component.name(), e))
+    .and_then(|result| result);
+    let _ = fs::remove_file(&archive_path);
+    if let Err(e) = unpacked {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(format!("Failed to unpack {}: {}", component.name(), e));
+    }
+
+    // The archive holds a single "dxvk-2.x" folder
+    let (x64, _) = component.arch_dirs();
+    let release_dir = fs::read_dir(&staging)
+        .map_err(|e| format!("Failed to read {}: {}", staging.display(), e))?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| path.join(x64).is_dir())
+        .ok_or_else(|| format!("{} archive has an unexpected layout", component.name()))?;
+    let target = root.join(&version);
+    fs::rename(&release_dir, &target)
+        .map_err(|e| format!("Failed to install {}: {}", component.name(), e))?;
+    let _ = fs::remove_dir_all(&staging);
+    write_cache_marker(root, &version);
+    println!("Downloaded {} {}", component.name(), version);
+    Ok(version)
+}
+
+// Version to install: the cached one, unless there is none or an update check is due
+async fn ensure_cached(
+    app: &AppHandle,
+    component: Component,
+    check_for_update: bool,
+) -> Result<(String, PathBuf), String> {
+    let root = cache_root(app, component)?;
+    let cached = read_cache_marker(&root).filter(|m| root.join(&m.version).is_dir());
+    let due = cached.as_ref().is_none_or(|marker| {
+        chrono::DateTime::parse_from_rfc3339(&marker.checked_at).is_ok_and(|at| {
+            chrono::Utc::now().signed_duration_since(at)
+                >= chrono::Duration::hours(UPDATE_CHECK_INTERVAL_HOURS)
+        })
+    });
+    let version = match cached {
+        Some(marker) if !(check_for_update && due) => marker.version,
+        // A failed update check is not worth refusing to launch over
+        Some(marker) => match download_release(app, component, &root).await {
+            Ok(version) => version,
+            Err(e) => {
+                println!("{} update check failed: {}", component.name(), e);
+                marker.version
+            }
+        },
+        None => download_release(app, component, &root).await?,
+    };
+    let dir = root.join(&version);
+    Ok((version, dir))
+}
+
+fn run_wine(prefix: &Path, program: &str, args: &[&str]) -> Result<(), String> {
+    let status = StdCommand::new(program)
+        .args(args)
+        .env("WINEPREFIX", prefix)
+        // Keeps wineboot from offering to install Mono/Gecko
+        .env("WINEDLLOVERRIDES", "mscoree,mshtml=")
+        .status()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !status.success() {
+        return Err(format!("{} exited with {}", program, status));
+    }
+    Ok(())
+}
+
+fn system_dirs(prefix: &Path) -> (PathBuf, PathBuf) {
+    let windows = prefix.join("drive_c").join("windows");
+    (windows.join("system32"), windows.join("syswow64"))
+}
+
+fn init_prefix(prefix: &Path) -> Result<(), String> {
+    let (system32, _) = system_dirs(prefix);
+    if system32.is_dir() {
+        return Ok(());
+    }
+    fs::create_dir_all(prefix)
+        .map_err(|e| format!("Failed to create {}: {}", prefix.display(), e))?;
+    println!("Creating Wine prefix {}", prefix.display());
+    run_wine(prefix, "wineboot", &["-u"])?;
+    run_wine(prefix, "wineserver", &["-w"])
+}
+
+// (release folder, prefix folder) pairs: 64-bit prefixes keep 32-bit DLLs in syswow64
+fn dll_targets(prefix: &Path, component: Component, release: &Path) -> Vec<(PathBuf, PathBuf)> {
+    let (x64, x32) = component.arch_dirs();
+    let (system32, syswow64) = system_dirs(prefix);
+    if syswow64.is_dir() {
+        vec![(release.join(x64), system32), (release.join(x32), syswow64)]
+    } else {
+        vec![(release.join(x32), system32)]
+    }
+}
+
+fn release_dlls(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("dll"))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn dll_names(prefix: &Path, component: Component, release: &Path) -> BTreeSet<String> {
+    dll_targets(prefix, component, release)
+        .iter()
+        .flat_map(|(from, _)| release_dlls(from))
+        .filter_map(|dll| dll.file_stem().map(|s| s.to_string_lossy().to_lowercase()))
+        .collect()
+}
+
+fn copy_dlls(prefix: &Path, component: Component, release: &Path) -> Result<(), String> {
+    for (from, to) in dll_targets(prefix, component, release) {
+        for dll in release_dlls(&from) {
+            let Some(name) = dll.file_name() else {
+                continue;
+            };
+            fs::copy(&dll, to.join(name))
+                .map_err(|e| format!("Failed to copy {}: {}", dll.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+// Files differing in size from the release were replaced, e.g. by `wineboot -u` after a Wine upgrade
+fn missing_dlls(prefix: &Path, component: Component, release: &Path) -> Vec<String> {
+    let mut missing = BTreeSet::new();
+    for (from, to) in dll_targets(prefix, component, release) {
+        for dll in release_dlls(&from) {
+            let Some(name) = dll.file_name() else {
+                continue;
+            };
+            let expected = fs::metadata(&dll).map(|m| m.len()).ok();
+            let actual = fs::metadata(to.join(name)).map(|m| m.len()).ok();
+            if actual.is_none() || actual != expected {
+                missing.insert(name.to_string_lossy().to_lowercase());
+            }
+        }
+    }
+    missing.into_iter().collect()
+}
+
+// Reads user.reg directly: wineserver writes it out on exit, and this avoids starting Wine
+fn missing_overrides(prefix: &Path, dlls: &BTreeSet<String>) -> Vec<String> {
+    let content = fs::read_to_string(prefix.join("user.reg")).unwrap_or_default();
+    let mut in_section = false;
+    let mut native = BTreeSet::new();
+    for line in content.lines() {
+        if line.starts_with('[') {
+            in_section = line.starts_with(OVERRIDES_SECTION);
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+        // "d3d11"="native" or "*d3d11"="native,builtin"
+        if let Some((name, value)) = line.split_once('=') {
+            let name = name
+                .trim_matches('"')
+                .trim_start_matches('*')
+                .to_lowercase();
+            if value.trim_matches('"').starts_with("native") {
+                native.insert(name);
+            }
+        }
+    }
+    dlls.iter()
+        .filter(|dll| !native.contains(*dll))
+        .cloned()
+        .collect()
+}
+
+// One regedit import instead of a `wine reg add` per DLL
+fn set_overrides(prefix: &Path, dlls: &BTreeSet<String>, mode: &str) -> Result<(), String> {
+    if dlls.is_empty() {
+        return Ok(());
+    }
+    let mut reg = String::from("REGEDIT4\n\n[HKEY_CURRENT_USER\\Software\\Wine\\DllOverrides]\n");
+    for dll in dlls {
+        reg.push_str(&format!("\"{}\"=\"{}\"\n", dll, mode));
+    }
+    let reg_path = prefix.join(".chanomhub-dlloverrides.reg");
+    fs::write(&reg_path, reg).map_err(|e| format!("Failed to write DLL overrides: {}", e))?;
+    let result = run_wine(
+        prefix,
+        "wine",
+        &["regedit", "/S", &reg_path.to_string_lossy()],
+    )
+    .and_then(|_| run_wine(prefix, "wineserver", &["-w"]));
+    let _ = fs::remove_file(&reg_path);
+    result
+}
+
+fn component_status(
+    app: &AppHandle,
+    prefix: &Path,
+    marker: &PrefixMarker,
+    component: Component,
+    settings: &WineGraphics,
+) -> ComponentStatus {
+    let cached_version = cache_root(app, component)
+        .ok()
+        .and_then(|root| read_cache_marker(&root))
+        .map(|m| m.version);
+    let installed_version = marker.version(component).cloned();
+    let release = installed_version
+        .as_ref()
+        .and_then(|version| {
+            cache_root(app, component)
+                .ok()
+                .map(|root| root.join(version))
+        })
+        .filter(|dir| dir.is_dir());
+    let (missing_dlls, missing_overrides) = match &release {
+        Some(release) => (
+            missing_dlls(prefix, component, release),
+            missing_overrides(prefix, &dll_names(prefix, component, release)),
+        ),
+        None => (Vec::new(), Vec::new()),
+    };
+    ComponentStatus {
+        component,
+        enabled: component.enabled(settings),
+        installed_version,
+        cached_version,
+        missing_dlls,
+        missing_overrides,
+    }
+}
+
+pub fn status(app: &AppHandle, launch_config: &LaunchConfig) -> Result<WineGraphicsStatus, String> {
+    let prefix = prefix_for(app, launch_config)?;
+    let settings = launch_config.wine_graphics.clone().unwrap_or_default();
+    let marker = read_prefix_marker(&prefix);
+    Ok(WineGraphicsStatus {
+        prefix: prefix.to_string_lossy().to_string(),
+        prefix_ready: system_dirs(&prefix).0.is_dir(),
+        components: Component::ALL
+            .iter()
+            .map(|&c| component_status(app, &prefix, &marker, c, &settings))
+            .collect(),
+    })
+}
+
+// Brings the prefix in line with the game's settings before a wine launch:
+// installs or updates enabled components, repairs their DLLs and overrides, and switches
+// components that were turned off back to Wine's builtin DLLs
+pub async fn prepare(
+    app: &AppHandle,
+    launch_config: &LaunchConfig,
+    force_update: bool,
+) -> Result<(), String> {
+    if cfg!(target_os = "windows") {
+        return Ok(());
+    }
+    let Some(settings) = launch_config.wine_graphics.clone() else {
+        return Ok(());
+    };
+    let prefix = prefix_for(app, launch_config)?;
+    // A prefix chosen by hand is written into like any other folder outside the app's own
+    if settings
+        .prefix
+        .as_deref()
+        .is_some_and(|p| !p.trim().is_empty())
+    {
+        pathscope::ensure_allowed(app, "modify", &prefix).await?;
+    }
+
+    let mut releases = Vec::new();
+    for component in Component::ALL {
+        if component.enabled(&settings) {
+            let (version, dir) =
+                ensure_cached(app, component, settings.auto_update || force_update).await?;
+            releases.push((component, Some((version, dir))));
+        } else {
+            releases.push((component, None));
+        }
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut marker = read_prefix_marker(&prefix);
+        let needed = releases.iter().any(|(_, release)| release.is_some());
+        if needed {
+            init_prefix(&prefix)?;
+        }
+        for (component, release) in releases {
+            match release {
+                Some((version, dir)) => {
+                    let dlls = dll_names(&prefix, component, &dir);
+                    if marker.version(component) != Some(&version)
+                        || !missing_dlls(&prefix, component, &dir).is_empty()
+                    {
+                        copy_dlls(&prefix, component, &dir)?;
+                        println!(
+                            "Installed {} {} into {}",
+                            component.name(),
+                            version,
+                            prefix.display()
+                        );
+                    }
+                    if !missing_overrides(&prefix, &dlls).is_empty() {
+                        set_overrides(&prefix, &dlls, "native")?;
+                    }
+                    let missing = missing_overrides(&prefix, &dlls);
+                    if !missing.is_empty() {
+                        return Err(format!(
+                            "{} DLL overrides are not in place: {}",
+                            component.name(),
+                            missing.join(", ")
+                        ));
+                    }
+                    marker.set_version(component, Some(version));
+                }
+                None => {
+                    // Builtin again, so Wine uses its own implementation (DLL files stay, harmlessly)
+                    let Some(version) = marker.version(component).cloned() else {
+                        continue;
+                    };
+                    if let Ok(root) = cache_root(&app, component) {
+                        let dlls = dll_names(&prefix, component, &root.join(&version));
+                        set_overrides(&prefix, &dlls, "builtin")?;
+                    }
+                    println!("Disabled {} in {}", component.name(), prefix.display());
+                    marker.set_version(component, None);
+                }
+            }
+        }
+        if prefix.is_dir() {
+            write_prefix_marker(&prefix, &marker)?;
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Preparing the Wine prefix panicked: {}", e))?
+}
@@ -17,6 +17,7 @@ import ArticlePage from "./routes/article.tsx";
 import Downloads from "./routes/downloads.tsx";
 import PluginManager from "./routes/plugin.tsx";
 import Games from "./routes/games.tsx";
+import ProgressOverlay from "./routes/overlay.tsx";
 
 const router = createBrowserRouter([
     {
@@ -58,6 +59,11 @@ const router = createBrowserRouter([
             },
         ],
     },
+    {
+        // Standalone route for the always-on-top progress window (no navigation)
+        path: "/overlay",
+        element: <ProgressOverlay />,
+    },
 ]);
 
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
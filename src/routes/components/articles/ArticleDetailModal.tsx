@@ -204,7 +204,7 @@ export const ArticleDetailModal: React.FC<{
                         )}
 
                         {activeTab === 'downloads' && (
-                            <ArticleDownloads downloads={articleDownloads} />
+                            <ArticleDownloads downloads={articleDownloads} article={articleDetail} />
                         )}
 
                         {activeTab === 'translations' && (
@@ -1,9 +1,9 @@
 import React, { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
-import {ArticleDownload} from "../components/articles/types/types.ts";
+import {ArticleDetail, ArticleDownload} from "../components/articles/types/types.ts";
 
-export const ArticleDownloads: React.FC<{ downloads: ArticleDownload[] }> = ({ downloads }) => {
+export const ArticleDownloads: React.FC<{ downloads: ArticleDownload[]; article?: ArticleDetail | null }> = ({ downloads, article }) => {
     const [downloadStatus, setDownloadStatus] = useState<{ [key: string]: string }>({});
     const [downloadProgress, setDownloadProgress] = useState<{ [key: string]: number }>({});
 
@@ -40,10 +40,13 @@ export const ArticleDownloads: React.FC<{ downloads: ArticleDownload[] }> = ({ d
             setDownloadStatus((prev) => ({ ...prev, [downloadId]: 'กำลังเริ่มต้น...' }));
             setDownloadProgress((prev) => ({ ...prev, [downloadId]: 0 }));
 
-            await invoke('start_webview2_download', {
+            await invoke('start_download', {
                 url: download.url,
                 filename: download.name,
                 downloadId,
+                article: article
+                    ? { slug: article.slug, title: article.title, coverImage: article.mainImage }
+                    : null,
             });
         } catch (error) {
             setDownloadStatus((prev) => ({ ...prev, [downloadId]: `ข้อผิดพลาด: ${String(error)}` }));
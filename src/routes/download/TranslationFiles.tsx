@@ -40,7 +40,7 @@ export const TranslationFiles: React.FC<{ translationFiles: TranslationFile[] }>
             setDownloadStatus((prev) => ({ ...prev, [downloadId]: 'กำลังเริ่มต้น...' }));
             setDownloadProgress((prev) => ({ ...prev, [downloadId]: 0 }));
 
-            await invoke('start_webview2_download', {
+            await invoke('start_download', {
                 url: file.fileUrl,
                 filename: file.name,
                 downloadId,
@@ -163,7 +163,7 @@ export default function DownloadManager() {
         setDownloads((prev) => [...prev, newDownload]);
 
         try {
-            await invoke("start_webview2_download", {
+            await invoke("start_download", {
                 url: downloadUrl,
                 filename,
                 downloadId,
@@ -326,6 +326,7 @@ const Games: React.FC = () => {
                     downloadedAt: game.downloadedAt,
                     launchConfig: game.launch_config,
                     iconPath: game.icon_path,
+                    article: game.article,
                     url: '',
                     provider: null,
                     error: null,
@@ -400,6 +401,7 @@ const Games: React.FC = () => {
                     extracted_path: file.extractedPath,
                     launch_config: file.launchConfig,
                     icon_path: file.iconPath,
+                    article: file.article,
                     extraction_status: file.extractionStatus || 'idle',
                     extraction_progress: file.extractionProgress || 0,
                 }));
@@ -473,7 +475,7 @@ const Games: React.FC = () => {
             setSelectedExecutable(executablePath as string);
             if (executablePath) {
                 try {
-                    const iconPath = await invoke('extract_icon', { executablePath });
+                    const iconPath = await invoke('extract_icon', { executablePath, gameId });
                     setFiles((prev) =>
                         prev.map((f) => (f.id === gameId ? { ...f, iconPath: iconPath as string } : f))
                     );
@@ -0,0 +1,57 @@
+// overlay.tsx
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import { useEffect, useState } from "react";
+import { DownloadItem } from "./types/types.ts";
+
+// Compact always-on-top progress window opened by the `set_progress_overlay` command
+export default function ProgressOverlay() {
+    const [downloads, setDownloads] = useState<DownloadItem[]>([]);
+
+    useEffect(() => {
+        const isActive = (d: DownloadItem) =>
+            (d.status as string) === "starting" || d.status === "downloading";
+
+        invoke<DownloadItem[]>("get_active_downloads")
+            .then((items) => setDownloads(items.filter(isActive)))
+            .catch((err) => console.error("Failed to load active downloads:", err));
+
+        const unlistenChanged = listen<DownloadItem[]>("active-downloads-changed", (event) => {
+            setDownloads(event.payload.filter(isActive));
+        });
+        const unlistenProgress = listen("download-progress", (event) => {
+            const { id, progress } = event.payload as { id: string; progress: number };
+            setDownloads((current) =>
+                current.map((d) => (d.id === id ? { ...d, progress } : d))
+            );
+        });
+
+        return () => {
+            unlistenChanged.then((unlisten) => unlisten());
+            unlistenProgress.then((unlisten) => unlisten());
+        };
+    }, []);
+
+    return (
+        <div
+            className="h-screen p-3 bg-base-200 text-sm select-none overflow-y-auto"
+            onMouseDown={() => getCurrentWindow().startDragging()}
+        >
+            {downloads.length === 0 ? (
+                <p className="opacity-70">ไม่มีการดาวน์โหลด</p>
+            ) : (
+                downloads.map((download) => (
+                    <div key={download.id} className="mb-2">
+                        <p className="truncate">{download.filename}</p>
+                        <progress
+                            className="progress progress-primary w-full"
+                            value={download.progress}
+                            max="100"
+                        />
+                    </div>
+                ))
+            )}
+        </div>
+    );
+}
@@ -26,6 +26,7 @@ export default function Settings() {
         cloud_name: "",
         api_key: "",
         api_secret: "",
+        upload_preset: "",
     });
     const [downloadDir, setDownloadDir] = useState("");
     const [statusMessage, setStatusMessage] = useState("");
@@ -113,6 +114,7 @@ export default function Settings() {
                     cloud_name: config.cloud_name || "",
                     api_key: config.api_key || "",
                     api_secret: config.api_secret || "",
+                    upload_preset: config.upload_preset || "",
                 };
                 setCloudinaryConfig(cloudConfig);
                 localStorage.setItem(LOCAL_STORAGE_KEYS.CLOUDINARY, JSON.stringify(cloudConfig));
@@ -165,6 +167,7 @@ export default function Settings() {
                     cloudName: newConfig.cloud_name,
                     apiKey: newConfig.api_key,
                     apiSecret: newConfig.api_secret,
+                    uploadPreset: newConfig.upload_preset || null,
                 });
                 showStatus(`Cloudinary ${field} updated successfully`);
             } catch (err) {
@@ -283,6 +286,7 @@ export default function Settings() {
                         cloud_name: cloudinaryConfig.cloud_name,
                         api_key: cloudinaryConfig.api_key,
                         api_secret: cloudinaryConfig.api_secret,
+                        upload_preset: cloudinaryConfig.upload_preset || null,
                     },
                 });
                 showStatus("All settings saved successfully");
@@ -394,6 +398,17 @@ export default function Settings() {
                     placeholder="Enter Cloudinary API secret"
                 />
             </div>
+
+            <div>
+                <label className="block text-sm font-medium">Cloudinary Upload Preset</label>
+                <input
+                    type="text"
+                    value={cloudinaryConfig.upload_preset ?? ""}
+                    onChange={(e) => handleCloudinaryChange("upload_preset", e.target.value)}
+                    className="input input-bordered w-full max-w-xs"
+                    placeholder="Optional; an unsigned preset needs no API secret"
+                />
+            </div>
         </>
     );
 
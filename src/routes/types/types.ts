@@ -3,17 +3,478 @@ export interface DownloadItem {
     filename: string;
     url: string;
     progress: number;
-    status: "pending" | "downloading" | "completed" | "failed" | "cancelled";
+    status: "pending" | "queued" | "downloading" | "paused" | "completed" | "failed" | "cancelled" | "interrupted";
     path?: string;
     error?: string;
     provider?: string;
+    bytes_downloaded?: number | null;
+    total_bytes?: number | null;
+    priority?: DownloadPriority;
+    queued_at?: string | null;
+    segments?: number | null;
+    paused_at?: string | null;
+    chunks_verified?: boolean;
 }
 
+export type DownloadPriority = "high" | "normal" | "low";
+
+export interface StartDownloadOptions {
+    mirrors?: string[];
+    priority?: DownloadPriority;
+    // Parallel range connections; omitted uses the download settings
+    segments?: number;
+    chunkManifest?: ChunkManifest;
+}
+
+// SHA-256 (hex) of every chunkSize bytes of the file; the last chunk may be shorter
+export interface ChunkManifest {
+    chunkSize: number;
+    chunks: string[];
+}
+
+
+export interface ArticleMeta {
+    slug: string;
+    title?: string | null;
+    coverImage?: string | null;
+    version?: number | null;
+}
 
 export interface LaunchConfig {
     executablePath: string;
     launchMethod: 'direct' | 'python' | 'wine' | 'custom';
     customCommand?: string;
+    preLaunch?: string | null;
+    postExit?: string | null;
+    hookTimeoutSecs?: number | null;
+    // Pre-launch hook keeps running next to the game instead of having to finish first
+    preLaunchDetached?: boolean;
+    sessionLimit?: SessionLimit | null;
+    portableSaves?: PortableSaves | null;
+    // Windows only
+    gpuPreference?: GpuPreference | null;
+    // Wine launch method only
+    wineGraphics?: WineGraphics | null;
+    inputRemapper?: InputRemapper | null;
+    windowActions?: WindowActions | null;
+    elevate?: boolean;
+}
+
+export interface WindowActions {
+    mode: 'normal' | 'maximized' | 'borderless';
+    // Monitor name from availableMonitors()
+    monitor?: string | null;
+    alwaysOnTop: boolean;
+    waitSecs?: number | null;
+}
+
+export interface LaunchEntry {
+    name: string;
+    launchConfig: LaunchConfig;
+}
+
+export interface InputRemapper {
+    enabled: boolean;
+    kind: 'antimicrox' | 'hidhide' | 'custom';
+    program?: string | null;
+    profile?: string | null;
+    args: string[];
+}
+
+export interface WineGraphics {
+    dxvk: boolean;
+    vkd3d: boolean;
+    autoUpdate: boolean;
+    // Prefix to use instead of the per-game one the app creates, e.g. a Proton compatdata folder
+    prefix?: string | null;
+}
+
+export interface WineGraphicsComponentStatus {
+    component: 'dxvk' | 'vkd3d';
+    enabled: boolean;
+    installedVersion?: string;
+    cachedVersion?: string;
+    missingDlls: string[];
+    missingOverrides: string[];
+}
+
+export interface WineGraphicsStatus {
+    prefix: string;
+    prefixReady: boolean;
+    components: WineGraphicsComponentStatus[];
+}
+
+export type GpuPreference = 'default' | 'powerSaving' | 'highPerformance';
+
+export interface PortableSaves {
+    enabled: boolean;
+    // Windows only, e.g. "%USERPROFILE%\\Documents\\My Games\\Foo"
+    savePaths?: string[];
+}
+
+export interface SessionLimit {
+    reminderMinutes?: number | null;
+    hardLimitMinutes?: number | null;
+}
+
+export interface LaunchCheck {
+    check: 'disk' | 'battery';
+    severity: 'ok' | 'warn' | 'block';
+    message: string;
+    details?: Record<string, unknown> | null;
+}
+
+export interface LaunchCheckReport {
+    canLaunch: boolean;
+    hasWarnings: boolean;
+    checks: LaunchCheck[];
+}
+
+export interface ArticleChange {
+    path: string;
+    kind: 'added' | 'removed' | 'changed';
+    before?: unknown;
+    after?: unknown;
+}
+
+export interface ArticleChangelog {
+    slug: string;
+    firstView: boolean;
+    lastSeenAt?: string;
+    previousVersion?: number;
+    latestVersion?: number;
+    changes: ArticleChange[];
+}
+
+export interface Subscription {
+    kind: 'tag' | 'author';
+    id: number;
+    name: string;
+}
+
+export interface NewArticle {
+    id: number;
+    slug: string;
+    title: string;
+    mainImage?: string;
+    matchedBy: Subscription[];
+}
+
+export interface MonthlyBandwidth {
+    month: string;
+    bytes: number;
+}
+
+export interface BandwidthUsage {
+    month: string;
+    usedBytes: number;
+    capBytes?: number;
+    percent?: number;
+    capEnforced: boolean;
+    history: MonthlyBandwidth[];
+}
+
+export interface ArchiveTool {
+    kind: "7z" | "unrar" | "tar";
+    path: string;
+    managed: boolean;
+    formats: string[];
+}
+
+export interface ArchiveCapabilities {
+    native: string[];
+    tools: ArchiveTool[];
+    missing: string[];
+}
+
+export interface SuggestedLaunch {
+    launchMethod: "direct" | "python" | "wine" | "custom";
+    customCommand?: string;
+    preLaunch?: string;
+    postExit?: string;
+}
+
+export interface CompatNote {
+    id: number;
+    text: string;
+    tags: string[];
+    platform?: string;
+    author?: string;
+    score: number;
+    createdAt?: string;
+    suggested?: SuggestedLaunch;
+}
+
+export interface LaunchTemplate {
+    // Empty when creating a template
+    id: string;
+    name: string;
+    launchMethod: "direct" | "python" | "wine" | "custom";
+    customCommand?: string;
+    preLaunch?: string;
+    postExit?: string;
+    hookTimeoutSecs?: number;
+    portableSaves?: PortableSaves | null;
+}
+
+// launch_game result; alreadyRunning means the existing window was (maybe) focused instead
+export interface LaunchResult {
+    status: 'launched' | 'alreadyRunning';
+    pid?: number | null;
+    focused: boolean;
+}
+
+// launch_game_by_slug result: the installed game the slug matched, and what happened
+export interface SlugLaunchResult extends LaunchResult {
+    gameId: string;
+}
+
+// Payload of the game-crash-loop event
+export interface CrashLoopReport {
+    gameId: string;
+    crashes: number;
+    windowMinutes: number;
+    exitStatuses: string[];
+    launchMethod: string;
+    logTail: string[];
+    suggestions: {
+        id: 'verifyFiles' | 'reinstallRuntime' | 'disableWineGraphics' | 'disableMods' | 'checkLaunchConfig';
+        title: string;
+        detail: string;
+    }[];
+}
+
+// One entry of get_download_timeline
+export interface TimelineEntry {
+    at: string;
+    kind: 'download' | 'extraction' | 'event';
+    state: string;
+    source: string;
+    detail?: string | null;
+}
+
+// Downloads of one article returned by get_download_groups
+export interface DownloadGroup {
+    slug: string;
+    title?: string | null;
+    coverImage?: string | null;
+    downloadIds: string[];
+    completed: number;
+    failed: number;
+    progress: number;
+    status: 'downloading' | 'failed' | 'completed' | 'extracted';
+    split?: 'rarVolumes' | 'numbered' | null;
+}
+
+// Returned by download_split_release; progress arrives as split-release-progress events
+export interface SplitRelease {
+    slug: string;
+    base: string;
+    kind: 'rarVolumes' | 'numbered';
+    parts: {
+        number: number;
+        downloadId: string;
+        url: string;
+        filename: string;
+    }[];
+}
+
+export interface RetentionSettings {
+    maxAgeDays: number;
+    deleteArchives: boolean;
+}
+
+// Returned by preview_retention_cleanup / run_retention_cleanup and the retention-cleanup event
+export interface RetentionPlan {
+    maxAgeDays: number;
+    deleteArchives: boolean;
+    items: {
+        id: string;
+        filename: string;
+        downloadedAt: string;
+        ageDays: number;
+        archivePath?: string | null;
+    }[];
+    keptInstalled: number;
+}
+
+export interface InstallationReport {
+    checked: number;
+    problems: {
+        path: string;
+        reason: 'missing' | 'modified';
+    }[];
+    checkedAt: string;
+}
+
+export interface ArticleRenderSettings {
+    loadImages: boolean;
+    maxImageMb: number;
+    externalLinks: boolean;
+}
+
+export interface RenderedArticle {
+    slug: string;
+    title: string;
+    html: string;
+    images: number;
+    missingImages: number;
+    offline: boolean;
+}
+
+export interface FeedSettings {
+    hubFeed: boolean;
+    feeds: { url: string; name: string }[];
+    pollIntervalMinutes: number;
+    showNotifications: boolean;
+}
+
+export interface FeedItem {
+    feedUrl: string;
+    feedName: string;
+    id: string;
+    title: string;
+    link?: string | null;
+    published?: string | null;
+    summary?: string | null;
+}
+
+export interface ScreenshotGallery {
+    folder: string;
+    slug: string;
+    uploads: {
+        file: string;
+        modified: number;
+        url: string;
+        thumbnailUrl: string;
+        uploadedAt: string;
+    }[];
+}
+
+export interface UploadedAsset {
+    public_id: string;
+    secure_url: string;
+    format?: string | null;
+    bytes: number;
+    width?: number | null;
+    height?: number | null;
+    created_at?: string | null;
+}
+
+export interface UploadPage {
+    resources: UploadedAsset[];
+    next_cursor?: string | null;
+}
+
+export interface VideoUpload {
+    upload_id: string;
+    public_id: string;
+    secure_url: string;
+    trailer_url: string;
+}
+
+export interface SoundSettings {
+    enabled: boolean;
+    volume: number;
+    // "" for silence, "chime" | "alert" | "ping", or a path to a .wav/.mp3/.ogg file
+    downloadComplete: string;
+    downloadFailed: string;
+    extractionComplete: string;
+    extractionFailed: string;
+}
+
+export interface NowPlayingSettings {
+    writeStatusFile: boolean;
+    statusFilePath?: string | null;
+}
+
+export interface GameSession {
+    gameId: string;
+    title: string;
+    articleSlug?: string | null;
+    coverImage?: string | null;
+    executable: string;
+    pid: number;
+    startedAt: string;
+}
+
+export interface NetworkSettings {
+    offlineMode: boolean;
+    maxApiRequests: number;
+}
+
+export interface QueuedDownload {
+    downloadId: string;
+    url: string;
+    filename: string;
+    article?: ArticleMeta | null;
+    referer?: string | null;
+    queuedAt: string;
+    mirrors: string[];
+    priority: DownloadPriority;
+}
+
+// Starts by itself at scheduledAt (RFC 3339)
+export interface ScheduledDownload {
+    downloadId: string;
+    url: string;
+    filename: string;
+    article?: ArticleMeta | null;
+    referer?: string | null;
+    scheduledAt: string;
+    mirrors?: string[];
+    priority?: DownloadPriority;
+    segments?: number | null;
+}
+
+export type JobStage = "download" | "verify" | "extract" | "detectExe" | "ready";
+
+export type JobStageStatus =
+    | "pending"
+    | "running"
+    | "waiting"
+    | "paused"
+    | "done"
+    | "skipped"
+    | "failed"
+    | "cancelled";
+
+// Payload of the job-progress event; id is the download id
+export interface Job {
+    id: string;
+    title: string;
+    stage: JobStage;
+    status: JobStageStatus;
+    progress: number;
+    stages: { stage: JobStage; status: JobStageStatus; progress: number }[];
+    executables: string[];
+    error?: string | null;
+    updatedAt: string;
+}
+
+// Payload of disk-space-warning, sent before a download or extraction that would not fit
+export interface DiskSpaceWarning {
+    id: string;
+    stage: "download" | "extraction";
+    shortfall: {
+        path: string;
+        needed: number;
+        available: number;
+        missing: number;
+    };
+}
+
+export interface SpeedSample {
+    timestamp: number;
+    bytesPerSec: number;
+    bytesReceived: number;
+}
+
+export interface IntegrityFailure {
+    file: string;
+    reason: string;
+    restoredFromBackup: boolean;
+    detectedAt: string;
 }
 
 export interface DownloadedFile {
@@ -32,6 +493,7 @@ export interface DownloadedFile {
     progress?: number;
     extractionStatus?: 'idle' | 'extracting' | 'completed' | 'failed';
     extractionProgress?: number;
+    article?: ArticleMeta | null;
 }
 
 export interface SavedGameInfo {
@@ -43,6 +505,9 @@ export interface SavedGameInfo {
     downloadedAt: string;
     launch_config?: LaunchConfig;
     icon_path?: string;
+    article?: ArticleMeta | null;
+    // Extra entry points (config tool, patcher) launched with launch_game's entryName
+    launch_entries?: LaunchEntry[];
 }
 
 
@@ -84,4 +549,5 @@ export interface CloudinaryConfig {
     cloud_name: string;
     api_key: string;
     api_secret: string;
+    upload_preset?: string | null;
 }
\ No newline at end of file